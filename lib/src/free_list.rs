@@ -29,6 +29,9 @@ impl<T: Pod> FreeListNode<T> {
     pub fn has_next(&self) -> bool {
         self.next_index != NIL
     }
+    pub fn get_next(&self) -> DataIndex {
+        self.next_index
+    }
 }
 
 impl<'a, T: Pod> FreeList<'a, T> {
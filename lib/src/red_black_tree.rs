@@ -14,6 +14,7 @@ pub const RBTREE_OVERHEAD_BYTES: usize = 16;
 // Public
 //  struct RedBlackTree<'a, V: Payload>
 //    fn new(data: &'a mut [u8], root_index: DataIndex, max_index: DataIndex) -> Self
+//    fn move_node(&mut self, from_index: DataIndex, to_index: DataIndex)
 //    GetRedBlackTreeReadOnlyData
 //    GetRedBlackTreeData
 //    HyperTreeWriteOperations
@@ -1164,6 +1165,37 @@ impl<'a, V: Payload> RedBlackTree<'a, V> {
         }
     }
 
+    /// Relocate the node at from_index to to_index without changing the shape
+    /// or coloring of the tree. The block at to_index must not be in use. The
+    /// block at from_index is zeroed afterwards so it can be freed.
+    pub fn move_node(&mut self, from_index: DataIndex, to_index: DataIndex) {
+        trace!("TREE move {from_index}->{to_index}");
+        if from_index == to_index || from_index == NIL || to_index == NIL {
+            return;
+        }
+
+        let parent_index: DataIndex = self.get_parent_index::<V>(from_index);
+        let left_index: DataIndex = self.get_left_index::<V>(from_index);
+        let right_index: DataIndex = self.get_right_index::<V>(from_index);
+
+        if parent_index == NIL {
+            self.root_index = to_index;
+        } else if self.get_left_index::<V>(parent_index) == from_index {
+            self.set_left_index::<V>(parent_index, to_index);
+        } else {
+            self.set_right_index::<V>(parent_index, to_index);
+        }
+        self.set_parent_index::<V>(left_index, to_index);
+        self.set_parent_index::<V>(right_index, to_index);
+        if self.max_index == from_index {
+            self.max_index = to_index;
+        }
+
+        let node: RBNode<V> = *get_helper::<RBNode<V>>(self.data, from_index);
+        *get_mut_helper::<RBNode<V>>(self.data, to_index) = node;
+        *get_mut_helper::<RBNode<V>>(self.data, from_index) = RBNode::<V>::zeroed();
+    }
+
     #[cfg(test)]
     fn remove_by_value(&mut self, value: &V) {
         let index: DataIndex = self.lookup_index(value);
@@ -1714,6 +1746,49 @@ pub(crate) mod test {
         assert_eq!(tree.get_max_index(), TEST_BLOCK_WIDTH * 11);
     }
 
+    #[test]
+    fn test_move_node() {
+        let mut data: [u8; 100000] = [0; 100000];
+        let mut tree: RedBlackTree<TestOrderBid> = init_simple_tree(&mut data);
+        let root_index: DataIndex = tree.get_root_index();
+
+        // Move every node, including the root and the max, to a new block.
+        for i in 1..12 {
+            tree.move_node(TEST_BLOCK_WIDTH * i, TEST_BLOCK_WIDTH * (i + 20));
+            tree.verify_rb_tree::<TestOrderBid>();
+        }
+        assert_eq!(tree.get_root_index(), root_index + TEST_BLOCK_WIDTH * 20);
+        assert_eq!(tree.get_max_index(), TEST_BLOCK_WIDTH * 31);
+        for i in 1..12 {
+            assert_eq!(
+                tree.lookup_index(&TestOrderBid::new(i * 1_000)),
+                TEST_BLOCK_WIDTH * (i as DataIndex + 20)
+            );
+        }
+
+        // Old blocks are zeroed so they can go back on a free list.
+        assert!(
+            data[TEST_BLOCK_WIDTH as usize..(TEST_BLOCK_WIDTH * 12) as usize]
+                .iter()
+                .all(|byte| *byte == 0)
+        );
+    }
+
+    #[test]
+    fn test_move_node_then_remove() {
+        let mut data: [u8; 100000] = [0; 100000];
+        let mut tree: RedBlackTree<TestOrderBid> = init_simple_tree(&mut data);
+        tree.move_node(TEST_BLOCK_WIDTH * 4, TEST_BLOCK_WIDTH * 40);
+        tree.remove_by_value(&TestOrderBid::new(4 * 1_000));
+        tree.verify_rb_tree::<TestOrderBid>();
+        tree.insert(TEST_BLOCK_WIDTH * 4, TestOrderBid::new(4 * 1_000));
+        tree.verify_rb_tree::<TestOrderBid>();
+        assert_eq!(
+            tree.lookup_index(&TestOrderBid::new(4 * 1_000)),
+            TEST_BLOCK_WIDTH * 4
+        );
+    }
+
     #[test]
     fn test_insert_right_left() {
         let mut data: [u8; 100000] = [0; 100000];
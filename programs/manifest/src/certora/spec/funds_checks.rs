@@ -277,9 +277,14 @@ fn rest_remaining_check<const IS_BID: bool>() {
         price: nondet(),
         is_bid: IS_BID,
         last_valid_slot: nondet(),
+        last_valid_unix_timestamp: state::NO_EXPIRATION_LAST_VALID_UNIX_TIMESTAMP,
         order_type: state::OrderType::Limit,
+        self_trade_behavior: state::SelfTradeBehavior::Allow,
+        min_base_atoms_filled: BaseAtoms::ZERO,
         global_trade_accounts_opts: &[None, None],
         current_slot: Some(nondet()),
+        price_band_opt: None,
+        peg_opt: None,
     };
 
     let remaining_base_atoms_arg: BaseAtoms = nondet();
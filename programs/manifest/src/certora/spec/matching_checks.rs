@@ -7,7 +7,7 @@ use certora::hooks::last_called_remove_order_from_tree_and_free;
 use solana_program::account_info::AccountInfo;
 
 use certora::spec::place_order_checks::place_single_order_nondet_inputs;
-use state::get_helper_order;
+use state::{get_helper_order, utils::get_now_unix_timestamp};
 
 use crate::{
     certora::spec::no_funds_loss_util::*,
@@ -50,7 +50,7 @@ pub fn matching_if_maker_order_exists<const IS_BID: bool>() {
     let maker_order: &RestingOrder = get_helper_order(dynamic, maker_order_index).get_value();
     let maker_order_price = maker_order.get_price();
     // -- maker_order is not expired
-    cvt_assume!(!maker_order.is_expired(now_slot));
+    cvt_assume!(!maker_order.is_expired(now_slot, get_now_unix_timestamp()));
     // -- maker_order matches on price
     if IS_BID {
         cvt_assume!(maker_order_price <= args.price);
@@ -388,7 +388,7 @@ pub fn matching_fully_matched_if_order_removed<const IS_BID: bool>() {
     let dynamic: &mut [u8; 8] = &mut [0; 8];
     let maker_order: &RestingOrder = get_helper_order(dynamic, maker_order_index).get_value();
     // -- maker_order is not expired
-    cvt_assume!(!maker_order.is_expired(now_slot));
+    cvt_assume!(!maker_order.is_expired(now_slot, get_now_unix_timestamp()));
 
     // -- call to place_single_order
     let (res, _total_base_atoms_traded, _total_quote_atoms_traded) = place_single_order!(
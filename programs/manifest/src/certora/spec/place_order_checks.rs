@@ -28,9 +28,14 @@ pub fn place_single_order_nondet_inputs<const IS_BID: bool>(
         price: QuoteAtomsPerBaseAtom::nondet_price_u32(),
         is_bid: IS_BID,
         last_valid_slot: nondet(),
+        last_valid_unix_timestamp: state::NO_EXPIRATION_LAST_VALID_UNIX_TIMESTAMP,
         order_type: state::OrderType::Limit,
+        self_trade_behavior: state::SelfTradeBehavior::Allow,
+        min_base_atoms_filled: BaseAtoms::ZERO,
         global_trade_accounts_opts: &[None, None],
         current_slot: Some(nondet()),
+        price_band_opt: None,
+        peg_opt: None,
     };
     let remaining_base_atoms: BaseAtoms = nondet();
     let now_slot: u32 = nondet();
@@ -13,14 +13,26 @@ pub mod certora;
 
 use hypertree::trace;
 use program::{
-    batch_update::process_batch_update, claim_seat::process_claim_seat,
-    commit_market::process_commit_market, create_market::process_create_market,
-    delegate_market::process_delegate_market, deposit::process_deposit,
-    expand_market::process_expand_market, global_add_trader::process_global_add_trader,
-    global_clean::process_global_clean, global_create::process_global_create,
-    global_deposit::process_global_deposit, global_evict::process_global_evict,
-    global_withdraw::process_global_withdraw, process_swap,
-    undelegate_market::process_undelegate_market, withdraw::process_withdraw, ManifestInstruction,
+    batch_update::process_batch_update, cancel_all::process_cancel_all,
+    cancel_trigger_order::process_cancel_trigger_order, claim_seat::process_claim_seat,
+    clean_expired::process_clean_expired, commit_market::process_commit_market,
+    compact_market::process_compact_market, crank_trigger_orders::process_crank_trigger_orders,
+    create_market::process_create_market, delegate_market::process_delegate_market,
+    deposit::process_deposit, expand_market::process_expand_market,
+    global_add_trader::process_global_add_trader, global_clean::process_global_clean,
+    global_create::process_global_create, global_deposit::process_global_deposit,
+    global_evict::process_global_evict, global_withdraw::process_global_withdraw,
+    init_market_metadata::process_init_market_metadata,
+    init_market_stats::process_init_market_stats,
+    market_global_create::process_market_global_create,
+    market_global_deposit::process_market_global_deposit,
+    market_global_withdraw::process_market_global_withdraw,
+    place_trigger_order::process_place_trigger_order, poke_twap::process_poke_twap, process_swap,
+    release_seat::process_release_seat, set_market_authority::process_set_market_authority,
+    transfer_market_metadata_authority::process_transfer_market_metadata_authority,
+    undelegate_market::process_undelegate_market,
+    update_market_metadata::process_update_market_metadata, withdraw::process_withdraw,
+    ManifestInstruction,
 };
 use solana_program::{
     account_info::AccountInfo, declare_id, entrypoint::ProgramResult, program_error::ProgramError,
@@ -87,6 +99,12 @@ security_txt! {
 // an account is needed per eviction, and that will quickly run into the solana
 // transaction limit before an attacker is able to clear a substantial number of
 // seats in one transaction.
+//
+// Trigger order spam
+// Trigger orders are not backed by funds until they trigger, so each one takes
+// the same 5_000 lamport gas prepayment as a global order, on top of the rent
+// for its block. The prepayment goes to whoever cranks it, so triggered,
+// expired and unfunded trigger orders are profitable to clear.
 
 declare_id!("MNFSTqtC93rEfYHB6hF82sKdZpUDFWkViLByLd1k1Ms");
 
@@ -112,7 +130,10 @@ pub fn process_instruction(
             process_create_market(program_id, accounts, data)?;
         }
         ManifestInstruction::ClaimSeat => {
-            process_claim_seat(program_id, accounts, data)?;
+            process_claim_seat(program_id, accounts, data, false)?;
+        }
+        ManifestInstruction::ClaimSeatV2 => {
+            process_claim_seat(program_id, accounts, data, true)?;
         }
         ManifestInstruction::Deposit => {
             process_deposit(program_id, accounts, data)?;
@@ -130,7 +151,10 @@ pub fn process_instruction(
             process_expand_market(program_id, accounts, data)?;
         }
         ManifestInstruction::BatchUpdate => {
-            process_batch_update(program_id, accounts, data)?;
+            process_batch_update(program_id, accounts, data, false)?;
+        }
+        ManifestInstruction::BatchUpdateV2 => {
+            process_batch_update(program_id, accounts, data, true)?;
         }
         ManifestInstruction::GlobalCreate => {
             process_global_create(program_id, accounts, data)?;
@@ -159,6 +183,54 @@ pub fn process_instruction(
         ManifestInstruction::CommitMarket => {
             process_commit_market(program_id, accounts, data)?;
         }
+        ManifestInstruction::ReleaseSeat => {
+            process_release_seat(program_id, accounts, data)?;
+        }
+        ManifestInstruction::CancelAll => {
+            process_cancel_all(program_id, accounts, data)?;
+        }
+        ManifestInstruction::CleanExpired => {
+            process_clean_expired(program_id, accounts, data)?;
+        }
+        ManifestInstruction::CompactMarket => {
+            process_compact_market(program_id, accounts, data)?;
+        }
+        ManifestInstruction::InitMarketMetadata => {
+            process_init_market_metadata(program_id, accounts, data)?;
+        }
+        ManifestInstruction::UpdateMarketMetadata => {
+            process_update_market_metadata(program_id, accounts, data)?;
+        }
+        ManifestInstruction::TransferMarketMetadataAuthority => {
+            process_transfer_market_metadata_authority(program_id, accounts, data)?;
+        }
+        ManifestInstruction::PlaceTriggerOrder => {
+            process_place_trigger_order(program_id, accounts, data)?;
+        }
+        ManifestInstruction::CancelTriggerOrder => {
+            process_cancel_trigger_order(program_id, accounts, data)?;
+        }
+        ManifestInstruction::CrankTriggerOrders => {
+            process_crank_trigger_orders(program_id, accounts, data)?;
+        }
+        ManifestInstruction::InitMarketStats => {
+            process_init_market_stats(program_id, accounts, data)?;
+        }
+        ManifestInstruction::PokeTwap => {
+            process_poke_twap(program_id, accounts, data)?;
+        }
+        ManifestInstruction::MarketGlobalCreate => {
+            process_market_global_create(program_id, accounts, data)?;
+        }
+        ManifestInstruction::MarketGlobalDeposit => {
+            process_market_global_deposit(program_id, accounts, data)?;
+        }
+        ManifestInstruction::MarketGlobalWithdraw => {
+            process_market_global_withdraw(program_id, accounts, data)?;
+        }
+        ManifestInstruction::SetMarketAuthority => {
+            process_set_market_authority(program_id, accounts, data)?;
+        }
     }
 
     Ok(())
@@ -5,7 +5,7 @@ use solana_program::{program_error::ProgramError, pubkey::Pubkey};
 
 use crate::{
     quantities::{BaseAtoms, GlobalAtoms, QuoteAtoms, QuoteAtomsPerBaseAtom},
-    state::OrderType,
+    state::{OrderType, TriggerOrderType},
 };
 
 /// Serialize and log an event
@@ -51,6 +51,13 @@ pub struct ClaimSeatLog {
     pub trader: Pubkey,
 }
 
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+pub struct ReleaseSeatLog {
+    pub market: Pubkey,
+    pub trader: Pubkey,
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
 pub struct DepositLog {
@@ -87,6 +94,42 @@ pub struct FillLog {
     pub _padding: [u8; 14],
 }
 
+/// Emitted after each FillLog with the maker seat balances once the fill
+/// settled, so that indexers can resync balances from any fill instead of
+/// replaying every deposit, withdraw, fill and cancel. The taker balances
+/// are only final once the order is done, see TakerSettlementLog.
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+pub struct FillSettlementLog {
+    pub market: Pubkey,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    /// Withdrawable balances after the fill. When the maker is a reverse
+    /// order, this is after the reverse order was placed.
+    pub maker_base_balance: BaseAtoms,
+    pub maker_quote_balance: QuoteAtoms,
+    /// Size left on the maker order, zero when fully filled.
+    pub maker_remaining_base_atoms: BaseAtoms,
+    pub maker_sequence_number: u64,
+    pub taker_sequence_number: u64,
+    pub maker_fully_filled: PodBool,
+    pub _padding: [u8; 7],
+}
+
+/// Emitted once for an order that filled, after its FillSettlementLogs, with
+/// the taker seat balances after the rest of the order was placed or, for
+/// swaps, after the proceeds were withdrawn. Zero when a swap used a
+/// temporary seat.
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+pub struct TakerSettlementLog {
+    pub market: Pubkey,
+    pub taker: Pubkey,
+    pub taker_base_balance: BaseAtoms,
+    pub taker_quote_balance: QuoteAtoms,
+    pub taker_sequence_number: u64,
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
 pub struct PlaceOrderLog {
@@ -126,6 +169,72 @@ pub struct CancelOrderLog {
     pub order_sequence_number: u64,
 }
 
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+pub struct ModifyOrderLog {
+    pub market: Pubkey,
+    pub trader: Pubkey,
+    pub order_sequence_number: u64,
+    pub base_atoms: BaseAtoms,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+pub struct CompactMarketLog {
+    pub market: Pubkey,
+    pub receiver: Pubkey,
+    pub lamports_refunded: u64,
+    pub num_blocks_removed: u32,
+    pub _padding: [u8; 4],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+pub struct PlaceTriggerOrderLog {
+    pub market: Pubkey,
+    pub trader: Pubkey,
+    pub trigger_price: QuoteAtomsPerBaseAtom,
+    pub price: QuoteAtomsPerBaseAtom,
+    pub base_atoms: BaseAtoms,
+    pub order_sequence_number: u64,
+    pub order_index: u32,
+    pub last_valid_slot: u32,
+    pub trigger_type: TriggerOrderType,
+    pub is_bid: PodBool,
+    pub _padding: [u8; 6],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+pub struct ActivateTriggerOrderLog {
+    pub market: Pubkey,
+    pub trader: Pubkey,
+    pub cranker: Pubkey,
+    pub trigger_sequence_number: u64,
+    /// Sequence number the order was placed with once triggered.
+    pub order_sequence_number: u64,
+    pub base_atoms_traded: BaseAtoms,
+    pub quote_atoms_traded: QuoteAtoms,
+    /// Index of what rested on the book, NIL when nothing did.
+    pub order_index: u32,
+    pub _padding: [u8; 4],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+pub struct MarketMetadataLog {
+    pub market: Pubkey,
+    pub market_metadata: Pubkey,
+    pub authority: Pubkey,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
+pub struct MarketAuthorityLog {
+    pub market: Pubkey,
+    pub authority: Pubkey,
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Zeroable, Pod, ShankAccount)]
 pub struct GlobalCreateLog {
@@ -188,6 +297,7 @@ pub struct DelegateMarketLog {
     pub market: Pubkey,
     pub update_frequency_ms: u64,
     pub time_limit: u64,
+    pub validator: Pubkey,
 }
 
 #[repr(C)]
@@ -229,6 +339,8 @@ const CLAIM_SEAT_LOG_DISCRIMINANT: [u8; 8] = [129, 77, 152, 210, 218, 144, 163,
 const DEPOSIT_LOG_DISCRIMINANT: [u8; 8] = [23, 214, 24, 34, 52, 104, 109, 188];
 const WITHDRAW_LOG_DISCRIMINANT: [u8; 8] = [112, 218, 111, 63, 18, 95, 136, 35];
 const FILL_LOG_DISCRIMINANT: [u8; 8] = [58, 230, 242, 3, 75, 113, 4, 169];
+const FILL_SETTLEMENT_LOG_DISCRIMINANT: [u8; 8] = [94, 14, 80, 255, 72, 67, 4, 87];
+const TAKER_SETTLEMENT_LOG_DISCRIMINANT: [u8; 8] = [40, 177, 224, 64, 188, 0, 182, 43];
 const PLACE_ORDER_LOG_DISCRIMINANT: [u8; 8] = [157, 118, 247, 213, 47, 19, 164, 120];
 const PLACE_ORDER_LOG_V2_DISCRIMINANT: [u8; 8] = [189, 97, 159, 235, 136, 5, 1, 141];
 const CANCEL_ORDER_LOG_DISCRIMINANT: [u8; 8] = [22, 65, 71, 33, 244, 235, 255, 215];
@@ -242,6 +354,13 @@ const GLOBAL_CLEANUP_LOG_DISCRIMINANT: [u8; 8] = [193, 249, 115, 186, 42, 126, 1
 const DELEGATE_MARKET_LOG_DISCRIMINANT: [u8; 8] = [101, 45, 78, 123, 89, 156, 234, 67];
 const UNDELEGATE_MARKET_LOG_DISCRIMINANT: [u8; 8] = [202, 90, 156, 246, 178, 67, 123, 134];
 const COMMIT_MARKET_LOG_DISCRIMINANT: [u8; 8] = [55, 134, 89, 178, 234, 123, 67, 201];
+const RELEASE_SEAT_LOG_DISCRIMINANT: [u8; 8] = [18, 175, 154, 240, 197, 138, 96, 203];
+const MODIFY_ORDER_LOG_DISCRIMINANT: [u8; 8] = [78, 128, 223, 28, 169, 253, 16, 29];
+const COMPACT_MARKET_LOG_DISCRIMINANT: [u8; 8] = [21, 98, 126, 185, 114, 251, 211, 210];
+const MARKET_METADATA_LOG_DISCRIMINANT: [u8; 8] = [246, 238, 32, 82, 207, 212, 202, 136];
+const PLACE_TRIGGER_ORDER_LOG_DISCRIMINANT: [u8; 8] = [198, 232, 87, 116, 250, 4, 149, 87];
+const ACTIVATE_TRIGGER_ORDER_LOG_DISCRIMINANT: [u8; 8] = [53, 147, 155, 203, 88, 11, 32, 193];
+const MARKET_AUTHORITY_LOG_DISCRIMINANT: [u8; 8] = [5, 216, 36, 161, 27, 55, 151, 248];
 
 discriminant!(
     CreateMarketLog,
@@ -256,6 +375,16 @@ discriminant!(
 discriminant!(DepositLog, DEPOSIT_LOG_DISCRIMINANT, test_deposit_log);
 discriminant!(WithdrawLog, WITHDRAW_LOG_DISCRIMINANT, test_withdraw_log);
 discriminant!(FillLog, FILL_LOG_DISCRIMINANT, test_fill_log);
+discriminant!(
+    FillSettlementLog,
+    FILL_SETTLEMENT_LOG_DISCRIMINANT,
+    test_fill_settlement_log
+);
+discriminant!(
+    TakerSettlementLog,
+    TAKER_SETTLEMENT_LOG_DISCRIMINANT,
+    test_taker_settlement_log
+);
 discriminant!(
     PlaceOrderLog,
     PLACE_ORDER_LOG_DISCRIMINANT,
@@ -321,3 +450,38 @@ discriminant!(
     COMMIT_MARKET_LOG_DISCRIMINANT,
     test_commit_market_log
 );
+discriminant!(
+    ReleaseSeatLog,
+    RELEASE_SEAT_LOG_DISCRIMINANT,
+    test_release_seat_log
+);
+discriminant!(
+    ModifyOrderLog,
+    MODIFY_ORDER_LOG_DISCRIMINANT,
+    test_modify_order_log
+);
+discriminant!(
+    CompactMarketLog,
+    COMPACT_MARKET_LOG_DISCRIMINANT,
+    test_compact_market_log
+);
+discriminant!(
+    MarketMetadataLog,
+    MARKET_METADATA_LOG_DISCRIMINANT,
+    test_market_metadata_log
+);
+discriminant!(
+    PlaceTriggerOrderLog,
+    PLACE_TRIGGER_ORDER_LOG_DISCRIMINANT,
+    test_place_trigger_order_log
+);
+discriminant!(
+    ActivateTriggerOrderLog,
+    ACTIVATE_TRIGGER_ORDER_LOG_DISCRIMINANT,
+    test_activate_trigger_order_log
+);
+discriminant!(
+    MarketAuthorityLog,
+    MARKET_AUTHORITY_LOG_DISCRIMINANT,
+    test_market_authority_log
+);
@@ -48,6 +48,30 @@ pub enum ManifestError {
     InvalidEvict = 20,
     #[error("Tried to clean order that was not eligible to be cleaned")]
     InvalidClean = 21,
+    #[error("Cannot release a seat that still has balances or open orders")]
+    SeatNotEmpty = 22,
+    #[error("Invalid modify order params")]
+    InvalidModify = 23,
+    #[error("Order did not fill the required minimum")]
+    MinimumFillNotMet = 24,
+    #[error("Order is below the market minimum size or off the price tick")]
+    InvalidOrderSizeOrTick = 25,
+    #[error("Invalid market metadata")]
+    InvalidMarketMetadata = 26,
+    #[error("Signer is not the market metadata authority")]
+    InvalidMarketMetadataAuthority = 27,
+    #[error("Oracle price is stale, not trading, or unreadable")]
+    InvalidOraclePrice = 28,
+    #[error("Execution price is outside the oracle price band")]
+    PriceOutsideOracleBand = 29,
+    #[error("Pegged order needs a peg oracle price from this slot")]
+    MissingPegOraclePrice = 30,
+    #[error("Invalid trigger order params")]
+    InvalidTriggerOrder = 31,
+    #[error("Market is delegated to an ephemeral rollup, send to the rollup or undelegate first")]
+    MarketDelegated = 32,
+    #[error("Signer is not the market authority")]
+    InvalidMarketAuthority = 33,
 }
 
 impl From<ManifestError> for ProgramError {
@@ -57,13 +57,16 @@ pub enum ManifestInstruction {
     #[account(10, optional, name = "quote_mint", desc = "Quote mint, only included if base is Token22, otherwise not required")]
     #[account(11, writable, optional, name = "global", desc = "Global account")]
     #[account(12, writable, optional, name = "global_vault", desc = "Global vault")]
+    #[account(13, optional, name = "oracle", desc = "Pyth or Switchboard price account, last account and only included with an oracle price band")]
     Swap = 4,
 
     /// Expand a market.
     /// 
     /// This is not used in normal operations because expansion happens within
     /// instructions that could require it.
-    /// This is useful for when rent payer != transaction signer.
+    /// This is useful for when rent payer != transaction signer, or to
+    /// provision many blocks ahead of time so trading never has to pay for
+    /// expansion.
     #[account(0, writable, signer, name = "payer", desc = "Payer")]
     #[account(1, writable, name = "market", desc = "Account holding all market state")]
     #[account(2, name = "system_program", desc = "System program")]
@@ -161,10 +164,14 @@ pub enum ManifestInstruction {
     #[account(11, optional, name = "quote_mint", desc = "Quote mint, only included if base is Token22, otherwise not required")]
     #[account(12, writable, optional, name = "global", desc = "Global account")]
     #[account(13, writable, optional, name = "global_vault", desc = "Global vault")]
+    #[account(14, optional, name = "oracle", desc = "Pyth or Switchboard price account, last account and only included with an oracle price band")]
     SwapV2 = 13,
 
-    /// Delegate market account and vaults to ephemeral rollup
-    #[account(0, writable, signer, name = "payer", desc = "Payer")]
+    /// Delegate market account and vaults to ephemeral rollup. Only the market
+    /// authority can delegate. Market globals of the market to delegate along
+    /// with it follow the listed accounts, each one followed by its delegation
+    /// buffer, record and metadata. The market records which ones went along.
+    #[account(0, writable, signer, name = "payer", desc = "Payer, must be the market authority")]
     #[account(1, name = "system_program", desc = "System program")]
     #[account(2, writable, name = "market", desc = "Market account to delegate")]
     #[account(3, name = "owner_program", desc = "Owner program (Manifest)")]
@@ -184,7 +191,10 @@ pub enum ManifestInstruction {
     #[account(17, name = "delegation_program", desc = "MagicBlock delegation program")]
     DelegateMarket = 14,
 
-    /// Undelegate market account and vaults from ephemeral rollup
+    /// Undelegate market account and vaults from ephemeral rollup. Only the
+    /// market authority can undelegate until the delegation time limit runs
+    /// out, then anyone can. Every market global delegated with the market
+    /// follows the listed accounts.
     #[account(0, writable, signer, name = "payer", desc = "Payer")]
     #[account(1, writable, name = "market", desc = "Market account to undelegate")]
     #[account(2, writable, name = "base_vault", desc = "Base vault PDA to undelegate")]
@@ -193,7 +203,9 @@ pub enum ManifestInstruction {
     #[account(5, name = "magic_program", desc = "MagicBlock program")]
     UndelegateMarket = 15,
 
-    /// Commit market and vault state to base layer without undelegating
+    /// Commit market and vault state to base layer without undelegating.
+    /// Every market global delegated with the market follows the listed
+    /// accounts.
     #[account(0, writable, signer, name = "payer", desc = "Payer")]
     #[account(1, writable, name = "market", desc = "Market account to commit")]
     #[account(2, writable, name = "base_vault", desc = "Base vault PDA to commit")]
@@ -201,6 +213,176 @@ pub enum ManifestInstruction {
     #[account(4, name = "magic_context", desc = "MagicBlock context account")]
     #[account(5, name = "magic_program", desc = "MagicBlock program")]
     CommitMarket = 16,
+
+    /// Release a seat back to the market. The trader must have withdrawn all
+    /// funds and have no resting orders.
+    #[account(0, writable, signer, name = "payer", desc = "Payer, trader whose seat is released")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    ReleaseSeat = 17,
+
+    /// Cancel all of a trader's resting orders, optionally only on one side.
+    /// Global accounts are optional and only needed to return gas prepayments
+    /// on global orders.
+    #[account(0, writable, signer, name = "payer", desc = "Payer")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    #[account(2, name = "system_program", desc = "System program")]
+    #[account(3, optional, name = "base_mint", desc = "Mint for the base global account")]
+    #[account(4, optional, writable, name = "base_global", desc = "Base global account")]
+    #[account(5, optional, name = "base_global_vault", desc = "Base global vault")]
+    #[account(6, optional, name = "base_market_vault", desc = "Base market vault")]
+    #[account(7, optional, name = "base_token_program", desc = "Token program(22)")]
+    #[account(8, optional, name = "quote_mint", desc = "Mint for this global account")]
+    #[account(9, optional, writable, name = "quote_global", desc = "Quote global account")]
+    #[account(10, optional, name = "quote_global_vault", desc = "Quote global vault")]
+    #[account(11, optional, name = "quote_market_vault", desc = "Quote market vault")]
+    #[account(12, optional, name = "quote_token_program", desc = "Token program(22)")]
+    CancelAll = 18,
+
+    /// Remove expired orders that are not global and return the funds to the
+    /// owners. Checks the given order indices and the top of each side.
+    #[account(0, writable, signer, name = "payer", desc = "Payer")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    CleanExpired = 19,
+
+    /// Move live blocks from the end of the market into free blocks, shrink
+    /// the account and send the rent that is no longer needed to the
+    /// receiver. Moved orders and seats get new indices. Only the market
+    /// authority can compact.
+    #[account(0, writable, signer, name = "payer", desc = "Payer, must be the market authority")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    #[account(2, writable, name = "receiver", desc = "Receives the excess lamports")]
+    CompactMarket = 20,
+
+    /// Create the metadata account for a market. Only the market authority
+    /// can, and it becomes the metadata authority, which only controls the
+    /// display fields.
+    #[account(0, writable, signer, name = "payer", desc = "Payer, must be the market authority")]
+    #[account(1, name = "market", desc = "Account holding all market state")]
+    #[account(2, writable, name = "market_metadata", desc = "Market metadata PDA, seeds are [b'market-metadata', market]")]
+    #[account(3, name = "system_program", desc = "System program")]
+    InitMarketMetadata = 21,
+
+    /// Update the name, symbol, uri and display params of a market.
+    #[account(0, signer, name = "authority", desc = "Metadata authority")]
+    #[account(1, writable, name = "market_metadata", desc = "Market metadata PDA, seeds are [b'market-metadata', market]")]
+    UpdateMarketMetadata = 22,
+
+    /// Hand the metadata authority to a new key.
+    #[account(0, signer, name = "authority", desc = "Metadata authority")]
+    #[account(1, writable, name = "market_metadata", desc = "Market metadata PDA, seeds are [b'market-metadata', market]")]
+    TransferMarketMetadataAuthority = 23,
+
+    /// Place a stop, take profit or trailing stop order. It waits in the
+    /// trigger tree without reserving funds until a crank sees the price cross
+    /// the trigger. Takes a gas deposit that pays the cranker.
+    #[account(0, writable, signer, name = "payer", desc = "Payer, trader placing the order")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    #[account(2, name = "system_program", desc = "System program")]
+    PlaceTriggerOrder = 24,
+
+    /// Cancel a trigger order that has not triggered and get the gas deposit
+    /// back.
+    #[account(0, writable, signer, name = "payer", desc = "Payer, trader who placed the order")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    CancelTriggerOrder = 25,
+
+    /// Check the given trigger orders against the last trade price and the peg
+    /// oracle. Triggered orders are placed for their traders, expired and
+    /// unfunded ones are removed. The payer gets the gas deposit of each.
+    #[account(0, writable, signer, name = "payer", desc = "Payer, receives the gas deposits")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    #[account(2, name = "system_program", desc = "System program")]
+    #[account(3, optional, name = "oracle", desc = "Market peg oracle, to reprice pegged orders and trigger on the oracle price")]
+    CrankTriggerOrders = 26,
+
+    /// Opt a market in to on-chain trading stats, cumulative base volume and
+    /// the open, high and low of the current epoch. Permissionless, the payer
+    /// covers the rent for the block they take.
+    #[account(0, writable, signer, name = "payer", desc = "Payer")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    #[account(2, name = "system_program", desc = "System program")]
+    InitMarketStats = 27,
+
+    /// Bring the market TWAP accumulator up to the current slot without a
+    /// fill. Permissionless. Not required for correct TWAPs, since readers can
+    /// extrapolate from the last update, but keeps the stored value fresh.
+    #[account(0, signer, name = "payer", desc = "Payer")]
+    #[account(1, writable, name = "market", desc = "Account holding all market state")]
+    PokeTwap = 28,
+
+    /// Create the market global that a market keeps for one of its mints.
+    /// It works like the global for the mint, but holds its balances in the
+    /// market vault, so global orders backed by it also fill while the market
+    /// is delegated to an ephemeral rollup. Permissionless.
+    #[account(0, writable, signer, name = "payer", desc = "Payer")]
+    #[account(1, writable, name = "market_global", desc = "Market global PDA, seeds are [b'market-global', market, mint]")]
+    #[account(2, name = "system_program", desc = "System program")]
+    #[account(3, name = "market", desc = "Account holding all market state")]
+    #[account(4, name = "mint", desc = "Base or quote mint of the market")]
+    MarketGlobalCreate = 29,
+
+    /// Move part of the payer balance on the global for a mint to the market
+    /// global, taking a seat on it if needed. Only while the market is on the
+    /// base layer.
+    #[account(0, writable, signer, name = "payer", desc = "Payer")]
+    #[account(1, name = "market", desc = "Account holding all market state")]
+    #[account(2, name = "system_program", desc = "System program")]
+    #[account(3, writable, name = "global", desc = "Global account")]
+    #[account(4, writable, name = "market_global", desc = "Market global PDA, seeds are [b'market-global', market, mint]")]
+    #[account(5, name = "mint", desc = "Mint for these global accounts")]
+    #[account(6, writable, name = "global_vault", desc = "Global vault")]
+    #[account(7, writable, name = "market_vault", desc = "Market vault PDA, seeds are [b'vault', market, mint]")]
+    #[account(8, name = "token_program", desc = "Token program(22)")]
+    MarketGlobalDeposit = 30,
+
+    /// Move part of the payer balance on the market global back to the global
+    /// for the mint, once the market is back on the base layer.
+    #[account(0, writable, signer, name = "payer", desc = "Payer")]
+    #[account(1, name = "market", desc = "Account holding all market state")]
+    #[account(2, name = "system_program", desc = "System program")]
+    #[account(3, writable, name = "global", desc = "Global account")]
+    #[account(4, writable, name = "market_global", desc = "Market global PDA, seeds are [b'market-global', market, mint]")]
+    #[account(5, name = "mint", desc = "Mint for these global accounts")]
+    #[account(6, writable, name = "global_vault", desc = "Global vault")]
+    #[account(7, writable, name = "market_vault", desc = "Market vault PDA, seeds are [b'vault', market, mint]")]
+    #[account(8, name = "token_program", desc = "Token program(22)")]
+    MarketGlobalWithdraw = 31,
+
+    /// Allocate a seat for a trader while someone else pays the rent. Same as
+    /// ClaimSeat with the trader separate from the payer.
+    #[account(0, writable, signer, name = "payer", desc = "Payer of the rent")]
+    #[account(1, signer, name = "owner", desc = "Trader the seat is for")]
+    #[account(2, writable, name = "market", desc = "Account holding all market state")]
+    #[account(3, name = "system_program", desc = "System program")]
+    ClaimSeatV2 = 32,
+
+    /// Batch update for a trader while someone else pays the rent and the
+    /// global order gas prepayments. Same as BatchUpdate with the trader
+    /// separate from the payer.
+    #[account(0, writable, signer, name = "payer", desc = "Payer of the rent and gas prepayments")]
+    #[account(1, signer, name = "owner", desc = "Trader the orders are for")]
+    #[account(2, writable, name = "market", desc = "Account holding all market state")]
+    #[account(3, name = "system_program", desc = "System program")]
+    #[account(4, optional, name = "base_mint", desc = "Mint for the base global account")]
+    #[account(5, optional, writable, name = "base_global", desc = "Base global account")]
+    #[account(6, optional, name = "base_global_vault", desc = "Base global vault")]
+    #[account(7, optional, name = "base_market_vault", desc = "Base market vault")]
+    #[account(8, optional, name = "base_token_program", desc = "Token program(22)")]
+    #[account(9, optional, name = "quote_mint", desc = "Mint for this global account")]
+    #[account(10, optional, writable, name = "quote_global", desc = "Quote global account")]
+    #[account(11, optional, name = "quote_global_vault", desc = "Quote global vault")]
+    #[account(12, optional, name = "quote_market_vault", desc = "Quote market vault")]
+    #[account(13, optional, name = "quote_token_program", desc = "Token program(22)")]
+    BatchUpdateV2 = 33,
+
+    /// Hand the market authority, which can create the market metadata,
+    /// compact the market and delegate it, to a new key. Markets start without
+    /// one and the market keypair signs as the authority to set the first one.
+    #[account(0, writable, signer, name = "payer", desc = "Payer for the block the first authority takes")]
+    #[account(1, signer, name = "authority", desc = "Market authority, or the market itself when it has none")]
+    #[account(2, writable, name = "market", desc = "Account holding all market state")]
+    #[account(3, name = "system_program", desc = "System program")]
+    SetMarketAuthority = 34,
 }
 
 impl ManifestInstruction {
@@ -211,7 +393,7 @@ impl ManifestInstruction {
 
 #[test]
 fn test_instruction_serialization() {
-    let num_instructions: u8 = 16;
+    let num_instructions: u8 = 34;
     for i in 0..=255 {
         let instruction: ManifestInstruction = match ManifestInstruction::try_from(i) {
             Ok(j) => {
@@ -3,10 +3,12 @@ use crate::program::batch_update::{CancelOrderParams, PlaceOrderParams};
 #[cfg(not(feature = "certora"))]
 use crate::{
     program::{
-        batch_update::{BatchUpdateParams, CancelOrderParams, PlaceOrderParams},
+        batch_update::{BatchUpdateParams, CancelOrderParams, ModifyOrderParams, PlaceOrderParams},
         ManifestInstruction,
     },
-    validation::{get_global_address, get_global_vault_address, get_vault_address},
+    validation::{
+        get_global_address, get_global_vault_address, get_market_global_address, get_vault_address,
+    },
 };
 #[cfg(not(feature = "certora"))]
 use borsh::BorshSerialize;
@@ -34,6 +36,170 @@ pub fn batch_update_instruction(
     quote_mint_opt: Option<Pubkey>,
     quote_mint_token_program_opt: Option<Pubkey>,
 ) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: batch_update_account_metas(
+            market,
+            payer,
+            base_mint_opt,
+            base_mint_token_program_opt,
+            quote_mint_opt,
+            quote_mint_token_program_opt,
+        ),
+        data: [
+            ManifestInstruction::BatchUpdate.to_vec(),
+            BatchUpdateParams::new(trader_index_hint, cancels, orders)
+                .try_to_vec()
+                .unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+// Same as batch_update_instruction, with modifies applied between the cancels
+// and the orders.
+#[cfg(not(feature = "certora"))]
+#[allow(clippy::too_many_arguments)]
+pub fn batch_update_with_modifies_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    trader_index_hint: Option<DataIndex>,
+    cancels: Vec<CancelOrderParams>,
+    modifies: Vec<ModifyOrderParams>,
+    orders: Vec<PlaceOrderParams>,
+    base_mint_opt: Option<Pubkey>,
+    base_mint_token_program_opt: Option<Pubkey>,
+    quote_mint_opt: Option<Pubkey>,
+    quote_mint_token_program_opt: Option<Pubkey>,
+) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: batch_update_account_metas(
+            market,
+            payer,
+            base_mint_opt,
+            base_mint_token_program_opt,
+            quote_mint_opt,
+            quote_mint_token_program_opt,
+        ),
+        data: [
+            ManifestInstruction::BatchUpdate.to_vec(),
+            BatchUpdateParams::new_with_modifies(trader_index_hint, cancels, modifies, orders)
+                .try_to_vec()
+                .unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+// Same as batch_update_instruction, but global orders are backed by the
+// market globals of the market instead of the globals for the mints. Use this
+// while the market is delegated to an ephemeral rollup.
+#[cfg(not(feature = "certora"))]
+#[allow(clippy::too_many_arguments)]
+pub fn batch_update_with_market_globals_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    trader_index_hint: Option<DataIndex>,
+    cancels: Vec<CancelOrderParams>,
+    orders: Vec<PlaceOrderParams>,
+    base_mint_opt: Option<Pubkey>,
+    base_mint_token_program_opt: Option<Pubkey>,
+    quote_mint_opt: Option<Pubkey>,
+    quote_mint_token_program_opt: Option<Pubkey>,
+) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: batch_update_account_metas_with_globals(
+            market,
+            payer,
+            base_mint_opt,
+            base_mint_token_program_opt,
+            quote_mint_opt,
+            quote_mint_token_program_opt,
+            true,
+        ),
+        data: [
+            ManifestInstruction::BatchUpdate.to_vec(),
+            BatchUpdateParams::new(trader_index_hint, cancels, orders)
+                .try_to_vec()
+                .unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+// Same as batch_update_instruction, but the payer only covers the rent and
+// global order gas prepayments. The orders are for the owner.
+#[cfg(not(feature = "certora"))]
+#[allow(clippy::too_many_arguments)]
+pub fn batch_update_v2_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    owner: &Pubkey,
+    trader_index_hint: Option<DataIndex>,
+    cancels: Vec<CancelOrderParams>,
+    orders: Vec<PlaceOrderParams>,
+    base_mint_opt: Option<Pubkey>,
+    base_mint_token_program_opt: Option<Pubkey>,
+    quote_mint_opt: Option<Pubkey>,
+    quote_mint_token_program_opt: Option<Pubkey>,
+) -> Instruction {
+    let mut account_metas: Vec<AccountMeta> = batch_update_account_metas(
+        market,
+        payer,
+        base_mint_opt,
+        base_mint_token_program_opt,
+        quote_mint_opt,
+        quote_mint_token_program_opt,
+    );
+    account_metas.insert(1, AccountMeta::new_readonly(*owner, true));
+    Instruction {
+        program_id: crate::id(),
+        accounts: account_metas,
+        data: [
+            ManifestInstruction::BatchUpdateV2.to_vec(),
+            BatchUpdateParams::new(trader_index_hint, cancels, orders)
+                .try_to_vec()
+                .unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+// Account layout shared by instructions that load BatchUpdateContext.
+#[cfg(not(feature = "certora"))]
+pub(crate) fn batch_update_account_metas(
+    market: &Pubkey,
+    payer: &Pubkey,
+    base_mint_opt: Option<Pubkey>,
+    base_mint_token_program_opt: Option<Pubkey>,
+    quote_mint_opt: Option<Pubkey>,
+    quote_mint_token_program_opt: Option<Pubkey>,
+) -> Vec<AccountMeta> {
+    batch_update_account_metas_with_globals(
+        market,
+        payer,
+        base_mint_opt,
+        base_mint_token_program_opt,
+        quote_mint_opt,
+        quote_mint_token_program_opt,
+        false,
+    )
+}
+
+// A market global keeps its balances in the market vault, which then also
+// goes in the spot of the global vault.
+#[cfg(not(feature = "certora"))]
+fn batch_update_account_metas_with_globals(
+    market: &Pubkey,
+    payer: &Pubkey,
+    base_mint_opt: Option<Pubkey>,
+    base_mint_token_program_opt: Option<Pubkey>,
+    quote_mint_opt: Option<Pubkey>,
+    quote_mint_token_program_opt: Option<Pubkey>,
+    use_market_globals: bool,
+) -> Vec<AccountMeta> {
     let mut account_metas: Vec<AccountMeta> = vec![
         AccountMeta::new(*payer, true),
         AccountMeta::new(*market, false),
@@ -44,9 +210,15 @@ pub fn batch_update_instruction(
         (quote_mint_opt, quote_mint_token_program_opt),
     ] {
         if let Some(mint) = mint_opt {
-            let (global, _) = get_global_address(&mint);
-            let (global_vault, _) = get_global_vault_address(&mint);
             let (market_vault, _) = get_vault_address(market, &mint);
+            let (global, global_vault) = if use_market_globals {
+                (get_market_global_address(market, &mint).0, market_vault)
+            } else {
+                (
+                    get_global_address(&mint).0,
+                    get_global_vault_address(&mint).0,
+                )
+            };
             let mut global_account_metas: Vec<AccountMeta> = vec![
                 AccountMeta::new_readonly(mint, false),
                 AccountMeta::new(global, false),
@@ -63,18 +235,7 @@ pub fn batch_update_instruction(
             account_metas.extend(global_account_metas);
         }
     }
-
-    Instruction {
-        program_id: crate::id(),
-        accounts: account_metas,
-        data: [
-            ManifestInstruction::BatchUpdate.to_vec(),
-            BatchUpdateParams::new(trader_index_hint, cancels, orders)
-                .try_to_vec()
-                .unwrap(),
-        ]
-        .concat(),
-    }
+    account_metas
 }
 
 #[cfg(feature = "certora")]
@@ -0,0 +1,45 @@
+#[cfg(not(feature = "certora"))]
+use crate::program::{
+    batch_update_instruction::batch_update_account_metas, cancel_all::CancelAllParams,
+    ManifestInstruction,
+};
+#[cfg(not(feature = "certora"))]
+use borsh::BorshSerialize;
+#[cfg(not(feature = "certora"))]
+use hypertree::DataIndex;
+#[cfg(not(feature = "certora"))]
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+
+// Global accounts are only needed to get back gas prepayments on global
+// orders. Without them, the prepayment stays on the global account.
+#[cfg(not(feature = "certora"))]
+#[allow(clippy::too_many_arguments)]
+pub fn cancel_all_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    trader_index_hint: Option<DataIndex>,
+    is_bid_opt: Option<bool>,
+    base_mint_opt: Option<Pubkey>,
+    base_mint_token_program_opt: Option<Pubkey>,
+    quote_mint_opt: Option<Pubkey>,
+    quote_mint_token_program_opt: Option<Pubkey>,
+) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: batch_update_account_metas(
+            market,
+            payer,
+            base_mint_opt,
+            base_mint_token_program_opt,
+            quote_mint_opt,
+            quote_mint_token_program_opt,
+        ),
+        data: [
+            ManifestInstruction::CancelAll.to_vec(),
+            CancelAllParams::new(trader_index_hint, is_bid_opt)
+                .try_to_vec()
+                .unwrap(),
+        ]
+        .concat(),
+    }
+}
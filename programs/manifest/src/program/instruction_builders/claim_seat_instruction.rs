@@ -16,3 +16,18 @@ pub fn claim_seat_instruction(market: &Pubkey, payer: &Pubkey) -> Instruction {
         data: [ManifestInstruction::ClaimSeat.to_vec()].concat(),
     }
 }
+
+// Same as claim_seat_instruction, but the payer only covers the rent. The seat
+// is for the owner.
+pub fn claim_seat_v2_instruction(market: &Pubkey, payer: &Pubkey, owner: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: [ManifestInstruction::ClaimSeatV2.to_vec()].concat(),
+    }
+}
@@ -0,0 +1,29 @@
+use crate::program::{clean_expired::CleanExpiredParams, ManifestInstruction};
+use borsh::BorshSerialize;
+use hypertree::DataIndex;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+pub fn clean_expired_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    order_indices: Vec<DataIndex>,
+    num_orders_to_scan: u16,
+) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*market, false),
+        ],
+        data: [
+            ManifestInstruction::CleanExpired.to_vec(),
+            CleanExpiredParams::new(order_indices, num_orders_to_scan)
+                .try_to_vec()
+                .unwrap(),
+        ]
+        .concat(),
+    }
+}
@@ -0,0 +1,21 @@
+use crate::program::ManifestInstruction;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+pub fn compact_market_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    receiver: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(*receiver, false),
+        ],
+        data: [ManifestInstruction::CompactMarket.to_vec()].concat(),
+    }
+}
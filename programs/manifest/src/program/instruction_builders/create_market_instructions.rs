@@ -1,6 +1,10 @@
 use crate::{
-    program::ManifestInstruction, state::MarketFixed, validation::get_vault_address, ProgramError,
+    program::{create_market::CreateMarketParams, ManifestInstruction},
+    state::MarketFixed,
+    validation::get_vault_address,
+    ProgramError,
 };
+use borsh::BorshSerialize;
 use solana_program::{
     instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
@@ -28,6 +32,27 @@ pub fn create_market_instructions(
     ])
 }
 
+/// Creates the account and populates it with rent. The market enforces a
+/// minimum order size and price tick.
+pub fn create_market_with_params_instructions(
+    market: &Pubkey,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+    market_creator: &Pubkey,
+    params: CreateMarketParams,
+) -> Result<Vec<Instruction>, ProgramError> {
+    let mut instructions: Vec<Instruction> =
+        create_market_instructions(market, base_mint, quote_mint, market_creator)?;
+    instructions[1] = create_market_with_params_instruction(
+        market,
+        base_mint,
+        quote_mint,
+        market_creator,
+        params,
+    );
+    Ok(instructions)
+}
+
 pub fn create_market_instruction(
     market: &Pubkey,
     base_mint: &Pubkey,
@@ -52,3 +77,18 @@ pub fn create_market_instruction(
         data: [ManifestInstruction::CreateMarket.to_vec()].concat(),
     }
 }
+
+pub fn create_market_with_params_instruction(
+    market: &Pubkey,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+    market_creator: &Pubkey,
+    params: CreateMarketParams,
+) -> Instruction {
+    let mut instruction: Instruction =
+        create_market_instruction(market, base_mint, quote_mint, market_creator);
+    instruction
+        .data
+        .extend_from_slice(&params.try_to_vec().unwrap());
+    instruction
+}
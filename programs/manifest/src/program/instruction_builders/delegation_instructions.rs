@@ -0,0 +1,129 @@
+use crate::{
+    program::{delegate_market::DelegateMarketParams, ManifestInstruction},
+    validation::{
+        get_delegation_addresses, get_market_global_address, get_vault_address,
+        DELEGATION_PROGRAM_ID, MAGIC_CONTEXT_ID, MAGIC_PROGRAM_ID,
+    },
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+
+// Market globals for the mints in market_global_mints go to the rollup with
+// the market. They need to have been created with MarketGlobalCreate.
+pub fn delegate_market_instruction(
+    market: &Pubkey,
+    authority: &Pubkey,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+    market_global_mints: &[Pubkey],
+    params: DelegateMarketParams,
+) -> Instruction {
+    let (base_vault, _) = get_vault_address(market, base_mint);
+    let (quote_vault, _) = get_vault_address(market, quote_mint);
+    let (market_buffer, market_record, market_delegation_metadata) =
+        get_delegation_addresses(market);
+    let (base_vault_buffer, base_vault_record, base_vault_delegation_metadata) =
+        get_delegation_addresses(&base_vault);
+    let (quote_vault_buffer, quote_vault_record, quote_vault_delegation_metadata) =
+        get_delegation_addresses(&quote_vault);
+    let mut account_metas: Vec<AccountMeta> = vec![
+        AccountMeta::new(*authority, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(*market, false),
+        AccountMeta::new_readonly(crate::id(), false),
+        AccountMeta::new(base_vault, false),
+        AccountMeta::new(quote_vault, false),
+        AccountMeta::new_readonly(*base_mint, false),
+        AccountMeta::new_readonly(*quote_mint, false),
+        AccountMeta::new(market_buffer, false),
+        AccountMeta::new(market_record, false),
+        AccountMeta::new(market_delegation_metadata, false),
+        AccountMeta::new(base_vault_buffer, false),
+        AccountMeta::new(base_vault_record, false),
+        AccountMeta::new(base_vault_delegation_metadata, false),
+        AccountMeta::new(quote_vault_buffer, false),
+        AccountMeta::new(quote_vault_record, false),
+        AccountMeta::new(quote_vault_delegation_metadata, false),
+        AccountMeta::new_readonly(DELEGATION_PROGRAM_ID, false),
+    ];
+    for mint in market_global_mints {
+        let (market_global, _) = get_market_global_address(market, mint);
+        let (market_global_buffer, market_global_record, market_global_delegation_metadata) =
+            get_delegation_addresses(&market_global);
+        account_metas.extend([
+            AccountMeta::new(market_global, false),
+            AccountMeta::new(market_global_buffer, false),
+            AccountMeta::new(market_global_record, false),
+            AccountMeta::new(market_global_delegation_metadata, false),
+        ]);
+    }
+    Instruction {
+        program_id: crate::id(),
+        accounts: account_metas,
+        data: [
+            ManifestInstruction::DelegateMarket.to_vec(),
+            params.try_to_vec().unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+pub fn undelegate_market_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+    market_global_mints: &[Pubkey],
+) -> Instruction {
+    let (base_vault, _) = get_vault_address(market, base_mint);
+    let (quote_vault, _) = get_vault_address(market, quote_mint);
+    let mut account_metas: Vec<AccountMeta> = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(*market, false),
+        AccountMeta::new(base_vault, false),
+        AccountMeta::new(quote_vault, false),
+        AccountMeta::new(MAGIC_CONTEXT_ID, false),
+        AccountMeta::new_readonly(MAGIC_PROGRAM_ID, false),
+    ];
+    for mint in market_global_mints {
+        let (market_global, _) = get_market_global_address(market, mint);
+        account_metas.push(AccountMeta::new(market_global, false));
+    }
+    Instruction {
+        program_id: crate::id(),
+        accounts: account_metas,
+        data: [ManifestInstruction::UndelegateMarket.to_vec()].concat(),
+    }
+}
+
+pub fn commit_market_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+    market_global_mints: &[Pubkey],
+) -> Instruction {
+    let (base_vault, _) = get_vault_address(market, base_mint);
+    let (quote_vault, _) = get_vault_address(market, quote_mint);
+    let mut account_metas: Vec<AccountMeta> = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(*market, false),
+        AccountMeta::new(base_vault, false),
+        AccountMeta::new(quote_vault, false),
+        AccountMeta::new(MAGIC_CONTEXT_ID, false),
+        AccountMeta::new_readonly(MAGIC_PROGRAM_ID, false),
+    ];
+    for mint in market_global_mints {
+        let (market_global, _) = get_market_global_address(market, mint);
+        account_metas.push(AccountMeta::new(market_global, false));
+    }
+    Instruction {
+        program_id: crate::id(),
+        accounts: account_metas,
+        data: [ManifestInstruction::CommitMarket.to_vec()].concat(),
+    }
+}
@@ -1,4 +1,5 @@
-use crate::program::ManifestInstruction;
+use crate::program::{expand_market::ExpandMarketParams, ManifestInstruction};
+use borsh::BorshSerialize;
 use solana_program::{
     instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
@@ -16,3 +17,15 @@ pub fn expand_market_instruction(market: &Pubkey, payer: &Pubkey) -> Instruction
         data: [ManifestInstruction::Expand.to_vec()].concat(),
     }
 }
+
+pub fn expand_market_by_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    num_blocks: u32,
+) -> Instruction {
+    let mut instruction: Instruction = expand_market_instruction(market, payer);
+    instruction
+        .data
+        .extend_from_slice(&ExpandMarketParams::new(num_blocks).try_to_vec().unwrap());
+    instruction
+}
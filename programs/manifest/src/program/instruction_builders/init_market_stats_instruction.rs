@@ -0,0 +1,18 @@
+use crate::program::ManifestInstruction;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+
+pub fn init_market_stats_instruction(market: &Pubkey, payer: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: [ManifestInstruction::InitMarketStats.to_vec()].concat(),
+    }
+}
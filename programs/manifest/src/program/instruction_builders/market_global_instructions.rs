@@ -0,0 +1,97 @@
+use crate::{
+    program::{
+        market_global_deposit::MarketGlobalDepositParams,
+        market_global_withdraw::MarketGlobalWithdrawParams, ManifestInstruction,
+    },
+    validation::{
+        get_global_address, get_global_vault_address, get_market_global_address, get_vault_address,
+    },
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+
+pub fn market_global_create_instruction(
+    market: &Pubkey,
+    mint: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let (market_global, _market_global_bump) = get_market_global_address(market, mint);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(market_global, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new_readonly(*mint, false),
+        ],
+        data: [ManifestInstruction::MarketGlobalCreate.to_vec()].concat(),
+    }
+}
+
+pub fn market_global_deposit_instruction(
+    market: &Pubkey,
+    mint: &Pubkey,
+    payer: &Pubkey,
+    token_program: &Pubkey,
+    num_atoms: u64,
+) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: market_global_account_metas(market, mint, payer, token_program),
+        data: [
+            ManifestInstruction::MarketGlobalDeposit.to_vec(),
+            MarketGlobalDepositParams::new(num_atoms)
+                .try_to_vec()
+                .unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+pub fn market_global_withdraw_instruction(
+    market: &Pubkey,
+    mint: &Pubkey,
+    payer: &Pubkey,
+    token_program: &Pubkey,
+    num_atoms: u64,
+) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: market_global_account_metas(market, mint, payer, token_program),
+        data: [
+            ManifestInstruction::MarketGlobalWithdraw.to_vec(),
+            MarketGlobalWithdrawParams::new(num_atoms)
+                .try_to_vec()
+                .unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+fn market_global_account_metas(
+    market: &Pubkey,
+    mint: &Pubkey,
+    payer: &Pubkey,
+    token_program: &Pubkey,
+) -> Vec<AccountMeta> {
+    let (global, _global_bump) = get_global_address(mint);
+    let (market_global, _market_global_bump) = get_market_global_address(market, mint);
+    let (global_vault, _global_vault_bump) = get_global_vault_address(mint);
+    let (market_vault, _market_vault_bump) = get_vault_address(market, mint);
+    vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(*market, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new(global, false),
+        AccountMeta::new(market_global, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(global_vault, false),
+        AccountMeta::new(market_vault, false),
+        AccountMeta::new_readonly(*token_program, false),
+    ]
+}
@@ -0,0 +1,78 @@
+use crate::{
+    program::{
+        init_market_metadata::MarketMetadataParams,
+        transfer_market_metadata_authority::TransferMarketMetadataAuthorityParams,
+        ManifestInstruction,
+    },
+    validation::get_market_metadata_address,
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+
+pub fn init_market_metadata_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    params: MarketMetadataParams,
+) -> Instruction {
+    let (market_metadata, _) = get_market_metadata_address(market);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new(market_metadata, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: [
+            ManifestInstruction::InitMarketMetadata.to_vec(),
+            params.try_to_vec().unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+pub fn update_market_metadata_instruction(
+    market: &Pubkey,
+    authority: &Pubkey,
+    params: MarketMetadataParams,
+) -> Instruction {
+    let (market_metadata, _) = get_market_metadata_address(market);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(market_metadata, false),
+        ],
+        data: [
+            ManifestInstruction::UpdateMarketMetadata.to_vec(),
+            params.try_to_vec().unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+pub fn transfer_market_metadata_authority_instruction(
+    market: &Pubkey,
+    authority: &Pubkey,
+    new_authority: &Pubkey,
+) -> Instruction {
+    let (market_metadata, _) = get_market_metadata_address(market);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(market_metadata, false),
+        ],
+        data: [
+            ManifestInstruction::TransferMarketMetadataAuthority.to_vec(),
+            TransferMarketMetadataAuthorityParams::new(*new_authority)
+                .try_to_vec()
+                .unwrap(),
+        ]
+        .concat(),
+    }
+}
@@ -1,6 +1,10 @@
 pub mod batch_update_instruction;
+pub mod cancel_all_instruction;
 pub mod claim_seat_instruction;
+pub mod clean_expired_instruction;
+pub mod compact_market_instruction;
 pub mod create_market_instructions;
+pub mod delegation_instructions;
 pub mod deposit_instruction;
 pub mod expand_market_instruction;
 pub mod global_add_trader_instruction;
@@ -9,13 +13,24 @@ pub mod global_create_instruction;
 pub mod global_deposit_instruction;
 pub mod global_evict_instruction;
 pub mod global_withdraw_instruction;
+pub mod init_market_stats_instruction;
+pub mod market_global_instructions;
+pub mod market_metadata_instructions;
+pub mod poke_twap_instruction;
+pub mod release_seat_instruction;
+pub mod set_market_authority_instruction;
 pub mod swap_instruction;
 pub mod swap_v2_instruction;
+pub mod trigger_order_instructions;
 pub mod withdraw_instruction;
 
 pub use batch_update_instruction::*;
+pub use cancel_all_instruction::*;
 pub use claim_seat_instruction::*;
+pub use clean_expired_instruction::*;
+pub use compact_market_instruction::*;
 pub use create_market_instructions::*;
+pub use delegation_instructions::*;
 pub use deposit_instruction::*;
 pub use expand_market_instruction::*;
 pub use global_add_trader_instruction::*;
@@ -24,6 +39,13 @@ pub use global_create_instruction::*;
 pub use global_deposit_instruction::*;
 pub use global_evict_instruction::*;
 pub use global_withdraw_instruction::*;
+pub use init_market_stats_instruction::*;
+pub use market_global_instructions::*;
+pub use market_metadata_instructions::*;
+pub use poke_twap_instruction::*;
+pub use release_seat_instruction::*;
+pub use set_market_authority_instruction::*;
 pub use swap_instruction::*;
 pub use swap_v2_instruction::*;
+pub use trigger_order_instructions::*;
 pub use withdraw_instruction::*;
@@ -0,0 +1,16 @@
+use crate::program::ManifestInstruction;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+pub fn poke_twap_instruction(market: &Pubkey, payer: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(*payer, true),
+            AccountMeta::new(*market, false),
+        ],
+        data: [ManifestInstruction::PokeTwap.to_vec()].concat(),
+    }
+}
@@ -0,0 +1,16 @@
+use crate::program::ManifestInstruction;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+pub fn release_seat_instruction(market: &Pubkey, payer: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*market, false),
+        ],
+        data: [ManifestInstruction::ReleaseSeat.to_vec()].concat(),
+    }
+}
@@ -0,0 +1,33 @@
+use crate::program::{set_market_authority::SetMarketAuthorityParams, ManifestInstruction};
+use borsh::BorshSerialize;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+
+/// The authority is the current market authority, or the market itself for
+/// markets that do not have one yet.
+pub fn set_market_authority_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    authority: &Pubkey,
+    new_authority: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: [
+            ManifestInstruction::SetMarketAuthority.to_vec(),
+            SetMarketAuthorityParams::new(*new_authority)
+                .try_to_vec()
+                .unwrap(),
+        ]
+        .concat(),
+    }
+}
@@ -1,5 +1,8 @@
 use crate::{
-    program::{swap::SwapParams, ManifestInstruction},
+    program::{
+        swap::{OraclePriceBandParams, SwapParams},
+        ManifestInstruction,
+    },
     validation::{get_global_address, get_global_vault_address, get_vault_address},
 };
 use borsh::BorshSerialize;
@@ -67,3 +70,55 @@ pub fn swap_v2_instruction(
         .concat(),
     }
 }
+
+/// Same as swap_v2_instruction, but the program rejects the swap if any fill
+/// is outside of the band around the price in the oracle account.
+#[allow(clippy::too_many_arguments)]
+pub fn swap_v2_with_oracle_price_band_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    owner: &Pubkey,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+    trader_base_account: &Pubkey,
+    trader_quote_account: &Pubkey,
+    in_atoms: u64,
+    out_atoms: u64,
+    is_base_in: bool,
+    is_exact_in: bool,
+    token_program_base: Pubkey,
+    token_program_quote: Pubkey,
+    include_global: bool,
+    oracle: &Pubkey,
+    oracle_price_band: OraclePriceBandParams,
+) -> Instruction {
+    let mut instruction: Instruction = swap_v2_instruction(
+        market,
+        payer,
+        owner,
+        base_mint,
+        quote_mint,
+        trader_base_account,
+        trader_quote_account,
+        in_atoms,
+        out_atoms,
+        is_base_in,
+        is_exact_in,
+        token_program_base,
+        token_program_quote,
+        include_global,
+    );
+    // The oracle goes last so it does not get confused with global accounts.
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(*oracle, false));
+    instruction.data = [
+        ManifestInstruction::Swap.to_vec(),
+        SwapParams::new(in_atoms, out_atoms, is_base_in, is_exact_in)
+            .with_oracle_price_band(oracle_price_band)
+            .try_to_vec()
+            .unwrap(),
+    ]
+    .concat();
+    instruction
+}
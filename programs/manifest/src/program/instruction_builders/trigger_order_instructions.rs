@@ -0,0 +1,81 @@
+use crate::program::{
+    cancel_trigger_order::CancelTriggerOrderParams, crank_trigger_orders::CrankTriggerOrdersParams,
+    place_trigger_order::PlaceTriggerOrderParams, ManifestInstruction,
+};
+use borsh::BorshSerialize;
+use hypertree::DataIndex;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+
+pub fn place_trigger_order_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    params: PlaceTriggerOrderParams,
+) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: [
+            ManifestInstruction::PlaceTriggerOrder.to_vec(),
+            params.try_to_vec().unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+pub fn cancel_trigger_order_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    order_sequence_number: u64,
+) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*market, false),
+        ],
+        data: [
+            ManifestInstruction::CancelTriggerOrder.to_vec(),
+            CancelTriggerOrderParams::new(order_sequence_number)
+                .try_to_vec()
+                .unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+/// Include the peg oracle to trigger on the oracle price as well as the last
+/// trade price.
+pub fn crank_trigger_orders_instruction(
+    market: &Pubkey,
+    payer: &Pubkey,
+    order_indices: Vec<DataIndex>,
+    peg_oracle_opt: Option<&Pubkey>,
+) -> Instruction {
+    let mut accounts: Vec<AccountMeta> = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(*market, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    if let Some(peg_oracle) = peg_oracle_opt {
+        accounts.push(AccountMeta::new_readonly(*peg_oracle, false));
+    }
+    Instruction {
+        program_id: crate::id(),
+        accounts,
+        data: [
+            ManifestInstruction::CrankTriggerOrders.to_vec(),
+            CrankTriggerOrdersParams::new(order_indices)
+                .try_to_vec()
+                .unwrap(),
+        ]
+        .concat(),
+    }
+}
@@ -1,24 +1,27 @@
 use std::cell::RefMut;
 
 use crate::{
-    logs::{emit_stack, CancelOrderLog, PlaceOrderLog},
+    logs::{emit_stack, CancelOrderLog, ModifyOrderLog, PlaceOrderLog, TakerSettlementLog},
     program::get_trader_index_with_hint,
     quantities::{BaseAtoms, PriceConversionError, QuoteAtomsPerBaseAtom, WrapperU64},
     require,
     state::{
-        utils::get_now_slot, AddOrderToMarketArgs, AddOrderToMarketResult, MarketRefMut, OrderType,
-        RestingOrder, MARKET_BLOCK_SIZE,
+        utils::get_now_slot, AddOrderToMarketArgs, AddOrderToMarketResult, MarketFixed,
+        MarketRefMut, OrderPeg, OrderType, RestingOrder, SelfTradeBehavior, MARKET_BLOCK_SIZE,
+        NO_EXPIRATION_LAST_VALID_UNIX_TIMESTAMP,
     },
-    validation::loaders::BatchUpdateContext,
+    validation::{loaders::BatchUpdateContext, ManifestAccountInfo, Signer},
 };
 use borsh::{BorshDeserialize, BorshSerialize};
 
-use hypertree::{get_helper, trace, DataIndex, PodBool, RBNode};
+use hypertree::{get_helper, is_not_nil, trace, DataIndex, PodBool, RBNode, NIL};
 use solana_program::{
     account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
     pubkey::Pubkey,
 };
 
+#[cfg(not(feature = "certora"))]
+use super::shared::reprice_pegged_orders_if_oracle_included;
 use super::{expand_market_if_needed, shared::get_mut_dynamic_account};
 
 use crate::validation::loaders::GlobalTradeAccounts;
@@ -56,7 +59,7 @@ impl CancelOrderParams {
     }
 }
 
-#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+#[derive(Debug, Clone)]
 pub struct PlaceOrderParams {
     base_atoms: u64,
     price_mantissa: u32,
@@ -64,6 +67,48 @@ pub struct PlaceOrderParams {
     is_bid: bool,
     last_valid_slot: u32,
     order_type: OrderType,
+    options: PlaceOrderOptions,
+}
+
+/// Order settings that were added after the PlaceOrderParams layout was
+/// deployed. BatchUpdateParams carries them in a trailing vector with one
+/// entry per order. All zero is the behavior from before they existed.
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone, Copy, Default, PartialEq)]
+pub struct PlaceOrderOptions {
+    pub self_trade_behavior: SelfTradeBehavior,
+    pub min_base_atoms_filled: u64,
+    pub last_valid_unix_timestamp: u32,
+    /// Offset from the peg oracle price for pegged orders. The price is the
+    /// limit for those.
+    pub peg_offset_mantissa: i32,
+    pub peg_offset_exponent: i8,
+}
+
+// Only the original fields are on the wire so that existing clients and
+// wrapper CPIs keep working. Options travel in BatchUpdateParams.
+impl BorshSerialize for PlaceOrderParams {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.base_atoms.serialize(writer)?;
+        self.price_mantissa.serialize(writer)?;
+        self.price_exponent.serialize(writer)?;
+        self.is_bid.serialize(writer)?;
+        self.last_valid_slot.serialize(writer)?;
+        self.order_type.serialize(writer)
+    }
+}
+
+impl BorshDeserialize for PlaceOrderParams {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        Ok(PlaceOrderParams {
+            base_atoms: BorshDeserialize::deserialize_reader(reader)?,
+            price_mantissa: BorshDeserialize::deserialize_reader(reader)?,
+            price_exponent: BorshDeserialize::deserialize_reader(reader)?,
+            is_bid: BorshDeserialize::deserialize_reader(reader)?,
+            last_valid_slot: BorshDeserialize::deserialize_reader(reader)?,
+            order_type: BorshDeserialize::deserialize_reader(reader)?,
+            options: PlaceOrderOptions::default(),
+        })
+    }
 }
 
 impl PlaceOrderParams {
@@ -82,8 +127,26 @@ impl PlaceOrderParams {
             is_bid,
             order_type,
             last_valid_slot,
+            options: PlaceOrderOptions::default(),
         }
     }
+    pub fn with_self_trade_behavior(mut self, self_trade_behavior: SelfTradeBehavior) -> Self {
+        self.options.self_trade_behavior = self_trade_behavior;
+        self
+    }
+    pub fn with_min_base_atoms_filled(mut self, min_base_atoms_filled: u64) -> Self {
+        self.options.min_base_atoms_filled = min_base_atoms_filled;
+        self
+    }
+    pub fn with_last_valid_unix_timestamp(mut self, last_valid_unix_timestamp: u32) -> Self {
+        self.options.last_valid_unix_timestamp = last_valid_unix_timestamp;
+        self
+    }
+    pub fn with_peg_offset(mut self, peg_offset_mantissa: i32, peg_offset_exponent: i8) -> Self {
+        self.options.peg_offset_mantissa = peg_offset_mantissa;
+        self.options.peg_offset_exponent = peg_offset_exponent;
+        self
+    }
     pub fn base_atoms(&self) -> u64 {
         self.base_atoms
     }
@@ -103,9 +166,82 @@ impl PlaceOrderParams {
     pub fn order_type(&self) -> OrderType {
         self.order_type
     }
+    pub fn self_trade_behavior(&self) -> SelfTradeBehavior {
+        self.options.self_trade_behavior
+    }
+    pub fn min_base_atoms_filled(&self) -> u64 {
+        self.options.min_base_atoms_filled
+    }
+    pub fn last_valid_unix_timestamp(&self) -> u32 {
+        self.options.last_valid_unix_timestamp
+    }
+    /// Peg for pegged orders, with the order price as the limit.
+    pub fn peg(&self) -> Option<OrderPeg> {
+        if self.order_type != OrderType::Pegged {
+            return None;
+        }
+        Some(OrderPeg::new(
+            self.options.peg_offset_mantissa,
+            self.options.peg_offset_exponent,
+            self.price_mantissa,
+            self.price_exponent,
+        ))
+    }
 }
 
-#[derive(BorshDeserialize, BorshSerialize)]
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct ModifyOrderParams {
+    order_sequence_number: u64,
+    order_index_hint: Option<DataIndex>,
+    base_atoms: u64,
+    price_mantissa: u32,
+    price_exponent: i8,
+    /// Used when the modify places a new order.
+    self_trade_behavior: SelfTradeBehavior,
+}
+
+impl ModifyOrderParams {
+    pub fn new(
+        order_sequence_number: u64,
+        order_index_hint: Option<DataIndex>,
+        base_atoms: u64,
+        price_mantissa: u32,
+        price_exponent: i8,
+    ) -> Self {
+        ModifyOrderParams {
+            order_sequence_number,
+            order_index_hint,
+            base_atoms,
+            price_mantissa,
+            price_exponent,
+            self_trade_behavior: SelfTradeBehavior::default(),
+        }
+    }
+    pub fn with_self_trade_behavior(mut self, self_trade_behavior: SelfTradeBehavior) -> Self {
+        self.self_trade_behavior = self_trade_behavior;
+        self
+    }
+    pub fn order_sequence_number(&self) -> u64 {
+        self.order_sequence_number
+    }
+    pub fn order_index_hint(&self) -> Option<DataIndex> {
+        self.order_index_hint
+    }
+    pub fn base_atoms(&self) -> u64 {
+        self.base_atoms
+    }
+    pub fn self_trade_behavior(&self) -> SelfTradeBehavior {
+        self.self_trade_behavior
+    }
+    pub fn try_price(&self) -> Result<QuoteAtomsPerBaseAtom, PriceConversionError> {
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(
+            self.price_mantissa,
+            self.price_exponent,
+        )
+    }
+}
+
+#[cfg_attr(feature = "certora", derive(BorshSerialize, BorshDeserialize))]
 pub struct BatchUpdateParams {
     /// Optional hint for what index the trader's ClaimedSeat is at.
     pub trader_index_hint: Option<DataIndex>,
@@ -117,6 +253,79 @@ pub struct BatchUpdateParams {
     pub orders: Vec<PlaceOrderParams>,
     #[cfg(feature = "certora")]
     pub orders: NoResizableVec<PlaceOrderParams>,
+    /// Applied after cancels and before orders. Reducing size at the same
+    /// price keeps queue priority, anything else is a cancel and replace.
+    #[cfg(not(feature = "certora"))]
+    pub modifies: Vec<ModifyOrderParams>,
+}
+
+// The options of each order are written after modifies. The vector is left
+// empty when no order sets any, which is the same as all defaults.
+#[cfg(not(feature = "certora"))]
+impl BorshSerialize for BatchUpdateParams {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.trader_index_hint.serialize(writer)?;
+        self.cancels.serialize(writer)?;
+        self.orders.serialize(writer)?;
+        self.modifies.serialize(writer)?;
+        let order_options: Vec<PlaceOrderOptions> = if self
+            .orders
+            .iter()
+            .all(|order: &PlaceOrderParams| order.options == PlaceOrderOptions::default())
+        {
+            Vec::new()
+        } else {
+            self.orders
+                .iter()
+                .map(|order: &PlaceOrderParams| order.options)
+                .collect()
+        };
+        order_options.serialize(writer)
+    }
+}
+
+#[cfg(not(feature = "certora"))]
+impl BorshDeserialize for BatchUpdateParams {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let trader_index_hint: Option<DataIndex> = BorshDeserialize::deserialize_reader(reader)?;
+        let cancels: Vec<CancelOrderParams> = BorshDeserialize::deserialize_reader(reader)?;
+        let orders: Vec<PlaceOrderParams> = BorshDeserialize::deserialize_reader(reader)?;
+        let modifies: Vec<ModifyOrderParams> = BorshDeserialize::deserialize_reader(reader)?;
+        let order_options: Vec<PlaceOrderOptions> = BorshDeserialize::deserialize_reader(reader)?;
+        BatchUpdateParams::with_order_options(
+            trader_index_hint,
+            cancels,
+            orders,
+            modifies,
+            order_options,
+        )
+    }
+
+    // Modifies and then order options were added after the other fields.
+    // Clients that predate them stop early, so missing trailing vectors are
+    // treated as empty.
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        let trader_index_hint: Option<DataIndex> = BorshDeserialize::deserialize(buf)?;
+        let cancels: Vec<CancelOrderParams> = BorshDeserialize::deserialize(buf)?;
+        let orders: Vec<PlaceOrderParams> = BorshDeserialize::deserialize(buf)?;
+        let modifies: Vec<ModifyOrderParams> = if buf.is_empty() {
+            Vec::new()
+        } else {
+            BorshDeserialize::deserialize(buf)?
+        };
+        let order_options: Vec<PlaceOrderOptions> = if buf.is_empty() {
+            Vec::new()
+        } else {
+            BorshDeserialize::deserialize(buf)?
+        };
+        BatchUpdateParams::with_order_options(
+            trader_index_hint,
+            cancels,
+            orders,
+            modifies,
+            order_options,
+        )
+    }
 }
 
 impl BatchUpdateParams {
@@ -131,13 +340,58 @@ impl BatchUpdateParams {
             trader_index_hint,
             cancels,
             orders,
+            #[cfg(not(feature = "certora"))]
+            modifies: Vec::new(),
         }
     }
+
+    #[cfg(not(feature = "certora"))]
+    pub fn new_with_modifies(
+        trader_index_hint: Option<DataIndex>,
+        cancels: Vec<CancelOrderParams>,
+        modifies: Vec<ModifyOrderParams>,
+        orders: Vec<PlaceOrderParams>,
+    ) -> Self {
+        BatchUpdateParams {
+            trader_index_hint,
+            cancels,
+            orders,
+            modifies,
+        }
+    }
+
+    #[cfg(not(feature = "certora"))]
+    fn with_order_options(
+        trader_index_hint: Option<DataIndex>,
+        cancels: Vec<CancelOrderParams>,
+        mut orders: Vec<PlaceOrderParams>,
+        modifies: Vec<ModifyOrderParams>,
+        order_options: Vec<PlaceOrderOptions>,
+    ) -> std::io::Result<Self> {
+        if !order_options.is_empty() {
+            if order_options.len() != orders.len() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "Order options do not match orders",
+                ));
+            }
+            for (order, options) in orders.iter_mut().zip(order_options) {
+                order.options = options;
+            }
+        }
+        Ok(BatchUpdateParams {
+            trader_index_hint,
+            cancels,
+            orders,
+            modifies,
+        })
+    }
 }
 
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct BatchUpdateReturn {
-    /// Vector of tuples of (order_sequence_number, DataIndex)
+    /// Vector of tuples of (order_sequence_number, DataIndex). One per order,
+    /// followed by one per modify. DataIndex is NIL when nothing rests.
     pub orders: Vec<(u64, DataIndex)>,
 }
 
@@ -149,15 +403,25 @@ pub enum MarketDataTreeNodeType {
     #[default]
     ClaimedSeat = 1,
     RestingOrder = 2,
+    // Not in a tree, MarketFixed points to it.
+    PegConfig = 3,
+    TriggerOrder = 4,
+    // Not in a tree, MarketFixed points to it.
+    MarketStats = 5,
+    // Not in a tree, MarketFixed points to it.
+    DelegationStatus = 6,
+    // Not in a tree, MarketFixed points to it.
+    MarketAuthority = 7,
 }
 
 pub(crate) fn process_batch_update(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     data: &[u8],
+    is_v2: bool,
 ) -> ProgramResult {
     let params: BatchUpdateParams = BatchUpdateParams::try_from_slice(data)?;
-    process_batch_update_core(program_id, accounts, params)
+    process_batch_update_core(program_id, accounts, params, is_v2)
 }
 
 #[cfg(not(feature = "certora"))]
@@ -205,17 +469,64 @@ fn batch_place_order(
     mock_place_order(dynamic_account, args)
 }
 
+// Simple sanity check on an order index hint. Make sure that it aligns with
+// block boundaries, is an order, and is owned by the trader.
+fn assert_valid_order_index_hint(
+    dynamic_account: &MarketRefMut,
+    trader_index: DataIndex,
+    hinted_order_index: DataIndex,
+    order_sequence_number: u64,
+) -> ProgramResult {
+    require!(
+        hinted_order_index % (MARKET_BLOCK_SIZE as DataIndex) == 0,
+        crate::program::ManifestError::WrongIndexHintParams,
+        "Invalid cancel hint index {}",
+        hinted_order_index,
+    )?;
+    // Hints can go stale when the market is compacted and shrinks.
+    require!(
+        (hinted_order_index as usize) < dynamic_account.dynamic.len(),
+        crate::program::ManifestError::WrongIndexHintParams,
+        "Invalid cancel hint index {}",
+        hinted_order_index,
+    )?;
+    require!(
+        get_helper::<RBNode<RestingOrder>>(&dynamic_account.dynamic, hinted_order_index)
+            .get_payload_type()
+            == MarketDataTreeNodeType::RestingOrder as u8,
+        crate::program::ManifestError::WrongIndexHintParams,
+        "Invalid cancel hint index {}",
+        hinted_order_index,
+    )?;
+    let order: &RestingOrder = dynamic_account.get_order_by_index(hinted_order_index);
+    require!(
+        trader_index == order.get_trader_index(),
+        crate::program::ManifestError::WrongIndexHintParams,
+        "Invalid cancel hint index {}",
+        hinted_order_index,
+    )?;
+    require!(
+        order_sequence_number == order.get_sequence_number(),
+        crate::program::ManifestError::WrongIndexHintParams,
+        "Invalid cancel hint sequence number index {}",
+        hinted_order_index,
+    )?;
+    Ok(())
+}
+
 #[cfg_attr(all(feature = "certora", not(feature = "certora-test")), early_panic)]
 pub(crate) fn process_batch_update_core(
     _program_id: &Pubkey,
     accounts: &[AccountInfo],
     params: BatchUpdateParams,
+    is_v2: bool,
 ) -> ProgramResult {
-    let batch_update_context: BatchUpdateContext = BatchUpdateContext::load(accounts)?;
+    let batch_update_context: BatchUpdateContext = BatchUpdateContext::load(accounts, is_v2)?;
 
     let BatchUpdateContext {
         market,
         payer,
+        owner,
         global_trade_accounts_opts,
         ..
     } = batch_update_context;
@@ -224,10 +535,15 @@ pub(crate) fn process_batch_update_core(
         trader_index_hint,
         cancels,
         orders,
+        #[cfg(not(feature = "certora"))]
+        modifies,
     } = params;
 
     let current_slot: Option<u32> = Some(get_now_slot());
 
+    #[cfg(not(feature = "certora"))]
+    reprice_pegged_orders_if_oracle_included(&market, accounts)?;
+
     trace!("batch_update trader_index_hint:{trader_index_hint:?} cancels:{cancels:?} orders:{orders:?}");
 
     let trader_index: DataIndex = {
@@ -235,7 +551,7 @@ pub(crate) fn process_batch_update_core(
 
         let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
         let trader_index: DataIndex =
-            get_trader_index_with_hint(trader_index_hint, &dynamic_account, &payer)?;
+            get_trader_index_with_hint(trader_index_hint, &dynamic_account, &owner)?;
 
         for cancel_order_params in cancels {
             // Hinted is preferred because that is O(1) to find and O(log n) to
@@ -252,39 +568,11 @@ pub(crate) fn process_batch_update_core(
                     )?;
                 }
                 Some(hinted_cancel_index) => {
-                    // Simple sanity check on the hint given. Make sure that it
-                    // aligns with block boundaries. We do a check that it is an
-                    // order owned by the payer inside the handler.
-                    require!(
-                        hinted_cancel_index % (MARKET_BLOCK_SIZE as DataIndex) == 0,
-                        crate::program::ManifestError::WrongIndexHintParams,
-                        "Invalid cancel hint index {}",
-                        hinted_cancel_index,
-                    )?;
-                    require!(
-                        get_helper::<RBNode<RestingOrder>>(
-                            &dynamic_account.dynamic,
-                            hinted_cancel_index,
-                        )
-                        .get_payload_type()
-                            == MarketDataTreeNodeType::RestingOrder as u8,
-                        crate::program::ManifestError::WrongIndexHintParams,
-                        "Invalid cancel hint index {}",
-                        hinted_cancel_index,
-                    )?;
-                    let order: &RestingOrder =
-                        dynamic_account.get_order_by_index(hinted_cancel_index);
-                    require!(
-                        trader_index == order.get_trader_index(),
-                        crate::program::ManifestError::WrongIndexHintParams,
-                        "Invalid cancel hint index {}",
-                        hinted_cancel_index,
-                    )?;
-                    require!(
-                        cancel_order_params.order_sequence_number() == order.get_sequence_number(),
-                        crate::program::ManifestError::WrongIndexHintParams,
-                        "Invalid cancel hint sequence number index {}",
+                    assert_valid_order_index_hint(
+                        &dynamic_account,
+                        trader_index,
                         hinted_cancel_index,
+                        cancel_order_params.order_sequence_number(),
                     )?;
                     dynamic_account
                         .cancel_order_by_index(hinted_cancel_index, &global_trade_accounts_opts)?;
@@ -293,13 +581,30 @@ pub(crate) fn process_batch_update_core(
 
             emit_stack(CancelOrderLog {
                 market: *market.key,
-                trader: *payer.key,
+                trader: *owner.key,
                 order_sequence_number: cancel_order_params.order_sequence_number(),
             })?;
         }
         trader_index
     };
 
+    // Modify results are reported after the order results so that clients
+    // which only place orders see the same return data as before.
+    #[cfg(not(feature = "certora"))]
+    let mut modify_result: Vec<(u64, DataIndex)> = Vec::with_capacity(modifies.len());
+    #[cfg(not(feature = "certora"))]
+    for modify_order_params in modifies {
+        modify_result.push(modify_order(
+            &market,
+            &owner,
+            trader_index,
+            &modify_order_params,
+            &global_trade_accounts_opts,
+            current_slot,
+        )?);
+        expand_market_if_needed(&payer, &market)?;
+    }
+
     // Result is a vector of (order_sequence_number, data_index)
     #[cfg(not(feature = "certora"))]
     let mut result: Vec<(u64, DataIndex)> = Vec::with_capacity(orders.len());
@@ -325,21 +630,40 @@ pub(crate) fn process_batch_update_core(
                     price,
                     is_bid: place_order_params.is_bid(),
                     last_valid_slot,
+                    last_valid_unix_timestamp: place_order_params.last_valid_unix_timestamp(),
                     order_type,
+                    self_trade_behavior: place_order_params.self_trade_behavior(),
+                    min_base_atoms_filled: BaseAtoms::new(
+                        place_order_params.min_base_atoms_filled(),
+                    ),
                     global_trade_accounts_opts: &global_trade_accounts_opts,
                     current_slot,
+                    price_band_opt: None,
+                    peg_opt: place_order_params.peg(),
                 },
             )?;
 
             let AddOrderToMarketResult {
                 order_index,
                 order_sequence_number,
+                base_atoms_traded,
                 ..
             } = add_order_to_market_result;
 
+            // Post only slide and pegged orders may have rested at a
+            // different price.
+            let price: QuoteAtomsPerBaseAtom = if (order_type == OrderType::PostOnlySlide
+                || order_type == OrderType::Pegged)
+                && is_not_nil!(order_index)
+            {
+                dynamic_account.get_order_by_index(order_index).get_price()
+            } else {
+                price
+            };
+
             emit_stack(PlaceOrderLog {
                 market: *market.key,
-                trader: *payer.key,
+                trader: *owner.key,
                 base_atoms,
                 price,
                 order_type,
@@ -349,6 +673,13 @@ pub(crate) fn process_batch_update_core(
                 order_index,
                 last_valid_slot,
             })?;
+            emit_taker_settlement(
+                &dynamic_account,
+                market.key,
+                owner.key,
+                base_atoms_traded,
+                order_sequence_number,
+            )?;
             result.push((order_sequence_number, order_index));
         }
         expand_market_if_needed(&payer, &market)?;
@@ -357,6 +688,7 @@ pub(crate) fn process_batch_update_core(
     // Formal verification does not cover return values.
     #[cfg(not(feature = "certora"))]
     {
+        result.extend(modify_result);
         let mut buffer: Vec<u8> = Vec::with_capacity(
             std::mem::size_of::<BatchUpdateReturn>()
                 + result.len() * 2 * std::mem::size_of::<u64>(),
@@ -368,3 +700,170 @@ pub(crate) fn process_batch_update_core(
 
     Ok(())
 }
+
+#[cfg(not(feature = "certora"))]
+fn modify_order<'a, 'info>(
+    market: &ManifestAccountInfo<'a, 'info, MarketFixed>,
+    owner: &Signer<'a, 'info>,
+    trader_index: DataIndex,
+    modify_order_params: &ModifyOrderParams,
+    global_trade_accounts_opts: &[Option<GlobalTradeAccounts<'a, 'info>>; 2],
+    current_slot: Option<u32>,
+) -> Result<(u64, DataIndex), ProgramError> {
+    let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+    let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+
+    let order_sequence_number: u64 = modify_order_params.order_sequence_number();
+    let order_index: DataIndex = match modify_order_params.order_index_hint() {
+        None => {
+            let order_index: DataIndex =
+                dynamic_account.get_order_index_by_sequence_number(order_sequence_number);
+            require!(
+                is_not_nil!(order_index)
+                    && dynamic_account
+                        .get_order_by_index(order_index)
+                        .get_trader_index()
+                        == trader_index,
+                crate::program::ManifestError::InvalidModify,
+                "Order {} not found for trader",
+                order_sequence_number,
+            )?;
+            order_index
+        }
+        Some(hinted_order_index) => {
+            assert_valid_order_index_hint(
+                &dynamic_account,
+                trader_index,
+                hinted_order_index,
+                order_sequence_number,
+            )?;
+            hinted_order_index
+        }
+    };
+
+    let resting_order: RestingOrder = *dynamic_account.get_order_by_index(order_index);
+    let base_atoms: BaseAtoms = BaseAtoms::new(modify_order_params.base_atoms());
+    let price: QuoteAtomsPerBaseAtom = modify_order_params.try_price()?;
+
+    // Shrinking at the same price keeps the order where it is in the queue.
+    // The price of a pegged order is its limit.
+    if price == resting_order.get_funding_price()
+        && base_atoms > BaseAtoms::ZERO
+        && base_atoms <= resting_order.get_num_base_atoms()
+    {
+        dynamic_account.reduce_order_by_index(order_index, base_atoms)?;
+        emit_stack(ModifyOrderLog {
+            market: *market.key,
+            trader: *owner.key,
+            order_sequence_number,
+            base_atoms,
+        })?;
+        return Ok((order_sequence_number, order_index));
+    }
+
+    dynamic_account.cancel_order_by_index(order_index, global_trade_accounts_opts)?;
+    emit_stack(CancelOrderLog {
+        market: *market.key,
+        trader: *owner.key,
+        order_sequence_number,
+    })?;
+    if base_atoms == BaseAtoms::ZERO {
+        return Ok((order_sequence_number, NIL));
+    }
+
+    let is_bid: bool = resting_order.get_is_bid();
+    let order_type: OrderType = resting_order.get_order_type();
+    // Reverse orders pass their spread where other orders pass expiration.
+    let last_valid_slot: u32 = if order_type == OrderType::Reverse {
+        resting_order.get_reverse_spread() as u32
+    } else {
+        resting_order.get_last_valid_slot()
+    };
+    // Pegged orders keep their offset and take the new price as the limit.
+    let peg_opt: Option<OrderPeg> = if order_type == OrderType::Pegged {
+        let peg: OrderPeg = resting_order.get_peg();
+        Some(OrderPeg::new(
+            peg.offset_mantissa,
+            peg.offset_exponent,
+            modify_order_params.price_mantissa,
+            modify_order_params.price_exponent,
+        ))
+    } else {
+        None
+    };
+    let AddOrderToMarketResult {
+        order_sequence_number,
+        order_index,
+        base_atoms_traded,
+        ..
+    } = batch_place_order(
+        &mut dynamic_account,
+        AddOrderToMarketArgs {
+            market: *market.key,
+            trader_index,
+            num_base_atoms: base_atoms,
+            price,
+            is_bid,
+            last_valid_slot,
+            last_valid_unix_timestamp: resting_order.get_last_valid_unix_timestamp(),
+            order_type,
+            self_trade_behavior: modify_order_params.self_trade_behavior(),
+            min_base_atoms_filled: BaseAtoms::ZERO,
+            global_trade_accounts_opts,
+            current_slot,
+            price_band_opt: None,
+            peg_opt,
+        },
+    )?;
+
+    let price: QuoteAtomsPerBaseAtom = if (order_type == OrderType::PostOnlySlide
+        || order_type == OrderType::Pegged)
+        && is_not_nil!(order_index)
+    {
+        dynamic_account.get_order_by_index(order_index).get_price()
+    } else {
+        price
+    };
+
+    emit_stack(PlaceOrderLog {
+        market: *market.key,
+        trader: *owner.key,
+        base_atoms,
+        price,
+        order_type,
+        is_bid: PodBool::from(is_bid),
+        _padding: [0; 6],
+        order_sequence_number,
+        order_index,
+        last_valid_slot,
+    })?;
+    emit_taker_settlement(
+        &dynamic_account,
+        market.key,
+        owner.key,
+        base_atoms_traded,
+        order_sequence_number,
+    )?;
+    Ok((order_sequence_number, order_index))
+}
+
+/// Taker balances once the order rested, for orders that filled.
+fn emit_taker_settlement(
+    dynamic_account: &MarketRefMut,
+    market: &Pubkey,
+    owner: &Pubkey,
+    base_atoms_traded: BaseAtoms,
+    order_sequence_number: u64,
+) -> ProgramResult {
+    if base_atoms_traded == BaseAtoms::ZERO {
+        return Ok(());
+    }
+    let (taker_base_balance, taker_quote_balance) = dynamic_account.get_trader_balance(owner);
+    emit_stack(TakerSettlementLog {
+        market: *market,
+        taker: *owner,
+        taker_base_balance,
+        taker_quote_balance,
+        taker_sequence_number: order_sequence_number,
+    })
+}
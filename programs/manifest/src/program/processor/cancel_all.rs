@@ -0,0 +1,72 @@
+use std::cell::RefMut;
+
+use crate::{
+    logs::{emit_stack, CancelOrderLog},
+    program::get_trader_index_with_hint,
+    state::{utils::assert_already_has_seat, MarketRefMut},
+    validation::loaders::BatchUpdateContext,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use hypertree::{trace, DataIndex};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+use super::shared::get_mut_dynamic_account;
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct CancelAllParams {
+    /// Optional hint for what index the trader's ClaimedSeat is at.
+    pub trader_index_hint: Option<DataIndex>,
+    /// Only cancel bids if true, only asks if false, both sides if None.
+    pub is_bid_opt: Option<bool>,
+}
+
+impl CancelAllParams {
+    pub fn new(trader_index_hint: Option<DataIndex>, is_bid_opt: Option<bool>) -> Self {
+        CancelAllParams {
+            trader_index_hint,
+            is_bid_opt,
+        }
+    }
+}
+
+pub(crate) fn process_cancel_all(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    // Same accounts as batch update so that global gas prepayments can be
+    // returned to the trader.
+    let batch_update_context: BatchUpdateContext = BatchUpdateContext::load(accounts, false)?;
+    let BatchUpdateContext {
+        market,
+        owner,
+        global_trade_accounts_opts,
+        ..
+    } = batch_update_context;
+
+    let CancelAllParams {
+        trader_index_hint,
+        is_bid_opt,
+    } = CancelAllParams::try_from_slice(data)?;
+
+    trace!("cancel_all trader_index_hint:{trader_index_hint:?} is_bid_opt:{is_bid_opt:?}");
+
+    let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+    let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+    let trader_index: DataIndex =
+        get_trader_index_with_hint(trader_index_hint, &dynamic_account, &owner)?;
+    assert_already_has_seat(trader_index)?;
+
+    let cancelled_sequence_numbers: Vec<u64> =
+        dynamic_account.cancel_all_orders(trader_index, is_bid_opt, &global_trade_accounts_opts)?;
+
+    for order_sequence_number in cancelled_sequence_numbers {
+        emit_stack(CancelOrderLog {
+            market: *market.key,
+            trader: *owner.key,
+            order_sequence_number,
+        })?;
+    }
+
+    Ok(())
+}
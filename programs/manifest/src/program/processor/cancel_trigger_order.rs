@@ -0,0 +1,82 @@
+use std::cell::RefMut;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use hypertree::{trace, DataIndex};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+use crate::{
+    logs::{emit_stack, CancelOrderLog},
+    program::get_mut_dynamic_account,
+    state::{utils::assert_already_has_seat, MarketRefMut, GAS_DEPOSIT_LAMPORTS},
+    validation::loaders::CancelTriggerOrderContext,
+};
+
+#[derive(Debug, BorshDeserialize, BorshSerialize)]
+pub struct CancelTriggerOrderParams {
+    pub order_sequence_number: u64,
+}
+
+impl CancelTriggerOrderParams {
+    pub fn new(order_sequence_number: u64) -> Self {
+        CancelTriggerOrderParams {
+            order_sequence_number,
+        }
+    }
+}
+
+pub(crate) fn process_cancel_trigger_order(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let cancel_trigger_order_context: CancelTriggerOrderContext =
+        CancelTriggerOrderContext::load(accounts)?;
+    let CancelTriggerOrderContext { payer, market } = cancel_trigger_order_context;
+
+    let CancelTriggerOrderParams {
+        order_sequence_number,
+    } = CancelTriggerOrderParams::try_from_slice(data)?;
+    trace!("cancel_trigger_order order_sequence_number:{order_sequence_number}");
+
+    {
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        let trader_index: DataIndex = dynamic_account.get_trader_index(payer.key);
+        assert_already_has_seat(trader_index)?;
+        cancel_trigger_order(&mut dynamic_account, trader_index, order_sequence_number)?;
+    }
+
+    // The market is owned by this program, so the gas deposit can be moved
+    // directly instead of through the system program.
+    **market.info.try_borrow_mut_lamports()? -= GAS_DEPOSIT_LAMPORTS;
+    **payer.try_borrow_mut_lamports()? += GAS_DEPOSIT_LAMPORTS;
+
+    emit_stack(CancelOrderLog {
+        market: *market.key,
+        trader: *payer.key,
+        order_sequence_number,
+    })?;
+
+    Ok(())
+}
+
+#[cfg(not(feature = "certora"))]
+fn cancel_trigger_order(
+    dynamic_account: &mut MarketRefMut,
+    trader_index: DataIndex,
+    order_sequence_number: u64,
+) -> ProgramResult {
+    dynamic_account.cancel_trigger_order(trader_index, order_sequence_number)?;
+    Ok(())
+}
+
+// Trigger orders are not part of the verified properties, so there is never
+// one to cancel for formal verification.
+#[cfg(feature = "certora")]
+fn cancel_trigger_order(
+    _dynamic_account: &mut MarketRefMut,
+    _trader_index: DataIndex,
+    _order_sequence_number: u64,
+) -> ProgramResult {
+    Err(crate::program::ManifestError::InvalidCancel.into())
+}
@@ -17,13 +17,20 @@ pub(crate) fn process_claim_seat(
     _program_id: &Pubkey,
     accounts: &[AccountInfo],
     _data: &[u8],
+    is_v2: bool,
 ) -> ProgramResult {
-    let claim_seat_context: ClaimSeatContext = ClaimSeatContext::load(accounts)?;
-    let ClaimSeatContext { market, payer, .. } = claim_seat_context;
-
-    process_claim_seat_internal(&market, &payer)?;
-
-    // Leave a free block on the market
+    let claim_seat_context: ClaimSeatContext = ClaimSeatContext::load(accounts, is_v2)?;
+    let ClaimSeatContext {
+        market,
+        payer,
+        owner,
+        ..
+    } = claim_seat_context;
+
+    process_claim_seat_internal(&market, &owner)?;
+
+    // Leave a free block on the market. The payer covers the rent, which is
+    // the trader unless it is ClaimSeatV2.
     expand_market_if_needed(&payer, &market)?;
 
     Ok(())
@@ -32,15 +39,15 @@ pub(crate) fn process_claim_seat(
 #[cfg_attr(all(feature = "certora", not(feature = "certora-test")), early_panic)]
 pub(crate) fn process_claim_seat_internal<'a, 'info>(
     market: &ManifestAccountInfo<'a, 'info, MarketFixed>,
-    payer: &Signer<'a, 'info>,
+    trader: &Signer<'a, 'info>,
 ) -> ProgramResult {
     let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
     let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
-    dynamic_account.claim_seat(payer.key)?;
+    dynamic_account.claim_seat(trader.key)?;
 
     emit_stack(ClaimSeatLog {
         market: *market.key,
-        trader: *payer.key,
+        trader: *trader.key,
     })?;
 
     Ok(())
@@ -0,0 +1,87 @@
+use std::cell::RefMut;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use hypertree::{get_helper, trace, DataIndex, RBNode};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+use crate::{
+    logs::{emit_stack, CancelOrderLog},
+    program::{batch_update::MarketDataTreeNodeType, get_mut_dynamic_account},
+    require,
+    state::{MarketRefMut, RestingOrder, MARKET_BLOCK_SIZE},
+    validation::loaders::CleanExpiredContext,
+};
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct CleanExpiredParams {
+    /// Indices of orders to remove if they are expired.
+    pub order_indices: Vec<DataIndex>,
+    /// Number of orders from the top of each side of the book to check.
+    pub num_orders_to_scan: u16,
+}
+
+impl CleanExpiredParams {
+    pub fn new(order_indices: Vec<DataIndex>, num_orders_to_scan: u16) -> Self {
+        CleanExpiredParams {
+            order_indices,
+            num_orders_to_scan,
+        }
+    }
+}
+
+pub(crate) fn process_clean_expired(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let clean_expired_context: CleanExpiredContext = CleanExpiredContext::load(accounts)?;
+    let CleanExpiredContext { market, .. } = clean_expired_context;
+
+    let CleanExpiredParams {
+        order_indices,
+        num_orders_to_scan,
+    } = CleanExpiredParams::try_from_slice(data)?;
+
+    trace!("clean_expired order_indices:{order_indices:?} num_orders_to_scan:{num_orders_to_scan}");
+
+    let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+    let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+
+    let mut removed_orders: Vec<RestingOrder> = Vec::new();
+    for order_index in order_indices {
+        require!(
+            order_index % (MARKET_BLOCK_SIZE as DataIndex) == 0,
+            crate::program::ManifestError::WrongIndexHintParams,
+            "Invalid order index {}",
+            order_index,
+        )?;
+        // Another cleaner or a taker may have already removed the order, or
+        // a compaction may have shrunk the market, so anything that is no
+        // longer a resting order is skipped rather than failing the whole
+        // instruction. Freed blocks are zeroed.
+        if order_index >= dynamic_account.fixed.get_num_bytes_allocated()
+            || get_helper::<RBNode<RestingOrder>>(&dynamic_account.dynamic, order_index)
+                .get_payload_type()
+                != MarketDataTreeNodeType::RestingOrder as u8
+        {
+            continue;
+        }
+        if let Some(resting_order) = dynamic_account.remove_expired_order_by_index(order_index)? {
+            removed_orders.push(resting_order);
+        }
+    }
+    for is_bid in [true, false] {
+        removed_orders
+            .extend(dynamic_account.remove_expired_orders(is_bid, num_orders_to_scan as usize)?);
+    }
+
+    for resting_order in removed_orders {
+        emit_stack(CancelOrderLog {
+            market: *market.key,
+            trader: *dynamic_account.get_trader_key_by_index(resting_order.get_trader_index()),
+            order_sequence_number: resting_order.get_sequence_number(),
+        })?;
+    }
+
+    Ok(())
+}
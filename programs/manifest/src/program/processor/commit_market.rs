@@ -1,5 +1,9 @@
+use std::cell::RefMut;
+
 use crate::{
     logs::{emit_stack, CommitMarketLog},
+    program::{assert_delegated_market_globals, get_mut_dynamic_account},
+    state::{utils::get_now_slot, MarketRefMut},
     validation::loaders::CommitMarketContext,
 };
 use ephemeral_rollups_sdk::ephem::commit_accounts;
@@ -20,15 +24,37 @@ pub(crate) fn process_commit_market(
     if *commit_context.quote_vault.key != *market_data.get_quote_vault() {
         return Err(solana_program::program_error::ProgramError::InvalidAccountData);
     }
+    drop(market_data);
+
+    // The market globals that went to the rollup have to come back with the
+    // market.
+    assert_delegated_market_globals(&commit_context.market, &commit_context.market_globals)?;
+
+    // Written before the commit so that the base layer copy has it.
+    {
+        let market_data: &mut RefMut<&mut [u8]> =
+            &mut commit_context.market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        set_committed(&mut dynamic_account, get_now_slot());
+    }
 
-    // Commit all accounts (market and both vaults) state to base layer without undelegating
+    // Commit the market, both vaults and the market globals to the base layer
+    // without undelegating. The market globals go in the same commit as the
+    // market vaults that hold their balances.
+    let mut accounts_to_commit: Vec<&AccountInfo> = vec![
+        commit_context.market.as_ref(),
+        commit_context.base_vault,
+        commit_context.quote_vault,
+    ];
+    accounts_to_commit.extend(
+        commit_context
+            .market_globals
+            .iter()
+            .map(|market_global| market_global.as_ref()),
+    );
     commit_accounts(
         commit_context.payer.as_ref(),
-        vec![
-            commit_context.market.as_ref(),
-            commit_context.base_vault,
-            commit_context.quote_vault,
-        ],
+        accounts_to_commit,
         commit_context.magic_context,
         commit_context.magic_program.as_ref(),
     )?;
@@ -39,3 +65,12 @@ pub(crate) fn process_commit_market(
 
     Ok(())
 }
+
+#[cfg(not(feature = "certora"))]
+fn set_committed(dynamic_account: &mut MarketRefMut, now_slot: u32) {
+    dynamic_account.set_committed(now_slot)
+}
+
+// Delegation is not part of the verified properties.
+#[cfg(feature = "certora")]
+fn set_committed(_dynamic_account: &mut MarketRefMut, _now_slot: u32) {}
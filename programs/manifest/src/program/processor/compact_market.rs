@@ -0,0 +1,93 @@
+use std::cell::RefMut;
+
+use hypertree::trace;
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+    pubkey::Pubkey, rent::Rent, sysvar::Sysvar,
+};
+#[cfg(not(feature = "certora"))]
+use {crate::state::TriggerOrder, hypertree::HyperTreeValueIteratorTrait};
+
+use crate::{
+    logs::{emit_stack, CompactMarketLog},
+    program::{assert_market_authority, get_mut_dynamic_account},
+    state::{MarketRefMut, GAS_DEPOSIT_LAMPORTS, MARKET_BLOCK_SIZE},
+    validation::loaders::CompactMarketContext,
+};
+
+pub(crate) fn process_compact_market(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    let compact_market_context: CompactMarketContext = CompactMarketContext::load(accounts)?;
+    let CompactMarketContext {
+        payer,
+        market,
+        receiver,
+    } = compact_market_context;
+
+    // The refund goes wherever the caller says, so only the market authority
+    // may compact.
+    assert_market_authority(&market, &payer)?;
+
+    let (num_blocks_removed, num_trigger_orders): (u32, u64) = {
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        (
+            compact(&mut dynamic_account)?,
+            get_num_trigger_orders(&dynamic_account),
+        )
+    };
+    trace!("compact_market num_blocks_removed:{num_blocks_removed}");
+    if num_blocks_removed == 0 {
+        return Ok(());
+    }
+
+    let new_size: usize = market.info.data_len() - num_blocks_removed as usize * MARKET_BLOCK_SIZE;
+    market.info.realloc(new_size, false)?;
+
+    // The market is owned by this program, so lamports can be moved directly
+    // instead of through the system program. Gas deposits for trigger orders
+    // stay behind for whoever cranks or cancels them.
+    let lamports_refunded: u64 = market.info.lamports().saturating_sub(
+        Rent::get()?.minimum_balance(new_size) + num_trigger_orders * GAS_DEPOSIT_LAMPORTS,
+    );
+    **market.info.try_borrow_mut_lamports()? -= lamports_refunded;
+    **receiver.try_borrow_mut_lamports()? += lamports_refunded;
+
+    emit_stack(CompactMarketLog {
+        market: *market.key,
+        receiver: *receiver.key,
+        lamports_refunded,
+        num_blocks_removed,
+        _padding: [0; 4],
+    })?;
+
+    Ok(())
+}
+
+#[cfg(not(feature = "certora"))]
+fn compact(dynamic_account: &mut MarketRefMut) -> Result<u32, ProgramError> {
+    dynamic_account.market_compact()
+}
+
+#[cfg(not(feature = "certora"))]
+fn get_num_trigger_orders(dynamic_account: &MarketRefMut) -> u64 {
+    dynamic_account
+        .get_trigger_orders()
+        .iter::<TriggerOrder>()
+        .count() as u64
+}
+
+#[cfg(feature = "certora")]
+fn get_num_trigger_orders(_dynamic_account: &MarketRefMut) -> u64 {
+    0
+}
+
+// Compaction only moves blocks around and is not part of the verified
+// properties, so it is a no-op for formal verification.
+#[cfg(feature = "certora")]
+fn compact(_dynamic_account: &mut MarketRefMut) -> Result<u32, ProgramError> {
+    Ok(0)
+}
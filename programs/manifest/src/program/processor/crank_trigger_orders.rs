@@ -0,0 +1,137 @@
+use std::cell::RefMut;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use hypertree::{trace, DataIndex};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+#[cfg(not(feature = "certora"))]
+use super::shared::reprice_pegged_orders_if_oracle_included;
+use crate::{
+    logs::{emit_stack, ActivateTriggerOrderLog, CancelOrderLog, TakerSettlementLog},
+    program::{expand_market_if_needed, get_mut_dynamic_account, ManifestError},
+    quantities::BaseAtoms,
+    require,
+    state::{
+        utils::get_now_slot, CrankTriggerOrderResult, MarketRefMut, GAS_DEPOSIT_LAMPORTS,
+        MARKET_BLOCK_SIZE,
+    },
+    validation::loaders::CrankTriggerOrdersContext,
+};
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct CrankTriggerOrdersParams {
+    /// Indices of trigger orders to check.
+    pub order_indices: Vec<DataIndex>,
+}
+
+impl CrankTriggerOrdersParams {
+    pub fn new(order_indices: Vec<DataIndex>) -> Self {
+        CrankTriggerOrdersParams { order_indices }
+    }
+}
+
+pub(crate) fn process_crank_trigger_orders(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let crank_trigger_orders_context: CrankTriggerOrdersContext =
+        CrankTriggerOrdersContext::load(accounts)?;
+    let CrankTriggerOrdersContext { payer, market, .. } = crank_trigger_orders_context;
+
+    let CrankTriggerOrdersParams { order_indices } =
+        CrankTriggerOrdersParams::try_from_slice(data)?;
+    trace!("crank_trigger_orders order_indices:{order_indices:?}");
+
+    // Triggers on the oracle price need pegged orders repriced in this slot.
+    #[cfg(not(feature = "certora"))]
+    reprice_pegged_orders_if_oracle_included(&market, accounts)?;
+
+    let now_slot: u32 = get_now_slot();
+    for order_index in order_indices {
+        require!(
+            order_index % (MARKET_BLOCK_SIZE as DataIndex) == 0,
+            ManifestError::WrongIndexHintParams,
+            "Invalid trigger order index {}",
+            order_index,
+        )?;
+
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        // Another cranker or the trader may have already removed the order,
+        // so those are skipped rather than failing the whole instruction.
+        match crank_trigger_order(&mut dynamic_account, *market.key, order_index, now_slot)? {
+            CrankTriggerOrderResult::Skipped => continue,
+            CrankTriggerOrderResult::Activated(trigger_order, result) => {
+                emit_stack(ActivateTriggerOrderLog {
+                    market: *market.key,
+                    trader: *dynamic_account
+                        .get_trader_key_by_index(trigger_order.get_trader_index()),
+                    cranker: *payer.key,
+                    trigger_sequence_number: trigger_order.get_sequence_number(),
+                    order_sequence_number: result.order_sequence_number,
+                    base_atoms_traded: result.base_atoms_traded,
+                    quote_atoms_traded: result.quote_atoms_traded,
+                    order_index: result.order_index,
+                    _padding: [0; 4],
+                })?;
+                if result.base_atoms_traded > BaseAtoms::ZERO {
+                    let trader: Pubkey =
+                        *dynamic_account.get_trader_key_by_index(trigger_order.get_trader_index());
+                    let (taker_base_balance, taker_quote_balance) =
+                        dynamic_account.get_trader_balance(&trader);
+                    emit_stack(TakerSettlementLog {
+                        market: *market.key,
+                        taker: trader,
+                        taker_base_balance,
+                        taker_quote_balance,
+                        taker_sequence_number: result.order_sequence_number,
+                    })?;
+                }
+            }
+            CrankTriggerOrderResult::Removed(trigger_order) => {
+                emit_stack(CancelOrderLog {
+                    market: *market.key,
+                    trader: *dynamic_account
+                        .get_trader_key_by_index(trigger_order.get_trader_index()),
+                    order_sequence_number: trigger_order.get_sequence_number(),
+                })?;
+            }
+        }
+
+        // The market is owned by this program, so the gas deposit can be
+        // moved directly instead of through the system program.
+        **market.info.try_borrow_mut_lamports()? -= GAS_DEPOSIT_LAMPORTS;
+        **payer.try_borrow_mut_lamports()? += GAS_DEPOSIT_LAMPORTS;
+    }
+
+    // Leave a free block on the market
+    expand_market_if_needed(&payer, &market)?;
+
+    Ok(())
+}
+
+#[cfg(not(feature = "certora"))]
+fn crank_trigger_order(
+    dynamic_account: &mut MarketRefMut,
+    market: Pubkey,
+    order_index: DataIndex,
+    now_slot: u32,
+) -> Result<CrankTriggerOrderResult, ProgramError> {
+    dynamic_account.crank_trigger_order(market, order_index, now_slot)
+}
+
+// Trigger orders are not part of the verified properties, so there is never
+// one to crank for formal verification.
+#[cfg(feature = "certora")]
+fn crank_trigger_order(
+    _dynamic_account: &mut MarketRefMut,
+    _market: Pubkey,
+    _order_index: DataIndex,
+    _now_slot: u32,
+) -> Result<CrankTriggerOrderResult, ProgramError> {
+    Ok(CrankTriggerOrderResult::Skipped)
+}
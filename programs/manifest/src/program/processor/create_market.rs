@@ -1,13 +1,18 @@
-use std::{cell::Ref, mem::size_of};
+use std::{
+    cell::{Ref, RefMut},
+    mem::size_of,
+};
 
 use crate::{
     logs::{emit_stack, CreateMarketLog},
-    program::{expand_market_if_needed, invoke},
+    program::{expand_market_if_needed, invoke, ManifestError},
+    quantities::{BaseAtoms, QuoteAtomsPerBaseAtom, WrapperU64},
     require,
-    state::MarketFixed,
+    state::{MarketFixed, MarketRefMut, PegConfig},
     utils::create_account,
     validation::{get_vault_address, loaders::CreateMarketContext},
 };
+use borsh::{BorshDeserialize, BorshSerialize};
 use hypertree::{get_mut_helper, trace};
 use solana_program::{
     account_info::AccountInfo, entrypoint::ProgramResult, program_pack::Pack, pubkey::Pubkey,
@@ -22,13 +27,96 @@ use spl_token_2022::{
     state::{Account, Mint},
 };
 
+use super::shared::get_mut_dynamic_account;
+
+#[derive(BorshSerialize, Default, Clone, Copy)]
+pub struct CreateMarketParams {
+    /// Smallest order size that can rest on the market. Zero for no minimum.
+    pub min_base_atoms: u64,
+    /// Resting prices must be a multiple of mantissa * 10^exponent. Zero
+    /// mantissa for no tick.
+    pub price_tick_mantissa: u32,
+    pub price_tick_exponent: i8,
+    /// Oracle that pegged orders follow. Markets without one cannot have
+    /// pegged orders. Cannot be changed after creation.
+    pub peg_oracle: Option<PegOracleParams>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Default, Clone, Copy)]
+pub struct PegOracleParams {
+    /// Pyth or Switchboard price account.
+    pub oracle: Pubkey,
+    /// Oldest oracle update, in slots, that pegged orders are repriced to.
+    pub max_staleness_slots: u64,
+}
+
+impl CreateMarketParams {
+    pub fn new(min_base_atoms: u64, price_tick_mantissa: u32, price_tick_exponent: i8) -> Self {
+        CreateMarketParams {
+            min_base_atoms,
+            price_tick_mantissa,
+            price_tick_exponent,
+            peg_oracle: None,
+        }
+    }
+    pub fn with_peg_oracle(mut self, oracle: Pubkey, max_staleness_slots: u64) -> Self {
+        self.peg_oracle = Some(PegOracleParams {
+            oracle,
+            max_staleness_slots,
+        });
+        self
+    }
+}
+
+impl BorshDeserialize for CreateMarketParams {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        Ok(CreateMarketParams {
+            min_base_atoms: BorshDeserialize::deserialize_reader(reader)?,
+            price_tick_mantissa: BorshDeserialize::deserialize_reader(reader)?,
+            price_tick_exponent: BorshDeserialize::deserialize_reader(reader)?,
+            peg_oracle: BorshDeserialize::deserialize_reader(reader)?,
+        })
+    }
+
+    // CreateMarket originally took no data. Clients that predate the params
+    // send nothing, which creates a market without limits. The peg oracle
+    // came later, so a missing trailing peg oracle is treated as none.
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        if buf.is_empty() {
+            return Ok(CreateMarketParams::default());
+        }
+        let min_base_atoms: u64 = BorshDeserialize::deserialize(buf)?;
+        let price_tick_mantissa: u32 = BorshDeserialize::deserialize(buf)?;
+        let price_tick_exponent: i8 = BorshDeserialize::deserialize(buf)?;
+        let peg_oracle: Option<PegOracleParams> = if buf.is_empty() {
+            None
+        } else {
+            BorshDeserialize::deserialize(buf)?
+        };
+        Ok(CreateMarketParams {
+            min_base_atoms,
+            price_tick_mantissa,
+            price_tick_exponent,
+            peg_oracle,
+        })
+    }
+}
+
 pub(crate) fn process_create_market(
     _program_id: &Pubkey,
     accounts: &[AccountInfo],
-    _data: &[u8],
+    data: &[u8],
 ) -> ProgramResult {
     trace!("process_create_market accs={accounts:?}");
     let create_market_context: CreateMarketContext = CreateMarketContext::load(accounts)?;
+    let CreateMarketParams {
+        min_base_atoms,
+        price_tick_mantissa,
+        price_tick_exponent,
+        #[cfg(not(feature = "certora"))]
+        peg_oracle,
+        ..
+    } = CreateMarketParams::try_from_slice(data)?;
 
     let CreateMarketContext {
         market,
@@ -44,9 +132,20 @@ pub(crate) fn process_create_market(
 
     require!(
         base_mint.info.key != quote_mint.info.key,
-        crate::program::ManifestError::InvalidMarketParameters,
+        ManifestError::InvalidMarketParameters,
         "Base and quote must be different",
     )?;
+    require!(
+        price_tick_mantissa == 0
+            || QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(
+                price_tick_mantissa,
+                price_tick_exponent
+            )
+            .is_ok(),
+        ManifestError::InvalidMarketParameters,
+        "Invalid price tick exponent {}",
+        price_tick_exponent,
+    )?;
 
     for mint in [base_mint.as_ref(), quote_mint.as_ref()] {
         if *mint.owner == spl_token_2022::id() {
@@ -169,8 +268,13 @@ pub(crate) fn process_create_market(
         // would use an inactive market when multiple exist.
 
         // Setup the empty market
-        let empty_market_fixed: MarketFixed =
+        let mut empty_market_fixed: MarketFixed =
             MarketFixed::new_empty(&base_mint, &quote_mint, market.key);
+        empty_market_fixed.set_order_limits(
+            BaseAtoms::new(min_base_atoms),
+            price_tick_mantissa,
+            price_tick_exponent,
+        );
         assert_eq!(market.data_len(), size_of::<MarketFixed>());
 
         let market_bytes: &mut [u8] = &mut market.try_borrow_mut_data()?[..];
@@ -184,6 +288,18 @@ pub(crate) fn process_create_market(
         })?;
     }
 
+    #[cfg(not(feature = "certora"))]
+    if let Some(PegOracleParams {
+        oracle,
+        max_staleness_slots,
+    }) = peg_oracle
+    {
+        expand_market_if_needed(&payer, &market)?;
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        dynamic_account.init_peg_config(PegConfig::new(&oracle, max_staleness_slots))?;
+    }
+
     // Leave a free block on the market so takers can use and leave it.
     expand_market_if_needed(&payer, &market)?;
 
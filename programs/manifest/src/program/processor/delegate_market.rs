@@ -1,5 +1,15 @@
+use std::cell::{Ref, RefMut};
+
 use crate::{
     logs::{emit_stack, DelegateMarketLog},
+    program::{
+        assert_market_authority, expand_market_if_needed, get_market_global_sides,
+        get_mut_dynamic_account,
+    },
+    state::{
+        utils::{get_now_slot, get_now_unix_timestamp},
+        GlobalFixed, MarketRefMut,
+    },
     validation::loaders::DelegateMarketContext,
 };
 use borsh::{BorshDeserialize, BorshSerialize};
@@ -10,8 +20,11 @@ use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubke
 pub struct DelegateMarketParams {
     /// How often to sync state with base layer (in milliseconds)
     pub update_frequency_ms: u32,
-    /// Time limit for delegation (0 = no limit)
+    /// Seconds after which anyone can undelegate the market, not only the
+    /// market authority (0 = no limit)
     pub time_limit: u64,
+    /// Rollup validator to delegate to (None = delegation program default)
+    pub validator: Option<Pubkey>,
 }
 
 pub(crate) fn process_delegate_market(
@@ -22,14 +35,54 @@ pub(crate) fn process_delegate_market(
     let params: DelegateMarketParams = DelegateMarketParams::try_from_slice(data)?;
     let delegate_context: DelegateMarketContext = DelegateMarketContext::load(accounts)?;
 
-    // Validate vault addresses match the market's expected vaults
-    let market_data = delegate_context.market.get_fixed()?;
-    if *delegate_context.base_vault.key != *market_data.get_base_vault() {
-        return Err(solana_program::program_error::ProgramError::InvalidAccountData);
+    // Only the market authority can move the market to a rollup.
+    assert_market_authority(&delegate_context.market, &delegate_context.payer)?;
+
+    let mut market_global_mints: Vec<Pubkey> =
+        Vec::with_capacity(delegate_context.market_globals.len());
+    for market_global_accounts in delegate_context.market_globals.iter() {
+        market_global_mints.push(*market_global_accounts.market_global.get_fixed()?.get_mint());
     }
-    if *delegate_context.quote_vault.key != *market_data.get_quote_vault() {
-        return Err(solana_program::program_error::ProgramError::InvalidAccountData);
+
+    // Validate vault addresses match the market's expected vaults
+    let (base_vault_bump, quote_vault_bump, has_base_market_global, has_quote_market_global) = {
+        let market_data = delegate_context.market.get_fixed()?;
+        if *delegate_context.base_vault.key != *market_data.get_base_vault() {
+            return Err(solana_program::program_error::ProgramError::InvalidAccountData);
+        }
+        if *delegate_context.quote_vault.key != *market_data.get_quote_vault() {
+            return Err(solana_program::program_error::ProgramError::InvalidAccountData);
+        }
+        // Recorded so that CommitMarket and UndelegateMarket can require the
+        // same market globals back.
+        let (has_base_market_global, has_quote_market_global) =
+            get_market_global_sides(&market_data, &market_global_mints)?;
+        (
+            market_data.get_base_vault_bump(),
+            market_data.get_quote_vault_bump(),
+            has_base_market_global,
+            has_quote_market_global,
+        )
+    };
+
+    // Record the delegation before the market data is handed over. The
+    // status takes a block the first time, so make sure there is one.
+    expand_market_if_needed(&delegate_context.payer, &delegate_context.market)?;
+    {
+        let market_data: &mut RefMut<&mut [u8]> =
+            &mut delegate_context.market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        set_delegated(
+            &mut dynamic_account,
+            &params.validator.unwrap_or_default(),
+            get_now_slot(),
+            get_now_unix_timestamp(),
+            params.time_limit,
+            has_base_market_global,
+            has_quote_market_global,
+        )?;
     }
+    expand_market_if_needed(&delegate_context.payer, &delegate_context.market)?;
 
     // 1. Delegate the market account (not a PDA)
     let market_delegate_accounts = DelegateAccounts {
@@ -45,7 +98,7 @@ pub(crate) fn process_delegate_market(
     let market_pda_seeds: &[&[u8]] = &[]; // Market accounts don't use seeds
     let market_delegate_config = DelegateConfig {
         commit_frequency_ms: params.update_frequency_ms,
-        validator: None, // Use default validator
+        validator: params.validator,
     };
     delegate_account(
         market_delegate_accounts,
@@ -64,7 +117,6 @@ pub(crate) fn process_delegate_market(
         delegation_program: delegate_context.delegation_program.as_ref(),
         system_program: delegate_context.system_program.as_ref(),
     };
-    let base_vault_bump = market_data.get_base_vault_bump();
     let base_vault_seeds_array = [
         b"vault".as_ref(),
         delegate_context.market.key.as_ref(),
@@ -74,7 +126,7 @@ pub(crate) fn process_delegate_market(
     let base_vault_seeds: &[&[u8]] = &base_vault_seeds_array;
     let base_vault_delegate_config = DelegateConfig {
         commit_frequency_ms: params.update_frequency_ms,
-        validator: None, // Use default validator
+        validator: params.validator,
     };
     delegate_account(
         base_vault_delegate_accounts,
@@ -93,7 +145,6 @@ pub(crate) fn process_delegate_market(
         delegation_program: delegate_context.delegation_program.as_ref(),
         system_program: delegate_context.system_program.as_ref(),
     };
-    let quote_vault_bump = market_data.get_quote_vault_bump();
     let quote_vault_seeds_array = [
         b"vault".as_ref(),
         delegate_context.market.key.as_ref(),
@@ -103,7 +154,7 @@ pub(crate) fn process_delegate_market(
     let quote_vault_seeds: &[&[u8]] = &quote_vault_seeds_array;
     let quote_vault_delegate_config = DelegateConfig {
         commit_frequency_ms: params.update_frequency_ms,
-        validator: None, // Use default validator
+        validator: params.validator,
     };
     delegate_account(
         quote_vault_delegate_accounts,
@@ -111,11 +162,86 @@ pub(crate) fn process_delegate_market(
         quote_vault_delegate_config,
     )?;
 
+    // 4. Delegate the market globals (PDAs with seeds), so that global orders
+    // backed by them keep filling on the rollup.
+    for market_global_accounts in delegate_context.market_globals.iter() {
+        let market_global: &AccountInfo = market_global_accounts.market_global.as_ref();
+        let (mint, market_global_bump) = {
+            let market_global_fixed: Ref<GlobalFixed> =
+                market_global_accounts.market_global.get_fixed()?;
+            (
+                *market_global_fixed.get_mint(),
+                market_global_fixed.get_global_bump(),
+            )
+        };
+        let market_global_delegate_accounts = DelegateAccounts {
+            payer: delegate_context.payer.as_ref(),
+            pda: market_global,
+            owner_program: delegate_context.owner_program.as_ref(),
+            buffer: market_global_accounts.delegation_buffer,
+            delegation_record: market_global_accounts.delegation_record,
+            delegation_metadata: market_global_accounts.delegation_metadata,
+            delegation_program: delegate_context.delegation_program.as_ref(),
+            system_program: delegate_context.system_program.as_ref(),
+        };
+        let market_global_seeds_array = [
+            b"market-global".as_ref(),
+            delegate_context.market.key.as_ref(),
+            mint.as_ref(),
+            &[market_global_bump],
+        ];
+        let market_global_seeds: &[&[u8]] = &market_global_seeds_array;
+        let market_global_delegate_config = DelegateConfig {
+            commit_frequency_ms: params.update_frequency_ms,
+            validator: params.validator,
+        };
+        delegate_account(
+            market_global_delegate_accounts,
+            market_global_seeds,
+            market_global_delegate_config,
+        )?;
+    }
+
     emit_stack(DelegateMarketLog {
         market: *delegate_context.market.key,
         update_frequency_ms: params.update_frequency_ms as u64,
         time_limit: params.time_limit,
+        validator: params.validator.unwrap_or_default(),
     })?;
 
     Ok(())
 }
+
+#[cfg(not(feature = "certora"))]
+fn set_delegated(
+    dynamic_account: &mut MarketRefMut,
+    validator: &Pubkey,
+    now_slot: u32,
+    now_unix_timestamp: u32,
+    time_limit_seconds: u64,
+    has_base_market_global: bool,
+    has_quote_market_global: bool,
+) -> ProgramResult {
+    dynamic_account.set_delegated(
+        validator,
+        now_slot,
+        now_unix_timestamp,
+        time_limit_seconds,
+        has_base_market_global,
+        has_quote_market_global,
+    )
+}
+
+// Delegation is not part of the verified properties.
+#[cfg(feature = "certora")]
+fn set_delegated(
+    _dynamic_account: &mut MarketRefMut,
+    _validator: &Pubkey,
+    _now_slot: u32,
+    _now_unix_timestamp: u32,
+    _time_limit_seconds: u64,
+    _has_base_market_global: bool,
+    _has_quote_market_global: bool,
+) -> ProgramResult {
+    Ok(())
+}
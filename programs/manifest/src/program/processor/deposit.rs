@@ -11,6 +11,8 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use hypertree::DataIndex;
 use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
 
+#[cfg(not(feature = "certora"))]
+use super::shared::invoke_transfer_checked_with_hook;
 use super::{get_trader_index_with_hint, shared::get_mut_dynamic_account};
 
 #[cfg(feature = "certora")]
@@ -63,6 +65,7 @@ pub(crate) fn process_deposit_core(
         vault,
         token_program,
         mint,
+        hook_accounts,
     } = deposit_context;
 
     let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
@@ -91,6 +94,7 @@ pub(crate) fn process_deposit_core(
             } else {
                 dynamic_account.fixed.get_quote_mint_decimals()
             },
+            hook_accounts,
         )?;
 
         let after_vault_balance_atoms: u64 = vault.get_balance_atoms();
@@ -174,7 +178,21 @@ fn spl_token_2022_transfer_from_trader_to_vault<'a, 'info>(
     payer: &Signer<'a, 'info>,
     amount: u64,
     decimals: u8,
+    hook_accounts: &[AccountInfo<'info>],
 ) -> ProgramResult {
+    if !hook_accounts.is_empty() {
+        return invoke_transfer_checked_with_hook(
+            token_program.as_ref(),
+            trader_account.as_ref(),
+            mint.unwrap().as_ref(),
+            vault.as_ref(),
+            payer.as_ref(),
+            hook_accounts,
+            amount,
+            decimals,
+            &[],
+        );
+    }
     crate::program::invoke(
         &spl_token_2022::instruction::transfer_checked(
             token_program.key,
@@ -207,6 +225,7 @@ fn spl_token_2022_transfer_from_trader_to_vault<'a, 'info>(
     payer: &Signer<'a, 'info>,
     amount: u64,
     _decimals: u8,
+    _hook_accounts: &[AccountInfo<'info>],
 ) -> ProgramResult {
     spl_token_2022_transfer(trader_account.info, vault.info, payer.info, amount)
 }
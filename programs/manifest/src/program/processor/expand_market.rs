@@ -1,19 +1,64 @@
+use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
 
 use crate::{
-    program::get_dynamic_account, state::MarketRef, validation::loaders::ExpandMarketContext,
+    program::{get_dynamic_account, ManifestError},
+    require,
+    state::{constants::MARKET_MAX_EXPAND_BLOCKS, MarketRef},
+    validation::loaders::ExpandMarketContext,
 };
 use std::cell::Ref;
 
-use super::expand_market;
+use super::{expand_market, expand_market_by};
+
+#[derive(BorshSerialize, Default, Clone, Copy)]
+pub struct ExpandMarketParams {
+    /// Number of blocks to add, at most MARKET_MAX_EXPAND_BLOCKS. Zero keeps
+    /// the original behavior of topping up to two free blocks.
+    pub num_blocks: u32,
+}
+
+impl ExpandMarketParams {
+    pub fn new(num_blocks: u32) -> Self {
+        ExpandMarketParams { num_blocks }
+    }
+}
+
+impl BorshDeserialize for ExpandMarketParams {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        Ok(ExpandMarketParams {
+            num_blocks: BorshDeserialize::deserialize_reader(reader)?,
+        })
+    }
+
+    // Expand originally took no data.
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        if buf.is_empty() {
+            return Ok(ExpandMarketParams::default());
+        }
+        Self::deserialize_reader(buf)
+    }
+}
 
 pub(crate) fn process_expand_market(
     _program_id: &Pubkey,
     accounts: &[AccountInfo],
-    _data: &[u8],
+    data: &[u8],
 ) -> ProgramResult {
     let expand_market_context: ExpandMarketContext = ExpandMarketContext::load(accounts)?;
     let ExpandMarketContext { market, payer, .. } = expand_market_context;
+    let ExpandMarketParams { num_blocks } = ExpandMarketParams::try_from_slice(data)?;
+
+    if num_blocks > 0 {
+        require!(
+            num_blocks <= MARKET_MAX_EXPAND_BLOCKS,
+            ManifestError::InvalidMarketParameters,
+            "Cannot expand by {} blocks, max is {}",
+            num_blocks,
+            MARKET_MAX_EXPAND_BLOCKS,
+        )?;
+        return expand_market_by(&payer, &market, num_blocks);
+    }
 
     let has_two_free_blocks: bool = {
         let market_data: Ref<'_, &mut [u8]> = market.try_borrow_data()?;
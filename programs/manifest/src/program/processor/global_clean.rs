@@ -8,7 +8,10 @@ use crate::{
     program::{batch_update::MarketDataTreeNodeType, get_mut_dynamic_account},
     quantities::{GlobalAtoms, WrapperU64},
     require,
-    state::{utils::get_now_slot, GlobalRefMut, MarketRefMut, RestingOrder, MARKET_BLOCK_SIZE},
+    state::{
+        utils::{get_now_slot, get_now_unix_timestamp},
+        GlobalRefMut, MarketRefMut, RestingOrder, MARKET_BLOCK_SIZE,
+    },
     validation::loaders::{GlobalCleanContext, GlobalTradeAccounts},
 };
 
@@ -36,11 +39,13 @@ pub(crate) fn process_global_clean(
         market,
         global,
         system_program,
+        is_market_global,
     } = global_clean_context;
 
     let global_trade_accounts: GlobalTradeAccounts = GlobalTradeAccounts {
         mint_opt: None,
         global: global.clone(),
+        is_market_global,
         global_vault_opt: None,
         market_vault_opt: None,
         token_program_opt: None,
@@ -48,6 +53,7 @@ pub(crate) fn process_global_clean(
         market: *market.key,
         gas_payer_opt: None,
         gas_receiver_opt: Some(payer),
+        hook_accounts: &[],
     };
 
     let GlobalCleanParams { order_index } = GlobalCleanParams::try_from_slice(data)?;
@@ -85,9 +91,13 @@ pub(crate) fn process_global_clean(
         };
         let global_mint: &Pubkey = global_dynamic_account.fixed.get_mint();
 
-        // Verify that the resting order uses the global account given.
+        // Verify that the resting order uses the global account given. The
+        // other global for the mint would show a balance that does not back
+        // the order.
         require!(
-            *expected_global_mint == *global_mint,
+            *expected_global_mint == *global_mint
+                && (!resting_order.is_global()
+                    || resting_order.is_market_global() == is_market_global),
             crate::program::ManifestError::InvalidClean,
             "Wrong global provided",
         )?;
@@ -101,7 +111,7 @@ pub(crate) fn process_global_clean(
     let maker: &Pubkey = market_dynamic_account.get_trader_key_by_index(maker_index);
 
     // Verify that the RestingOrder is clean eligible
-    let is_expired: bool = resting_order.is_expired(get_now_slot());
+    let is_expired: bool = resting_order.is_expired(get_now_slot(), get_now_unix_timestamp());
     // Balance is zero when evicted.
     let maker_global_balance: GlobalAtoms = global_dynamic_account.get_balance_atoms(maker);
     let required_global_atoms: u64 = if resting_order.get_is_bid() {
@@ -11,7 +11,7 @@ use crate::{
     validation::loaders::GlobalDepositContext,
 };
 
-use super::invoke;
+use super::{invoke, invoke_transfer_checked_with_hook};
 
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct GlobalDepositParams {
@@ -45,6 +45,7 @@ pub(crate) fn process_global_deposit(
         global_vault,
         trader_token: trader_token_account,
         token_program,
+        hook_accounts,
     } = global_deposit_context;
 
     let global_data: &mut RefMut<&mut [u8]> = &mut global.try_borrow_mut_data()?;
@@ -54,25 +55,39 @@ pub(crate) fn process_global_deposit(
     // Do the token transfer
     if *global_vault.owner == spl_token_2022::id() {
         let before_vault_balance_atoms: u64 = global_vault.get_balance_atoms();
-        invoke(
-            &spl_token_2022::instruction::transfer_checked(
-                token_program.key,
-                trader_token_account.key,
-                mint.info.key,
-                global_vault.key,
-                payer.key,
-                &[],
+        if hook_accounts.is_empty() {
+            invoke(
+                &spl_token_2022::instruction::transfer_checked(
+                    token_program.key,
+                    trader_token_account.key,
+                    mint.info.key,
+                    global_vault.key,
+                    payer.key,
+                    &[],
+                    amount_atoms,
+                    mint.mint.decimals,
+                )?,
+                &[
+                    token_program.as_ref().clone(),
+                    trader_token_account.as_ref().clone(),
+                    mint.as_ref().clone(),
+                    global_vault.as_ref().clone(),
+                    payer.as_ref().clone(),
+                ],
+            )?;
+        } else {
+            invoke_transfer_checked_with_hook(
+                token_program.as_ref(),
+                trader_token_account.as_ref(),
+                mint.as_ref(),
+                global_vault.as_ref(),
+                payer.as_ref(),
+                hook_accounts,
                 amount_atoms,
                 mint.mint.decimals,
-            )?,
-            &[
-                token_program.as_ref().clone(),
-                trader_token_account.as_ref().clone(),
-                mint.as_ref().clone(),
-                global_vault.as_ref().clone(),
-                payer.as_ref().clone(),
-            ],
-        )?;
+                &[],
+            )?;
+        }
 
         let after_vault_balance_atoms: u64 = global_vault.get_balance_atoms();
         deposited_amount_atoms = after_vault_balance_atoms
@@ -14,7 +14,7 @@ use crate::{
 };
 use solana_program::program::invoke_signed;
 
-use super::invoke;
+use super::{invoke, invoke_transfer_checked_with_hook};
 
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct GlobalEvictParams {
@@ -44,6 +44,7 @@ pub(crate) fn process_global_evict(
         trader_token,
         evictee_token,
         token_program,
+        hook_accounts,
     } = global_evict_context;
 
     // 1. Withdraw for the evictee
@@ -74,7 +75,19 @@ pub(crate) fn process_global_evict(
         let (_, bump) = get_global_vault_address(mint.info.key);
 
         // Do the token transfer
-        if *global_vault.owner == spl_token_2022::id() {
+        if *global_vault.owner == spl_token_2022::id() && !hook_accounts.is_empty() {
+            invoke_transfer_checked_with_hook(
+                token_program.as_ref(),
+                global_vault.as_ref(),
+                mint.as_ref(),
+                evictee_token.as_ref(),
+                global_vault.as_ref(),
+                hook_accounts,
+                evictee_balance.into(),
+                mint.mint.decimals,
+                global_vault_seeds_with_bump!(mint.info.key, bump),
+            )?;
+        } else if *global_vault.owner == spl_token_2022::id() {
             invoke_signed(
                 &spl_token_2022::instruction::transfer_checked(
                     token_program.key,
@@ -138,7 +151,19 @@ pub(crate) fn process_global_evict(
         global_dynamic_account.deposit_global(payer.key, GlobalAtoms::new(amount_atoms))?;
 
         // Do the token transfer
-        if *global_vault.owner == spl_token_2022::id() {
+        if *global_vault.owner == spl_token_2022::id() && !hook_accounts.is_empty() {
+            invoke_transfer_checked_with_hook(
+                token_program.as_ref(),
+                trader_token.as_ref(),
+                mint.as_ref(),
+                global_vault.as_ref(),
+                payer.as_ref(),
+                hook_accounts,
+                amount_atoms,
+                mint.mint.decimals,
+                &[],
+            )?;
+        } else if *global_vault.owner == spl_token_2022::id() {
             invoke(
                 &spl_token_2022::instruction::transfer_checked(
                     token_program.key,
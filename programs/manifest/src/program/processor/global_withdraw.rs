@@ -8,7 +8,7 @@ use solana_program::{
 use crate::{
     global_vault_seeds_with_bump,
     logs::{emit_stack, GlobalWithdrawLog},
-    program::get_mut_dynamic_account,
+    program::{get_mut_dynamic_account, invoke_transfer_checked_with_hook},
     quantities::{GlobalAtoms, WrapperU64},
     state::GlobalRefMut,
     validation::{get_global_vault_address, loaders::GlobalWithdrawContext},
@@ -44,6 +44,7 @@ pub(crate) fn process_global_withdraw(
         global_vault,
         trader_token,
         token_program,
+        hook_accounts,
     } = global_withdraw_context;
 
     let global_data: &mut RefMut<&mut [u8]> = &mut global.try_borrow_mut_data()?;
@@ -53,7 +54,19 @@ pub(crate) fn process_global_withdraw(
     let (_, bump) = get_global_vault_address(mint.info.key);
 
     // Do the token transfer
-    if *global_vault.owner == spl_token_2022::id() {
+    if *global_vault.owner == spl_token_2022::id() && !hook_accounts.is_empty() {
+        invoke_transfer_checked_with_hook(
+            token_program.as_ref(),
+            global_vault.as_ref(),
+            mint.as_ref(),
+            trader_token.as_ref(),
+            global_vault.as_ref(),
+            hook_accounts,
+            amount_atoms,
+            mint.mint.decimals,
+            global_vault_seeds_with_bump!(mint.info.key, bump),
+        )?;
+    } else if *global_vault.owner == spl_token_2022::id() {
         invoke_signed(
             &spl_token_2022::instruction::transfer_checked(
                 token_program.key,
@@ -0,0 +1,105 @@
+use std::mem::size_of;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use hypertree::{get_mut_helper, trace};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey, rent::Rent,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    logs::{emit_stack, MarketMetadataLog},
+    program::assert_market_authority,
+    state::MarketMetadata,
+    utils::create_account,
+    validation::{get_market_metadata_address, loaders::InitMarketMetadataContext},
+};
+
+#[derive(BorshDeserialize, BorshSerialize, Default, Clone)]
+pub struct MarketMetadataParams {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub price_display_decimals: u8,
+    pub size_display_decimals: u8,
+}
+
+impl MarketMetadataParams {
+    pub fn new(
+        name: &str,
+        symbol: &str,
+        uri: &str,
+        price_display_decimals: u8,
+        size_display_decimals: u8,
+    ) -> Self {
+        MarketMetadataParams {
+            name: name.to_string(),
+            symbol: symbol.to_string(),
+            uri: uri.to_string(),
+            price_display_decimals,
+            size_display_decimals,
+        }
+    }
+}
+
+pub(crate) fn process_init_market_metadata(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let init_market_metadata_context: InitMarketMetadataContext =
+        InitMarketMetadataContext::load(accounts)?;
+    let InitMarketMetadataContext {
+        payer,
+        market,
+        market_metadata,
+        system_program,
+    } = init_market_metadata_context;
+    let params: MarketMetadataParams = MarketMetadataParams::try_from_slice(data)?;
+    trace!(
+        "init_market_metadata market:{} params:{}",
+        market.key,
+        params.name
+    );
+
+    // Otherwise anyone could take the metadata authority before the creator
+    // gets to it.
+    assert_market_authority(&market, &payer)?;
+
+    let (_expected_market_metadata_key, market_metadata_bump) =
+        get_market_metadata_address(market.key);
+    let market_metadata_seeds: Vec<Vec<u8>> = vec![
+        b"market-metadata".to_vec(),
+        market.key.as_ref().to_vec(),
+        vec![market_metadata_bump],
+    ];
+    create_account(
+        payer.as_ref(),
+        market_metadata.info,
+        system_program.as_ref(),
+        &crate::id(),
+        &Rent::get()?,
+        size_of::<MarketMetadata>() as u64,
+        market_metadata_seeds,
+    )?;
+
+    let mut metadata: MarketMetadata =
+        MarketMetadata::new_empty(market.key, payer.key, market_metadata_bump);
+    metadata.set_display(
+        &params.name,
+        &params.symbol,
+        &params.uri,
+        params.price_display_decimals,
+        params.size_display_decimals,
+    )?;
+    let metadata_bytes: &mut [u8] = &mut market_metadata.info.try_borrow_mut_data()?[..];
+    *get_mut_helper::<MarketMetadata>(metadata_bytes, 0_u32) = metadata;
+
+    emit_stack(MarketMetadataLog {
+        market: *market.key,
+        market_metadata: *market_metadata.info.key,
+        authority: *payer.key,
+    })?;
+
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use std::cell::RefMut;
+
+use hypertree::trace;
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+use crate::{
+    program::{expand_market_if_needed, get_mut_dynamic_account},
+    state::MarketRefMut,
+    validation::loaders::InitMarketStatsContext,
+};
+
+pub(crate) fn process_init_market_stats(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    let init_market_stats_context: InitMarketStatsContext = InitMarketStatsContext::load(accounts)?;
+    let InitMarketStatsContext { payer, market, .. } = init_market_stats_context;
+    trace!("init_market_stats market:{}", market.key);
+
+    // The stats take a block, so make sure there is one to take.
+    expand_market_if_needed(&payer, &market)?;
+    {
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        init_market_stats(&mut dynamic_account)?;
+    }
+
+    // Leave a free block on the market so takers can use and leave it.
+    expand_market_if_needed(&payer, &market)?;
+
+    Ok(())
+}
+
+#[cfg(not(feature = "certora"))]
+fn init_market_stats(dynamic_account: &mut MarketRefMut) -> ProgramResult {
+    dynamic_account.init_market_stats()
+}
+
+// Stats are informational only and not part of the verified properties.
+#[cfg(feature = "certora")]
+fn init_market_stats(_dynamic_account: &mut MarketRefMut) -> ProgramResult {
+    Ok(())
+}
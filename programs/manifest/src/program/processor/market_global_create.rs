@@ -0,0 +1,62 @@
+use std::mem::size_of;
+
+use hypertree::{get_mut_helper, trace};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey, rent::Rent,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    logs::{emit_stack, GlobalCreateLog},
+    state::GlobalFixed,
+    utils::create_account,
+    validation::{get_market_global_address, loaders::MarketGlobalCreateContext},
+};
+
+pub(crate) fn process_market_global_create(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    trace!("process_market_global_create accs={accounts:?}");
+    let market_global_create_context: MarketGlobalCreateContext =
+        MarketGlobalCreateContext::load(accounts)?;
+    let MarketGlobalCreateContext {
+        payer,
+        market_global,
+        system_program,
+        market,
+        mint,
+    } = market_global_create_context;
+
+    let (_expected_market_global_key, market_global_bump) =
+        get_market_global_address(market.key, mint.info.key);
+    let market_global_seeds: Vec<Vec<u8>> = vec![
+        b"market-global".to_vec(),
+        market.key.as_ref().to_vec(),
+        mint.info.key.as_ref().to_vec(),
+        vec![market_global_bump],
+    ];
+    create_account(
+        payer.as_ref(),
+        market_global.info,
+        system_program.as_ref(),
+        &crate::id(),
+        &Rent::get()?,
+        size_of::<GlobalFixed>() as u64,
+        market_global_seeds,
+    )?;
+
+    // No vault to make, the market vault for the mint holds the balances.
+    let empty_global_fixed: GlobalFixed =
+        GlobalFixed::new_empty_for_market(market.key, mint.info.key);
+    let market_global_bytes: &mut [u8] = &mut market_global.info.try_borrow_mut_data()?[..];
+    *get_mut_helper::<GlobalFixed>(market_global_bytes, 0_u32) = empty_global_fixed;
+
+    emit_stack(GlobalCreateLog {
+        global: *market_global.info.key,
+        creator: *payer.key,
+    })?;
+
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use std::cell::{Ref, RefMut};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+use crate::{
+    global_vault_seeds_with_bump,
+    logs::{emit_stack, GlobalDepositLog, GlobalWithdrawLog},
+    program::{expand_global, get_dynamic_account, get_mut_dynamic_account, transfer_from_vault},
+    quantities::{GlobalAtoms, WrapperU64},
+    state::{GlobalRef, GlobalRefMut},
+    validation::loaders::MarketGlobalContext,
+};
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct MarketGlobalDepositParams {
+    pub amount_atoms: u64,
+}
+
+impl MarketGlobalDepositParams {
+    pub fn new(amount_atoms: u64) -> Self {
+        MarketGlobalDepositParams { amount_atoms }
+    }
+}
+
+/// Moves part of the balance of the payer on the global for a mint to the
+/// market global that a market keeps for the mint. The tokens go from the
+/// global vault to the market vault, so this only works while the market is
+/// on the base layer.
+pub(crate) fn process_market_global_deposit(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let market_global_context: MarketGlobalContext = MarketGlobalContext::load(accounts)?;
+    let MarketGlobalDepositParams { amount_atoms } =
+        MarketGlobalDepositParams::try_from_slice(data)?;
+
+    let MarketGlobalContext {
+        payer,
+        global,
+        market_global,
+        mint,
+        global_vault,
+        market_vault,
+        token_program,
+        hook_accounts,
+        ..
+    } = market_global_context;
+
+    let global_vault_bump: u8 = {
+        let global_data: &mut RefMut<&mut [u8]> = &mut global.try_borrow_mut_data()?;
+        let mut global_dynamic_account: GlobalRefMut = get_mut_dynamic_account(global_data);
+        global_dynamic_account.withdraw_global(payer.key, GlobalAtoms::new(amount_atoms))?;
+        global_dynamic_account.fixed.get_vault_bump()
+    };
+
+    // Due to transfer fees, the market vault might receive less.
+    let before_market_vault_balance_atoms: u64 = market_vault.get_balance_atoms();
+    transfer_from_vault(
+        &token_program,
+        &global_vault,
+        &mint,
+        &market_vault,
+        hook_accounts,
+        amount_atoms,
+        global_vault_seeds_with_bump!(mint.info.key, global_vault_bump),
+    )?;
+    let deposited_amount_atoms: u64 = market_vault
+        .get_balance_atoms()
+        .checked_sub(before_market_vault_balance_atoms)
+        .unwrap();
+
+    // Seats on a market global are only taken here, so take one the first time.
+    let has_trader: bool = {
+        let market_global_data: &Ref<&mut [u8]> = &market_global.try_borrow_data()?;
+        let market_global_dynamic_account: GlobalRef = get_dynamic_account(market_global_data);
+        market_global_dynamic_account.has_trader(payer.key)
+    };
+    if !has_trader {
+        expand_global(&payer, &market_global)?;
+    }
+    {
+        let market_global_data: &mut RefMut<&mut [u8]> =
+            &mut market_global.try_borrow_mut_data()?;
+        let mut market_global_dynamic_account: GlobalRefMut =
+            get_mut_dynamic_account(market_global_data);
+        if !has_trader {
+            market_global_dynamic_account.add_trader(payer.key)?;
+        }
+        market_global_dynamic_account
+            .deposit_global(payer.key, GlobalAtoms::new(deposited_amount_atoms))?;
+    }
+
+    emit_stack(GlobalWithdrawLog {
+        global: *global.key,
+        trader: *payer.key,
+        global_atoms: GlobalAtoms::new(amount_atoms),
+    })?;
+    emit_stack(GlobalDepositLog {
+        global: *market_global.key,
+        trader: *payer.key,
+        global_atoms: GlobalAtoms::new(deposited_amount_atoms),
+    })?;
+
+    Ok(())
+}
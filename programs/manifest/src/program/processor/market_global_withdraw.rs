@@ -0,0 +1,97 @@
+use std::cell::RefMut;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+use crate::{
+    logs::{emit_stack, GlobalDepositLog, GlobalWithdrawLog},
+    market_vault_seeds_with_bump,
+    program::{get_mut_dynamic_account, transfer_from_vault},
+    quantities::{GlobalAtoms, WrapperU64},
+    state::GlobalRefMut,
+    validation::loaders::MarketGlobalContext,
+};
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct MarketGlobalWithdrawParams {
+    pub amount_atoms: u64,
+}
+
+impl MarketGlobalWithdrawParams {
+    pub fn new(amount_atoms: u64) -> Self {
+        MarketGlobalWithdrawParams { amount_atoms }
+    }
+}
+
+/// Moves part of the balance of the payer on a market global back to the
+/// global for the mint. This is how balances that were used on an ephemeral
+/// rollup get back to the global once the market is undelegated.
+pub(crate) fn process_market_global_withdraw(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let market_global_context: MarketGlobalContext = MarketGlobalContext::load(accounts)?;
+    let MarketGlobalWithdrawParams { amount_atoms } =
+        MarketGlobalWithdrawParams::try_from_slice(data)?;
+
+    let MarketGlobalContext {
+        payer,
+        market,
+        global,
+        market_global,
+        mint,
+        global_vault,
+        market_vault,
+        token_program,
+        hook_accounts,
+        ..
+    } = market_global_context;
+
+    let market_vault_bump: u8 = {
+        let market_global_data: &mut RefMut<&mut [u8]> =
+            &mut market_global.try_borrow_mut_data()?;
+        let mut market_global_dynamic_account: GlobalRefMut =
+            get_mut_dynamic_account(market_global_data);
+        market_global_dynamic_account.withdraw_global(payer.key, GlobalAtoms::new(amount_atoms))?;
+        market_global_dynamic_account.fixed.get_vault_bump()
+    };
+
+    // Due to transfer fees, the global vault might receive less.
+    let before_global_vault_balance_atoms: u64 = global_vault.get_balance_atoms();
+    transfer_from_vault(
+        &token_program,
+        &market_vault,
+        &mint,
+        &global_vault,
+        hook_accounts,
+        amount_atoms,
+        market_vault_seeds_with_bump!(market.key, mint.info.key, market_vault_bump),
+    )?;
+    let deposited_amount_atoms: u64 = global_vault
+        .get_balance_atoms()
+        .checked_sub(before_global_vault_balance_atoms)
+        .unwrap();
+
+    // Fails if the trader was evicted from the global in the meantime. They
+    // can take a seat again with GlobalAddTrader and retry.
+    {
+        let global_data: &mut RefMut<&mut [u8]> = &mut global.try_borrow_mut_data()?;
+        let mut global_dynamic_account: GlobalRefMut = get_mut_dynamic_account(global_data);
+        global_dynamic_account
+            .deposit_global(payer.key, GlobalAtoms::new(deposited_amount_atoms))?;
+    }
+
+    emit_stack(GlobalWithdrawLog {
+        global: *market_global.key,
+        trader: *payer.key,
+        global_atoms: GlobalAtoms::new(amount_atoms),
+    })?;
+    emit_stack(GlobalDepositLog {
+        global: *global.key,
+        trader: *payer.key,
+        global_atoms: GlobalAtoms::new(deposited_amount_atoms),
+    })?;
+
+    Ok(())
+}
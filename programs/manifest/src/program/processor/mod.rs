@@ -1,6 +1,11 @@
 pub mod batch_update;
+pub mod cancel_all;
+pub mod cancel_trigger_order;
 pub mod claim_seat;
+pub mod clean_expired;
 pub mod commit_market;
+pub mod compact_market;
+pub mod crank_trigger_orders;
 pub mod create_market;
 pub mod delegate_market;
 pub mod deposit;
@@ -11,9 +16,20 @@ pub mod global_create;
 pub mod global_deposit;
 pub mod global_evict;
 pub mod global_withdraw;
+pub mod init_market_metadata;
+pub mod init_market_stats;
+pub mod market_global_create;
+pub mod market_global_deposit;
+pub mod market_global_withdraw;
+pub mod place_trigger_order;
+pub mod poke_twap;
+pub mod release_seat;
+pub mod set_market_authority;
 pub mod shared;
 pub mod swap;
+pub mod transfer_market_metadata_authority;
 pub mod undelegate_market;
+pub mod update_market_metadata;
 pub mod withdraw;
 
 pub use shared::*;
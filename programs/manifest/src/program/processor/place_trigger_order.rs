@@ -0,0 +1,199 @@
+use std::cell::RefMut;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use hypertree::{trace, DataIndex, PodBool};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    logs::{emit_stack, PlaceTriggerOrderLog},
+    program::{expand_market_if_needed, get_mut_dynamic_account, invoke, ManifestError},
+    quantities::{BaseAtoms, QuoteAtomsPerBaseAtom, WrapperU64},
+    require,
+    state::{
+        utils::{assert_not_already_expired, get_now_slot},
+        MarketRefMut, SelfTradeBehavior, TriggerOrder, TriggerOrderType, GAS_DEPOSIT_LAMPORTS,
+    },
+    validation::loaders::PlaceTriggerOrderContext,
+};
+
+#[derive(Debug, BorshDeserialize, BorshSerialize, Clone)]
+pub struct PlaceTriggerOrderParams {
+    base_atoms: u64,
+    trigger_price_mantissa: u32,
+    trigger_price_exponent: i8,
+    /// Limit for stop limit orders, worst price for the others.
+    price_mantissa: u32,
+    price_exponent: i8,
+    is_bid: bool,
+    trigger_type: TriggerOrderType,
+    last_valid_slot: u32,
+    /// Distance a trailing stop keeps from the price. Zero for other types.
+    trail_mantissa: u32,
+    trail_exponent: i8,
+    /// Used by the order placed once triggered.
+    self_trade_behavior: SelfTradeBehavior,
+}
+
+impl PlaceTriggerOrderParams {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        base_atoms: u64,
+        trigger_price_mantissa: u32,
+        trigger_price_exponent: i8,
+        price_mantissa: u32,
+        price_exponent: i8,
+        is_bid: bool,
+        trigger_type: TriggerOrderType,
+        last_valid_slot: u32,
+    ) -> Self {
+        PlaceTriggerOrderParams {
+            base_atoms,
+            trigger_price_mantissa,
+            trigger_price_exponent,
+            price_mantissa,
+            price_exponent,
+            is_bid,
+            trigger_type,
+            last_valid_slot,
+            trail_mantissa: 0,
+            trail_exponent: 0,
+            self_trade_behavior: SelfTradeBehavior::default(),
+        }
+    }
+    pub fn with_trail(mut self, trail_mantissa: u32, trail_exponent: i8) -> Self {
+        self.trail_mantissa = trail_mantissa;
+        self.trail_exponent = trail_exponent;
+        self
+    }
+    pub fn with_self_trade_behavior(mut self, self_trade_behavior: SelfTradeBehavior) -> Self {
+        self.self_trade_behavior = self_trade_behavior;
+        self
+    }
+}
+
+pub(crate) fn process_place_trigger_order(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let place_trigger_order_context: PlaceTriggerOrderContext =
+        PlaceTriggerOrderContext::load(accounts)?;
+    let PlaceTriggerOrderContext {
+        payer,
+        market,
+        system_program,
+    } = place_trigger_order_context;
+
+    let params: PlaceTriggerOrderParams = PlaceTriggerOrderParams::try_from_slice(data)?;
+    trace!("place_trigger_order params:{params:?}");
+
+    let trigger_price: QuoteAtomsPerBaseAtom =
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(
+            params.trigger_price_mantissa,
+            params.trigger_price_exponent,
+        )?;
+    let price: QuoteAtomsPerBaseAtom = QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(
+        params.price_mantissa,
+        params.price_exponent,
+    )?;
+    require!(
+        params.base_atoms > 0 && trigger_price != QuoteAtomsPerBaseAtom::ZERO,
+        ManifestError::InvalidTriggerOrder,
+        "Trigger order needs a size and a trigger price",
+    )?;
+    require!(
+        (params.trigger_type == TriggerOrderType::TrailingStop) == (params.trail_mantissa != 0),
+        ManifestError::InvalidTriggerOrder,
+        "Trailing stops need a trail and other trigger orders cannot have one",
+    )?;
+    assert_not_already_expired(params.last_valid_slot, get_now_slot())?;
+
+    let mut trigger_order: TriggerOrder = TriggerOrder::new(
+        DataIndex::default(),
+        BaseAtoms::new(params.base_atoms),
+        trigger_price,
+        price,
+        0,
+        params.last_valid_slot,
+        params.is_bid,
+        params.trigger_type,
+    );
+    trigger_order.set_trail(params.trail_mantissa, params.trail_exponent);
+    trigger_order.set_self_trade_behavior(params.self_trade_behavior);
+    trigger_order.try_trail()?;
+
+    let (order_sequence_number, order_index) = {
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+
+        // Stop limits can rest once triggered, so hold them to the same
+        // limits as resting orders now rather than fail at the crank.
+        if params.trigger_type == TriggerOrderType::StopLimit {
+            let min_base_atoms: BaseAtoms = dynamic_account.fixed.get_min_base_atoms();
+            let price_tick: QuoteAtomsPerBaseAtom = dynamic_account.fixed.get_price_tick();
+            require!(
+                trigger_order.get_num_base_atoms() >= min_base_atoms
+                    && (price_tick == QuoteAtomsPerBaseAtom::ZERO
+                        || price.is_multiple_of(price_tick)),
+                ManifestError::InvalidOrderSizeOrTick,
+                "Stop limit is below the market minimum {} or off the tick {}",
+                min_base_atoms,
+                price_tick,
+            )?;
+        }
+
+        let trader_index: DataIndex = dynamic_account.get_trader_index(payer.key);
+        trigger_order.set_trader_index(trader_index);
+        place_trigger_order(&mut dynamic_account, trigger_order)?
+    };
+
+    // Held on the market until the order is cranked or cancelled.
+    invoke(
+        &solana_program::system_instruction::transfer(payer.key, market.key, GAS_DEPOSIT_LAMPORTS),
+        &[
+            payer.info.clone(),
+            market.info.clone(),
+            system_program.info.clone(),
+        ],
+    )?;
+
+    // Leave a free block for the order once it triggers.
+    expand_market_if_needed(&payer, &market)?;
+
+    emit_stack(PlaceTriggerOrderLog {
+        market: *market.key,
+        trader: *payer.key,
+        trigger_price,
+        price,
+        base_atoms: trigger_order.get_num_base_atoms(),
+        order_sequence_number,
+        order_index,
+        last_valid_slot: params.last_valid_slot,
+        trigger_type: params.trigger_type,
+        is_bid: PodBool::from(params.is_bid),
+        _padding: [0; 6],
+    })?;
+
+    Ok(())
+}
+
+#[cfg(not(feature = "certora"))]
+fn place_trigger_order(
+    dynamic_account: &mut MarketRefMut,
+    trigger_order: TriggerOrder,
+) -> Result<(u64, DataIndex), ProgramError> {
+    dynamic_account.place_trigger_order(trigger_order)
+}
+
+// Trigger orders are not part of the verified properties, so placing one is a
+// no-op for formal verification.
+#[cfg(feature = "certora")]
+fn place_trigger_order(
+    _dynamic_account: &mut MarketRefMut,
+    _trigger_order: TriggerOrder,
+) -> Result<(u64, DataIndex), ProgramError> {
+    Ok((0, hypertree::NIL))
+}
@@ -0,0 +1,37 @@
+use std::cell::RefMut;
+
+use hypertree::trace;
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+use crate::{
+    program::get_mut_dynamic_account,
+    state::{utils::get_now_slot, MarketRefMut},
+    validation::loaders::PokeTwapContext,
+};
+
+pub(crate) fn process_poke_twap(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    let poke_twap_context: PokeTwapContext = PokeTwapContext::load(accounts)?;
+    let PokeTwapContext { market, .. } = poke_twap_context;
+
+    let now_slot: u32 = get_now_slot();
+    trace!("poke_twap market:{} now_slot:{now_slot}", market.key);
+
+    let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+    let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+    update_twap(&mut dynamic_account, now_slot);
+
+    Ok(())
+}
+
+#[cfg(not(feature = "certora"))]
+fn update_twap(dynamic_account: &mut MarketRefMut, now_slot: u32) {
+    dynamic_account.fixed.update_twap(now_slot);
+}
+
+// The TWAP is informational only and not part of the verified properties.
+#[cfg(feature = "certora")]
+fn update_twap(_dynamic_account: &mut MarketRefMut, _now_slot: u32) {}
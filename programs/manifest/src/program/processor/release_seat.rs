@@ -0,0 +1,75 @@
+use std::cell::RefMut;
+
+#[cfg(not(feature = "certora"))]
+use crate::state::TriggerOrder;
+use crate::{
+    logs::{emit_stack, ReleaseSeatLog},
+    program::ManifestError,
+    quantities::{BaseAtoms, QuoteAtoms},
+    require,
+    state::{utils::assert_already_has_seat, MarketRefMut, RestingOrder},
+    validation::loaders::ReleaseSeatContext,
+};
+use hypertree::{DataIndex, HyperTreeValueIteratorTrait};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+use super::shared::get_mut_dynamic_account;
+
+pub(crate) fn process_release_seat(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    let release_seat_context: ReleaseSeatContext = ReleaseSeatContext::load(accounts)?;
+    let ReleaseSeatContext { market, payer } = release_seat_context;
+
+    let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+    let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+
+    let trader_index: DataIndex = dynamic_account.get_trader_index(payer.key);
+    assert_already_has_seat(trader_index)?;
+
+    let (base_balance, quote_balance): (BaseAtoms, QuoteAtoms) =
+        dynamic_account.get_trader_balance(payer.key);
+    require!(
+        base_balance == BaseAtoms::ZERO && quote_balance == QuoteAtoms::ZERO,
+        ManifestError::SeatNotEmpty,
+        "Withdraw all funds before releasing seat, base {} quote {}",
+        base_balance,
+        quote_balance,
+    )?;
+
+    // Orders hold the trader index, so the seat cannot be reused while any of
+    // them are still on the book.
+    let has_resting_orders: bool = dynamic_account
+        .get_bids()
+        .iter::<RestingOrder>()
+        .chain(dynamic_account.get_asks().iter::<RestingOrder>())
+        .any(|(_, order)| order.get_trader_index() == trader_index);
+    require!(
+        !has_resting_orders,
+        ManifestError::SeatNotEmpty,
+        "Cancel all orders before releasing seat",
+    )?;
+    #[cfg(not(feature = "certora"))]
+    {
+        let has_trigger_orders: bool = dynamic_account
+            .get_trigger_orders()
+            .iter::<TriggerOrder>()
+            .any(|(_, order)| order.get_trader_index() == trader_index);
+        require!(
+            !has_trigger_orders,
+            ManifestError::SeatNotEmpty,
+            "Cancel all trigger orders before releasing seat",
+        )?;
+    }
+
+    dynamic_account.release_seat(payer.key)?;
+
+    emit_stack(ReleaseSeatLog {
+        market: *market.key,
+        trader: *payer.key,
+    })?;
+
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use std::cell::RefMut;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use hypertree::trace;
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+use crate::{
+    logs::{emit_stack, MarketAuthorityLog},
+    program::{assert_market_authority, expand_market_if_needed, get_mut_dynamic_account},
+    state::MarketRefMut,
+    validation::loaders::SetMarketAuthorityContext,
+};
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct SetMarketAuthorityParams {
+    pub new_authority: Pubkey,
+}
+
+impl SetMarketAuthorityParams {
+    pub fn new(new_authority: Pubkey) -> Self {
+        SetMarketAuthorityParams { new_authority }
+    }
+}
+
+pub(crate) fn process_set_market_authority(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let set_market_authority_context: SetMarketAuthorityContext =
+        SetMarketAuthorityContext::load(accounts)?;
+    let SetMarketAuthorityContext {
+        payer,
+        authority,
+        market,
+        ..
+    } = set_market_authority_context;
+    let SetMarketAuthorityParams { new_authority } =
+        SetMarketAuthorityParams::try_from_slice(data)?;
+    trace!(
+        "set_market_authority market:{} new_authority:{}",
+        market.key,
+        new_authority
+    );
+
+    // The market keypair signs to set the first one.
+    assert_market_authority(&market, &authority)?;
+
+    // The first authority takes a block, so make sure there is one to take.
+    expand_market_if_needed(&payer, &market)?;
+    {
+        let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        set_market_authority(&mut dynamic_account, &new_authority)?;
+    }
+
+    // Leave a free block on the market so takers can use and leave it.
+    expand_market_if_needed(&payer, &market)?;
+
+    emit_stack(MarketAuthorityLog {
+        market: *market.key,
+        authority: new_authority,
+    })?;
+
+    Ok(())
+}
+
+#[cfg(not(feature = "certora"))]
+fn set_market_authority(dynamic_account: &mut MarketRefMut, authority: &Pubkey) -> ProgramResult {
+    dynamic_account.set_market_authority(authority)
+}
+
+// The authority only gates the market metadata, compaction and delegation,
+// which are not part of the verified properties.
+#[cfg(feature = "certora")]
+fn set_market_authority(_dynamic_account: &mut MarketRefMut, _authority: &Pubkey) -> ProgramResult {
+    Ok(())
+}
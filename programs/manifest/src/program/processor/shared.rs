@@ -3,13 +3,20 @@ use std::{
     mem::size_of,
 };
 
+#[cfg(not(feature = "certora"))]
+use crate::{
+    quantities::QuoteAtomsPerBaseAtom, state::utils::get_now_slot, validation::OracleAccountInfo,
+};
 use crate::{
     require,
     state::{
         claimed_seat::ClaimedSeat, constants::MARKET_BLOCK_SIZE, DynamicAccount, GlobalFixed,
-        MarketFixed, MarketRefMut, GLOBAL_BLOCK_SIZE,
+        MarketFixed, MarketRef, MarketRefMut, GLOBAL_BLOCK_SIZE,
+    },
+    validation::{
+        ManifestAccount, ManifestAccountInfo, MintAccountInfo, Signer, TokenAccountInfo,
+        TokenProgram,
     },
-    validation::{ManifestAccount, ManifestAccountInfo, Signer},
 };
 use bytemuck::Pod;
 use hypertree::{get_helper, get_mut_helper, DataIndex, Get, RBNode};
@@ -17,7 +24,7 @@ use hypertree::{get_helper, get_mut_helper, DataIndex, Get, RBNode};
 use solana_program::sysvar::Sysvar;
 use solana_program::{
     account_info::AccountInfo, entrypoint::ProgramResult, instruction::Instruction,
-    sysvar::slot_history::ProgramError,
+    program::invoke_signed, pubkey::Pubkey, sysvar::slot_history::ProgramError,
 };
 
 use super::batch_update::MarketDataTreeNodeType;
@@ -42,8 +49,22 @@ pub(crate) fn expand_market<'a, 'info, T: ManifestAccount + Pod + Clone>(
     payer: &Signer<'a, 'info>,
     manifest_account: &ManifestAccountInfo<'a, 'info, T>,
 ) -> ProgramResult {
-    expand_dynamic(payer, manifest_account, MARKET_BLOCK_SIZE)?;
-    expand_market_fixed(manifest_account.info)?;
+    expand_market_by(payer, manifest_account, 1)
+}
+
+// Grows the market by num_blocks with a single realloc. Caller is responsible
+// for staying under MARKET_MAX_EXPAND_BLOCKS.
+pub(crate) fn expand_market_by<'a, 'info, T: ManifestAccount + Pod + Clone>(
+    payer: &Signer<'a, 'info>,
+    manifest_account: &ManifestAccountInfo<'a, 'info, T>,
+    num_blocks: u32,
+) -> ProgramResult {
+    expand_dynamic(
+        payer,
+        manifest_account,
+        MARKET_BLOCK_SIZE * num_blocks as usize,
+    )?;
+    expand_market_fixed(manifest_account.info, num_blocks)?;
     Ok(())
 }
 
@@ -108,11 +129,11 @@ fn expand_dynamic<'a, 'info, T: ManifestAccount + Pod + Clone>(
     Ok(())
 }
 
-fn expand_market_fixed(expandable_account: &AccountInfo) -> ProgramResult {
+fn expand_market_fixed(expandable_account: &AccountInfo, num_blocks: u32) -> ProgramResult {
     let market_data: &mut RefMut<&mut [u8]> = &mut expandable_account.try_borrow_mut_data()?;
     let mut dynamic_account: DynamicAccount<&mut MarketFixed, &mut [u8]> =
         get_mut_dynamic_account(market_data);
-    dynamic_account.market_expand()?;
+    dynamic_account.market_expand_by(num_blocks)?;
     Ok(())
 }
 
@@ -159,6 +180,95 @@ pub fn get_dynamic_value<T: Get>(data: &[u8]) -> DynamicAccount<T, Vec<u8>> {
     dynamic_account
 }
 
+/// Fails unless the signer is the market authority. Until one is set with
+/// SetMarketAuthority, the market keypair is the authority since it is the
+/// only key tied to the creation of the market.
+pub(crate) fn assert_market_authority(
+    market_account_info: &AccountInfo,
+    authority: &Signer,
+) -> ProgramResult {
+    let market_data: &Ref<&mut [u8]> = &market_account_info.try_borrow_data()?;
+    let dynamic_account: MarketRef = get_dynamic_account(market_data);
+    let market_authority: &Pubkey = dynamic_account
+        .get_market_authority()
+        .unwrap_or(market_account_info.key);
+    require!(
+        market_authority == authority.key,
+        crate::program::ManifestError::InvalidMarketAuthority,
+        "Expected market authority {}",
+        market_authority,
+    )
+}
+
+/// Which of the base and quote market globals are among the given mints.
+/// Each has to be for a mint of the market and can only be given once.
+pub(crate) fn get_market_global_sides(
+    market_fixed: &MarketFixed,
+    market_global_mints: &[Pubkey],
+) -> Result<(bool, bool), ProgramError> {
+    let mut has_base_market_global: bool = false;
+    let mut has_quote_market_global: bool = false;
+    for mint in market_global_mints {
+        if mint == market_fixed.get_base_mint() && !has_base_market_global {
+            has_base_market_global = true;
+        } else if mint == market_fixed.get_quote_mint() && !has_quote_market_global {
+            has_quote_market_global = true;
+        } else {
+            return Err(crate::program::ManifestError::IncorrectAccount.into());
+        }
+    }
+    Ok((has_base_market_global, has_quote_market_global))
+}
+
+/// Fails unless the market globals are exactly the ones that went to the
+/// rollup with the market. One left out of UndelegateMarket would stay with
+/// the delegation program and its balances with it.
+#[cfg(not(feature = "certora"))]
+pub(crate) fn assert_delegated_market_globals(
+    market_account_info: &AccountInfo,
+    market_globals: &[ManifestAccountInfo<GlobalFixed>],
+) -> ProgramResult {
+    let mut market_global_mints: Vec<Pubkey> = Vec::with_capacity(market_globals.len());
+    for market_global in market_globals {
+        market_global_mints.push(*market_global.get_fixed()?.get_mint());
+    }
+    let market_data: &Ref<&mut [u8]> = &market_account_info.try_borrow_data()?;
+    let dynamic_account: MarketRef = get_dynamic_account(market_data);
+    let (has_base_market_global, has_quote_market_global) =
+        get_market_global_sides(dynamic_account.fixed, &market_global_mints)?;
+    let (expected_base_market_global, expected_quote_market_global) = dynamic_account
+        .get_delegation_status()
+        .map_or((false, false), |status| {
+            (
+                status.has_base_market_global(),
+                status.has_quote_market_global(),
+            )
+        });
+    require!(
+        has_base_market_global >= expected_base_market_global
+            && has_quote_market_global >= expected_quote_market_global,
+        crate::program::ManifestError::MissingGlobal,
+        "Expected the market globals delegated with the market base:{} quote:{}",
+        expected_base_market_global,
+        expected_quote_market_global,
+    )?;
+    require!(
+        has_base_market_global == expected_base_market_global
+            && has_quote_market_global == expected_quote_market_global,
+        crate::program::ManifestError::IncorrectAccount,
+        "Market global was not delegated with the market",
+    )
+}
+
+// Delegation is not part of the verified properties.
+#[cfg(feature = "certora")]
+pub(crate) fn assert_delegated_market_globals(
+    _market_account_info: &AccountInfo,
+    _market_globals: &[ManifestAccountInfo<GlobalFixed>],
+) -> ProgramResult {
+    Ok(())
+}
+
 // Uses a MarketRefMut instead of a MarketRef because callers will have mutable data.
 pub(crate) fn get_trader_index_with_hint(
     trader_index_hint: Option<DataIndex>,
@@ -186,6 +296,12 @@ fn verify_trader_index_hint(
         "Invalid trader hint index {} did not align",
         hinted_index,
     )?;
+    require!(
+        (hinted_index as usize) < dynamic_account.dynamic.len(),
+        crate::program::ManifestError::WrongIndexHintParams,
+        "Invalid trader hint index {} out of bounds",
+        hinted_index,
+    )?;
     require!(
         get_helper::<RBNode<ClaimedSeat>>(&dynamic_account.dynamic, hinted_index)
             .get_payload_type()
@@ -205,6 +321,40 @@ fn verify_trader_index_hint(
     Ok(())
 }
 
+/// Reprices pegged orders when the market peg oracle is one of the accounts
+/// and fresh. Without it, pegged orders keep their last price, cannot be
+/// placed and are walked past when matching.
+#[cfg(not(feature = "certora"))]
+pub(crate) fn reprice_pegged_orders_if_oracle_included(
+    market: &ManifestAccountInfo<MarketFixed>,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
+    let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+    let Some(peg_config) = dynamic_account.get_peg_config() else {
+        return Ok(());
+    };
+    let max_staleness_slots: u64 = peg_config.get_max_staleness_slots();
+    let Some(oracle_info) = accounts
+        .iter()
+        .find(|account| account.key == peg_config.get_oracle())
+    else {
+        return Ok(());
+    };
+
+    let oracle: OracleAccountInfo = OracleAccountInfo::new(oracle_info)?;
+    let now_slot: u32 = get_now_slot();
+    // A stale oracle must not stop others from trading on the market.
+    if !oracle.price.is_fresh(now_slot as u64, max_staleness_slots) {
+        return Ok(());
+    }
+    let oracle_price: QuoteAtomsPerBaseAtom = oracle.price.get_quote_atoms_per_base_atom(
+        dynamic_account.fixed.get_base_mint_decimals(),
+        dynamic_account.fixed.get_quote_mint_decimals(),
+    )?;
+    dynamic_account.reprice_pegged_orders(oracle_price, now_slot)
+}
+
 // TODO: Same for invoke_signed
 
 pub fn invoke(ix: &Instruction, account_infos: &[AccountInfo<'_>]) -> ProgramResult {
@@ -217,3 +367,95 @@ pub fn invoke(ix: &Instruction, account_infos: &[AccountInfo<'_>]) -> ProgramRes
         solana_program::program::invoke(ix, account_infos)
     }
 }
+
+/// Token22 transfer_checked for a mint with a transfer hook. The hook program,
+/// its extra account metas list and every account in that list must be in
+/// hook_accounts. Anything else in there is ignored.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn invoke_transfer_checked_with_hook<'info>(
+    token_program: &AccountInfo<'info>,
+    source: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    hook_accounts: &[AccountInfo<'info>],
+    amount: u64,
+    decimals: u8,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    spl_token_2022::onchain::invoke_transfer_checked(
+        token_program.key,
+        source.clone(),
+        mint.clone(),
+        destination.clone(),
+        authority.clone(),
+        hook_accounts,
+        amount,
+        decimals,
+        signer_seeds,
+    )
+}
+
+/// Transfer out of a vault that is its own owner, like the market and global
+/// vaults, into another token account. The signer seeds are the ones of the
+/// source vault.
+pub(crate) fn transfer_from_vault<'a, 'info>(
+    token_program: &TokenProgram<'a, 'info>,
+    vault: &TokenAccountInfo<'a, 'info>,
+    mint: &MintAccountInfo<'a, 'info>,
+    destination: &TokenAccountInfo<'a, 'info>,
+    hook_accounts: &[AccountInfo<'info>],
+    amount_atoms: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    if *vault.owner == spl_token_2022::id() && !hook_accounts.is_empty() {
+        invoke_transfer_checked_with_hook(
+            token_program.as_ref(),
+            vault.as_ref(),
+            mint.as_ref(),
+            destination.as_ref(),
+            vault.as_ref(),
+            hook_accounts,
+            amount_atoms,
+            mint.mint.decimals,
+            signer_seeds,
+        )
+    } else if *vault.owner == spl_token_2022::id() {
+        invoke_signed(
+            &spl_token_2022::instruction::transfer_checked(
+                token_program.key,
+                vault.key,
+                mint.info.key,
+                destination.key,
+                vault.key,
+                &[],
+                amount_atoms,
+                mint.mint.decimals,
+            )?,
+            &[
+                token_program.as_ref().clone(),
+                vault.as_ref().clone(),
+                mint.as_ref().clone(),
+                destination.as_ref().clone(),
+            ],
+            signer_seeds,
+        )
+    } else {
+        invoke_signed(
+            &spl_token::instruction::transfer(
+                token_program.key,
+                vault.key,
+                destination.key,
+                vault.key,
+                &[],
+                amount_atoms,
+            )?,
+            &[
+                token_program.as_ref().clone(),
+                vault.as_ref().clone(),
+                destination.as_ref().clone(),
+            ],
+            signer_seeds,
+        )
+    }
+}
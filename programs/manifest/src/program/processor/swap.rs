@@ -1,20 +1,23 @@
 use std::cell::RefMut;
 
+#[cfg(not(feature = "certora"))]
+use super::shared::reprice_pegged_orders_if_oracle_included;
 use crate::{
-    logs::{emit_stack, PlaceOrderLogV2},
+    logs::{emit_stack, PlaceOrderLogV2, TakerSettlementLog},
     program::expand_market_if_needed,
     quantities::{BaseAtoms, QuoteAtoms, QuoteAtomsPerBaseAtom, WrapperU64},
     require,
     state::{
-        AddOrderToMarketArgs, AddOrderToMarketResult, MarketRefMut, OrderType,
-        NO_EXPIRATION_LAST_VALID_SLOT,
+        utils::get_now_slot, AddOrderToMarketArgs, AddOrderToMarketResult, MarketRefMut, OrderType,
+        PriceBand, SelfTradeBehavior, NO_EXPIRATION_LAST_VALID_SLOT,
+        NO_EXPIRATION_LAST_VALID_UNIX_TIMESTAMP,
     },
-    validation::loaders::SwapContext,
+    validation::{loaders::SwapContext, OracleAccountInfo},
 };
 #[cfg(not(feature = "certora"))]
 use crate::{
     market_vault_seeds_with_bump,
-    program::{invoke, ManifestError},
+    program::{invoke, invoke_transfer_checked_with_hook, ManifestError},
 };
 use borsh::{BorshDeserialize, BorshSerialize};
 use hypertree::{trace, DataIndex, NIL};
@@ -32,7 +35,7 @@ use {
 use crate::validation::{MintAccountInfo, Signer, TokenAccountInfo, TokenProgram};
 use solana_program::program_error::ProgramError;
 
-#[derive(BorshDeserialize, BorshSerialize)]
+#[derive(BorshSerialize)]
 pub struct SwapParams {
     pub in_atoms: u64,
     pub out_atoms: u64,
@@ -41,6 +44,26 @@ pub struct SwapParams {
     // desired. If not that much can be fulfilled, less will be allowed assuming
     // the min_out/max_in is satisfied.
     pub is_exact_in: bool,
+    /// When set, the last account is a Pyth or Switchboard price account and
+    /// the swap fails if any fill is too far from the oracle price.
+    pub oracle_price_band: Option<OraclePriceBandParams>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug)]
+pub struct OraclePriceBandParams {
+    /// Allowed distance of every fill from the oracle price.
+    pub max_deviation_bps: u16,
+    /// Oldest oracle update that is still accepted.
+    pub max_staleness_slots: u64,
+}
+
+impl OraclePriceBandParams {
+    pub fn new(max_deviation_bps: u16, max_staleness_slots: u64) -> Self {
+        OraclePriceBandParams {
+            max_deviation_bps,
+            max_staleness_slots,
+        }
+    }
 }
 
 impl SwapParams {
@@ -50,8 +73,46 @@ impl SwapParams {
             out_atoms,
             is_base_in,
             is_exact_in,
+            oracle_price_band: None,
         }
     }
+    pub fn with_oracle_price_band(mut self, oracle_price_band: OraclePriceBandParams) -> Self {
+        self.oracle_price_band = Some(oracle_price_band);
+        self
+    }
+}
+
+impl BorshDeserialize for SwapParams {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        Ok(SwapParams {
+            in_atoms: BorshDeserialize::deserialize_reader(reader)?,
+            out_atoms: BorshDeserialize::deserialize_reader(reader)?,
+            is_base_in: BorshDeserialize::deserialize_reader(reader)?,
+            is_exact_in: BorshDeserialize::deserialize_reader(reader)?,
+            oracle_price_band: BorshDeserialize::deserialize_reader(reader)?,
+        })
+    }
+
+    // The oracle price band was added after the other fields. Clients that
+    // predate it stop after is_exact_in, so a missing band is treated as none.
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        let in_atoms: u64 = BorshDeserialize::deserialize(buf)?;
+        let out_atoms: u64 = BorshDeserialize::deserialize(buf)?;
+        let is_base_in: bool = BorshDeserialize::deserialize(buf)?;
+        let is_exact_in: bool = BorshDeserialize::deserialize(buf)?;
+        let oracle_price_band: Option<OraclePriceBandParams> = if buf.is_empty() {
+            None
+        } else {
+            BorshDeserialize::deserialize(buf)?
+        };
+        Ok(SwapParams {
+            in_atoms,
+            out_atoms,
+            is_base_in,
+            is_exact_in,
+            oracle_price_band,
+        })
+    }
 }
 
 pub(crate) fn process_swap(
@@ -83,9 +144,13 @@ pub(crate) fn process_swap_core(
         token_program_quote,
         base_mint,
         quote_mint,
+        hook_accounts,
         global_trade_accounts_opts,
     } = swap_context;
 
+    #[cfg(not(feature = "certora"))]
+    reprice_pegged_orders_if_oracle_included(&market, accounts)?;
+
     let (existing_seat_index, trader_index, initial_base_atoms, initial_quote_atoms) = {
         let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
         let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
@@ -120,10 +185,30 @@ pub(crate) fn process_swap_core(
         out_atoms,
         is_base_in,
         is_exact_in,
+        oracle_price_band,
     } = params;
 
     trace!("swap in_atoms:{in_atoms} out_atoms:{out_atoms} is_base_in:{is_base_in} is_exact_in:{is_exact_in}");
 
+    let price_band_opt: Option<PriceBand> = match oracle_price_band {
+        Some(OraclePriceBandParams {
+            max_deviation_bps,
+            max_staleness_slots,
+        }) => {
+            let oracle: OracleAccountInfo =
+                OracleAccountInfo::new(accounts.last().ok_or(ProgramError::NotEnoughAccountKeys)?)?;
+            oracle
+                .price
+                .assert_fresh(get_now_slot() as u64, max_staleness_slots)?;
+            Some(oracle.price.get_price_band(
+                dynamic_account.fixed.get_base_mint_decimals(),
+                dynamic_account.fixed.get_quote_mint_decimals(),
+                max_deviation_bps,
+            )?)
+        }
+        None => None,
+    };
+
     // This check is redundant with the check that will be done within token
     // program on deposit, but it is done here to future proof in case we later
     // remove checked math.
@@ -226,9 +311,14 @@ pub(crate) fn process_swap_core(
             price,
             is_bid: !is_base_in,
             last_valid_slot,
+            last_valid_unix_timestamp: NO_EXPIRATION_LAST_VALID_UNIX_TIMESTAMP,
             order_type,
+            self_trade_behavior: SelfTradeBehavior::Allow,
+            min_base_atoms_filled: BaseAtoms::ZERO,
             global_trade_accounts_opts: &global_trade_accounts_opts,
             current_slot: None,
+            price_band_opt,
+            peg_opt: None,
         },
     )?;
 
@@ -285,6 +375,7 @@ pub(crate) fn process_swap_core(
                 &owner,
                 (initial_credit_base_atoms.checked_sub(extra_base_atoms)?).as_u64(),
                 dynamic_account.fixed.get_base_mint_decimals(),
+                hook_accounts,
             )?;
         } else {
             spl_token_transfer_from_trader_to_vault(
@@ -309,6 +400,7 @@ pub(crate) fn process_swap_core(
                 dynamic_account.fixed.get_quote_mint_decimals(),
                 market.key,
                 quote_vault_bump,
+                hook_accounts,
             )?;
         } else {
             spl_token_transfer_from_vault_to_trader(
@@ -339,6 +431,7 @@ pub(crate) fn process_swap_core(
                 &owner,
                 (initial_credit_quote_atoms.checked_sub(extra_quote_atoms)?).as_u64(),
                 dynamic_account.fixed.get_quote_mint_decimals(),
+                hook_accounts,
             )?;
         } else {
             spl_token_transfer_from_trader_to_vault(
@@ -363,6 +456,7 @@ pub(crate) fn process_swap_core(
                 dynamic_account.fixed.get_base_mint_decimals(),
                 market.key,
                 base_vault_bump,
+                hook_accounts,
             )?;
         } else {
             spl_token_transfer_from_vault_to_trader(
@@ -377,15 +471,18 @@ pub(crate) fn process_swap_core(
         }
     }
 
-    if existing_seat_index == NIL {
-        dynamic_account.release_seat(owner.key)?;
-    } else {
-        // Withdraw in case there already was a seat so it doesnt mess with their
-        // balances. Need to withdraw base and quote in case the order wasnt fully
-        // filled.
-        dynamic_account.withdraw(trader_index, extra_base_atoms.as_u64(), true)?;
-        dynamic_account.withdraw(trader_index, extra_quote_atoms.as_u64(), false)?;
-    }
+    let (taker_base_balance, taker_quote_balance): (BaseAtoms, QuoteAtoms) =
+        if existing_seat_index == NIL {
+            dynamic_account.release_seat(owner.key)?;
+            (BaseAtoms::ZERO, QuoteAtoms::ZERO)
+        } else {
+            // Withdraw in case there already was a seat so it doesnt mess with their
+            // balances. Need to withdraw base and quote in case the order wasnt fully
+            // filled.
+            dynamic_account.withdraw(trader_index, extra_base_atoms.as_u64(), true)?;
+            dynamic_account.withdraw(trader_index, extra_quote_atoms.as_u64(), false)?;
+            dynamic_account.get_trader_balance(owner.key)
+        };
     // Verify that there wasnt a reverse order that took the only spare block.
     require!(
         dynamic_account.has_free_block(),
@@ -406,6 +503,15 @@ pub(crate) fn process_swap_core(
         order_index,
         last_valid_slot,
     })?;
+    if base_atoms_traded > BaseAtoms::ZERO {
+        emit_stack(TakerSettlementLog {
+            market: *market.key,
+            taker: *owner.key,
+            taker_base_balance,
+            taker_quote_balance,
+            taker_sequence_number: order_sequence_number,
+        })?;
+    }
 
     Ok(())
 }
@@ -475,7 +581,21 @@ fn spl_token_2022_transfer_from_trader_to_vault<'a, 'info>(
     owner: &Signer<'a, 'info>,
     amount: u64,
     decimals: u8,
+    hook_accounts: &[AccountInfo<'info>],
 ) -> ProgramResult {
+    if !hook_accounts.is_empty() {
+        return invoke_transfer_checked_with_hook(
+            token_program.as_ref(),
+            trader_account.as_ref(),
+            mint.unwrap().as_ref(),
+            vault.as_ref(),
+            owner.as_ref(),
+            hook_accounts,
+            amount,
+            decimals,
+            &[],
+        );
+    }
     invoke(
         &spl_token_2022::instruction::transfer_checked(
             token_program.key,
@@ -508,6 +628,7 @@ fn spl_token_2022_transfer_from_trader_to_vault<'a, 'info>(
     owner: &Signer<'a, 'info>,
     amount: u64,
     _decimals: u8,
+    _hook_accounts: &[AccountInfo<'info>],
 ) -> ProgramResult {
     spl_token_2022_transfer(trader_account.info, vault.info, owner.info, amount)
 }
@@ -567,7 +688,21 @@ fn spl_token_2022_transfer_from_vault_to_trader<'a, 'info>(
     decimals: u8,
     market_key: &Pubkey,
     vault_bump: u8,
+    hook_accounts: &[AccountInfo<'info>],
 ) -> ProgramResult {
+    if !hook_accounts.is_empty() {
+        return invoke_transfer_checked_with_hook(
+            token_program.as_ref(),
+            vault.as_ref(),
+            mint.unwrap().as_ref(),
+            trader_account.as_ref(),
+            vault.as_ref(),
+            hook_accounts,
+            amount,
+            decimals,
+            market_vault_seeds_with_bump!(market_key, mint_pubkey, vault_bump),
+        );
+    }
     solana_program::program::invoke_signed(
         &spl_token_2022::instruction::transfer_checked(
             token_program.key,
@@ -601,6 +736,7 @@ fn spl_token_2022_transfer_from_vault_to_trader<'a, 'info>(
     _decimals: u8,
     _market_key: &Pubkey,
     _vault_bump: u8,
+    _hook_accounts: &[AccountInfo<'info>],
 ) -> ProgramResult {
     spl_token_2022_transfer(vault.info, trader_account.info, vault.info, amount)
 }
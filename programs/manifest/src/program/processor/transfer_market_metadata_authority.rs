@@ -0,0 +1,47 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use hypertree::{get_mut_helper, trace};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+use crate::{
+    logs::{emit_stack, MarketMetadataLog},
+    state::MarketMetadata,
+    validation::loaders::MarketMetadataAuthorityContext,
+};
+
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+pub struct TransferMarketMetadataAuthorityParams {
+    pub new_authority: Pubkey,
+}
+
+impl TransferMarketMetadataAuthorityParams {
+    pub fn new(new_authority: Pubkey) -> Self {
+        TransferMarketMetadataAuthorityParams { new_authority }
+    }
+}
+
+pub(crate) fn process_transfer_market_metadata_authority(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let market_metadata_authority_context: MarketMetadataAuthorityContext =
+        MarketMetadataAuthorityContext::load(accounts)?;
+    let MarketMetadataAuthorityContext {
+        market_metadata, ..
+    } = market_metadata_authority_context;
+    let TransferMarketMetadataAuthorityParams { new_authority } =
+        TransferMarketMetadataAuthorityParams::try_from_slice(data)?;
+    trace!("transfer_market_metadata_authority new_authority:{new_authority}");
+
+    let metadata_bytes: &mut [u8] = &mut market_metadata.info.try_borrow_mut_data()?[..];
+    let metadata: &mut MarketMetadata = get_mut_helper::<MarketMetadata>(metadata_bytes, 0_u32);
+    metadata.set_authority(&new_authority);
+
+    emit_stack(MarketMetadataLog {
+        market: *metadata.get_market(),
+        market_metadata: *market_metadata.info.key,
+        authority: new_authority,
+    })?;
+
+    Ok(())
+}
@@ -1,5 +1,15 @@
+use std::cell::{Ref, RefMut};
+
 use crate::{
     logs::{emit_stack, UndelegateMarketLog},
+    program::{
+        assert_delegated_market_globals, assert_market_authority, get_dynamic_account,
+        get_mut_dynamic_account,
+    },
+    state::{
+        utils::{get_now_slot, get_now_unix_timestamp},
+        MarketRef, MarketRefMut,
+    },
     validation::loaders::UndelegateMarketContext,
 };
 use ephemeral_rollups_sdk::ephem::commit_and_undelegate_accounts;
@@ -23,15 +33,51 @@ pub(crate) fn process_undelegate_market(
     if *undelegate_context.quote_vault.key != *market_data.get_quote_vault() {
         return Err(ProgramError::InvalidAccountData);
     }
+    drop(market_data);
+
+    // The market globals that went to the rollup have to come back with the
+    // market.
+    assert_delegated_market_globals(
+        &undelegate_context.market,
+        &undelegate_context.market_globals,
+    )?;
+
+    // Until the time limit runs out only the market authority can bring the
+    // market back, after that anyone can.
+    let is_time_limit_reached: bool = {
+        let market_data: &Ref<&mut [u8]> = &undelegate_context.market.try_borrow_data()?;
+        let dynamic_account: MarketRef = get_dynamic_account(market_data);
+        is_time_limit_reached(&dynamic_account, get_now_unix_timestamp())
+    };
+    if !is_time_limit_reached {
+        assert_market_authority(&undelegate_context.market, &undelegate_context.payer)?;
+    }
+
+    // Written before the commit so that the base layer copy has it.
+    {
+        let market_data: &mut RefMut<&mut [u8]> =
+            &mut undelegate_context.market.try_borrow_mut_data()?;
+        let mut dynamic_account: MarketRefMut = get_mut_dynamic_account(market_data);
+        set_undelegated(&mut dynamic_account, get_now_slot());
+    }
 
-    // Commit and undelegate all accounts (market and both vaults)
+    // Commit and undelegate the market, both vaults and the market globals.
+    // Once back on the base layer, traders can move their market global
+    // balances back to the global with MarketGlobalWithdraw.
+    let mut accounts_to_commit: Vec<&AccountInfo> = vec![
+        undelegate_context.market.as_ref(),
+        undelegate_context.base_vault,
+        undelegate_context.quote_vault,
+    ];
+    accounts_to_commit.extend(
+        undelegate_context
+            .market_globals
+            .iter()
+            .map(|market_global| market_global.as_ref()),
+    );
     commit_and_undelegate_accounts(
         undelegate_context.payer.as_ref(),
-        vec![
-            undelegate_context.market.as_ref(),
-            undelegate_context.base_vault,
-            undelegate_context.quote_vault,
-        ],
+        accounts_to_commit,
         undelegate_context.magic_context,
         undelegate_context.magic_program.as_ref(),
     )?;
@@ -42,3 +88,24 @@ pub(crate) fn process_undelegate_market(
 
     Ok(())
 }
+
+#[cfg(not(feature = "certora"))]
+fn is_time_limit_reached(dynamic_account: &MarketRef, now_unix_timestamp: u32) -> bool {
+    dynamic_account
+        .get_delegation_status()
+        .is_some_and(|status| status.is_time_limit_reached(now_unix_timestamp))
+}
+
+#[cfg(not(feature = "certora"))]
+fn set_undelegated(dynamic_account: &mut MarketRefMut, now_slot: u32) {
+    dynamic_account.set_undelegated(now_slot)
+}
+
+// Delegation is not part of the verified properties.
+#[cfg(feature = "certora")]
+fn is_time_limit_reached(_dynamic_account: &MarketRef, _now_unix_timestamp: u32) -> bool {
+    false
+}
+
+#[cfg(feature = "certora")]
+fn set_undelegated(_dynamic_account: &mut MarketRefMut, _now_slot: u32) {}
@@ -0,0 +1,44 @@
+use borsh::BorshDeserialize;
+use hypertree::{get_mut_helper, trace};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+use crate::{
+    logs::{emit_stack, MarketMetadataLog},
+    state::MarketMetadata,
+    validation::loaders::MarketMetadataAuthorityContext,
+};
+
+use super::init_market_metadata::MarketMetadataParams;
+
+pub(crate) fn process_update_market_metadata(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let market_metadata_authority_context: MarketMetadataAuthorityContext =
+        MarketMetadataAuthorityContext::load(accounts)?;
+    let MarketMetadataAuthorityContext {
+        authority,
+        market_metadata,
+    } = market_metadata_authority_context;
+    let params: MarketMetadataParams = MarketMetadataParams::try_from_slice(data)?;
+    trace!("update_market_metadata params:{}", params.name);
+
+    let metadata_bytes: &mut [u8] = &mut market_metadata.info.try_borrow_mut_data()?[..];
+    let metadata: &mut MarketMetadata = get_mut_helper::<MarketMetadata>(metadata_bytes, 0_u32);
+    metadata.set_display(
+        &params.name,
+        &params.symbol,
+        &params.uri,
+        params.price_display_decimals,
+        params.size_display_decimals,
+    )?;
+
+    emit_stack(MarketMetadataLog {
+        market: *metadata.get_market(),
+        market_metadata: *market_metadata.info.key,
+        authority: *authority.key,
+    })?;
+
+    Ok(())
+}
@@ -12,7 +12,10 @@ use hypertree::DataIndex;
 use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
 
 #[cfg(not(feature = "certora"))]
-use {crate::market_vault_seeds_with_bump, solana_program::program::invoke_signed};
+use {
+    super::shared::invoke_transfer_checked_with_hook, crate::market_vault_seeds_with_bump,
+    solana_program::program::invoke_signed,
+};
 
 #[cfg(feature = "certora")]
 use {
@@ -63,6 +66,7 @@ pub(crate) fn process_withdraw_core(
         vault,
         token_program,
         mint,
+        hook_accounts,
     } = withdraw_context;
 
     let market_data: &mut RefMut<&mut [u8]> = &mut market.try_borrow_mut_data()?;
@@ -99,6 +103,7 @@ pub(crate) fn process_withdraw_core(
             },
             market.key,
             bump,
+            hook_accounts,
         )?;
     } else {
         spl_token_transfer_from_vault_to_trader(
@@ -185,7 +190,21 @@ fn spl_token_2022_transfer_from_vault_to_trader_fixed<'a, 'info>(
     decimals: u8,
     market_key: &Pubkey,
     bump: u8,
+    hook_accounts: &[AccountInfo<'info>],
 ) -> ProgramResult {
+    if !hook_accounts.is_empty() {
+        return invoke_transfer_checked_with_hook(
+            token_program.as_ref(),
+            vault.as_ref(),
+            mint.unwrap().as_ref(),
+            trader_token.as_ref(),
+            vault.as_ref(),
+            hook_accounts,
+            amount_atoms,
+            decimals,
+            market_vault_seeds_with_bump!(market_key, mint_key, bump),
+        );
+    }
     invoke_signed(
         &spl_token_2022::instruction::transfer_checked(
             token_program.key,
@@ -220,6 +239,7 @@ fn spl_token_2022_transfer_from_vault_to_trader_fixed<'a, 'info>(
     _decimals: u8,
     _market_key: &Pubkey,
     _bump: u8,
+    _hook_accounts: &[AccountInfo<'info>],
 ) -> ProgramResult {
     spl_token_2022_transfer(vault.info, trader_token.info, vault.info, amount_atoms)
 }
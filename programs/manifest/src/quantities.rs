@@ -235,7 +235,7 @@ pub struct QuoteAtomsPerBaseAtom {
 // has no native support for u128 math and requires us only to be 8 byte
 // aligned.
 #[cfg(not(feature = "certora"))]
-const fn u128_to_u64_slice(a: u128) -> [u64; 2] {
+pub(crate) const fn u128_to_u64_slice(a: u128) -> [u64; 2] {
     unsafe {
         let ptr: *const u128 = &a;
         *ptr.cast::<[u64; 2]>()
@@ -346,6 +346,103 @@ impl QuoteAtomsPerBaseAtom {
         }
     }
 
+    /// Nearest price one tick behind self on the mantissa and exponent grid,
+    /// lower for a bid and higher for an ask. Uses the smallest exponent at
+    /// which the result still has a u32 mantissa.
+    pub fn one_tick_behind(self, is_bid: bool) -> Result<Self, PriceConversionError> {
+        let inner: u128 = u64_slice_to_u128(self.inner);
+        for exponent in Self::MIN_EXP..=Self::MAX_EXP {
+            let offset: usize = (Self::MAX_EXP as i64).wrapping_sub(exponent as i64) as usize;
+            let tick: u128 = DECIMAL_CONSTANTS[offset];
+            let mantissa: u128 = if is_bid {
+                inner.div_ceil(tick).saturating_sub(1)
+            } else {
+                inner.div(tick).wrapping_add(1)
+            };
+            if mantissa <= u32::MAX as u128 {
+                return Ok(Self::from_mantissa_and_exponent_(mantissa as u32, exponent));
+            }
+        }
+        trace!("no price one tick behind {self}");
+        Err(PriceConversionError(0x10))
+    }
+
+    /// Nearest multiple of tick behind self, lower for a bid and higher for an
+    /// ask. Falls back to one_tick_behind when tick is zero.
+    pub fn one_tick_behind_on_grid(
+        self,
+        tick: Self,
+        is_bid: bool,
+    ) -> Result<Self, PriceConversionError> {
+        let tick: u128 = u64_slice_to_u128(tick.inner);
+        if tick == 0 {
+            return self.one_tick_behind(is_bid);
+        }
+        let inner: u128 = u64_slice_to_u128(self.inner);
+        let num_ticks: Option<u128> = if is_bid {
+            inner.div_ceil(tick).checked_sub(1)
+        } else {
+            inner.div(tick).checked_add(1)
+        };
+        match num_ticks.and_then(|num_ticks| num_ticks.checked_mul(tick)) {
+            Some(new_inner) if new_inner <= u64_slice_to_u128(Self::MAX.inner) => {
+                Ok(QuoteAtomsPerBaseAtom {
+                    inner: u128_to_u64_slice(new_inner),
+                })
+            }
+            _ => {
+                trace!("no price one tick behind {self} on grid {tick}");
+                Err(PriceConversionError(0x10))
+            }
+        }
+    }
+
+    /// Self moved up, or down when is_negative, by offset. Saturates at zero
+    /// and MAX.
+    pub fn saturating_offset(self, offset: Self, is_negative: bool) -> Self {
+        let inner: u128 = u64_slice_to_u128(self.inner);
+        let offset: u128 = u64_slice_to_u128(offset.inner);
+        let new_inner: u128 = if is_negative {
+            inner.saturating_sub(offset)
+        } else {
+            inner
+                .saturating_add(offset)
+                .min(u64_slice_to_u128(Self::MAX.inner))
+        };
+        QuoteAtomsPerBaseAtom {
+            inner: u128_to_u64_slice(new_inner),
+        }
+    }
+
+    /// Nearest multiple of tick at or behind self, lower for a bid and higher
+    /// for an ask. Every price is on the grid of a zero tick.
+    pub fn round_to_grid(self, tick: Self, is_bid: bool) -> Self {
+        let tick: u128 = u64_slice_to_u128(tick.inner);
+        if tick == 0 {
+            return self;
+        }
+        let inner: u128 = u64_slice_to_u128(self.inner);
+        let rounded_down: u128 = inner - inner % tick;
+        let new_inner: u128 = if is_bid || rounded_down == inner {
+            rounded_down
+        } else {
+            rounded_down
+                .checked_add(tick)
+                .filter(|rounded_up| *rounded_up <= u64_slice_to_u128(Self::MAX.inner))
+                .unwrap_or(rounded_down)
+        };
+        QuoteAtomsPerBaseAtom {
+            inner: u128_to_u64_slice(new_inner),
+        }
+    }
+
+    /// Whether self is a whole number of ticks. Every price is on the grid of
+    /// a zero tick.
+    pub fn is_multiple_of(self, tick: Self) -> bool {
+        let tick: u128 = u64_slice_to_u128(tick.inner);
+        tick == 0 || u64_slice_to_u128(self.inner) % tick == 0
+    }
+
     pub fn try_from_mantissa_and_exponent(
         mantissa: u32,
         exponent: i8,
@@ -752,3 +849,102 @@ fn test_debug() {
         }
     );
 }
+
+#[test]
+fn test_one_tick_behind() {
+    let one: QuoteAtomsPerBaseAtom =
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(1, 0).unwrap();
+    // 1.0 still has a u32 mantissa at exponent -9.
+    assert_eq!(
+        one.one_tick_behind(true).unwrap(),
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(999_999_999, -9).unwrap()
+    );
+    assert_eq!(
+        one.one_tick_behind(false).unwrap(),
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(1_000_000_001, -9).unwrap()
+    );
+    assert_eq!(
+        QuoteAtomsPerBaseAtom::MAX.one_tick_behind(true).unwrap(),
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(u32::MAX - 1, 8).unwrap()
+    );
+    assert!(QuoteAtomsPerBaseAtom::MAX.one_tick_behind(false).is_err());
+}
+
+#[test]
+fn test_price_tick() {
+    let tick: QuoteAtomsPerBaseAtom =
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(5, -2).unwrap();
+    let on_grid: QuoteAtomsPerBaseAtom =
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(15, -1).unwrap();
+    let off_grid: QuoteAtomsPerBaseAtom =
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(151, -2).unwrap();
+    assert!(on_grid.is_multiple_of(tick));
+    assert!(!off_grid.is_multiple_of(tick));
+    assert!(off_grid.is_multiple_of(QuoteAtomsPerBaseAtom::ZERO));
+
+    assert_eq!(
+        on_grid.one_tick_behind_on_grid(tick, true).unwrap(),
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(145, -2).unwrap()
+    );
+    assert_eq!(
+        on_grid.one_tick_behind_on_grid(tick, false).unwrap(),
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(155, -2).unwrap()
+    );
+    assert_eq!(
+        off_grid.one_tick_behind_on_grid(tick, true).unwrap(),
+        on_grid
+    );
+    assert_eq!(
+        off_grid.one_tick_behind_on_grid(tick, false).unwrap(),
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(155, -2).unwrap()
+    );
+    assert_eq!(
+        on_grid
+            .one_tick_behind_on_grid(QuoteAtomsPerBaseAtom::ZERO, true)
+            .unwrap(),
+        on_grid.one_tick_behind(true).unwrap()
+    );
+    assert!(QuoteAtomsPerBaseAtom::MAX
+        .one_tick_behind_on_grid(tick, false)
+        .is_err());
+}
+
+#[test]
+fn test_peg_offset() {
+    let price: QuoteAtomsPerBaseAtom =
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(151, -2).unwrap();
+    let offset: QuoteAtomsPerBaseAtom =
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(2, -2).unwrap();
+    assert_eq!(
+        price.saturating_offset(offset, false),
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(153, -2).unwrap()
+    );
+    assert_eq!(
+        price.saturating_offset(offset, true),
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(149, -2).unwrap()
+    );
+    assert_eq!(
+        offset.saturating_offset(price, true),
+        QuoteAtomsPerBaseAtom::ZERO
+    );
+    assert_eq!(
+        QuoteAtomsPerBaseAtom::MAX.saturating_offset(offset, false),
+        QuoteAtomsPerBaseAtom::MAX
+    );
+
+    let tick: QuoteAtomsPerBaseAtom =
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(5, -2).unwrap();
+    assert_eq!(
+        price.round_to_grid(tick, true),
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(15, -1).unwrap()
+    );
+    assert_eq!(
+        price.round_to_grid(tick, false),
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(155, -2).unwrap()
+    );
+    assert_eq!(tick.round_to_grid(tick, false), tick);
+    assert_eq!(
+        price.round_to_grid(QuoteAtomsPerBaseAtom::ZERO, false),
+        price
+    );
+}
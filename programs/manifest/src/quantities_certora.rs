@@ -111,4 +111,28 @@ impl QuoteAtomsPerBaseAtom {
     pub fn divide_spread(self, _spread_e_5: u16) -> Self {
         todo!()
     }
+
+    pub fn one_tick_behind(self, _is_bid: bool) -> Result<Self, PriceConversionError> {
+        todo!()
+    }
+
+    pub fn one_tick_behind_on_grid(
+        self,
+        _tick: Self,
+        _is_bid: bool,
+    ) -> Result<Self, PriceConversionError> {
+        todo!()
+    }
+
+    pub fn saturating_offset(self, _offset: Self, _is_negative: bool) -> Self {
+        todo!()
+    }
+
+    pub fn round_to_grid(self, _tick: Self, _is_bid: bool) -> Self {
+        todo!()
+    }
+
+    pub fn is_multiple_of(self, _tick: Self) -> bool {
+        todo!()
+    }
 }
@@ -1,7 +1,9 @@
 use hypertree::RBTREE_OVERHEAD_BYTES;
+use solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
 
 pub const MARKET_FIXED_SIZE: usize = 256;
 pub const GLOBAL_FIXED_SIZE: usize = 96;
+pub const MARKET_METADATA_SIZE: usize = 256;
 
 // Red black tree overhead is 16 bytes. If each block is 80 bytes, then we get
 // 64 bytes for a RestingOrder or ClaimedSeat.
@@ -10,6 +12,11 @@ pub const MARKET_BLOCK_SIZE: usize = 80;
 const MARKET_BLOCK_PAYLOAD_SIZE: usize = MARKET_BLOCK_SIZE - RBTREE_OVERHEAD_BYTES;
 pub const RESTING_ORDER_SIZE: usize = MARKET_BLOCK_PAYLOAD_SIZE;
 pub const CLAIMED_SEAT_SIZE: usize = MARKET_BLOCK_PAYLOAD_SIZE;
+pub const PEG_CONFIG_SIZE: usize = MARKET_BLOCK_PAYLOAD_SIZE;
+pub const TRIGGER_ORDER_SIZE: usize = MARKET_BLOCK_PAYLOAD_SIZE;
+pub const MARKET_STATS_SIZE: usize = MARKET_BLOCK_PAYLOAD_SIZE;
+pub const DELEGATION_STATUS_SIZE: usize = MARKET_BLOCK_PAYLOAD_SIZE;
+pub const MARKET_AUTHORITY_SIZE: usize = MARKET_BLOCK_PAYLOAD_SIZE;
 const GLOBAL_BLOCK_PAYLOAD_SIZE: usize = GLOBAL_BLOCK_SIZE - RBTREE_OVERHEAD_BYTES;
 pub const GLOBAL_TRADER_SIZE: usize = GLOBAL_BLOCK_PAYLOAD_SIZE;
 pub const GLOBAL_DEPOSIT_SIZE: usize = GLOBAL_BLOCK_PAYLOAD_SIZE;
@@ -17,10 +24,26 @@ const FREE_LIST_OVERHEAD: usize = 4;
 pub const MARKET_FREE_LIST_BLOCK_SIZE: usize = MARKET_BLOCK_SIZE - FREE_LIST_OVERHEAD;
 pub const GLOBAL_FREE_LIST_BLOCK_SIZE: usize = GLOBAL_BLOCK_SIZE - FREE_LIST_OVERHEAD;
 
+// Most blocks a single Expand can add. An account can only grow by
+// MAX_PERMITTED_DATA_INCREASE bytes in one instruction.
+pub const MARKET_MAX_EXPAND_BLOCKS: u32 = (MAX_PERMITTED_DATA_INCREASE / MARKET_BLOCK_SIZE) as u32;
+
+// Compaction always leaves at least this many free blocks, on top of one free
+// block per live block, so that a compacted market can keep trading without
+// paying to expand right away.
+pub const MARKET_COMPACT_MIN_FREE_BLOCKS: u32 = 16;
+
+// Length in slots of the epochs that MarketStats keeps the open, high and low
+// for. Same as a mainnet epoch, about two days.
+pub const MARKET_STATS_EPOCH_SLOTS: u32 = 432_000;
+
 pub const NO_EXPIRATION_LAST_VALID_SLOT: u32 = 0;
+pub const NO_EXPIRATION_LAST_VALID_UNIX_TIMESTAMP: u32 = 0;
+pub const NO_DELEGATION_TIME_LIMIT: u32 = 0;
 
 pub const MARKET_FIXED_DISCRIMINANT: u64 = 4859840929024028656;
 pub const GLOBAL_FIXED_DISCRIMINANT: u64 = 10787423733276977665;
+pub const MARKET_METADATA_DISCRIMINANT: u64 = 10574833342072697155;
 
 // Amount of gas deposited for every global order. This is done to as an
 // economic disincentive to spam.
@@ -35,6 +58,10 @@ pub const GLOBAL_FIXED_DISCRIMINANT: u64 = 10787423733276977665;
 // Note that if your seat gets evicted, then all your orders are unbacked and
 // now are free to have their deposits claimed. So there is an incentive to keep
 // capital on the exchange to prevent that.
+//
+// Trigger orders take the same deposit, held on the market account. It goes to
+// the cranker that triggers or removes the order, or back to the trader on a
+// cancel.
 pub const GAS_DEPOSIT_LAMPORTS: u64 = 5_000;
 
 /// Limit on the number of global seats available. Set so that this is hit
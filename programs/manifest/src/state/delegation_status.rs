@@ -0,0 +1,170 @@
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use hypertree::{get_helper, get_mut_helper, DataIndex, Get, PodBool, RBTREE_OVERHEAD_BYTES};
+use shank::ShankType;
+use solana_program::pubkey::Pubkey;
+use static_assertions::const_assert_eq;
+
+use super::constants::{DELEGATION_STATUS_SIZE, NO_DELEGATION_TIME_LIMIT};
+
+/// Delegation of the market to an ephemeral rollup, kept up to date by
+/// DelegateMarket, CommitMarket and UndelegateMarket. Lives in a block of the
+/// market dynamic data that MarketFixed points to, like the PegConfig. The
+/// block is taken on the first delegation and reused after that.
+///
+/// The time limit is in unix timestamps rather than slots because the rollup
+/// has its own slots, which run much faster than the base layer. Each slot
+/// below is from the layer the instruction that wrote it ran on.
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, Zeroable, Pod, ShankType)]
+pub struct DelegationStatus {
+    /// Rollup validator the market was last delegated to. Default when the
+    /// delegation program picked one.
+    validator: Pubkey,
+    /// When the market was last delegated.
+    delegated_at_unix_timestamp: u32,
+    /// From this time on anyone can UndelegateMarket, not only the market
+    /// authority. NO_DELEGATION_TIME_LIMIT for never.
+    undelegate_after_unix_timestamp: u32,
+    /// Base layer slot of the last DelegateMarket.
+    delegated_at_slot: u32,
+    /// Rollup slot of the last CommitMarket or UndelegateMarket. Zero before
+    /// the first one.
+    last_commit_slot: u32,
+    /// Set from DelegateMarket until UndelegateMarket. The flag is written
+    /// before the data is handed over, so the base layer copy shows it too.
+    is_delegated: PodBool,
+    /// Whether the market globals for the base and quote mints went to the
+    /// rollup with the last DelegateMarket. CommitMarket and UndelegateMarket
+    /// must take exactly these, or a market global would stay with the
+    /// delegation program.
+    has_base_market_global: PodBool,
+    has_quote_market_global: PodBool,
+    _padding: [u8; 13],
+}
+// 32 + // validator
+//  4 + // delegated_at_unix_timestamp
+//  4 + // undelegate_after_unix_timestamp
+//  4 + // delegated_at_slot
+//  4 + // last_commit_slot
+//  1 + // is_delegated
+//  1 + // has_base_market_global
+//  1 + // has_quote_market_global
+// 13   // padding
+// = 64
+const_assert_eq!(size_of::<DelegationStatus>(), DELEGATION_STATUS_SIZE);
+const_assert_eq!(size_of::<DelegationStatus>() % 8, 0);
+impl Get for DelegationStatus {}
+
+impl DelegationStatus {
+    pub fn get_validator(&self) -> &Pubkey {
+        &self.validator
+    }
+    pub fn get_delegated_at_unix_timestamp(&self) -> u32 {
+        self.delegated_at_unix_timestamp
+    }
+    pub fn get_undelegate_after_unix_timestamp(&self) -> u32 {
+        self.undelegate_after_unix_timestamp
+    }
+    pub fn get_delegated_at_slot(&self) -> u32 {
+        self.delegated_at_slot
+    }
+    pub fn get_last_commit_slot(&self) -> u32 {
+        self.last_commit_slot
+    }
+    pub fn is_delegated(&self) -> bool {
+        self.is_delegated.0 == 1
+    }
+    pub fn has_base_market_global(&self) -> bool {
+        self.has_base_market_global.0 == 1
+    }
+    pub fn has_quote_market_global(&self) -> bool {
+        self.has_quote_market_global.0 == 1
+    }
+
+    /// Whether the time limit has run out, so that anyone can undelegate.
+    pub fn is_time_limit_reached(&self, now_unix_timestamp: u32) -> bool {
+        self.undelegate_after_unix_timestamp != NO_DELEGATION_TIME_LIMIT
+            && now_unix_timestamp >= self.undelegate_after_unix_timestamp
+    }
+
+    pub(crate) fn set_delegated(
+        &mut self,
+        validator: &Pubkey,
+        now_slot: u32,
+        now_unix_timestamp: u32,
+        time_limit_seconds: u64,
+        has_base_market_global: bool,
+        has_quote_market_global: bool,
+    ) {
+        self.validator = *validator;
+        self.is_delegated = PodBool::from(true);
+        self.has_base_market_global = PodBool::from(has_base_market_global);
+        self.has_quote_market_global = PodBool::from(has_quote_market_global);
+        self.delegated_at_slot = now_slot;
+        self.delegated_at_unix_timestamp = now_unix_timestamp;
+        self.undelegate_after_unix_timestamp = if time_limit_seconds == 0 {
+            NO_DELEGATION_TIME_LIMIT
+        } else {
+            // Saturates instead of wrapping to a time in the past, which would
+            // let anyone undelegate right away.
+            u32::try_from((now_unix_timestamp as u64).saturating_add(time_limit_seconds))
+                .unwrap_or(u32::MAX)
+        };
+    }
+
+    pub(crate) fn set_committed(&mut self, now_slot: u32) {
+        self.last_commit_slot = now_slot;
+    }
+
+    pub(crate) fn set_undelegated(&mut self, now_slot: u32) {
+        self.last_commit_slot = now_slot;
+        self.is_delegated = PodBool::from(false);
+    }
+}
+
+/// Read the DelegationStatus in the block at a given index.
+pub fn get_helper_delegation_status(data: &[u8], index: DataIndex) -> &DelegationStatus {
+    get_helper::<DelegationStatus>(data, index + RBTREE_OVERHEAD_BYTES as DataIndex)
+}
+
+/// Mutably read the DelegationStatus in the block at a given index.
+pub fn get_mut_helper_delegation_status(
+    data: &mut [u8],
+    index: DataIndex,
+) -> &mut DelegationStatus {
+    get_mut_helper::<DelegationStatus>(data, index + RBTREE_OVERHEAD_BYTES as DataIndex)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_time_limit() {
+        let mut status: DelegationStatus = DelegationStatus::default();
+        status.set_delegated(&Pubkey::new_unique(), 5, 1_000, 60, false, true);
+        assert!(status.is_delegated());
+        assert!(!status.has_base_market_global());
+        assert!(status.has_quote_market_global());
+        assert_eq!(status.get_delegated_at_slot(), 5);
+        assert_eq!(status.get_delegated_at_unix_timestamp(), 1_000);
+        assert_eq!(status.get_undelegate_after_unix_timestamp(), 1_060);
+        assert!(!status.is_time_limit_reached(1_059));
+        assert!(status.is_time_limit_reached(1_060));
+
+        status.set_committed(100);
+        assert_eq!(status.get_last_commit_slot(), 100);
+        assert!(status.is_delegated());
+        status.set_undelegated(200);
+        assert_eq!(status.get_last_commit_slot(), 200);
+        assert!(!status.is_delegated());
+
+        status.set_delegated(&Pubkey::default(), 6, 2_000, 0, false, false);
+        assert!(!status.is_time_limit_reached(u32::MAX));
+
+        status.set_delegated(&Pubkey::default(), 7, u32::MAX - 1, u64::MAX, true, false);
+        assert_eq!(status.get_undelegate_after_unix_timestamp(), u32::MAX);
+    }
+}
@@ -18,7 +18,10 @@ use static_assertions::const_assert_eq;
 use crate::{
     quantities::{GlobalAtoms, WrapperU64},
     require,
-    validation::{get_global_address, get_global_vault_address, ManifestAccount},
+    validation::{
+        get_global_address, get_global_vault_address, get_market_global_address, get_vault_address,
+        ManifestAccount,
+    },
 };
 
 use super::{
@@ -182,6 +185,28 @@ impl GlobalFixed {
             num_seats_claimed: 0,
         }
     }
+    /// Market global for one of the mints of a market. It has the same
+    /// layout as the global for the mint, but its balances are held in the
+    /// market vault instead of a vault of its own. That way global orders
+    /// backed by it fill without moving tokens, which also works while the
+    /// market is delegated to an ephemeral rollup.
+    pub fn new_empty_for_market(market: &Pubkey, mint: &Pubkey) -> Self {
+        let (vault, vault_bump) = get_vault_address(market, mint);
+        let (_, global_bump) = get_market_global_address(market, mint);
+        GlobalFixed {
+            discriminant: GLOBAL_FIXED_DISCRIMINANT,
+            mint: *mint,
+            vault,
+            global_traders_root_index: NIL,
+            global_deposits_root_index: NIL,
+            global_deposits_max_index: NIL,
+            free_list_head_index: NIL,
+            num_bytes_allocated: 0,
+            vault_bump,
+            global_bump,
+            num_seats_claimed: 0,
+        }
+    }
     pub fn get_mint(&self) -> &Pubkey {
         &self.mint
     }
@@ -191,6 +216,9 @@ impl GlobalFixed {
     pub fn get_vault_bump(&self) -> u8 {
         self.vault_bump
     }
+    pub fn get_global_bump(&self) -> u8 {
+        self.global_bump
+    }
 }
 
 impl ManifestAccount for GlobalFixed {
@@ -261,6 +289,11 @@ impl<Fixed: DerefOrBorrow<GlobalFixed>, Dynamic: DerefOrBorrow<[u8]>>
         }
     }
 
+    pub fn has_trader(&self, trader: &Pubkey) -> bool {
+        let DynamicAccount { fixed, dynamic } = self.borrow_global();
+        get_global_trader(fixed, dynamic, trader).is_some()
+    }
+
     pub fn verify_min_balance(&self, trader: &Pubkey) -> ProgramResult {
         let DynamicAccount { fixed, dynamic } = self.borrow_global();
 
@@ -641,4 +674,19 @@ mod test {
         assert!(global_deposit1 > global_deposit2);
         assert!(global_deposit1 != global_deposit2);
     }
+
+    #[test]
+    fn test_market_global_uses_market_vault() {
+        let market: Pubkey = Pubkey::new_unique();
+        let mint: Pubkey = Pubkey::new_unique();
+        let global_fixed: GlobalFixed = GlobalFixed::new_empty_for_market(&market, &mint);
+        let (market_vault, market_vault_bump) = get_vault_address(&market, &mint);
+        assert_eq!(*global_fixed.get_vault(), market_vault);
+        assert_eq!(global_fixed.get_vault_bump(), market_vault_bump);
+        assert_eq!(
+            global_fixed.get_global_bump(),
+            get_market_global_address(&market, &mint).1
+        );
+        assert_eq!(global_fixed.get_mint(), &mint);
+    }
 }
@@ -18,6 +18,16 @@ use shank::ShankType;
 use solana_program::{entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey};
 use static_assertions::const_assert_eq;
 use std::mem::size_of;
+#[cfg(not(feature = "certora"))]
+use {
+    super::delegation_status::{
+        get_helper_delegation_status, get_mut_helper_delegation_status, DelegationStatus,
+    },
+    super::market_authority::{get_mut_helper_market_authority, MarketAuthority},
+    super::twap::TwapObservation,
+    crate::logs::FillSettlementLog,
+    crate::quantities::{u128_to_u64_slice, u64_slice_to_u128},
+};
 
 use crate::{
     logs::{emit_stack, FillLog},
@@ -25,8 +35,10 @@ use crate::{
     quantities::{BaseAtoms, GlobalAtoms, QuoteAtoms, QuoteAtomsPerBaseAtom, WrapperU64},
     require,
     state::{
-        utils::{assert_can_take, remove_from_global, try_to_move_global_tokens},
-        OrderType,
+        utils::{
+            assert_can_take, is_backing_global, remove_from_global, try_to_move_global_tokens,
+        },
+        OrderPeg, OrderType, SelfTradeBehavior,
     },
     validation::{
         get_vault_address, loaders::GlobalTradeAccounts, ManifestAccount, MintAccountInfo,
@@ -35,20 +47,31 @@ use crate::{
 
 use super::{
     claimed_seat::ClaimedSeat,
-    constants::{MARKET_BLOCK_SIZE, MARKET_FIXED_SIZE},
+    constants::{MARKET_BLOCK_SIZE, MARKET_COMPACT_MIN_FREE_BLOCKS, MARKET_FIXED_SIZE},
+    market_authority::get_helper_market_authority,
+    market_stats::{get_helper_market_stats, get_mut_helper_market_stats, MarketStats},
     order_type_can_rest,
+    peg_config::{get_helper_peg_config, get_mut_helper_peg_config, PegConfig},
     utils::{
-        assert_already_has_seat, assert_not_already_expired, can_back_order, get_now_slot,
-        try_to_add_to_global,
+        assert_already_has_seat, assert_not_already_expired,
+        assert_not_already_expired_unix_timestamp, can_back_order, get_now_slot,
+        get_now_unix_timestamp, try_to_add_to_global,
     },
-    DerefOrBorrow, DerefOrBorrowMut, DynamicAccount, RestingOrder, MARKET_FIXED_DISCRIMINANT,
-    MARKET_FREE_LIST_BLOCK_SIZE, NO_EXPIRATION_LAST_VALID_SLOT,
+    DerefOrBorrow, DerefOrBorrowMut, DynamicAccount, RestingOrder, TriggerOrder,
+    MARKET_FIXED_DISCRIMINANT, MARKET_FREE_LIST_BLOCK_SIZE, NO_EXPIRATION_LAST_VALID_SLOT,
+    NO_EXPIRATION_LAST_VALID_UNIX_TIMESTAMP,
 };
 
 #[path = "market_helpers.rs"]
 pub mod market_helpers;
 use market_helpers::*;
 
+#[path = "market_snapshot.rs"]
+#[cfg(not(feature = "certora"))]
+mod market_snapshot;
+#[cfg(not(feature = "certora"))]
+pub use market_snapshot::*;
+
 #[path = "cvt_munge.rs"]
 #[cfg(feature = "certora")]
 mod cvt_munge;
@@ -93,6 +116,16 @@ mod helpers {
     ) -> &mut RBNode<RestingOrder> {
         get_mut_helper::<RBNode<RestingOrder>>(data, index)
     }
+
+    pub fn get_helper_trigger_order(data: &[u8], index: DataIndex) -> &RBNode<TriggerOrder> {
+        get_helper::<RBNode<TriggerOrder>>(data, index)
+    }
+    pub fn get_mut_helper_trigger_order(
+        data: &mut [u8],
+        index: DataIndex,
+    ) -> &mut RBNode<TriggerOrder> {
+        get_mut_helper::<RBNode<TriggerOrder>>(data, index)
+    }
 }
 
 #[cfg(not(feature = "certora"))]
@@ -106,9 +139,32 @@ pub struct AddOrderToMarketArgs<'a, 'info> {
     pub price: QuoteAtomsPerBaseAtom,
     pub is_bid: bool,
     pub last_valid_slot: u32,
+    pub last_valid_unix_timestamp: u32,
     pub order_type: OrderType,
+    pub self_trade_behavior: SelfTradeBehavior,
+    /// Minimum base atoms that must fill while taking. Ignored for fill or
+    /// kill orders which always require the full size.
+    pub min_base_atoms_filled: BaseAtoms,
     pub global_trade_accounts_opts: &'a [Option<GlobalTradeAccounts<'a, 'info>>; 2],
     pub current_slot: Option<u32>,
+    /// Fails the order if it would match at a price outside of the band.
+    pub price_band_opt: Option<PriceBand>,
+    /// Required for pegged orders. The price arg is ignored for those and the
+    /// order is priced off of the market peg oracle.
+    pub peg_opt: Option<OrderPeg>,
+}
+
+/// Inclusive range of prices, usually derived from an oracle.
+#[derive(Clone, Copy, Debug)]
+pub struct PriceBand {
+    pub min_price: QuoteAtomsPerBaseAtom,
+    pub max_price: QuoteAtomsPerBaseAtom,
+}
+
+impl PriceBand {
+    pub fn contains(&self, price: QuoteAtomsPerBaseAtom) -> bool {
+        self.min_price <= price && price <= self.max_price
+    }
 }
 
 pub struct AddOrderToMarketResult {
@@ -118,6 +174,17 @@ pub struct AddOrderToMarketResult {
     pub quote_atoms_traded: QuoteAtoms,
 }
 
+/// What a crank did with a trigger order.
+pub enum CrankTriggerOrderResult {
+    /// Not a trigger order or not triggered yet. A trailing stop may have
+    /// moved its trigger.
+    Skipped,
+    /// Triggered and placed as a regular order.
+    Activated(TriggerOrder, AddOrderToMarketResult),
+    /// Expired, or triggered without the funds to back it, and dropped.
+    Removed(TriggerOrder),
+}
+
 #[repr(C, packed)]
 #[derive(Default, Copy, Clone, Pod, Zeroable)]
 pub struct MarketUnusedFreeListPadding {
@@ -131,6 +198,14 @@ const_assert_eq!(
 );
 // Does not need to align to word boundaries because does not deserialize.
 
+// Bits of MarketFixed::extension_flags. Each one says that the matching index
+// is set, since markets from before it existed have zero there.
+pub const MARKET_HAS_PEG_CONFIG: u8 = 1 << 0;
+pub const MARKET_HAS_TRIGGER_ORDERS: u8 = 1 << 1;
+pub const MARKET_HAS_MARKET_STATS: u8 = 1 << 2;
+pub const MARKET_HAS_DELEGATION_STATUS: u8 = 1 << 3;
+pub const MARKET_HAS_MARKET_AUTHORITY: u8 = 1 << 4;
+
 #[repr(C)]
 #[derive(Default, Copy, Clone, Zeroable, Pod, ShankType)]
 pub struct MarketFixed {
@@ -143,7 +218,13 @@ pub struct MarketFixed {
     quote_mint_decimals: u8,
     base_vault_bump: u8,
     quote_vault_bump: u8,
-    _padding1: [u8; 3],
+    /// Exponent of the price tick. Only meaningful when the tick mantissa is
+    /// non-zero.
+    price_tick_exponent: i8,
+    /// Which of the optional blocks and trees below the market has, as
+    /// MARKET_HAS_* bits. Older markets have zero here and in the indices.
+    extension_flags: u8,
+    _padding1: [u8; 1],
 
     /// Base mint
     base_mint: Pubkey,
@@ -176,7 +257,9 @@ pub struct MarketFixed {
     /// LinkedList representing all free blocks that could be used for ClaimedSeats or RestingOrders
     free_list_head_index: DataIndex,
 
-    _padding2: [u32; 1],
+    /// Mantissa of the price tick. Resting orders must be priced at a
+    /// multiple of mantissa * 10^exponent. Zero means no tick.
+    price_tick_mantissa: u32,
 
     /// Quote volume traded over lifetime, can overflow. This is for
     /// informational and monitoring purposes only. This is not guaranteed to
@@ -184,6 +267,14 @@ pub struct MarketFixed {
     /// Use at your own risk.
     quote_volume: QuoteAtoms,
 
+    /// Smallest order size accepted by place_order. Zero means no minimum.
+    min_base_atoms: BaseAtoms,
+
+    /// Block in the dynamic data that holds the PegConfig.
+    peg_config_index: DataIndex,
+    /// Block in the dynamic data that holds the MarketAuthority.
+    market_authority_index: DataIndex,
+
     // These are not included in the normal usage because they are informational
     // only and not worth the CU.
     #[cfg(feature = "certora")]
@@ -199,13 +290,29 @@ pub struct MarketFixed {
     /// Quote tokens reserved for non-global orders
     pub orderbook_quote_atoms: QuoteAtoms,
     #[cfg(feature = "certora")]
-    _padding3: [u64; 4],
+    _padding3: [u64; 2],
 
-    // Unused padding. Saved in case a later version wants to be backwards
-    // compatible. Also, it is nice to have the fixed size be a round number,
-    // 256 bytes.
+    /// Red-black tree root of the trigger orders.
     #[cfg(not(feature = "certora"))]
-    _padding3: [u64; 8],
+    triggers_root_index: DataIndex,
+    /// Block in the dynamic data that holds the DelegationStatus, for markets
+    /// that have been delegated to an ephemeral rollup.
+    #[cfg(not(feature = "certora"))]
+    delegation_status_index: DataIndex,
+    /// Price of the most recent fill between two different traders, which
+    /// trigger orders fire on. Zero before the first fill.
+    #[cfg(not(feature = "certora"))]
+    last_trade_price: QuoteAtomsPerBaseAtom,
+    /// Block in the dynamic data that holds the MarketStats, for markets that
+    /// opted in.
+    #[cfg(not(feature = "certora"))]
+    market_stats_index: DataIndex,
+    /// TWAP accumulator, the last trade price summed over every slot up to
+    /// twap_last_slot. Wraps. See TwapObservation.
+    #[cfg(not(feature = "certora"))]
+    twap_last_slot: u32,
+    #[cfg(not(feature = "certora"))]
+    twap_cumulative_price: [u64; 2],
 }
 const_assert_eq!(
     size_of::<MarketFixed>(),
@@ -215,7 +322,9 @@ const_assert_eq!(
     1 +   // quote_mint_decimals
     1 +   // base_vault_bump
     1 +   // quote_vault_bump
-    3 +   // padding
+    1 +   // price_tick_exponent
+    1 +   // extension_flags
+    1 +   // padding
     32 +  // base_mint
     32 +  // quote_mint
     32 +  // base_vault
@@ -227,10 +336,18 @@ const_assert_eq!(
     4 +   // asks_root_index
     4 +   // asks_best_index
     4 +   // claimed_seats_root_index
-    4 +   // claimed_seats_best_index
     4 +   // free_list_head_index
-    8 +   // padding2
-    64 // padding4
+    4 +   // price_tick_mantissa
+    8 +   // quote_volume
+    8 +   // min_base_atoms
+    4 +   // peg_config_index
+    4 +   // market_authority_index
+    4 +   // triggers_root_index
+    4 +   // delegation_status_index
+    16 +  // last_trade_price
+    4 +   // market_stats_index
+    4 +   // twap_last_slot
+    16 // twap_cumulative_price
 );
 const_assert_eq!(size_of::<MarketFixed>(), MARKET_FIXED_SIZE);
 const_assert_eq!(size_of::<MarketFixed>() % 8, 0);
@@ -251,7 +368,9 @@ impl MarketFixed {
             quote_mint_decimals: quote_mint.mint.decimals,
             base_vault_bump,
             quote_vault_bump,
-            _padding1: [0; 3],
+            price_tick_exponent: 0,
+            extension_flags: 0,
+            _padding1: [0; 1],
             base_mint: *base_mint.info.key,
             quote_mint: *quote_mint.info.key,
             base_vault,
@@ -268,10 +387,23 @@ impl MarketFixed {
             #[cfg(feature = "certora")]
             // non NIL
             free_list_head_index: 0,
-            _padding2: [0; 1],
+            price_tick_mantissa: 0,
             quote_volume: QuoteAtoms::ZERO,
+            min_base_atoms: BaseAtoms::ZERO,
+            peg_config_index: NIL,
+            market_authority_index: NIL,
+            #[cfg(not(feature = "certora"))]
+            triggers_root_index: NIL,
+            #[cfg(not(feature = "certora"))]
+            delegation_status_index: NIL,
             #[cfg(not(feature = "certora"))]
-            _padding3: [0; 8],
+            last_trade_price: QuoteAtomsPerBaseAtom::ZERO,
+            #[cfg(not(feature = "certora"))]
+            market_stats_index: NIL,
+            #[cfg(not(feature = "certora"))]
+            twap_last_slot: 0,
+            #[cfg(not(feature = "certora"))]
+            twap_cumulative_price: [0; 2],
             #[cfg(feature = "certora")]
             withdrawable_base_atoms: BaseAtoms::new(0),
             #[cfg(feature = "certora")]
@@ -281,7 +413,7 @@ impl MarketFixed {
             #[cfg(feature = "certora")]
             orderbook_quote_atoms: QuoteAtoms::new(0),
             #[cfg(feature = "certora")]
-            _padding3: [0; 4],
+            _padding3: [0; 2],
         }
     }
 
@@ -297,7 +429,9 @@ impl MarketFixed {
             quote_mint_decimals: nondet(),
             base_vault_bump: nondet(),
             quote_vault_bump: nondet(),
-            _padding1: [0; 3],
+            price_tick_exponent: 0,
+            extension_flags: 0,
+            _padding1: [0; 1],
             base_mint: nondet(),
             quote_mint: nondet(),
             base_vault: nondet(),
@@ -310,13 +444,16 @@ impl MarketFixed {
             asks_best_index: NIL,
             claimed_seats_root_index,
             free_list_head_index: 0,
-            _padding2: [0; 1],
+            price_tick_mantissa: 0,
             quote_volume: QuoteAtoms::ZERO,
+            min_base_atoms: BaseAtoms::ZERO,
+            peg_config_index: NIL,
+            market_authority_index: NIL,
             withdrawable_base_atoms: BaseAtoms::new(nondet()),
             withdrawable_quote_atoms: QuoteAtoms::new(nondet()),
             orderbook_base_atoms: BaseAtoms::new(nondet()),
             orderbook_quote_atoms: QuoteAtoms::new(nondet()),
-            _padding3: [0; 4],
+            _padding3: [0; 2],
         }
     }
 
@@ -347,6 +484,105 @@ impl MarketFixed {
     pub fn get_quote_volume(&self) -> QuoteAtoms {
         self.quote_volume
     }
+    pub fn get_min_base_atoms(&self) -> BaseAtoms {
+        self.min_base_atoms
+    }
+    /// Price that resting orders must be a multiple of. Zero when the market
+    /// has no tick.
+    pub fn get_price_tick(&self) -> QuoteAtomsPerBaseAtom {
+        if self.price_tick_mantissa == 0 {
+            return QuoteAtomsPerBaseAtom::ZERO;
+        }
+        // Exponent was validated when the market was created.
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(
+            self.price_tick_mantissa,
+            self.price_tick_exponent,
+        )
+        .unwrap_or(QuoteAtomsPerBaseAtom::ZERO)
+    }
+
+    /// Set the order size and price granularity. Only done at creation.
+    pub(crate) fn set_order_limits(
+        &mut self,
+        min_base_atoms: BaseAtoms,
+        price_tick_mantissa: u32,
+        price_tick_exponent: i8,
+    ) {
+        self.min_base_atoms = min_base_atoms;
+        self.price_tick_mantissa = price_tick_mantissa;
+        self.price_tick_exponent = price_tick_exponent;
+    }
+
+    /// Block holding the PegConfig, NIL when the market has no peg oracle.
+    pub fn get_peg_config_index(&self) -> DataIndex {
+        if self.has_extension(MARKET_HAS_PEG_CONFIG) {
+            self.peg_config_index
+        } else {
+            NIL
+        }
+    }
+
+    /// Block holding the MarketAuthority, NIL until SetMarketAuthority.
+    pub fn get_market_authority_index(&self) -> DataIndex {
+        if self.has_extension(MARKET_HAS_MARKET_AUTHORITY) {
+            self.market_authority_index
+        } else {
+            NIL
+        }
+    }
+
+    fn has_extension(&self, flag: u8) -> bool {
+        self.extension_flags & flag != 0
+    }
+
+    /// Price of the most recent fill that was not a self trade, zero before
+    /// the first one.
+    #[cfg(not(feature = "certora"))]
+    pub fn get_last_trade_price(&self) -> QuoteAtomsPerBaseAtom {
+        self.last_trade_price
+    }
+
+    /// TWAP accumulator as of now_slot, with the last trade price counted
+    /// from the last update on. Does not need the market to be written.
+    #[cfg(not(feature = "certora"))]
+    pub fn get_twap_observation(&self, now_slot: u32) -> TwapObservation {
+        TwapObservation::new(
+            u64_slice_to_u128(self.twap_cumulative_price),
+            self.twap_last_slot,
+        )
+        .advance(self.last_trade_price, now_slot)
+    }
+
+    /// Brings the TWAP accumulator up to now_slot. Done before every change
+    /// to the last trade price. Self trades do not change it, but trades
+    /// between two accounts of one owner do, see get_twap.
+    #[cfg(not(feature = "certora"))]
+    pub(crate) fn update_twap(&mut self, now_slot: u32) {
+        let observation: TwapObservation = self.get_twap_observation(now_slot);
+        self.twap_cumulative_price = u128_to_u64_slice(observation.cumulative_price);
+        self.twap_last_slot = observation.slot;
+    }
+
+    /// Block holding the MarketStats, NIL when the market has not opted in.
+    #[cfg(not(feature = "certora"))]
+    pub fn get_market_stats_index(&self) -> DataIndex {
+        if self.has_extension(MARKET_HAS_MARKET_STATS) {
+            self.market_stats_index
+        } else {
+            NIL
+        }
+    }
+
+    /// Block holding the DelegationStatus, NIL when the market has never been
+    /// delegated.
+    #[cfg(not(feature = "certora"))]
+    pub fn get_delegation_status_index(&self) -> DataIndex {
+        if self.has_extension(MARKET_HAS_DELEGATION_STATUS) {
+            self.delegation_status_index
+        } else {
+            NIL
+        }
+    }
 
     // Used only in this file to construct iterator
     pub(crate) fn get_bids_root_index(&self) -> DataIndex {
@@ -361,6 +597,17 @@ impl MarketFixed {
     pub(crate) fn get_asks_best_index(&self) -> DataIndex {
         self.asks_best_index
     }
+    pub(crate) fn get_num_bytes_allocated(&self) -> u32 {
+        self.num_bytes_allocated
+    }
+    #[cfg(not(feature = "certora"))]
+    pub(crate) fn get_triggers_root_index(&self) -> DataIndex {
+        if self.has_extension(MARKET_HAS_TRIGGER_ORDERS) {
+            self.triggers_root_index
+        } else {
+            NIL
+        }
+    }
 
     #[cfg(feature = "certora")]
     pub fn get_withdrawable_base_atoms(&self) -> BaseAtoms {
@@ -412,6 +659,8 @@ mod types {
     pub type ClaimedSeatTreeReadOnly<'a> = RedBlackTreeReadOnly<'a, ClaimedSeat>;
     pub type Bookside<'a> = RedBlackTree<'a, RestingOrder>;
     pub type BooksideReadOnly<'a> = RedBlackTreeReadOnly<'a, RestingOrder>;
+    pub type TriggerTree<'a> = RedBlackTree<'a, TriggerOrder>;
+    pub type TriggerTreeReadOnly<'a> = RedBlackTreeReadOnly<'a, TriggerOrder>;
 }
 #[cfg(not(feature = "certora"))]
 pub use types::*;
@@ -488,6 +737,7 @@ impl<Fixed: DerefOrBorrow<MarketFixed>, Dynamic: DerefOrBorrow<[u8]>>
         global_trade_accounts_opts: &[Option<GlobalTradeAccounts>; 2],
         now_slot: u32,
     ) -> Result<QuoteAtoms, ProgramError> {
+        let now_unix_timestamp: u32 = get_now_unix_timestamp();
         let book: BooksideReadOnly = if is_bid {
             self.get_asks()
         } else {
@@ -496,9 +746,14 @@ impl<Fixed: DerefOrBorrow<MarketFixed>, Dynamic: DerefOrBorrow<[u8]>>
 
         let mut total_matched_quote_atoms: QuoteAtoms = QuoteAtoms::ZERO;
         let mut remaining_base_atoms: BaseAtoms = limit_base_atoms;
+        let DynamicAccount { fixed, dynamic } = self.borrow_market();
         for (_, resting_order) in book.iter::<RestingOrder>() {
             // Skip expired orders
-            if resting_order.is_expired(now_slot) {
+            if resting_order.is_expired(now_slot, now_unix_timestamp) {
+                continue;
+            }
+            // Skip pegged orders that matching would walk past.
+            if is_stale_pegged_order(fixed, dynamic, resting_order, now_slot) {
                 continue;
             }
             let matched_price: QuoteAtomsPerBaseAtom = resting_order.get_price();
@@ -589,6 +844,7 @@ impl<Fixed: DerefOrBorrow<MarketFixed>, Dynamic: DerefOrBorrow<[u8]>>
         global_trade_accounts_opts: &[Option<GlobalTradeAccounts>; 2],
         now_slot: u32,
     ) -> Result<BaseAtoms, ProgramError> {
+        let now_unix_timestamp: u32 = get_now_unix_timestamp();
         let book: RedBlackTreeReadOnly<'_, RestingOrder> = if is_bid {
             self.get_asks()
         } else {
@@ -598,9 +854,14 @@ impl<Fixed: DerefOrBorrow<MarketFixed>, Dynamic: DerefOrBorrow<[u8]>>
         let mut total_matched_base_atoms: BaseAtoms = BaseAtoms::ZERO;
         let mut remaining_quote_atoms: QuoteAtoms = limit_quote_atoms;
 
+        let DynamicAccount { fixed, dynamic } = self.borrow_market();
         for (_, resting_order) in book.iter::<RestingOrder>() {
             // Skip expired orders.
-            if resting_order.is_expired(now_slot) {
+            if resting_order.is_expired(now_slot, now_unix_timestamp) {
+                continue;
+            }
+            // Skip pegged orders that matching would walk past.
+            if is_stale_pegged_order(fixed, dynamic, resting_order, now_slot) {
                 continue;
             }
 
@@ -677,6 +938,102 @@ impl<Fixed: DerefOrBorrow<MarketFixed>, Dynamic: DerefOrBorrow<[u8]>>
         &get_helper_order(dynamic, index).get_value()
     }
 
+    /// Settings for pegged orders. None when the market has no peg oracle.
+    pub fn get_peg_config(&self) -> Option<&PegConfig> {
+        let DynamicAccount { fixed, dynamic } = self.borrow_market();
+        let peg_config_index: DataIndex = fixed.get_peg_config_index();
+        if peg_config_index == NIL {
+            return None;
+        }
+        Some(get_helper_peg_config(dynamic, peg_config_index))
+    }
+
+    /// Key that can create the market metadata, compact the market and
+    /// delegate it. None until set with SetMarketAuthority, the market
+    /// keypair is the authority until then.
+    pub fn get_market_authority(&self) -> Option<&Pubkey> {
+        let DynamicAccount { fixed, dynamic } = self.borrow_market();
+        let market_authority_index: DataIndex = fixed.get_market_authority_index();
+        if market_authority_index == NIL {
+            return None;
+        }
+        Some(get_helper_market_authority(dynamic, market_authority_index).get_authority())
+    }
+
+    /// Trading stats. None when the market has not opted in with
+    /// InitMarketStats.
+    #[cfg(not(feature = "certora"))]
+    pub fn get_market_stats(&self) -> Option<&MarketStats> {
+        let DynamicAccount { fixed, dynamic } = self.borrow_market();
+        let market_stats_index: DataIndex = fixed.get_market_stats_index();
+        if market_stats_index == NIL {
+            return None;
+        }
+        Some(get_helper_market_stats(dynamic, market_stats_index))
+    }
+
+    /// Last delegation to an ephemeral rollup. None when the market has never
+    /// been delegated.
+    #[cfg(not(feature = "certora"))]
+    pub fn get_delegation_status(&self) -> Option<&DelegationStatus> {
+        let DynamicAccount { fixed, dynamic } = self.borrow_market();
+        let delegation_status_index: DataIndex = fixed.get_delegation_status_index();
+        if delegation_status_index == NIL {
+            return None;
+        }
+        Some(get_helper_delegation_status(
+            dynamic,
+            delegation_status_index,
+        ))
+    }
+
+    /// Whether the market is on an ephemeral rollup. When it is, the base
+    /// layer account is owned by the delegation program and only the rollup
+    /// can trade on it.
+    #[cfg(not(feature = "certora"))]
+    pub fn is_delegated(&self) -> bool {
+        self.get_delegation_status()
+            .is_some_and(|status| status.is_delegated())
+    }
+
+    /// Price of the best bid. This is the top of the tree, so it may be
+    /// expired or, for a global order, not backed.
+    #[cfg(not(feature = "certora"))]
+    pub fn get_best_bid_price(&self) -> Option<QuoteAtomsPerBaseAtom> {
+        let DynamicAccount { fixed, dynamic } = self.borrow_market();
+        let best_index: DataIndex = fixed.get_bids_best_index();
+        if best_index == NIL {
+            return None;
+        }
+        Some(
+            get_helper_order(dynamic, best_index)
+                .get_value()
+                .get_price(),
+        )
+    }
+
+    /// Price of the best ask, with the same caveats as the best bid.
+    #[cfg(not(feature = "certora"))]
+    pub fn get_best_ask_price(&self) -> Option<QuoteAtomsPerBaseAtom> {
+        let DynamicAccount { fixed, dynamic } = self.borrow_market();
+        let best_index: DataIndex = fixed.get_asks_best_index();
+        if best_index == NIL {
+            return None;
+        }
+        Some(
+            get_helper_order(dynamic, best_index)
+                .get_value()
+                .get_price(),
+        )
+    }
+
+    /// Trigger orders waiting for their trigger, ordered by trigger price.
+    #[cfg(not(feature = "certora"))]
+    pub fn get_trigger_orders(&self) -> TriggerTreeReadOnly {
+        let DynamicAccount { dynamic, fixed } = self.borrow_market();
+        TriggerTreeReadOnly::new(dynamic, fixed.get_triggers_root_index(), NIL)
+    }
+
     pub fn get_trader_balance(&self, trader: &Pubkey) -> (BaseAtoms, QuoteAtoms) {
         let DynamicAccount { fixed, dynamic } = self.borrow_market();
 
@@ -744,9 +1101,12 @@ impl<Fixed: DerefOrBorrow<MarketFixed>, Dynamic: DerefOrBorrow<[u8]>>
             } else {
                 &global_trade_accounts_opts[1]
             };
-            if global_trade_accounts_opt.is_none() {
-                return true;
-            }
+            // The other global for the mint does not back the order either.
+            return !global_trade_accounts_opt
+                .as_ref()
+                .is_some_and(|global_trade_accounts| {
+                    is_backing_global(global_trade_accounts, resting_order)
+                });
         }
         return false;
     }
@@ -786,6 +1146,18 @@ impl<Fixed: DerefOrBorrow<MarketFixed>, Dynamic: DerefOrBorrow<[u8]>>
         return false;
     }
 
+    /// Linear scan over both booksides. Returns NIL if the order is not found.
+    pub fn get_order_index_by_sequence_number(&self, order_sequence_number: u64) -> DataIndex {
+        for bookside in [self.get_bids(), self.get_asks()] {
+            for (index, resting_order) in bookside.iter::<RestingOrder>() {
+                if resting_order.get_sequence_number() == order_sequence_number {
+                    return index;
+                }
+            }
+        }
+        NIL
+    }
+
     pub fn get_trader_index(&self, trader: &Pubkey) -> DataIndex {
         let DynamicAccount { fixed, dynamic } = self.borrow_market();
 
@@ -812,16 +1184,527 @@ impl<
     }
 
     pub fn market_expand(&mut self) -> ProgramResult {
+        self.market_expand_by(1)
+    }
+
+    /// Puts num_blocks new blocks at the end of the dynamic data onto the free
+    /// list. The account must already be big enough to hold them.
+    pub fn market_expand_by(&mut self, num_blocks: u32) -> ProgramResult {
         let DynamicAccount { fixed, dynamic } = self.borrow_mut();
         let mut free_list: FreeList<MarketUnusedFreeListPadding> =
             FreeList::new(dynamic, fixed.free_list_head_index);
 
-        free_list.add(fixed.num_bytes_allocated);
-        fixed.num_bytes_allocated += MARKET_BLOCK_SIZE as u32;
+        // Add from the back so the lowest new block is handed out first.
+        for block in (0..num_blocks).rev() {
+            free_list.add(fixed.num_bytes_allocated + block * MARKET_BLOCK_SIZE as u32);
+        }
+        fixed.num_bytes_allocated += num_blocks * MARKET_BLOCK_SIZE as u32;
         fixed.free_list_head_index = free_list.get_head();
         Ok(())
     }
 
+    /// Moves live blocks out of the tail of the market into free blocks
+    /// nearer the front so that the tail can be truncated. Never shrinks
+    /// below MARKET_COMPACT_MIN_FREE_BLOCKS free blocks and one free block
+    /// per live block, so that compaction only reclaims space that is not
+    /// needed for current activity. Returns the number of blocks removed from
+    /// the end of the dynamic data.
+    ///
+    /// Resting orders and seats that are moved get new indices, so any index
+    /// hints held by clients or wrappers need to be looked up again.
+    #[cfg(not(feature = "certora"))]
+    pub fn market_compact(&mut self) -> Result<u32, ProgramError> {
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        let block_size: DataIndex = MARKET_BLOCK_SIZE as DataIndex;
+        let num_blocks: DataIndex = fixed.num_bytes_allocated / block_size;
+
+        let mut is_free: Vec<bool> = vec![false; num_blocks as usize];
+        let mut free_index: DataIndex = fixed.free_list_head_index;
+        while free_index != NIL {
+            is_free[(free_index / block_size) as usize] = true;
+            free_index =
+                get_helper::<FreeListNode<MarketUnusedFreeListPadding>>(dynamic, free_index)
+                    .get_next();
+        }
+        let num_live_blocks: DataIndex =
+            num_blocks - is_free.iter().filter(|free| **free).count() as DataIndex;
+        let num_blocks_to_keep: DataIndex =
+            num_blocks.min(num_live_blocks + num_live_blocks.max(MARKET_COMPACT_MIN_FREE_BLOCKS));
+        if num_blocks_to_keep == num_blocks {
+            return Ok(0);
+        }
+
+        // Free blocks below the cutoff, lowest first, that can hold the live
+        // blocks that are being moved down. There are always enough because
+        // everything from the cutoff on either is free or moves into one.
+        let holes: Vec<DataIndex> = (0..num_blocks_to_keep)
+            .filter(|block| is_free[*block as usize])
+            .map(|block| block * block_size)
+            .collect();
+        let mut num_holes_used: usize = 0;
+        let mut moved_seats: Vec<(DataIndex, DataIndex)> = Vec::new();
+        for block in num_blocks_to_keep..num_blocks {
+            if is_free[block as usize] {
+                continue;
+            }
+            let from_index: DataIndex = block * block_size;
+            let to_index: DataIndex = holes[num_holes_used];
+            num_holes_used += 1;
+            is_free[(to_index / block_size) as usize] = false;
+
+            let payload_type: u8 = get_helper_order(dynamic, from_index).get_payload_type();
+            if payload_type == MarketDataTreeNodeType::PegConfig as u8 {
+                // Not in a tree, so the whole block can be copied over.
+                dynamic.copy_within(
+                    from_index as usize..(from_index + block_size) as usize,
+                    to_index as usize,
+                );
+                fixed.peg_config_index = to_index;
+            } else if payload_type == MarketDataTreeNodeType::MarketStats as u8 {
+                dynamic.copy_within(
+                    from_index as usize..(from_index + block_size) as usize,
+                    to_index as usize,
+                );
+                fixed.market_stats_index = to_index;
+            } else if payload_type == MarketDataTreeNodeType::DelegationStatus as u8 {
+                dynamic.copy_within(
+                    from_index as usize..(from_index + block_size) as usize,
+                    to_index as usize,
+                );
+                fixed.delegation_status_index = to_index;
+            } else if payload_type == MarketDataTreeNodeType::MarketAuthority as u8 {
+                dynamic.copy_within(
+                    from_index as usize..(from_index + block_size) as usize,
+                    to_index as usize,
+                );
+                fixed.market_authority_index = to_index;
+            } else if payload_type == MarketDataTreeNodeType::TriggerOrder as u8 {
+                let mut triggers: TriggerTree =
+                    TriggerTree::new(dynamic, fixed.triggers_root_index, NIL);
+                triggers.move_node(from_index, to_index);
+                fixed.triggers_root_index = triggers.get_root_index();
+            } else if payload_type == MarketDataTreeNodeType::ClaimedSeat as u8 {
+                let mut claimed_seats_tree: ClaimedSeatTree =
+                    ClaimedSeatTree::new(dynamic, fixed.claimed_seats_root_index, NIL);
+                claimed_seats_tree.move_node(from_index, to_index);
+                fixed.claimed_seats_root_index = claimed_seats_tree.get_root_index();
+                moved_seats.push((from_index, to_index));
+            } else if get_helper_order(dynamic, from_index)
+                .get_value()
+                .get_is_bid()
+            {
+                let mut bids: Bookside =
+                    Bookside::new(dynamic, fixed.bids_root_index, fixed.bids_best_index);
+                bids.move_node(from_index, to_index);
+                fixed.bids_root_index = bids.get_root_index();
+                fixed.bids_best_index = bids.get_max_index();
+            } else {
+                let mut asks: Bookside =
+                    Bookside::new(dynamic, fixed.asks_root_index, fixed.asks_best_index);
+                asks.move_node(from_index, to_index);
+                fixed.asks_root_index = asks.get_root_index();
+                fixed.asks_best_index = asks.get_max_index();
+            }
+        }
+
+        // Resting and trigger orders refer to their trader by seat index.
+        if !moved_seats.is_empty() {
+            let find_moved_seat = |trader_index: DataIndex| -> Option<DataIndex> {
+                moved_seats
+                    .iter()
+                    .find(|(old, _)| *old == trader_index)
+                    .map(|(_, new)| *new)
+            };
+            for block in 0..num_blocks_to_keep {
+                let index: DataIndex = block * block_size;
+                let payload_type: u8 = get_helper_order(dynamic, index).get_payload_type();
+                if payload_type == MarketDataTreeNodeType::RestingOrder as u8 {
+                    let order: &mut RestingOrder =
+                        get_mut_helper_order(dynamic, index).get_mut_value();
+                    if let Some(new_trader_index) = find_moved_seat(order.get_trader_index()) {
+                        order.set_trader_index(new_trader_index);
+                    }
+                } else if payload_type == MarketDataTreeNodeType::TriggerOrder as u8 {
+                    let trigger_order: &mut TriggerOrder =
+                        get_mut_helper_trigger_order(dynamic, index).get_mut_value();
+                    if let Some(new_trader_index) =
+                        find_moved_seat(trigger_order.get_trader_index())
+                    {
+                        trigger_order.set_trader_index(new_trader_index);
+                    }
+                }
+            }
+        }
+
+        // Rebuild the free list from what is left below the cutoff, lowest
+        // block handed out first like in expand.
+        let mut free_list: FreeList<MarketUnusedFreeListPadding> = FreeList::new(dynamic, NIL);
+        for block in (0..num_blocks_to_keep).rev() {
+            if is_free[block as usize] {
+                free_list.add(block * block_size);
+            }
+        }
+        fixed.free_list_head_index = free_list.get_head();
+        fixed.num_bytes_allocated = num_blocks_to_keep * block_size;
+
+        Ok(num_blocks - num_blocks_to_keep)
+    }
+
+    /// Takes a free block for the peg oracle settings. Only done when the
+    /// market is created, so the oracle cannot be swapped out from under
+    /// resting pegged orders.
+    #[cfg(not(feature = "certora"))]
+    pub fn init_peg_config(&mut self, peg_config: PegConfig) -> ProgramResult {
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        require!(
+            fixed.get_peg_config_index() == NIL,
+            ManifestError::InvalidMarketParameters,
+            "Market already has a peg oracle",
+        )?;
+        let peg_config_index: DataIndex = get_free_address_on_market_fixed(fixed, dynamic);
+        dynamic[peg_config_index as usize..peg_config_index as usize + MARKET_BLOCK_SIZE].fill(0);
+        get_mut_helper_order(dynamic, peg_config_index)
+            .set_payload_type(MarketDataTreeNodeType::PegConfig as u8);
+        *get_mut_helper_peg_config(dynamic, peg_config_index) = peg_config;
+        fixed.peg_config_index = peg_config_index;
+        fixed.extension_flags |= MARKET_HAS_PEG_CONFIG;
+        Ok(())
+    }
+
+    /// Records the market authority, taking a free block for it the first
+    /// time.
+    #[cfg(not(feature = "certora"))]
+    pub fn set_market_authority(&mut self, authority: &Pubkey) -> ProgramResult {
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        let mut market_authority_index: DataIndex = fixed.get_market_authority_index();
+        if market_authority_index == NIL {
+            market_authority_index = get_free_address_on_market_fixed(fixed, dynamic);
+            dynamic[market_authority_index as usize
+                ..market_authority_index as usize + MARKET_BLOCK_SIZE]
+                .fill(0);
+            get_mut_helper_order(dynamic, market_authority_index)
+                .set_payload_type(MarketDataTreeNodeType::MarketAuthority as u8);
+            fixed.market_authority_index = market_authority_index;
+            fixed.extension_flags |= MARKET_HAS_MARKET_AUTHORITY;
+        }
+        *get_mut_helper_market_authority(dynamic, market_authority_index) =
+            MarketAuthority::new(authority);
+        Ok(())
+    }
+
+    /// Takes a free block for the trading stats. They start empty and only
+    /// count fills from here on.
+    #[cfg(not(feature = "certora"))]
+    pub fn init_market_stats(&mut self) -> ProgramResult {
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        require!(
+            fixed.get_market_stats_index() == NIL,
+            ManifestError::InvalidMarketParameters,
+            "Market already has stats",
+        )?;
+        let market_stats_index: DataIndex = get_free_address_on_market_fixed(fixed, dynamic);
+        dynamic[market_stats_index as usize..market_stats_index as usize + MARKET_BLOCK_SIZE]
+            .fill(0);
+        get_mut_helper_order(dynamic, market_stats_index)
+            .set_payload_type(MarketDataTreeNodeType::MarketStats as u8);
+        *get_mut_helper_market_stats(dynamic, market_stats_index) = MarketStats::default();
+        fixed.market_stats_index = market_stats_index;
+        fixed.extension_flags |= MARKET_HAS_MARKET_STATS;
+        Ok(())
+    }
+
+    /// Records a delegation to an ephemeral rollup, taking a free block for
+    /// the DelegationStatus the first time.
+    #[cfg(not(feature = "certora"))]
+    pub fn set_delegated(
+        &mut self,
+        validator: &Pubkey,
+        now_slot: u32,
+        now_unix_timestamp: u32,
+        time_limit_seconds: u64,
+        has_base_market_global: bool,
+        has_quote_market_global: bool,
+    ) -> ProgramResult {
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        let mut delegation_status_index: DataIndex = fixed.get_delegation_status_index();
+        if delegation_status_index == NIL {
+            delegation_status_index = get_free_address_on_market_fixed(fixed, dynamic);
+            dynamic[delegation_status_index as usize
+                ..delegation_status_index as usize + MARKET_BLOCK_SIZE]
+                .fill(0);
+            get_mut_helper_order(dynamic, delegation_status_index)
+                .set_payload_type(MarketDataTreeNodeType::DelegationStatus as u8);
+            fixed.delegation_status_index = delegation_status_index;
+            fixed.extension_flags |= MARKET_HAS_DELEGATION_STATUS;
+        }
+        get_mut_helper_delegation_status(dynamic, delegation_status_index).set_delegated(
+            validator,
+            now_slot,
+            now_unix_timestamp,
+            time_limit_seconds,
+            has_base_market_global,
+            has_quote_market_global,
+        );
+        Ok(())
+    }
+
+    /// Records a commit from the rollup. Markets delegated before the status
+    /// was tracked have nothing to update.
+    #[cfg(not(feature = "certora"))]
+    pub fn set_committed(&mut self, now_slot: u32) {
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        let delegation_status_index: DataIndex = fixed.get_delegation_status_index();
+        if delegation_status_index != NIL {
+            get_mut_helper_delegation_status(dynamic, delegation_status_index)
+                .set_committed(now_slot);
+        }
+    }
+
+    /// Records the final commit before the market goes back to the base
+    /// layer.
+    #[cfg(not(feature = "certora"))]
+    pub fn set_undelegated(&mut self, now_slot: u32) {
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        let delegation_status_index: DataIndex = fixed.get_delegation_status_index();
+        if delegation_status_index != NIL {
+            get_mut_helper_delegation_status(dynamic, delegation_status_index)
+                .set_undelegated(now_slot);
+        }
+    }
+
+    /// Moves every pegged order to the oracle price plus its offset and
+    /// records the oracle price for the rest of the slot. A pegged order that
+    /// would cross the other side rests one tick behind it instead.
+    ///
+    /// The side moving away from the other side goes first so that it never
+    /// holds back the side that follows it.
+    #[cfg(not(feature = "certora"))]
+    pub fn reprice_pegged_orders(
+        &mut self,
+        oracle_price: QuoteAtomsPerBaseAtom,
+        now_slot: u32,
+    ) -> ProgramResult {
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        let peg_config_index: DataIndex = fixed.get_peg_config_index();
+        require!(
+            is_not_nil!(peg_config_index),
+            ManifestError::MissingPegOraclePrice,
+            "Market does not have a peg oracle",
+        )?;
+        let peg_config: &mut PegConfig = get_mut_helper_peg_config(dynamic, peg_config_index);
+        let is_price_up: bool = oracle_price > peg_config.get_oracle_price();
+        peg_config.set_oracle_price(oracle_price, now_slot);
+
+        let price_tick: QuoteAtomsPerBaseAtom = fixed.get_price_tick();
+        let sides: [bool; 2] = if is_price_up {
+            [false, true]
+        } else {
+            [true, false]
+        };
+        for is_bid in sides {
+            let pegged_order_indices: Vec<DataIndex> = if is_bid {
+                BooksideReadOnly::new(dynamic, fixed.bids_root_index, fixed.bids_best_index)
+            } else {
+                BooksideReadOnly::new(dynamic, fixed.asks_root_index, fixed.asks_best_index)
+            }
+            .iter::<RestingOrder>()
+            .filter(|(_, resting_order)| resting_order.is_pegged())
+            .map(|(index, _)| index)
+            .collect();
+            let other_best_index: DataIndex = if is_bid {
+                fixed.asks_best_index
+            } else {
+                fixed.bids_best_index
+            };
+
+            for order_index in pegged_order_indices {
+                let mut resting_order: RestingOrder =
+                    *get_helper_order(dynamic, order_index).get_value();
+                let mut price: QuoteAtomsPerBaseAtom =
+                    resting_order
+                        .get_peg()
+                        .try_price(oracle_price, price_tick, is_bid)?;
+                if is_not_nil!(other_best_index) {
+                    let other_best_price: QuoteAtomsPerBaseAtom =
+                        get_helper_order(dynamic, other_best_index)
+                            .get_value()
+                            .get_price();
+                    if (is_bid && price >= other_best_price)
+                        || (!is_bid && price <= other_best_price)
+                    {
+                        price = other_best_price
+                            .one_tick_behind_on_grid(price_tick, is_bid)
+                            .unwrap_or(resting_order.get_price());
+                    }
+                }
+                if price == resting_order.get_price() {
+                    continue;
+                }
+
+                // Same block, new place in the tree.
+                remove_order_from_tree(fixed, dynamic, order_index, is_bid)?;
+                resting_order.set_price(price);
+                insert_order_into_tree(is_bid, fixed, dynamic, order_index, &resting_order);
+                set_payload_order(dynamic, order_index);
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds a trigger order for a trader with a seat. Nothing is reserved
+    /// until it triggers. Returns the sequence number and index it got.
+    #[cfg(not(feature = "certora"))]
+    pub fn place_trigger_order(
+        &mut self,
+        mut trigger_order: TriggerOrder,
+    ) -> Result<(u64, DataIndex), ProgramError> {
+        assert_already_has_seat(trigger_order.get_trader_index())?;
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        let order_sequence_number: u64 = fixed.order_sequence_number;
+        fixed.order_sequence_number = order_sequence_number.wrapping_add(1);
+        trigger_order.set_sequence_number(order_sequence_number);
+
+        let trigger_order_index: DataIndex = get_free_address_on_market_fixed(fixed, dynamic);
+        insert_trigger_order_into_tree(fixed, dynamic, trigger_order_index, &trigger_order);
+        Ok((order_sequence_number, trigger_order_index))
+    }
+
+    /// Removes a trigger order that has not triggered yet. Linear scan over
+    /// the trigger orders. Returns the removed order.
+    #[cfg(not(feature = "certora"))]
+    pub fn cancel_trigger_order(
+        &mut self,
+        trader_index: DataIndex,
+        order_sequence_number: u64,
+    ) -> Result<TriggerOrder, ProgramError> {
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        let found: Option<(DataIndex, TriggerOrder)> =
+            TriggerTreeReadOnly::new(dynamic, fixed.get_triggers_root_index(), NIL)
+                .iter::<TriggerOrder>()
+                .find(|(_, trigger_order)| {
+                    trigger_order.get_sequence_number() == order_sequence_number
+                })
+                .map(|(index, trigger_order)| (index, *trigger_order));
+        let (trigger_order_index, trigger_order) = found.ok_or(ManifestError::InvalidCancel)?;
+        require!(
+            trigger_order.get_trader_index() == trader_index,
+            ManifestError::InvalidCancel,
+            "Cannot cancel for another trader",
+        )?;
+        remove_trigger_order_from_tree(fixed, dynamic, trigger_order_index);
+        release_address_on_market_fixed(fixed, dynamic, trigger_order_index);
+        Ok(trigger_order)
+    }
+
+    /// Checks one trigger order against the last trade price and the peg
+    /// oracle price, when pegged orders were repriced in this slot. A
+    /// triggered order is placed as a regular order for its trader.
+    ///
+    /// A triggered order whose trader cannot fund it in full is dropped
+    /// rather than placed smaller, so a crank never fails on funds. Orders
+    /// that fail for other reasons, like a stop limit that would rest below
+    /// the market minimum after a partial fill, fail the crank and are left
+    /// for the trader to cancel.
+    #[cfg(not(feature = "certora"))]
+    pub fn crank_trigger_order(
+        &mut self,
+        market: Pubkey,
+        trigger_order_index: DataIndex,
+        now_slot: u32,
+    ) -> Result<CrankTriggerOrderResult, ProgramError> {
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        // Indices come from the cranker so make sure they point at a block.
+        if trigger_order_index % MARKET_BLOCK_SIZE as DataIndex != 0
+            || trigger_order_index >= fixed.num_bytes_allocated
+            || get_helper_trigger_order(dynamic, trigger_order_index).get_payload_type()
+                != MarketDataTreeNodeType::TriggerOrder as u8
+        {
+            return Ok(CrankTriggerOrderResult::Skipped);
+        }
+        let mut trigger_order: TriggerOrder =
+            *get_helper_trigger_order(dynamic, trigger_order_index).get_value();
+
+        if trigger_order.is_expired(now_slot) {
+            remove_trigger_order_from_tree(fixed, dynamic, trigger_order_index);
+            release_address_on_market_fixed(fixed, dynamic, trigger_order_index);
+            return Ok(CrankTriggerOrderResult::Removed(trigger_order));
+        }
+
+        let reference_prices: Vec<QuoteAtomsPerBaseAtom> = [
+            Some(fixed.last_trade_price).filter(|price| *price != QuoteAtomsPerBaseAtom::ZERO),
+            get_peg_oracle_price(fixed, dynamic, now_slot).ok(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        let mut is_triggered: bool = false;
+        for reference_price in reference_prices {
+            if let Some(trigger_price) =
+                trigger_order.try_trailing_trigger_price(reference_price)?
+            {
+                // Same block, new place in the tree.
+                remove_trigger_order_from_tree(fixed, dynamic, trigger_order_index);
+                trigger_order.set_trigger_price(trigger_price);
+                insert_trigger_order_into_tree(fixed, dynamic, trigger_order_index, &trigger_order);
+            }
+            is_triggered |= trigger_order.is_triggered(reference_price);
+        }
+        if !is_triggered {
+            return Ok(CrankTriggerOrderResult::Skipped);
+        }
+
+        // Global makers need their global accounts, which a crank does not
+        // have. Matching one would fail a stop limit and cut a stop market
+        // short, so the trigger waits until no global maker is in the way.
+        if would_match_global_order(
+            fixed,
+            dynamic,
+            &trigger_order,
+            now_slot,
+            get_now_unix_timestamp(),
+        ) {
+            return Ok(CrankTriggerOrderResult::Skipped);
+        }
+
+        remove_trigger_order_from_tree(fixed, dynamic, trigger_order_index);
+        release_address_on_market_fixed(fixed, dynamic, trigger_order_index);
+
+        let claimed_seat: &ClaimedSeat =
+            get_helper_seat(dynamic, trigger_order.get_trader_index()).get_value();
+        let has_funds: bool = if trigger_order.get_is_bid() {
+            trigger_order
+                .get_num_base_atoms()
+                .checked_mul(trigger_order.get_price(), true)?
+                <= claimed_seat.quote_withdrawable_balance
+        } else {
+            trigger_order.get_num_base_atoms() <= claimed_seat.base_withdrawable_balance
+        };
+        if !has_funds {
+            return Ok(CrankTriggerOrderResult::Removed(trigger_order));
+        }
+
+        // No global maker is matched, so no global accounts are needed.
+        let global_trade_accounts_opts: [Option<GlobalTradeAccounts>; 2] = [None, None];
+        let result: AddOrderToMarketResult = self.place_order(AddOrderToMarketArgs {
+            market,
+            trader_index: trigger_order.get_trader_index(),
+            num_base_atoms: trigger_order.get_num_base_atoms(),
+            price: trigger_order.get_price(),
+            is_bid: trigger_order.get_is_bid(),
+            last_valid_slot: trigger_order.get_last_valid_slot(),
+            last_valid_unix_timestamp: NO_EXPIRATION_LAST_VALID_UNIX_TIMESTAMP,
+            order_type: trigger_order.get_order_type(),
+            self_trade_behavior: trigger_order.get_self_trade_behavior(),
+            min_base_atoms_filled: BaseAtoms::ZERO,
+            global_trade_accounts_opts: &global_trade_accounts_opts,
+            current_slot: Some(now_slot),
+            price_band_opt: None,
+            peg_opt: None,
+        })?;
+        Ok(CrankTriggerOrderResult::Activated(trigger_order, result))
+    }
+
     pub fn claim_seat(&mut self, trader: &Pubkey) -> ProgramResult {
         let DynamicAccount { fixed, dynamic } = self.borrow_mut();
         let free_address: DataIndex = get_free_address_on_market_fixed_for_seat(fixed, dynamic);
@@ -850,9 +1733,9 @@ impl<
         Ok(())
     }
 
-    // Only used when temporarily claiming for swap and we dont have the system
-    // program to expand. Otherwise, there is no reason to ever give up your
-    // seat.
+    // Used when temporarily claiming for swap and we dont have the system
+    // program to expand, and by ReleaseSeat once a trader has no balances or
+    // open orders left. Callers are responsible for those checks.
     pub fn release_seat(&mut self, trader: &Pubkey) -> ProgramResult {
         let trader_seat_index: DataIndex = self.get_trader_index(trader);
         let DynamicAccount { fixed, dynamic } = self.borrow_mut();
@@ -913,23 +1796,53 @@ impl<
             market,
             trader_index,
             num_base_atoms,
-            price,
+            mut price,
             is_bid,
             last_valid_slot,
+            last_valid_unix_timestamp,
             order_type,
+            self_trade_behavior,
+            min_base_atoms_filled,
             global_trade_accounts_opts,
             current_slot,
+            price_band_opt,
+            peg_opt,
         } = args;
         assert_already_has_seat(trader_index)?;
         let now_slot: u32 = current_slot.unwrap_or_else(|| get_now_slot());
+        let now_unix_timestamp: u32 = get_now_unix_timestamp();
 
         // Reverse orders will have their last valid slot overriden to no expiration.
         if order_type != OrderType::Reverse {
             assert_not_already_expired(last_valid_slot, now_slot)?;
+            assert_not_already_expired_unix_timestamp(
+                last_valid_unix_timestamp,
+                now_unix_timestamp,
+            )?;
         }
 
         let DynamicAccount { fixed, dynamic } = self.borrow_mut();
 
+        // Pegged orders ignore the given price and start at the oracle price
+        // plus their offset.
+        if order_type == OrderType::Pegged {
+            let peg: OrderPeg = peg_opt.ok_or(ProgramError::InvalidArgument)?;
+            let oracle_price: QuoteAtomsPerBaseAtom =
+                get_peg_oracle_price(fixed, dynamic, now_slot)?;
+            price = peg.try_price(oracle_price, fixed.get_price_tick(), is_bid)?;
+        }
+
+        // Only orders that can add to the book are held to the market minimum.
+        if order_type_can_rest(order_type) {
+            require!(
+                num_base_atoms >= fixed.min_base_atoms,
+                ManifestError::InvalidOrderSizeOrTick,
+                "Order of {} base atoms is below the market minimum {}",
+                num_base_atoms,
+                fixed.min_base_atoms,
+            )?;
+        }
+
         let mut current_maker_order_index: DataIndex = if is_bid {
             fixed.asks_best_index
         } else {
@@ -940,12 +1853,17 @@ impl<
         let mut total_quote_atoms_traded: QuoteAtoms = QuoteAtoms::ZERO;
 
         let mut remaining_base_atoms: BaseAtoms = num_base_atoms;
+        let mut did_cancel_taking: bool = false;
+        // Best stale pegged order walked past, which nothing may rest across.
+        let mut stale_pegged_price_opt: Option<QuoteAtomsPerBaseAtom> = None;
         while remaining_base_atoms > BaseAtoms::ZERO && is_not_nil!(current_maker_order_index) {
             let maker_order: &RestingOrder =
                 get_helper::<RBNode<RestingOrder>>(dynamic, current_maker_order_index).get_value();
 
             // Remove the resting order if expired or somehow a zero order got on the book.
-            if maker_order.is_expired(now_slot) || maker_order.get_num_base_atoms().as_u64() == 0 {
+            if maker_order.is_expired(now_slot, now_unix_timestamp)
+                || maker_order.get_num_base_atoms().as_u64() == 0
+            {
                 let next_maker_order_index: DataIndex = get_next_candidate_match_index(
                     fixed,
                     dynamic,
@@ -969,6 +1887,33 @@ impl<
                 break;
             }
 
+            // A pegged maker is only at the right place in the book if it was
+            // repriced this slot. Otherwise walk past it and leave it resting
+            // for the next reprice, so a stale oracle does not block the
+            // market. Post only orders neither slide behind it nor fail on it
+            // since it is not matched.
+            if is_stale_pegged_order(fixed, dynamic, maker_order, now_slot) {
+                if stale_pegged_price_opt.is_none() {
+                    stale_pegged_price_opt = Some(maker_order.get_price());
+                }
+                current_maker_order_index = get_next_candidate_match_index(
+                    fixed,
+                    dynamic,
+                    current_maker_order_index,
+                    is_bid,
+                );
+                continue;
+            }
+
+            // Instead of crossing, reprice behind the best resting order and
+            // stop matching. Everything remaining rests at the new price.
+            if order_type == OrderType::PostOnlySlide {
+                price = maker_order
+                    .get_price()
+                    .one_tick_behind_on_grid(fixed.get_price_tick(), is_bid)?;
+                break;
+            }
+
             // Got a match. First make sure we are allowed to match. We check
             // inside the matching rather than skipping the matching altogether
             // because post only orders should fail, not produce a crossed book.
@@ -976,6 +1921,55 @@ impl<
 
             let maker_sequence_number = maker_order.get_sequence_number();
             let maker_trader_index: DataIndex = maker_order.get_trader_index();
+
+            // Self trade prevention. Resolved before any funds move so a self
+            // match never produces a fill or counts towards volume.
+            if maker_trader_index == trader_index && self_trade_behavior != SelfTradeBehavior::Allow
+            {
+                let maker_num_base_atoms: BaseAtoms = maker_order.get_num_base_atoms();
+                // A maker that would be left below the market minimum is
+                // removed instead of decremented.
+                let can_decrement_maker: bool = remaining_base_atoms < maker_num_base_atoms
+                    && maker_num_base_atoms.checked_sub(remaining_base_atoms)?
+                        >= fixed.min_base_atoms;
+                match self_trade_behavior {
+                    SelfTradeBehavior::CancelTaking => {
+                        did_cancel_taking = true;
+                        break;
+                    }
+                    SelfTradeBehavior::DecrementBoth if can_decrement_maker => {
+                        reduce_order_and_update_balance(
+                            fixed,
+                            dynamic,
+                            current_maker_order_index,
+                            remaining_base_atoms,
+                        )?;
+                        remaining_base_atoms = BaseAtoms::ZERO;
+                        break;
+                    }
+                    _ => {
+                        if self_trade_behavior == SelfTradeBehavior::DecrementBoth {
+                            remaining_base_atoms =
+                                remaining_base_atoms.saturating_sub(maker_num_base_atoms);
+                        }
+                        let next_maker_order_index: DataIndex = get_next_candidate_match_index(
+                            fixed,
+                            dynamic,
+                            current_maker_order_index,
+                            is_bid,
+                        );
+                        remove_and_update_balances(
+                            fixed,
+                            dynamic,
+                            current_maker_order_index,
+                            global_trade_accounts_opts,
+                        )?;
+                        current_maker_order_index = next_maker_order_index;
+                        continue;
+                    }
+                }
+            }
+
             let did_fully_match_resting_order: bool =
                 remaining_base_atoms >= maker_order.get_num_base_atoms();
             let base_atoms_traded: BaseAtoms = if did_fully_match_resting_order {
@@ -983,8 +1977,22 @@ impl<
             } else {
                 remaining_base_atoms
             };
+            #[cfg(not(feature = "certora"))]
+            let maker_remaining_base_atoms: BaseAtoms = maker_order
+                .get_num_base_atoms()
+                .checked_sub(base_atoms_traded)?;
 
             let matched_price: QuoteAtomsPerBaseAtom = maker_order.get_price();
+            if let Some(price_band) = price_band_opt {
+                require!(
+                    price_band.contains(matched_price),
+                    ManifestError::PriceOutsideOracleBand,
+                    "Match at {} is outside of the band {} to {}",
+                    matched_price,
+                    price_band.min_price,
+                    price_band.max_price,
+                )?;
+            }
 
             // on full fill: round in favor of the taker
             // on partial fill: round in favor of the maker
@@ -1011,8 +2019,14 @@ impl<
                 };
                 // When the global account is not included, a taker order can
                 // halt here, but a possible maker order will need to crash
-                // since that would result in a crossed book.
-                if global_trade_accounts_opt.is_none() {
+                // since that would result in a crossed book. The other global
+                // for the mint counts as not included.
+                if !global_trade_accounts_opt
+                    .as_ref()
+                    .is_some_and(|global_trade_accounts| {
+                        is_backing_global(global_trade_accounts, maker_order)
+                    })
+                {
                     if order_type_can_rest(order_type) {
                         return Err(ManifestError::MissingGlobal.into());
                     } else {
@@ -1076,11 +2090,15 @@ impl<
             // only the number of atoms required for the trade were brought
             // over.  The extra one that is no longer needed for taker rounding
             // is not brought over, so dont credit the maker for it.
+            //
+            // Pegged bids are backed at their limit, so this also returns the
+            // difference between the limit and the matched price.
             if !is_bid && !is_global {
                 // These are only used when is_bid.
+                let funding_price: QuoteAtomsPerBaseAtom = maker_order.get_funding_price();
                 let previous_maker_quote_atoms_allocated: QuoteAtoms =
-                    matched_price.checked_quote_for_base(maker_order.get_num_base_atoms(), true)?;
-                let new_maker_quote_atoms_allocated: QuoteAtoms = matched_price
+                    funding_price.checked_quote_for_base(maker_order.get_num_base_atoms(), true)?;
+                let new_maker_quote_atoms_allocated: QuoteAtoms = funding_price
                     .checked_quote_for_base(
                         maker_order
                             .get_num_base_atoms()
@@ -1149,6 +2167,8 @@ impl<
             record_volume_by_trader_index(dynamic, maker_trader_index, quote_atoms_traded);
             record_volume_by_trader_index(dynamic, trader_index, quote_atoms_traded);
 
+            // A reverse maker takes the next sequence number below.
+            let taker_sequence_number: u64 = fixed.order_sequence_number;
             emit_stack(FillLog {
                 market,
                 maker,
@@ -1159,22 +2179,40 @@ impl<
                 quote_atoms: quote_atoms_traded,
                 price: matched_price,
                 maker_sequence_number,
-                taker_sequence_number: fixed.order_sequence_number,
+                taker_sequence_number,
                 taker_is_buy: PodBool::from(is_bid),
                 is_maker_global: PodBool::from(is_global),
                 _padding: [0; 14],
             })?;
+            #[cfg(not(feature = "certora"))]
+            {
+                // Trading with yourself costs nothing, so it must not move
+                // the price that the TWAP and trigger orders follow, nor the
+                // volume and range in the stats.
+                if maker_trader_index != trader_index {
+                    fixed.update_twap(now_slot);
+                    fixed.last_trade_price = matched_price;
+                    let market_stats_index: DataIndex = fixed.get_market_stats_index();
+                    if market_stats_index != NIL {
+                        get_mut_helper_market_stats(dynamic, market_stats_index).record_fill(
+                            matched_price,
+                            base_atoms_traded,
+                            now_slot,
+                        );
+                    }
+                }
+            }
 
             if did_fully_match_resting_order {
                 // Get paid for removing a global order.
-                if get_helper::<RBNode<RestingOrder>>(dynamic, current_maker_order_index)
-                    .get_value()
-                    .is_global()
-                {
+                let maker_order: &RestingOrder =
+                    get_helper::<RBNode<RestingOrder>>(dynamic, current_maker_order_index)
+                        .get_value();
+                if maker_order.is_global() {
                     if is_bid {
-                        remove_from_global(&global_trade_accounts_opts[0])?;
+                        remove_from_global(&global_trade_accounts_opts[0], maker_order)?;
                     } else {
-                        remove_from_global(&global_trade_accounts_opts[1])?;
+                        remove_from_global(&global_trade_accounts_opts[1], maker_order)?;
                     }
                 }
 
@@ -1217,7 +2255,11 @@ impl<
                     // Bid @P --> Ask @P / (1 - spread)
                     matched_price.divide_spread(100_000_u32 - (maker_reverse_spread as u32))
                 };
-                let num_base_atoms_reverse: BaseAtoms = if is_bid {
+                // Rounded onto the market tick away from the match, so the
+                // spread only gets wider.
+                let price_reverse: QuoteAtomsPerBaseAtom =
+                    price_reverse.round_to_grid(fixed.get_price_tick(), is_bid);
+                let mut num_base_atoms_reverse: BaseAtoms = if is_bid {
                     // Maker is now buying with the exact number of quote atoms.
                     // Do not round_up because there might not be enough atoms
                     // for that.
@@ -1256,6 +2298,12 @@ impl<
                     }
                 }
 
+                // A new order below the market minimum is not placed and the
+                // maker keeps the funds as withdrawable balance.
+                if !coalesced && num_base_atoms_reverse < fixed.min_base_atoms {
+                    num_base_atoms_reverse = BaseAtoms::ZERO;
+                }
+
                 // If there was 1 atom and because taker rounding is in effect,
                 // then this would result in an empty order.
                 if !coalesced && num_base_atoms_reverse.as_u64() > 0 {
@@ -1315,6 +2363,24 @@ impl<
                 )?;
             }
 
+            #[cfg(not(feature = "certora"))]
+            {
+                let maker_seat: &ClaimedSeat =
+                    get_helper_seat(dynamic, maker_trader_index).get_value();
+                emit_stack(FillSettlementLog {
+                    market,
+                    maker,
+                    taker,
+                    maker_base_balance: maker_seat.base_withdrawable_balance,
+                    maker_quote_balance: maker_seat.quote_withdrawable_balance,
+                    maker_remaining_base_atoms,
+                    maker_sequence_number,
+                    taker_sequence_number,
+                    maker_fully_filled: PodBool::from(did_fully_match_resting_order),
+                    _padding: [0; 7],
+                })?;
+            }
+
             // Stop if the last resting order did not fully match since that
             // means the taker was exhausted.
             if !did_fully_match_resting_order {
@@ -1322,6 +2388,19 @@ impl<
             }
         }
 
+        let min_base_atoms_filled: BaseAtoms = if order_type == OrderType::FillOrKill {
+            num_base_atoms
+        } else {
+            min_base_atoms_filled
+        };
+        require!(
+            total_base_atoms_traded >= min_base_atoms_filled,
+            ManifestError::MinimumFillNotMet,
+            "Filled {} base atoms, required {}",
+            total_base_atoms_traded,
+            min_base_atoms_filled,
+        )?;
+
         // Record volume on market
         fixed.quote_volume = fixed.quote_volume.wrapping_add(total_quote_atoms_traded);
 
@@ -1330,9 +2409,23 @@ impl<
         let order_sequence_number: u64 = fixed.order_sequence_number;
         fixed.order_sequence_number = order_sequence_number.wrapping_add(1);
 
-        // If there is nothing left to rest, then return before resting.
+        // The stale pegged orders that were walked past are still on the
+        // book, so the rest goes one tick behind the best of them like a
+        // PostOnlySlide would. Without a price there it does not rest.
+        if let Some(stale_pegged_price) = stale_pegged_price_opt {
+            if (is_bid && price >= stale_pegged_price) || (!is_bid && price <= stale_pegged_price) {
+                price = stale_pegged_price
+                    .one_tick_behind_on_grid(fixed.get_price_tick(), is_bid)
+                    .unwrap_or(QuoteAtomsPerBaseAtom::ZERO);
+            }
+        }
+
+        // If there is nothing left to rest, then return before resting. A
+        // remainder below the market minimum is dropped like an IOC remainder.
         if !order_type_can_rest(order_type)
+            || did_cancel_taking
             || remaining_base_atoms == BaseAtoms::ZERO
+            || remaining_base_atoms < fixed.min_base_atoms
             || price == QuoteAtomsPerBaseAtom::ZERO
         {
             return Ok(AddOrderToMarketResult {
@@ -1344,7 +2437,7 @@ impl<
         }
 
         self.rest_remaining(
-            args,
+            AddOrderToMarketArgs { price, ..args },
             remaining_base_atoms,
             order_sequence_number,
             total_base_atoms_traded,
@@ -1384,12 +2477,24 @@ impl<
             price,
             is_bid,
             last_valid_slot,
+            last_valid_unix_timestamp,
             order_type,
             global_trade_accounts_opts,
+            peg_opt,
             ..
         } = args;
         let DynamicAccount { fixed, dynamic } = self.borrow_mut();
 
+        // Callers drop a remainder below the market minimum before resting.
+        let price_tick: QuoteAtomsPerBaseAtom = fixed.get_price_tick();
+        require!(
+            price_tick == QuoteAtomsPerBaseAtom::ZERO || price.is_multiple_of(price_tick),
+            ManifestError::InvalidOrderSizeOrTick,
+            "Price {} is not a multiple of the market tick {}",
+            price,
+            price_tick,
+        )?;
+
         // Put the remaining in an order on the other bookside.
         let free_address: DataIndex = if is_bid {
             get_free_address_on_market_fixed_for_bid_order(fixed, dynamic)
@@ -1413,6 +2518,11 @@ impl<
 
         if order_type == OrderType::Reverse {
             resting_order.set_reverse_spread(last_valid_slot as u16);
+        } else {
+            resting_order.set_last_valid_unix_timestamp(last_valid_unix_timestamp);
+        }
+        if let Some(peg) = peg_opt.filter(|_| order_type == OrderType::Pegged) {
+            resting_order.set_peg(peg);
         }
 
         if resting_order.is_global() {
@@ -1423,7 +2533,10 @@ impl<
                     ManifestError::MissingGlobal,
                     "Missing global accounts when adding a global",
                 )?;
-                try_to_add_to_global(&global_trade_account_opt.as_ref().unwrap(), &resting_order)?;
+                try_to_add_to_global(
+                    &global_trade_account_opt.as_ref().unwrap(),
+                    &mut resting_order,
+                )?;
             } else {
                 let global_trade_account_opt = &global_trade_accounts_opts[0];
                 require!(
@@ -1431,7 +2544,10 @@ impl<
                     ManifestError::MissingGlobal,
                     "Missing global accounts when adding a global",
                 )?;
-                try_to_add_to_global(&global_trade_account_opt.as_ref().unwrap(), &resting_order)?;
+                try_to_add_to_global(
+                    &global_trade_account_opt.as_ref().unwrap(),
+                    &mut resting_order,
+                )?;
             }
         } else {
             // Place the remaining.
@@ -1443,7 +2559,9 @@ impl<
                 !is_bid,
                 false,
                 if is_bid {
-                    remaining_base_atoms.checked_mul(price, true)?.into()
+                    remaining_base_atoms
+                        .checked_mul(resting_order.get_funding_price(), true)?
+                        .into()
                 } else {
                     remaining_base_atoms.into()
                 },
@@ -1520,6 +2638,121 @@ impl<
         Err(ManifestError::InvalidCancel.into())
     }
 
+    /// Shrinks a resting order in place so it keeps its queue priority. Funds
+    /// that were backing the removed size are returned to the trader. The
+    /// order cannot go below the market minimum.
+    pub fn reduce_order_by_index(
+        &mut self,
+        order_index: DataIndex,
+        num_base_atoms: BaseAtoms,
+    ) -> ProgramResult {
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+
+        let resting_order: &RestingOrder = get_helper_order(dynamic, order_index).get_value();
+        let previous_num_base_atoms: BaseAtoms = resting_order.get_num_base_atoms();
+        require!(
+            num_base_atoms > BaseAtoms::ZERO && num_base_atoms <= previous_num_base_atoms,
+            ManifestError::InvalidModify,
+            "Can only reduce size in place, from {} to {}",
+            previous_num_base_atoms,
+            num_base_atoms,
+        )?;
+        reduce_order_and_update_balance(
+            fixed,
+            dynamic,
+            order_index,
+            previous_num_base_atoms.checked_sub(num_base_atoms)?,
+        )
+    }
+
+    /// Cancels every resting order for the trader, optionally only on one
+    /// side. Returns the sequence numbers of the cancelled orders.
+    pub fn cancel_all_orders(
+        &mut self,
+        trader_index: DataIndex,
+        is_bid_opt: Option<bool>,
+        global_trade_accounts_opts: &[Option<GlobalTradeAccounts>; 2],
+    ) -> Result<Vec<u64>, ProgramError> {
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+
+        // Collect first because removing while iterating would invalidate the
+        // iterator. Removal does not move other nodes, so indices stay valid.
+        let mut orders_to_remove: Vec<(DataIndex, u64)> = Vec::new();
+        for is_bid in [true, false] {
+            if is_bid_opt.is_some_and(|side_is_bid| side_is_bid != is_bid) {
+                continue;
+            }
+            let tree: BooksideReadOnly = if is_bid {
+                BooksideReadOnly::new(dynamic, fixed.bids_root_index, fixed.bids_best_index)
+            } else {
+                BooksideReadOnly::new(dynamic, fixed.asks_root_index, fixed.asks_best_index)
+            };
+            for (index, resting_order) in tree.iter::<RestingOrder>() {
+                if resting_order.get_trader_index() == trader_index {
+                    orders_to_remove.push((index, resting_order.get_sequence_number()));
+                }
+            }
+        }
+
+        let mut cancelled_sequence_numbers: Vec<u64> = Vec::with_capacity(orders_to_remove.len());
+        for (order_index, order_sequence_number) in orders_to_remove {
+            self.cancel_order_by_index(order_index, global_trade_accounts_opts)?;
+            cancelled_sequence_numbers.push(order_sequence_number);
+        }
+        Ok(cancelled_sequence_numbers)
+    }
+
+    /// Removes expired orders found within the first max_orders of one side
+    /// of the book and credits the funds back to their owners. Global orders
+    /// are left for GlobalClean. Returns the removed orders.
+    pub fn remove_expired_orders(
+        &mut self,
+        is_bid: bool,
+        max_orders: usize,
+    ) -> Result<Vec<RestingOrder>, ProgramError> {
+        let now_slot: u32 = get_now_slot();
+        let now_unix_timestamp: u32 = get_now_unix_timestamp();
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+
+        let mut removed_orders: Vec<RestingOrder> = Vec::new();
+        let mut current_order_index: DataIndex = if is_bid {
+            fixed.bids_best_index
+        } else {
+            fixed.asks_best_index
+        };
+        for _ in 0..max_orders {
+            if current_order_index == NIL {
+                break;
+            }
+            // Walking a side from the top is the same as matching against it.
+            let next_order_index: DataIndex =
+                get_next_candidate_match_index(fixed, dynamic, current_order_index, !is_bid);
+            if let Some(resting_order) = remove_order_if_expired(
+                fixed,
+                dynamic,
+                current_order_index,
+                now_slot,
+                now_unix_timestamp,
+            )? {
+                removed_orders.push(resting_order);
+            }
+            current_order_index = next_order_index;
+        }
+        Ok(removed_orders)
+    }
+
+    /// Removes the order at order_index if it is expired and not global and
+    /// credits the funds back to the owner. Returns the removed order.
+    pub fn remove_expired_order_by_index(
+        &mut self,
+        order_index: DataIndex,
+    ) -> Result<Option<RestingOrder>, ProgramError> {
+        let now_slot: u32 = get_now_slot();
+        let now_unix_timestamp: u32 = get_now_unix_timestamp();
+        let DynamicAccount { fixed, dynamic } = self.borrow_mut();
+        remove_order_if_expired(fixed, dynamic, order_index, now_slot, now_unix_timestamp)
+    }
+
     #[cfg_attr(feature = "certora", cvt_hook_end(cancel_order_by_index_was_called()))]
     pub fn cancel_order_by_index(
         &mut self,
@@ -1538,7 +2771,7 @@ impl<
         // taker rounding when the order was placed.
         let amount_atoms: u64 = if is_bid {
             (resting_order
-                .get_price()
+                .get_funding_price()
                 .checked_quote_for_base(resting_order.get_num_base_atoms(), true)
                 .unwrap())
             .into()
@@ -1549,9 +2782,9 @@ impl<
         // Update the accounting for the order that was just canceled.
         if resting_order.is_global() {
             if is_bid {
-                remove_from_global(&global_trade_accounts_opts[1])?;
+                remove_from_global(&global_trade_accounts_opts[1], resting_order)?;
             } else {
-                remove_from_global(&global_trade_accounts_opts[0])?;
+                remove_from_global(&global_trade_accounts_opts[0], resting_order)?;
             }
 
             // Certora version was equivalent except it returned early here.
@@ -1577,6 +2810,119 @@ impl<
     }
 }
 
+/// Oracle price that pegged orders were repriced to in this slot. Pegged
+/// orders are only where they belong in the book after a reprice.
+fn get_peg_oracle_price(
+    fixed: &MarketFixed,
+    dynamic: &[u8],
+    now_slot: u32,
+) -> Result<QuoteAtomsPerBaseAtom, ProgramError> {
+    let peg_config_index: DataIndex = fixed.get_peg_config_index();
+    require!(
+        is_not_nil!(peg_config_index),
+        ManifestError::MissingPegOraclePrice,
+        "Market does not have a peg oracle",
+    )?;
+    let peg_config: &PegConfig = get_helper_peg_config(dynamic, peg_config_index);
+    require!(
+        peg_config.get_last_reprice_slot() == now_slot
+            && peg_config.get_oracle_price() != QuoteAtomsPerBaseAtom::ZERO,
+        ManifestError::MissingPegOraclePrice,
+        "Pegged orders were last repriced in slot {} not {}",
+        peg_config.get_last_reprice_slot(),
+        now_slot,
+    )?;
+    Ok(peg_config.get_oracle_price())
+}
+
+/// Pegged order that was not repriced this slot, so its price may be wrong.
+fn is_stale_pegged_order(
+    fixed: &MarketFixed,
+    dynamic: &[u8],
+    resting_order: &RestingOrder,
+    now_slot: u32,
+) -> bool {
+    resting_order.is_pegged() && get_peg_oracle_price(fixed, dynamic, now_slot).is_err()
+}
+
+/// Whether the order for a trigger would match a global maker before it is
+/// filled or stops crossing. Walks the book the way place_order does, except
+/// that makers of the same trader are counted as filling nothing, so it errs
+/// on the side of finding one.
+#[cfg(not(feature = "certora"))]
+fn would_match_global_order(
+    fixed: &MarketFixed,
+    dynamic: &[u8],
+    trigger_order: &TriggerOrder,
+    now_slot: u32,
+    now_unix_timestamp: u32,
+) -> bool {
+    let is_bid: bool = trigger_order.get_is_bid();
+    let price: QuoteAtomsPerBaseAtom = trigger_order.get_price();
+    let mut remaining_base_atoms: BaseAtoms = trigger_order.get_num_base_atoms();
+    let mut current_maker_order_index: DataIndex = if is_bid {
+        fixed.asks_best_index
+    } else {
+        fixed.bids_best_index
+    };
+    while remaining_base_atoms > BaseAtoms::ZERO && is_not_nil!(current_maker_order_index) {
+        let maker_order: &RestingOrder =
+            get_helper::<RBNode<RestingOrder>>(dynamic, current_maker_order_index).get_value();
+        let next_maker_order_index: DataIndex =
+            get_next_candidate_match_index(fixed, dynamic, current_maker_order_index, is_bid);
+
+        // Removed by place_order without touching the global.
+        if maker_order.is_expired(now_slot, now_unix_timestamp)
+            || maker_order.get_num_base_atoms() == BaseAtoms::ZERO
+        {
+            current_maker_order_index = next_maker_order_index;
+            continue;
+        }
+        if (is_bid && maker_order.get_price() > price)
+            || (!is_bid && maker_order.get_price() < price)
+        {
+            break;
+        }
+        if !is_stale_pegged_order(fixed, dynamic, maker_order, now_slot) {
+            if maker_order.is_global() {
+                return true;
+            }
+            if maker_order.get_trader_index() != trigger_order.get_trader_index() {
+                remaining_base_atoms =
+                    remaining_base_atoms.saturating_sub(maker_order.get_num_base_atoms());
+            }
+        }
+        current_maker_order_index = next_maker_order_index;
+    }
+    false
+}
+
+#[cfg(not(feature = "certora"))]
+fn insert_trigger_order_into_tree(
+    fixed: &mut MarketFixed,
+    dynamic: &mut [u8],
+    trigger_order_index: DataIndex,
+    trigger_order: &TriggerOrder,
+) {
+    let mut tree: TriggerTree = TriggerTree::new(dynamic, fixed.get_triggers_root_index(), NIL);
+    tree.insert(trigger_order_index, *trigger_order);
+    fixed.triggers_root_index = tree.get_root_index();
+    fixed.extension_flags |= MARKET_HAS_TRIGGER_ORDERS;
+    get_mut_helper_trigger_order(dynamic, trigger_order_index)
+        .set_payload_type(MarketDataTreeNodeType::TriggerOrder as u8);
+}
+
+#[cfg(not(feature = "certora"))]
+fn remove_trigger_order_from_tree(
+    fixed: &mut MarketFixed,
+    dynamic: &mut [u8],
+    trigger_order_index: DataIndex,
+) {
+    let mut tree: TriggerTree = TriggerTree::new(dynamic, fixed.get_triggers_root_index(), NIL);
+    tree.remove_by_index(trigger_order_index);
+    fixed.triggers_root_index = tree.get_root_index();
+}
+
 fn set_payload_order(dynamic: &mut [u8], free_address: DataIndex) {
     get_mut_helper_order(dynamic, free_address)
         .set_payload_type(MarketDataTreeNodeType::RestingOrder as u8);
@@ -1831,10 +3177,21 @@ fn remove_and_update_balances(
 
     // Global order balances are accounted for on the global accounts, not on the market.
     if resting_order_to_remove.is_global() {
-        if order_to_remove_is_bid {
-            remove_from_global(&global_trade_accounts_opts[1])?;
+        let global_trade_accounts_opt: &Option<GlobalTradeAccounts> = if order_to_remove_is_bid {
+            &global_trade_accounts_opts[1]
         } else {
-            remove_from_global(&global_trade_accounts_opts[0])?;
+            &global_trade_accounts_opts[0]
+        };
+        // A taker with the other global for the mint walks past without the
+        // gas prepayment instead of failing, same as without a global.
+        let is_other_global: bool =
+            global_trade_accounts_opt
+                .as_ref()
+                .is_some_and(|global_trade_accounts| {
+                    !is_backing_global(global_trade_accounts, resting_order_to_remove)
+                });
+        if !is_other_global {
+            remove_from_global(global_trade_accounts_opt, resting_order_to_remove)?;
         }
     } else {
         // Return the exact number of atoms if the resting order is an
@@ -1843,7 +3200,7 @@ fn remove_and_update_balances(
         // the minimum number of atoms required.
         let amount_atoms_to_return: u64 = if order_to_remove_is_bid {
             resting_order_to_remove
-                .get_price()
+                .get_funding_price()
                 .checked_quote_for_base(resting_order_to_remove.get_num_base_atoms(), true)?
                 .as_u64()
         } else {
@@ -1866,3 +3223,68 @@ fn remove_and_update_balances(
     )?;
     Ok(())
 }
+
+// Global orders are skipped because the gas prepayment can only be returned
+// with the global account, which GlobalClean handles.
+fn remove_order_if_expired(
+    fixed: &mut MarketFixed,
+    dynamic: &mut [u8],
+    order_index: DataIndex,
+    now_slot: u32,
+    now_unix_timestamp: u32,
+) -> Result<Option<RestingOrder>, ProgramError> {
+    let resting_order: RestingOrder = *get_helper_order(dynamic, order_index).get_value();
+    if resting_order.is_global() || !resting_order.is_expired(now_slot, now_unix_timestamp) {
+        return Ok(None);
+    }
+    remove_and_update_balances(fixed, dynamic, order_index, &[None, None])?;
+    Ok(Some(resting_order))
+}
+
+/// Reduces a resting order in place and credits the owner with the funds that
+/// no longer back it.
+fn reduce_order_and_update_balance(
+    fixed: &mut MarketFixed,
+    dynamic: &mut [u8],
+    order_index: DataIndex,
+    reduce_base_atoms: BaseAtoms,
+) -> ProgramResult {
+    let resting_order: &RestingOrder = get_helper_order(dynamic, order_index).get_value();
+    let previous_num_base_atoms: BaseAtoms = resting_order.get_num_base_atoms();
+    let num_base_atoms: BaseAtoms = previous_num_base_atoms.checked_sub(reduce_base_atoms)?;
+    require!(
+        num_base_atoms >= fixed.min_base_atoms,
+        ManifestError::InvalidOrderSizeOrTick,
+        "Cannot reduce to {} base atoms below the market minimum {}",
+        num_base_atoms,
+        fixed.min_base_atoms,
+    )?;
+    let is_bid: bool = resting_order.get_is_bid();
+    let trader_index: DataIndex = resting_order.get_trader_index();
+    let price: QuoteAtomsPerBaseAtom = resting_order.get_funding_price();
+
+    // Same rounding as cancel, the bid was backed by the rounded up quote.
+    let amount_atoms: u64 = if is_bid {
+        price
+            .checked_quote_for_base(previous_num_base_atoms, true)?
+            .checked_sub(price.checked_quote_for_base(num_base_atoms, true)?)?
+            .into()
+    } else {
+        reduce_base_atoms.into()
+    };
+
+    // Global orders are not backed by funds on the market.
+    if !resting_order.is_global() {
+        update_balance(fixed, dynamic, trader_index, !is_bid, true, amount_atoms)?;
+    }
+
+    #[cfg(feature = "certora")]
+    remove_from_orderbook_balance(fixed, dynamic, order_index);
+    get_mut_helper_order(dynamic, order_index)
+        .get_mut_value()
+        .reduce(reduce_base_atoms)?;
+    #[cfg(feature = "certora")]
+    add_to_orderbook_balance(fixed, dynamic, order_index);
+
+    Ok(())
+}
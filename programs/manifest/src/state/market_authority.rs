@@ -0,0 +1,52 @@
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use hypertree::{get_helper, get_mut_helper, DataIndex, Get, RBTREE_OVERHEAD_BYTES};
+use shank::ShankType;
+use solana_program::pubkey::Pubkey;
+use static_assertions::const_assert_eq;
+
+use super::constants::MARKET_AUTHORITY_SIZE;
+
+/// Key that can create the market metadata, compact the market and delegate it
+/// to an ephemeral rollup. Set with SetMarketAuthority. Lives in a block of the
+/// market dynamic data that MarketFixed points to, like the PegConfig.
+///
+/// Markets start without one and the market keypair acts as the authority
+/// until it sets one. That way CreateMarket does not take a block and seats
+/// and orders keep their indices.
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, Zeroable, Pod, ShankType)]
+pub struct MarketAuthority {
+    authority: Pubkey,
+    _padding: [u64; 4],
+}
+// 32 + // authority
+// 32   // padding
+// = 64
+const_assert_eq!(size_of::<MarketAuthority>(), MARKET_AUTHORITY_SIZE);
+const_assert_eq!(size_of::<MarketAuthority>() % 8, 0);
+impl Get for MarketAuthority {}
+
+impl MarketAuthority {
+    pub fn new(authority: &Pubkey) -> Self {
+        MarketAuthority {
+            authority: *authority,
+            ..Default::default()
+        }
+    }
+
+    pub fn get_authority(&self) -> &Pubkey {
+        &self.authority
+    }
+}
+
+/// Read the MarketAuthority in the block at a given index.
+pub fn get_helper_market_authority(data: &[u8], index: DataIndex) -> &MarketAuthority {
+    get_helper::<MarketAuthority>(data, index + RBTREE_OVERHEAD_BYTES as DataIndex)
+}
+
+/// Mutably read the MarketAuthority in the block at a given index.
+pub fn get_mut_helper_market_authority(data: &mut [u8], index: DataIndex) -> &mut MarketAuthority {
+    get_mut_helper::<MarketAuthority>(data, index + RBTREE_OVERHEAD_BYTES as DataIndex)
+}
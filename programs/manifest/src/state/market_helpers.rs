@@ -206,9 +206,14 @@ impl<'a, 'b, 'info> AddSingleOrderCtx<'a, 'b, 'info> {
             price,
             is_bid,
             last_valid_slot: _,
+            last_valid_unix_timestamp: _,
             order_type,
+            self_trade_behavior: _,
+            min_base_atoms_filled: _,
             global_trade_accounts_opts,
             current_slot: _,
+            price_band_opt: _,
+            peg_opt: _,
         } = self.args;
 
         let next_order_index: DataIndex =
@@ -217,7 +222,7 @@ impl<'a, 'b, 'info> AddSingleOrderCtx<'a, 'b, 'info> {
         let other_order: &RestingOrder = get_helper_order(dynamic, current_order_index).get_value();
 
         // Remove the resting order if expired.
-        if other_order.is_expired(now_slot) {
+        if other_order.is_expired(now_slot, get_now_unix_timestamp()) {
             remove_and_update_balances(
                 fixed,
                 dynamic,
@@ -424,15 +429,13 @@ impl<'a, 'b, 'info> AddSingleOrderCtx<'a, 'b, 'info> {
 
         if did_fully_match_resting_order {
             // Get paid for removing a global order.
-            if get_helper_order(dynamic, current_order_index)
-                .get_value()
-                .get_order_type()
-                == OrderType::Global
-            {
+            let resting_order: &RestingOrder =
+                get_helper_order(dynamic, current_order_index).get_value();
+            if resting_order.get_order_type() == OrderType::Global {
                 if is_bid {
-                    remove_from_global(&global_trade_accounts_opts[0])?;
+                    remove_from_global(&global_trade_accounts_opts[0], resting_order)?;
                 } else {
-                    remove_from_global(&global_trade_accounts_opts[1])?;
+                    remove_from_global(&global_trade_accounts_opts[1], resting_order)?;
                 }
             }
 
@@ -475,9 +478,14 @@ pub fn place_order_helper<
         price: _,
         is_bid,
         last_valid_slot,
+        last_valid_unix_timestamp: _,
         order_type,
+        self_trade_behavior: _,
+        min_base_atoms_filled: _,
         global_trade_accounts_opts: _,
         current_slot,
+        price_band_opt: _,
+        peg_opt: _,
     } = args;
     assert_already_has_seat(trader_index)?;
     let now_slot: u32 = current_slot.unwrap_or_else(|| get_now_slot());
@@ -533,8 +541,12 @@ pub fn place_order_helper<
     let order_sequence_number: u64 = fixed.order_sequence_number;
     fixed.order_sequence_number = order_sequence_number.wrapping_add(1);
 
-    // If there is nothing left to rest, then return before resting.
-    if !order_type_can_rest(order_type) || remaining_base_atoms == BaseAtoms::ZERO {
+    // If there is nothing left to rest, then return before resting. A
+    // remainder below the market minimum is dropped like an IOC remainder.
+    if !order_type_can_rest(order_type)
+        || remaining_base_atoms == BaseAtoms::ZERO
+        || remaining_base_atoms < fixed.min_base_atoms
+    {
         return Ok(AddOrderToMarketResult {
             order_sequence_number,
             order_index: NIL,
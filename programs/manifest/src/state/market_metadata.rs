@@ -0,0 +1,183 @@
+/// Optional companion account for a market that holds an authority and
+/// display information for UIs and indexers. It lives at the PDA
+/// [b"market-metadata", market] and nothing in trading reads it.
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use hypertree::Get;
+use shank::ShankType;
+use solana_program::{entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey};
+use static_assertions::const_assert_eq;
+
+use crate::{program::ManifestError, require, validation::ManifestAccount};
+
+use super::{MARKET_METADATA_DISCRIMINANT, MARKET_METADATA_SIZE};
+
+pub const MARKET_METADATA_NAME_LEN: usize = 32;
+pub const MARKET_METADATA_SYMBOL_LEN: usize = 16;
+pub const MARKET_METADATA_URI_LEN: usize = 128;
+
+#[repr(C)]
+#[derive(Copy, Clone, Zeroable, Pod, ShankType)]
+pub struct MarketMetadata {
+    /// Discriminant for identifying this type of account.
+    pub discriminant: u64,
+
+    /// Market this describes.
+    market: Pubkey,
+
+    /// Only key that can update the metadata or hand the authority to someone
+    /// else.
+    authority: Pubkey,
+
+    /// UTF-8, zero padded.
+    name: [u8; MARKET_METADATA_NAME_LEN],
+    symbol: [u8; MARKET_METADATA_SYMBOL_LEN],
+    /// Link to a website or a json file with more info about the market.
+    uri: [u8; MARKET_METADATA_URI_LEN],
+
+    /// Decimals UIs should show prices and sizes with. These are hints only
+    /// and have no relation to the tick size of the market.
+    price_display_decimals: u8,
+    size_display_decimals: u8,
+
+    bump: u8,
+
+    _padding: [u8; 5],
+}
+const_assert_eq!(
+    size_of::<MarketMetadata>(),
+    8 +   // discriminant
+    32 +  // market
+    32 +  // authority
+    32 +  // name
+    16 +  // symbol
+    128 + // uri
+    1 +   // price_display_decimals
+    1 +   // size_display_decimals
+    1 +   // bump
+    5 // padding
+);
+const_assert_eq!(size_of::<MarketMetadata>(), MARKET_METADATA_SIZE);
+const_assert_eq!(size_of::<MarketMetadata>() % 8, 0);
+impl Get for MarketMetadata {}
+
+impl MarketMetadata {
+    pub fn new_empty(market: &Pubkey, authority: &Pubkey, bump: u8) -> Self {
+        MarketMetadata {
+            discriminant: MARKET_METADATA_DISCRIMINANT,
+            market: *market,
+            authority: *authority,
+            bump,
+            ..Zeroable::zeroed()
+        }
+    }
+
+    pub fn get_market(&self) -> &Pubkey {
+        &self.market
+    }
+    pub fn get_authority(&self) -> &Pubkey {
+        &self.authority
+    }
+    pub fn get_name(&self) -> &str {
+        str_from_padded(&self.name)
+    }
+    pub fn get_symbol(&self) -> &str {
+        str_from_padded(&self.symbol)
+    }
+    pub fn get_uri(&self) -> &str {
+        str_from_padded(&self.uri)
+    }
+    pub fn get_price_display_decimals(&self) -> u8 {
+        self.price_display_decimals
+    }
+    pub fn get_size_display_decimals(&self) -> u8 {
+        self.size_display_decimals
+    }
+    pub fn get_bump(&self) -> u8 {
+        self.bump
+    }
+
+    pub(crate) fn set_authority(&mut self, authority: &Pubkey) {
+        self.authority = *authority;
+    }
+
+    pub(crate) fn set_display(
+        &mut self,
+        name: &str,
+        symbol: &str,
+        uri: &str,
+        price_display_decimals: u8,
+        size_display_decimals: u8,
+    ) -> ProgramResult {
+        write_padded(&mut self.name, name)?;
+        write_padded(&mut self.symbol, symbol)?;
+        write_padded(&mut self.uri, uri)?;
+        self.price_display_decimals = price_display_decimals;
+        self.size_display_decimals = size_display_decimals;
+        Ok(())
+    }
+}
+
+fn write_padded(field: &mut [u8], value: &str) -> ProgramResult {
+    require!(
+        value.len() <= field.len(),
+        ManifestError::InvalidMarketMetadata,
+        "Metadata string is {} bytes, max is {}",
+        value.len(),
+        field.len(),
+    )?;
+    field.fill(0);
+    field[..value.len()].copy_from_slice(value.as_bytes());
+    Ok(())
+}
+
+fn str_from_padded(field: &[u8]) -> &str {
+    let len: usize = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    // Only ever written from a str, so this cannot fail.
+    std::str::from_utf8(&field[..len]).unwrap_or_default()
+}
+
+impl ManifestAccount for MarketMetadata {
+    fn verify_discriminant(&self) -> ProgramResult {
+        require!(
+            self.discriminant == MARKET_METADATA_DISCRIMINANT,
+            ProgramError::InvalidAccountData,
+            "Invalid market metadata discriminant actual: {} expected: {}",
+            self.discriminant,
+            MARKET_METADATA_DISCRIMINANT
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_set_display() {
+        let mut metadata: MarketMetadata =
+            MarketMetadata::new_empty(&Pubkey::new_unique(), &Pubkey::new_unique(), 255);
+        metadata
+            .set_display("SOL/USDC", "SOL", "https://example.com", 3, 2)
+            .unwrap();
+        assert_eq!(metadata.get_name(), "SOL/USDC");
+        assert_eq!(metadata.get_symbol(), "SOL");
+        assert_eq!(metadata.get_uri(), "https://example.com");
+        assert_eq!(metadata.get_price_display_decimals(), 3);
+        assert_eq!(metadata.get_size_display_decimals(), 2);
+
+        // Shorter values do not leave bytes from the old ones behind.
+        metadata.set_display("SOL", "", "", 0, 0).unwrap();
+        assert_eq!(metadata.get_name(), "SOL");
+        assert_eq!(metadata.get_symbol(), "");
+
+        assert!(metadata
+            .set_display(&"a".repeat(MARKET_METADATA_NAME_LEN + 1), "", "", 0, 0)
+            .is_err());
+        assert!(metadata
+            .set_display(&"a".repeat(MARKET_METADATA_NAME_LEN), "", "", 0, 0)
+            .is_ok());
+    }
+}
@@ -0,0 +1,155 @@
+use hypertree::{DataIndex, HyperTreeValueIteratorTrait};
+use solana_program::pubkey::Pubkey;
+
+use crate::{
+    quantities::{BaseAtoms, GlobalAtoms, QuoteAtomsPerBaseAtom, WrapperU64},
+    state::{GlobalRef, OrderType, RestingOrder},
+};
+
+use super::{is_stale_pegged_order, BooksideReadOnly, DerefOrBorrow, DynamicAccount, MarketFixed};
+
+/// A single resting order as the matching engine would see it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct L3Order {
+    pub price: QuoteAtomsPerBaseAtom,
+    pub num_base_atoms: BaseAtoms,
+    pub trader: Pubkey,
+    pub sequence_number: u64,
+    pub order_type: OrderType,
+    pub last_valid_slot: u32,
+    pub last_valid_unix_timestamp: u32,
+    pub order_index: DataIndex,
+}
+
+/// All orders at one price, best price first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct L2Level {
+    pub price: QuoteAtomsPerBaseAtom,
+    pub num_base_atoms: BaseAtoms,
+    pub num_orders: u32,
+}
+
+// Read only snapshots for off chain consumers. Expiration is evaluated at the
+// given slot and unix timestamp rather than the Clock so that indexers get a
+// deterministic view.
+//
+// Stale pegged orders are left out, since matching walks past them.
+//
+// Global orders are included as is unless the global that backs them is given,
+// in which case unbacked ones are skipped the same way impact_* does. Globals
+// are indexed [base, quote], so asks are checked against the first and bids
+// against the second. A market global is told apart from the global for the
+// mint by its vault, which is the market vault.
+impl<Fixed: DerefOrBorrow<MarketFixed>, Dynamic: DerefOrBorrow<[u8]>>
+    DynamicAccount<Fixed, Dynamic>
+{
+    /// Every live order on one side of the book, best price first.
+    pub fn get_l3_snapshot(
+        &self,
+        is_bid: bool,
+        now_slot: u32,
+        now_unix_timestamp: u32,
+        globals: &[Option<GlobalRef>; 2],
+    ) -> Vec<L3Order> {
+        let book: BooksideReadOnly = if is_bid {
+            self.get_bids()
+        } else {
+            self.get_asks()
+        };
+        let global_opt: &Option<GlobalRef> = if is_bid { &globals[1] } else { &globals[0] };
+        let fixed: &MarketFixed = self.fixed.deref_or_borrow();
+        let dynamic: &[u8] = self.dynamic.deref_or_borrow();
+        let market_vault: &Pubkey = if is_bid {
+            fixed.get_quote_vault()
+        } else {
+            fixed.get_base_vault()
+        };
+
+        let mut orders: Vec<L3Order> = Vec::new();
+        for (order_index, resting_order) in book.iter::<RestingOrder>() {
+            if resting_order.is_expired(now_slot, now_unix_timestamp)
+                || resting_order.get_num_base_atoms() == BaseAtoms::ZERO
+                || is_stale_pegged_order(fixed, dynamic, resting_order, now_slot)
+            {
+                continue;
+            }
+            let trader: &Pubkey = self.get_trader_key_by_index(resting_order.get_trader_index());
+            if resting_order.is_global() {
+                if let Some(global) = global_opt {
+                    let is_market_global: bool = global.fixed.get_vault() == market_vault;
+                    if is_market_global == resting_order.is_market_global()
+                        && !is_backed_by_global(resting_order, is_bid, trader, global)
+                    {
+                        continue;
+                    }
+                }
+            }
+            orders.push(L3Order {
+                price: resting_order.get_price(),
+                num_base_atoms: resting_order.get_num_base_atoms(),
+                trader: *trader,
+                sequence_number: resting_order.get_sequence_number(),
+                order_type: resting_order.get_order_type(),
+                last_valid_slot: resting_order.get_last_valid_slot(),
+                last_valid_unix_timestamp: resting_order.get_last_valid_unix_timestamp(),
+                order_index,
+            });
+        }
+        orders
+    }
+
+    /// Depth ladder of up to num_levels prices on one side of the book, built
+    /// from the same orders as get_l3_snapshot.
+    pub fn get_l2_snapshot(
+        &self,
+        is_bid: bool,
+        num_levels: usize,
+        now_slot: u32,
+        now_unix_timestamp: u32,
+        globals: &[Option<GlobalRef>; 2],
+    ) -> Vec<L2Level> {
+        let mut levels: Vec<L2Level> = Vec::with_capacity(num_levels);
+        for order in self.get_l3_snapshot(is_bid, now_slot, now_unix_timestamp, globals) {
+            match levels.last_mut() {
+                Some(level) if level.price == order.price => {
+                    level.num_base_atoms =
+                        level.num_base_atoms.saturating_add(order.num_base_atoms);
+                    level.num_orders += 1;
+                }
+                _ => {
+                    if levels.len() == num_levels {
+                        break;
+                    }
+                    levels.push(L2Level {
+                        price: order.price,
+                        num_base_atoms: order.num_base_atoms,
+                        num_orders: 1,
+                    });
+                }
+            }
+        }
+        levels
+    }
+}
+
+fn is_backed_by_global(
+    resting_order: &RestingOrder,
+    is_bid: bool,
+    trader: &Pubkey,
+    global: &GlobalRef,
+) -> bool {
+    // Bids need the quote for a full fill, rounded up like the matching engine
+    // does when a taker fully matches the order.
+    let required_atoms: u64 = if is_bid {
+        match resting_order
+            .get_price()
+            .checked_quote_for_base(resting_order.get_num_base_atoms(), true)
+        {
+            Ok(quote_atoms) => quote_atoms.as_u64(),
+            Err(_) => return false,
+        }
+    } else {
+        resting_order.get_num_base_atoms().as_u64()
+    };
+    GlobalAtoms::new(required_atoms) <= global.get_balance_atoms(trader)
+}
@@ -0,0 +1,132 @@
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use hypertree::{get_helper, get_mut_helper, DataIndex, Get, RBTREE_OVERHEAD_BYTES};
+use shank::ShankType;
+use static_assertions::const_assert_eq;
+
+use crate::quantities::{BaseAtoms, QuoteAtomsPerBaseAtom, WrapperU64};
+
+use super::constants::{MARKET_STATS_EPOCH_SLOTS, MARKET_STATS_SIZE};
+
+/// Trading stats for markets that opted in with InitMarketStats. Lives in a
+/// block of the market dynamic data that MarketFixed points to, like the
+/// PegConfig. The last fill price is in MarketFixed, next to the trigger
+/// orders that fire on it.
+///
+/// These are informational and for monitoring, updated on every fill. They
+/// do not secure any value in manifest. Anyone reading the open, high and low
+/// should check the epoch against the current slot, since they are only
+/// rolled over by the first fill of a new epoch.
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, Zeroable, Pod, ShankType)]
+pub struct MarketStats {
+    /// First, highest and lowest fill prices in the epoch.
+    epoch_open_price: QuoteAtomsPerBaseAtom,
+    epoch_high_price: QuoteAtomsPerBaseAtom,
+    epoch_low_price: QuoteAtomsPerBaseAtom,
+    /// Base volume traded since the stats were created, can overflow.
+    base_volume: BaseAtoms,
+    /// Epoch of the fills above, slot / MARKET_STATS_EPOCH_SLOTS.
+    epoch: u32,
+    /// Slot of the most recent fill.
+    last_trade_slot: u32,
+}
+// 16 + // epoch_open_price
+// 16 + // epoch_high_price
+// 16 + // epoch_low_price
+//  8 + // base_volume
+//  4 + // epoch
+//  4   // last_trade_slot
+// = 64
+const_assert_eq!(size_of::<MarketStats>(), MARKET_STATS_SIZE);
+const_assert_eq!(size_of::<MarketStats>() % 8, 0);
+impl Get for MarketStats {}
+
+impl MarketStats {
+    pub fn get_epoch_open_price(&self) -> QuoteAtomsPerBaseAtom {
+        self.epoch_open_price
+    }
+    pub fn get_epoch_high_price(&self) -> QuoteAtomsPerBaseAtom {
+        self.epoch_high_price
+    }
+    pub fn get_epoch_low_price(&self) -> QuoteAtomsPerBaseAtom {
+        self.epoch_low_price
+    }
+    pub fn get_base_volume(&self) -> BaseAtoms {
+        self.base_volume
+    }
+    /// Epoch that the open, high and low are for. Zero before the first fill.
+    pub fn get_epoch(&self) -> u32 {
+        self.epoch
+    }
+    pub fn get_last_trade_slot(&self) -> u32 {
+        self.last_trade_slot
+    }
+
+    pub(crate) fn record_fill(
+        &mut self,
+        price: QuoteAtomsPerBaseAtom,
+        base_atoms: BaseAtoms,
+        now_slot: u32,
+    ) {
+        let epoch: u32 = get_market_stats_epoch(now_slot);
+        if epoch != self.epoch || self.epoch_open_price == QuoteAtomsPerBaseAtom::ZERO {
+            self.epoch = epoch;
+            self.epoch_open_price = price;
+            self.epoch_high_price = price;
+            self.epoch_low_price = price;
+        } else {
+            self.epoch_high_price = self.epoch_high_price.max(price);
+            self.epoch_low_price = self.epoch_low_price.min(price);
+        }
+        self.base_volume = self.base_volume.wrapping_add(base_atoms);
+        self.last_trade_slot = now_slot;
+    }
+}
+
+/// Epoch that a slot is in for the open, high and low.
+pub fn get_market_stats_epoch(slot: u32) -> u32 {
+    slot / MARKET_STATS_EPOCH_SLOTS
+}
+
+/// Read the MarketStats in the block at a given index.
+pub fn get_helper_market_stats(data: &[u8], index: DataIndex) -> &MarketStats {
+    get_helper::<MarketStats>(data, index + RBTREE_OVERHEAD_BYTES as DataIndex)
+}
+
+/// Mutably read the MarketStats in the block at a given index.
+pub fn get_mut_helper_market_stats(data: &mut [u8], index: DataIndex) -> &mut MarketStats {
+    get_mut_helper::<MarketStats>(data, index + RBTREE_OVERHEAD_BYTES as DataIndex)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn price(mantissa: u32) -> QuoteAtomsPerBaseAtom {
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(mantissa, 0).unwrap()
+    }
+
+    #[test]
+    fn test_record_fill() {
+        let mut stats: MarketStats = MarketStats::default();
+        stats.record_fill(price(10), BaseAtoms::new(1), 1);
+        stats.record_fill(price(12), BaseAtoms::new(2), 2);
+        stats.record_fill(price(9), BaseAtoms::new(3), 3);
+        assert_eq!(stats.get_epoch(), 0);
+        assert_eq!(stats.get_epoch_open_price(), price(10));
+        assert_eq!(stats.get_epoch_high_price(), price(12));
+        assert_eq!(stats.get_epoch_low_price(), price(9));
+        assert_eq!(stats.get_base_volume(), BaseAtoms::new(6));
+        assert_eq!(stats.get_last_trade_slot(), 3);
+
+        // The first fill of the next epoch starts over, volume carries on.
+        stats.record_fill(price(11), BaseAtoms::new(1), MARKET_STATS_EPOCH_SLOTS);
+        assert_eq!(stats.get_epoch(), 1);
+        assert_eq!(stats.get_epoch_open_price(), price(11));
+        assert_eq!(stats.get_epoch_high_price(), price(11));
+        assert_eq!(stats.get_epoch_low_price(), price(11));
+        assert_eq!(stats.get_base_volume(), BaseAtoms::new(7));
+    }
+}
@@ -1,13 +1,31 @@
 pub mod claimed_seat;
 pub mod constants;
+#[cfg(not(feature = "certora"))]
+pub mod delegation_status;
 pub mod dynamic_account;
 pub mod global;
 pub mod market;
+pub mod market_authority;
+pub mod market_metadata;
+pub mod market_stats;
+pub mod peg_config;
 pub mod resting_order;
+pub mod trigger_order;
+#[cfg(not(feature = "certora"))]
+pub mod twap;
 pub mod utils;
 
 pub use constants::*;
+#[cfg(not(feature = "certora"))]
+pub use delegation_status::*;
 pub use dynamic_account::*;
 pub use global::*;
 pub use market::*;
+pub use market_authority::*;
+pub use market_metadata::*;
+pub use market_stats::*;
+pub use peg_config::*;
 pub use resting_order::*;
+pub use trigger_order::*;
+#[cfg(not(feature = "certora"))]
+pub use twap::*;
@@ -0,0 +1,77 @@
+use std::mem::size_of;
+
+use bytemuck::{Pod, Zeroable};
+use hypertree::{get_helper, get_mut_helper, DataIndex, Get, RBTREE_OVERHEAD_BYTES};
+use shank::ShankType;
+use solana_program::pubkey::Pubkey;
+use static_assertions::const_assert_eq;
+
+use crate::quantities::QuoteAtomsPerBaseAtom;
+
+use super::constants::PEG_CONFIG_SIZE;
+
+/// Settings for pegged orders, chosen when the market is created. Lives in a
+/// block of the market dynamic data that MarketFixed points to. The block
+/// keeps the tree node header so that scans over blocks can tell it apart by
+/// payload type, but it is not in any tree.
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, Zeroable, Pod, ShankType)]
+pub struct PegConfig {
+    /// Pyth or Switchboard price account that pegged orders follow.
+    oracle: Pubkey,
+    /// Oldest oracle update, in slots, that pegged orders are repriced to.
+    max_staleness_slots: u64,
+    /// Oracle price in atoms that pegged orders were last repriced to.
+    oracle_price: QuoteAtomsPerBaseAtom,
+    /// Pegged orders can only be placed or matched in the slot that they were
+    /// last repriced in.
+    last_reprice_slot: u32,
+    _padding: [u8; 4],
+}
+// 32 + // oracle
+//  8 + // max_staleness_slots
+// 16 + // oracle_price
+//  4 + // last_reprice_slot
+//  4   // padding
+// = 64
+const_assert_eq!(size_of::<PegConfig>(), PEG_CONFIG_SIZE);
+const_assert_eq!(size_of::<PegConfig>() % 8, 0);
+impl Get for PegConfig {}
+
+impl PegConfig {
+    pub fn new(oracle: &Pubkey, max_staleness_slots: u64) -> Self {
+        PegConfig {
+            oracle: *oracle,
+            max_staleness_slots,
+            ..Default::default()
+        }
+    }
+
+    pub fn get_oracle(&self) -> &Pubkey {
+        &self.oracle
+    }
+    pub fn get_max_staleness_slots(&self) -> u64 {
+        self.max_staleness_slots
+    }
+    pub fn get_oracle_price(&self) -> QuoteAtomsPerBaseAtom {
+        self.oracle_price
+    }
+    pub fn get_last_reprice_slot(&self) -> u32 {
+        self.last_reprice_slot
+    }
+
+    pub(crate) fn set_oracle_price(&mut self, oracle_price: QuoteAtomsPerBaseAtom, slot: u32) {
+        self.oracle_price = oracle_price;
+        self.last_reprice_slot = slot;
+    }
+}
+
+/// Read the PegConfig in the block at a given index.
+pub fn get_helper_peg_config(data: &[u8], index: DataIndex) -> &PegConfig {
+    get_helper::<PegConfig>(data, index + RBTREE_OVERHEAD_BYTES as DataIndex)
+}
+
+/// Mutably read the PegConfig in the block at a given index.
+pub fn get_mut_helper_peg_config(data: &mut [u8], index: DataIndex) -> &mut PegConfig {
+    get_mut_helper::<PegConfig>(data, index + RBTREE_OVERHEAD_BYTES as DataIndex)
+}
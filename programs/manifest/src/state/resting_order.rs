@@ -1,6 +1,8 @@
 use std::mem::size_of;
 
-use crate::quantities::{u64_slice_to_u128, BaseAtoms, QuoteAtomsPerBaseAtom};
+use crate::quantities::{
+    u64_slice_to_u128, BaseAtoms, PriceConversionError, QuoteAtomsPerBaseAtom,
+};
 #[cfg(feature = "certora")]
 use crate::quantities::{QuoteAtoms, WrapperU64};
 use borsh::{BorshDeserialize, BorshSerialize};
@@ -12,7 +14,10 @@ use solana_program::{entrypoint::ProgramResult, program_error::ProgramError};
 use static_assertions::const_assert_eq;
 use std::cmp::Ordering;
 
-use super::{constants::NO_EXPIRATION_LAST_VALID_SLOT, RESTING_ORDER_SIZE};
+use super::{
+    constants::{NO_EXPIRATION_LAST_VALID_SLOT, NO_EXPIRATION_LAST_VALID_UNIX_TIMESTAMP},
+    RESTING_ORDER_SIZE,
+};
 
 #[derive(
     Debug,
@@ -42,6 +47,17 @@ pub enum OrderType {
     // Reverse orders behave like an AMM. When filled, they place an order on
     // the other side of the book with a small fee (spread).
     Reverse = 4,
+
+    // Does not rest. Fails unless the entire order fills.
+    FillOrKill = 5,
+
+    // Post only, but instead of failing when it would cross, rests one tick
+    // behind the best price on the other side.
+    PostOnlySlide = 6,
+
+    // Follows the market peg oracle. Priced at the oracle price plus an
+    // offset, but never more aggressive than the limit price.
+    Pegged = 7,
 }
 unsafe impl bytemuck::Zeroable for OrderType {}
 unsafe impl bytemuck::Pod for OrderType {}
@@ -51,12 +67,46 @@ impl Default for OrderType {
     }
 }
 
+/// What to do when an order would match against a resting order from the same
+/// trader. Only applies to the taking part of an order.
+#[derive(
+    Debug,
+    BorshDeserialize,
+    BorshSerialize,
+    PartialEq,
+    Clone,
+    Copy,
+    ShankType,
+    IntoPrimitive,
+    TryFromPrimitive,
+    Default,
+)]
+#[repr(u8)]
+pub enum SelfTradeBehavior {
+    // Match as with any other maker.
+    #[default]
+    Allow = 0,
+
+    // Cancel the resting order and keep matching.
+    CancelResting = 1,
+
+    // Stop matching and cancel the rest of the taking order, it does not rest.
+    CancelTaking = 2,
+
+    // Reduce both orders by the overlapping size without a fill.
+    DecrementBoth = 3,
+}
+unsafe impl bytemuck::Zeroable for SelfTradeBehavior {}
+unsafe impl bytemuck::Pod for SelfTradeBehavior {}
+
 pub fn order_type_can_rest(order_type: OrderType) -> bool {
-    order_type != OrderType::ImmediateOrCancel
+    order_type != OrderType::ImmediateOrCancel && order_type != OrderType::FillOrKill
 }
 
 pub fn order_type_can_take(order_type: OrderType) -> bool {
-    order_type != OrderType::PostOnly && order_type != OrderType::Global
+    order_type != OrderType::PostOnly
+        && order_type != OrderType::PostOnlySlide
+        && order_type != OrderType::Global
 }
 
 #[repr(C)]
@@ -71,7 +121,20 @@ pub struct RestingOrder {
     order_type: OrderType,
     // Spread for reverse orders. Defaults to zero.
     reverse_spread: u16,
-    _padding: [u8; 20],
+    // Optional expiration by unix timestamp, checked in addition to
+    // last_valid_slot. Zero for no expiration.
+    last_valid_unix_timestamp: u32,
+    // Offset from the oracle price and limit for pegged orders, in the same
+    // mantissa and exponent form as order prices. Zero for other orders.
+    peg_offset_mantissa: i32,
+    peg_limit_mantissa: u32,
+    peg_offset_exponent: i8,
+    peg_limit_exponent: i8,
+    // For global orders, whether the market global for the mint backs the
+    // order instead of the global for the mint. Only that one can fill or
+    // remove it.
+    is_market_global: PodBool,
+    _padding: [u8; 5],
 }
 
 // 16 +  // price
@@ -82,11 +145,74 @@ pub struct RestingOrder {
 //  1 +  // is_bid
 //  1 +  // order_type
 //  2 +  // spread
-// 20    // padding 2
+//  4 +  // last_valid_unix_timestamp
+//  4 +  // peg_offset_mantissa
+//  4 +  // peg_limit_mantissa
+//  1 +  // peg_offset_exponent
+//  1 +  // peg_limit_exponent
+//  1 +  // is_market_global
+//  5    // padding 2
 // = 64
 const_assert_eq!(size_of::<RestingOrder>(), RESTING_ORDER_SIZE);
 const_assert_eq!(size_of::<RestingOrder>() % 8, 0);
 
+/// Where a pegged order sits relative to the oracle price. The offset can be
+/// negative. The limit is the highest price a bid will pay or the lowest an
+/// ask will sell at.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct OrderPeg {
+    pub offset_mantissa: i32,
+    pub offset_exponent: i8,
+    pub limit_mantissa: u32,
+    pub limit_exponent: i8,
+}
+
+impl OrderPeg {
+    pub fn new(
+        offset_mantissa: i32,
+        offset_exponent: i8,
+        limit_mantissa: u32,
+        limit_exponent: i8,
+    ) -> Self {
+        OrderPeg {
+            offset_mantissa,
+            offset_exponent,
+            limit_mantissa,
+            limit_exponent,
+        }
+    }
+
+    pub fn try_limit_price(&self) -> Result<QuoteAtomsPerBaseAtom, PriceConversionError> {
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(
+            self.limit_mantissa,
+            self.limit_exponent,
+        )
+    }
+
+    /// Oracle price plus the offset, rounded behind onto the tick grid and
+    /// capped at the limit.
+    pub fn try_price(
+        &self,
+        oracle_price: QuoteAtomsPerBaseAtom,
+        price_tick: QuoteAtomsPerBaseAtom,
+        is_bid: bool,
+    ) -> Result<QuoteAtomsPerBaseAtom, PriceConversionError> {
+        let offset: QuoteAtomsPerBaseAtom = QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(
+            self.offset_mantissa.unsigned_abs(),
+            self.offset_exponent,
+        )?;
+        let limit_price: QuoteAtomsPerBaseAtom = self.try_limit_price()?;
+        let price: QuoteAtomsPerBaseAtom = oracle_price
+            .saturating_offset(offset, self.offset_mantissa < 0)
+            .round_to_grid(price_tick, is_bid);
+        Ok(if is_bid {
+            price.min(limit_price)
+        } else {
+            price.max(limit_price)
+        })
+    }
+}
+
 impl RestingOrder {
     pub fn new(
         trader_index: DataIndex,
@@ -112,6 +238,12 @@ impl RestingOrder {
             is_bid: PodBool::from_bool(is_bid),
             order_type,
             reverse_spread: 0,
+            last_valid_unix_timestamp: NO_EXPIRATION_LAST_VALID_UNIX_TIMESTAMP,
+            peg_offset_mantissa: 0,
+            peg_limit_mantissa: 0,
+            peg_offset_exponent: 0,
+            peg_limit_exponent: 0,
+            is_market_global: PodBool::from_bool(false),
             _padding: Default::default(),
         })
     }
@@ -120,6 +252,11 @@ impl RestingOrder {
         self.trader_index
     }
 
+    /// Only used when compaction moves the trader's seat.
+    pub(crate) fn set_trader_index(&mut self, trader_index: DataIndex) {
+        self.trader_index = trader_index;
+    }
+
     pub fn get_num_base_atoms(&self) -> BaseAtoms {
         self.num_base_atoms
     }
@@ -128,6 +265,35 @@ impl RestingOrder {
         self.price
     }
 
+    /// Only used when repricing pegged orders. Caller is responsible for
+    /// moving the order in the tree.
+    pub(crate) fn set_price(&mut self, price: QuoteAtomsPerBaseAtom) {
+        self.price = price;
+    }
+
+    /// Price that the funds backing a bid were reserved at. Pegged bids
+    /// reserve at their limit so that they can be repriced up to it.
+    pub fn get_funding_price(&self) -> QuoteAtomsPerBaseAtom {
+        if self.is_pegged() {
+            // The limit was validated when the order was placed.
+            self.get_peg().try_limit_price().unwrap_or(self.price)
+        } else {
+            self.price
+        }
+    }
+
+    pub fn get_last_valid_slot(&self) -> u32 {
+        self.last_valid_slot
+    }
+
+    pub fn get_last_valid_unix_timestamp(&self) -> u32 {
+        self.last_valid_unix_timestamp
+    }
+
+    pub fn set_last_valid_unix_timestamp(&mut self, last_valid_unix_timestamp: u32) {
+        self.last_valid_unix_timestamp = last_valid_unix_timestamp;
+    }
+
     #[cfg(any(test, feature = "no-clock"))]
     pub fn set_sequence_number(&mut self, sequence_number: u64) {
         self.sequence_number = sequence_number;
@@ -151,10 +317,38 @@ impl RestingOrder {
         self.order_type == OrderType::Global
     }
 
+    pub fn is_market_global(&self) -> bool {
+        self.is_market_global.0 == 1
+    }
+
+    pub(crate) fn set_is_market_global(&mut self, is_market_global: bool) {
+        self.is_market_global = PodBool::from_bool(is_market_global);
+    }
+
     pub fn is_reverse(&self) -> bool {
         self.order_type == OrderType::Reverse
     }
 
+    pub fn is_pegged(&self) -> bool {
+        self.order_type == OrderType::Pegged
+    }
+
+    pub fn get_peg(&self) -> OrderPeg {
+        OrderPeg {
+            offset_mantissa: self.peg_offset_mantissa,
+            offset_exponent: self.peg_offset_exponent,
+            limit_mantissa: self.peg_limit_mantissa,
+            limit_exponent: self.peg_limit_exponent,
+        }
+    }
+
+    pub fn set_peg(&mut self, peg: OrderPeg) {
+        self.peg_offset_mantissa = peg.offset_mantissa;
+        self.peg_offset_exponent = peg.offset_exponent;
+        self.peg_limit_mantissa = peg.limit_mantissa;
+        self.peg_limit_exponent = peg.limit_exponent;
+    }
+
     pub fn get_reverse_spread(self) -> u16 {
         self.reverse_spread
     }
@@ -167,8 +361,11 @@ impl RestingOrder {
         self.sequence_number
     }
 
-    pub fn is_expired(&self, current_slot: u32) -> bool {
-        self.last_valid_slot != NO_EXPIRATION_LAST_VALID_SLOT && self.last_valid_slot < current_slot
+    pub fn is_expired(&self, current_slot: u32, current_unix_timestamp: u32) -> bool {
+        (self.last_valid_slot != NO_EXPIRATION_LAST_VALID_SLOT
+            && self.last_valid_slot < current_slot)
+            || (self.last_valid_unix_timestamp != NO_EXPIRATION_LAST_VALID_UNIX_TIMESTAMP
+                && self.last_valid_unix_timestamp < current_unix_timestamp)
     }
 
     pub fn get_is_bid(&self) -> bool {
@@ -182,7 +379,9 @@ impl RestingOrder {
         if self.is_global() {
             return Ok((BaseAtoms::new(0), QuoteAtoms::new(0)));
         } else if self.get_is_bid() {
-            let quote_amount = self.num_base_atoms.checked_mul(self.price, true)?;
+            let quote_amount = self
+                .num_base_atoms
+                .checked_mul(self.get_funding_price(), true)?;
             return Ok((BaseAtoms::new(0), quote_amount));
         } else {
             return Ok((self.num_base_atoms, QuoteAtoms::new(0)));
@@ -194,7 +393,8 @@ impl RestingOrder {
         Ok(())
     }
 
-    // Only needed for combining orders. There is no edit_order function.
+    // Only needed for combining orders. Modifying an order can only shrink it
+    // in place, otherwise it is replaced.
     pub fn increase(&mut self, size: BaseAtoms) -> ProgramResult {
         self.num_base_atoms = self.num_base_atoms.checked_add(size)?;
         Ok(())
@@ -335,4 +535,90 @@ mod test {
         resting_order.set_last_valid_slot(1);
         resting_order.set_sequence_number(1);
     }
+
+    #[test]
+    fn test_is_expired() {
+        let mut resting_order: RestingOrder = RestingOrder::new(
+            0,
+            BaseAtoms::ZERO,
+            QuoteAtomsPerBaseAtom::ZERO,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            true,
+            OrderType::Limit,
+        )
+        .unwrap();
+        assert!(!resting_order.is_expired(u32::MAX, u32::MAX));
+
+        resting_order.set_last_valid_unix_timestamp(100);
+        assert!(!resting_order.is_expired(u32::MAX, 100));
+        assert!(resting_order.is_expired(0, 101));
+
+        resting_order.set_last_valid_slot(10);
+        assert!(resting_order.is_expired(11, 0));
+        assert!(!resting_order.is_expired(10, 100));
+    }
+
+    #[test]
+    fn test_peg() {
+        let oracle_price: QuoteAtomsPerBaseAtom =
+            QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(10, 0).unwrap();
+        let tick: QuoteAtomsPerBaseAtom =
+            QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(5, -2).unwrap();
+
+        // Bid 0.12 under the oracle, up to 11.
+        let bid_peg: OrderPeg = OrderPeg::new(-12, -2, 11, 0);
+        assert_eq!(
+            bid_peg.try_price(oracle_price, tick, true).unwrap(),
+            QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(985, -2).unwrap()
+        );
+        assert_eq!(
+            bid_peg
+                .try_price(
+                    QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(20, 0).unwrap(),
+                    tick,
+                    true
+                )
+                .unwrap(),
+            bid_peg.try_limit_price().unwrap()
+        );
+
+        // Ask 0.12 over the oracle, down to 10.5.
+        let ask_peg: OrderPeg = OrderPeg::new(12, -2, 105, -1);
+        assert_eq!(
+            ask_peg.try_price(oracle_price, tick, false).unwrap(),
+            ask_peg.try_limit_price().unwrap()
+        );
+        assert_eq!(
+            ask_peg
+                .try_price(
+                    QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(11, 0).unwrap(),
+                    tick,
+                    false
+                )
+                .unwrap(),
+            QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(1115, -2).unwrap()
+        );
+        assert!(OrderPeg::new(1, 20, 1, 0)
+            .try_price(oracle_price, tick, false)
+            .is_err());
+
+        let mut resting_order: RestingOrder = RestingOrder::new(
+            0,
+            BaseAtoms::ZERO,
+            QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(985, -2).unwrap(),
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            true,
+            OrderType::Pegged,
+        )
+        .unwrap();
+        resting_order.set_peg(bid_peg);
+        assert!(resting_order.is_pegged());
+        assert_eq!(resting_order.get_peg(), bid_peg);
+        assert_eq!(
+            resting_order.get_funding_price(),
+            bid_peg.try_limit_price().unwrap()
+        );
+    }
 }
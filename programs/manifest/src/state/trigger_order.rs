@@ -0,0 +1,355 @@
+use std::mem::size_of;
+
+use crate::quantities::{BaseAtoms, PriceConversionError, QuoteAtomsPerBaseAtom};
+use borsh::{BorshDeserialize, BorshSerialize};
+use bytemuck::{Pod, Zeroable};
+use hypertree::{DataIndex, PodBool};
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+use shank::ShankType;
+use static_assertions::const_assert_eq;
+use std::cmp::Ordering;
+
+use super::{
+    constants::{NO_EXPIRATION_LAST_VALID_SLOT, TRIGGER_ORDER_SIZE},
+    OrderType, SelfTradeBehavior,
+};
+
+#[derive(
+    Debug,
+    Default,
+    BorshDeserialize,
+    BorshSerialize,
+    PartialEq,
+    Clone,
+    Copy,
+    ShankType,
+    IntoPrimitive,
+    TryFromPrimitive,
+)]
+#[repr(u8)]
+pub enum TriggerOrderType {
+    // Once the price moves against the order, sells below or buys above the
+    // trigger, takes immediately up to the order price.
+    #[default]
+    StopMarket = 0,
+
+    // Same trigger as a stop market, but places a limit order at the order
+    // price that can rest.
+    StopLimit = 1,
+
+    // Once the price moves in favor of the order, sells above or buys below
+    // the trigger, takes immediately up to the order price.
+    TakeProfit = 2,
+
+    // Stop market where the trigger follows the price at a fixed distance
+    // when it moves in favor of the order.
+    TrailingStop = 3,
+}
+unsafe impl bytemuck::Zeroable for TriggerOrderType {}
+unsafe impl bytemuck::Pod for TriggerOrderType {}
+
+/// Order that is not on the book and is not backed by funds until it is
+/// triggered. Lives in the trigger tree and becomes a regular order when a
+/// crank sees the price cross the trigger.
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, Zeroable, Pod, ShankType)]
+pub struct TriggerOrder {
+    trigger_price: QuoteAtomsPerBaseAtom,
+    // Limit for stop limit orders. Worst price that the other types will
+    // take at.
+    price: QuoteAtomsPerBaseAtom,
+    num_base_atoms: BaseAtoms,
+    sequence_number: u64,
+    trader_index: DataIndex,
+    last_valid_slot: u32,
+    // Distance the trigger keeps from the price for trailing stops, in the
+    // same mantissa and exponent form as order prices. Zero for other orders.
+    trail_mantissa: u32,
+    is_bid: PodBool,
+    trigger_type: TriggerOrderType,
+    trail_exponent: i8,
+    // Applied to the order placed once triggered.
+    self_trade_behavior: SelfTradeBehavior,
+}
+
+// 16 +  // trigger_price
+// 16 +  // price
+//  8 +  // num_base_atoms
+//  8 +  // sequence_number
+//  4 +  // trader_index
+//  4 +  // last_valid_slot
+//  4 +  // trail_mantissa
+//  1 +  // is_bid
+//  1 +  // trigger_type
+//  1 +  // trail_exponent
+//  1    // self_trade_behavior
+// = 64
+const_assert_eq!(size_of::<TriggerOrder>(), TRIGGER_ORDER_SIZE);
+const_assert_eq!(size_of::<TriggerOrder>() % 8, 0);
+
+impl TriggerOrder {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        trader_index: DataIndex,
+        num_base_atoms: BaseAtoms,
+        trigger_price: QuoteAtomsPerBaseAtom,
+        price: QuoteAtomsPerBaseAtom,
+        sequence_number: u64,
+        last_valid_slot: u32,
+        is_bid: bool,
+        trigger_type: TriggerOrderType,
+    ) -> Self {
+        TriggerOrder {
+            trigger_price,
+            price,
+            num_base_atoms,
+            sequence_number,
+            trader_index,
+            last_valid_slot,
+            trail_mantissa: 0,
+            is_bid: PodBool::from_bool(is_bid),
+            trigger_type,
+            trail_exponent: 0,
+            self_trade_behavior: SelfTradeBehavior::Allow,
+        }
+    }
+
+    pub fn get_trigger_price(&self) -> QuoteAtomsPerBaseAtom {
+        self.trigger_price
+    }
+    pub fn get_price(&self) -> QuoteAtomsPerBaseAtom {
+        self.price
+    }
+    pub fn get_num_base_atoms(&self) -> BaseAtoms {
+        self.num_base_atoms
+    }
+    pub fn get_sequence_number(&self) -> u64 {
+        self.sequence_number
+    }
+    pub fn get_trader_index(&self) -> DataIndex {
+        self.trader_index
+    }
+    pub fn get_last_valid_slot(&self) -> u32 {
+        self.last_valid_slot
+    }
+    pub fn get_is_bid(&self) -> bool {
+        self.is_bid.0 == 1
+    }
+    pub fn get_trigger_type(&self) -> TriggerOrderType {
+        self.trigger_type
+    }
+    pub fn get_self_trade_behavior(&self) -> SelfTradeBehavior {
+        self.self_trade_behavior
+    }
+
+    pub fn is_expired(&self, current_slot: u32) -> bool {
+        self.last_valid_slot != NO_EXPIRATION_LAST_VALID_SLOT && self.last_valid_slot < current_slot
+    }
+
+    /// Order type that the trigger order is placed as once triggered.
+    pub fn get_order_type(&self) -> OrderType {
+        if self.trigger_type == TriggerOrderType::StopLimit {
+            OrderType::Limit
+        } else {
+            OrderType::ImmediateOrCancel
+        }
+    }
+
+    /// Whether the order triggers when the price goes up to the trigger price
+    /// rather than down to it. Stops buy when the price rises and sell when
+    /// it falls, take profits the other way around.
+    pub fn triggers_on_rise(&self) -> bool {
+        (self.trigger_type == TriggerOrderType::TakeProfit) != self.get_is_bid()
+    }
+
+    pub fn is_triggered(&self, price: QuoteAtomsPerBaseAtom) -> bool {
+        if self.triggers_on_rise() {
+            price >= self.trigger_price
+        } else {
+            price <= self.trigger_price
+        }
+    }
+
+    pub fn try_trail(&self) -> Result<QuoteAtomsPerBaseAtom, PriceConversionError> {
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(
+            self.trail_mantissa,
+            self.trail_exponent,
+        )
+    }
+
+    /// New trigger price for a trailing stop after the price moved in its
+    /// favor. None when the trigger stays where it is.
+    pub fn try_trailing_trigger_price(
+        &self,
+        price: QuoteAtomsPerBaseAtom,
+    ) -> Result<Option<QuoteAtomsPerBaseAtom>, PriceConversionError> {
+        if self.trigger_type != TriggerOrderType::TrailingStop {
+            return Ok(None);
+        }
+        let trail: QuoteAtomsPerBaseAtom = self.try_trail()?;
+        // Buy stops sit above the price and follow it down, sell stops sit
+        // below and follow it up.
+        let trigger_price: QuoteAtomsPerBaseAtom =
+            price.saturating_offset(trail, !self.get_is_bid());
+        let is_better: bool = if self.get_is_bid() {
+            trigger_price < self.trigger_price
+        } else {
+            trigger_price > self.trigger_price
+        };
+        Ok(if is_better { Some(trigger_price) } else { None })
+    }
+
+    pub fn set_trail(&mut self, trail_mantissa: u32, trail_exponent: i8) {
+        self.trail_mantissa = trail_mantissa;
+        self.trail_exponent = trail_exponent;
+    }
+
+    pub fn set_self_trade_behavior(&mut self, self_trade_behavior: SelfTradeBehavior) {
+        self.self_trade_behavior = self_trade_behavior;
+    }
+
+    pub(crate) fn set_trigger_price(&mut self, trigger_price: QuoteAtomsPerBaseAtom) {
+        self.trigger_price = trigger_price;
+    }
+
+    pub(crate) fn set_sequence_number(&mut self, sequence_number: u64) {
+        self.sequence_number = sequence_number;
+    }
+
+    pub(crate) fn set_trader_index(&mut self, trader_index: DataIndex) {
+        self.trader_index = trader_index;
+    }
+}
+
+impl Ord for TriggerOrder {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.trigger_price, self.sequence_number)
+            .cmp(&(other.trigger_price, other.sequence_number))
+    }
+}
+
+impl PartialOrd for TriggerOrder {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for TriggerOrder {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for TriggerOrder {}
+
+impl std::fmt::Display for TriggerOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}@{} if {}",
+            self.num_base_atoms, self.price, self.trigger_price
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::quantities::WrapperU64;
+
+    fn price(mantissa: u32) -> QuoteAtomsPerBaseAtom {
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(mantissa, 0).unwrap()
+    }
+
+    fn trigger_order(is_bid: bool, trigger_type: TriggerOrderType) -> TriggerOrder {
+        TriggerOrder::new(
+            0,
+            BaseAtoms::new(1),
+            price(10),
+            price(9),
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            is_bid,
+            trigger_type,
+        )
+    }
+
+    #[test]
+    fn test_display() {
+        format!("{}", trigger_order(true, TriggerOrderType::StopMarket));
+    }
+
+    #[test]
+    fn test_is_triggered() {
+        let sell_stop: TriggerOrder = trigger_order(false, TriggerOrderType::StopLimit);
+        assert_eq!(sell_stop.get_order_type(), OrderType::Limit);
+        assert!(sell_stop.is_triggered(price(10)));
+        assert!(sell_stop.is_triggered(price(9)));
+        assert!(!sell_stop.is_triggered(price(11)));
+
+        let buy_stop: TriggerOrder = trigger_order(true, TriggerOrderType::StopMarket);
+        assert_eq!(buy_stop.get_order_type(), OrderType::ImmediateOrCancel);
+        assert!(buy_stop.is_triggered(price(11)));
+        assert!(!buy_stop.is_triggered(price(9)));
+
+        let sell_take_profit: TriggerOrder = trigger_order(false, TriggerOrderType::TakeProfit);
+        assert!(sell_take_profit.is_triggered(price(11)));
+        assert!(!sell_take_profit.is_triggered(price(9)));
+
+        let buy_take_profit: TriggerOrder = trigger_order(true, TriggerOrderType::TakeProfit);
+        assert!(buy_take_profit.is_triggered(price(9)));
+        assert!(!buy_take_profit.is_triggered(price(11)));
+    }
+
+    #[test]
+    fn test_trailing_trigger_price() {
+        let mut sell_trailing_stop: TriggerOrder =
+            trigger_order(false, TriggerOrderType::TrailingStop);
+        sell_trailing_stop.set_trail(2, 0);
+        assert_eq!(
+            sell_trailing_stop
+                .try_trailing_trigger_price(price(13))
+                .unwrap(),
+            Some(price(11))
+        );
+        assert_eq!(
+            sell_trailing_stop
+                .try_trailing_trigger_price(price(11))
+                .unwrap(),
+            None
+        );
+
+        let mut buy_trailing_stop: TriggerOrder =
+            trigger_order(true, TriggerOrderType::TrailingStop);
+        buy_trailing_stop.set_trail(2, 0);
+        assert_eq!(
+            buy_trailing_stop
+                .try_trailing_trigger_price(price(7))
+                .unwrap(),
+            Some(price(9))
+        );
+        assert_eq!(
+            buy_trailing_stop
+                .try_trailing_trigger_price(price(9))
+                .unwrap(),
+            None
+        );
+
+        // Other types never move.
+        assert_eq!(
+            trigger_order(false, TriggerOrderType::StopMarket)
+                .try_trailing_trigger_price(price(13))
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_cmp() {
+        let low: TriggerOrder = trigger_order(true, TriggerOrderType::StopMarket);
+        let mut high: TriggerOrder = trigger_order(false, TriggerOrderType::StopMarket);
+        high.set_trigger_price(price(11));
+        assert!(low < high);
+        assert!(low != high);
+    }
+}
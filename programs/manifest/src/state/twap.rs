@@ -0,0 +1,83 @@
+use crate::quantities::{u128_to_u64_slice, u64_slice_to_u128, QuoteAtomsPerBaseAtom};
+
+/// Reading of the market TWAP accumulator, the sum of the last trade price
+/// times the number of slots it stood, like the Uniswap v2 price accumulator.
+/// The sum wraps, so only the difference between two observations means
+/// anything. Take one with MarketFixed::get_twap_observation and another one
+/// later, then get the TWAP between them with get_twap.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TwapObservation {
+    pub cumulative_price: u128,
+    pub slot: u32,
+}
+
+impl TwapObservation {
+    pub fn new(cumulative_price: u128, slot: u32) -> Self {
+        TwapObservation {
+            cumulative_price,
+            slot,
+        }
+    }
+
+    /// Observation at slot given that price stood since this one. Slots at or
+    /// before this one leave it as is.
+    pub fn advance(self, price: QuoteAtomsPerBaseAtom, slot: u32) -> Self {
+        if slot <= self.slot {
+            return self;
+        }
+        let num_slots: u128 = (slot - self.slot) as u128;
+        TwapObservation {
+            cumulative_price: self
+                .cumulative_price
+                .wrapping_add(u64_slice_to_u128(price.inner).wrapping_mul(num_slots)),
+            slot,
+        }
+    }
+}
+
+/// Time-weighted average of the last trade price between two observations.
+/// None unless end is after start. Slots before the first fill on the market
+/// count as a zero price.
+///
+/// Every slot counts the same no matter how much traded, and the program
+/// cannot tell two accounts of one owner apart. Holding a price through the
+/// end of a slot takes a fill at it, which can be tiny or against yourself
+/// from a second seat, so a thin market TWAP is cheap to move. Only self
+/// trades on one seat are left out. Use it over many slots and next to an
+/// oracle or the traded volume, not as a price source on its own.
+pub fn get_twap(start: &TwapObservation, end: &TwapObservation) -> Option<QuoteAtomsPerBaseAtom> {
+    if end.slot <= start.slot {
+        return None;
+    }
+    let num_slots: u128 = (end.slot - start.slot) as u128;
+    let inner: u128 = end.cumulative_price.wrapping_sub(start.cumulative_price) / num_slots;
+    Some(QuoteAtomsPerBaseAtom {
+        inner: u128_to_u64_slice(inner),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn price(mantissa: u32) -> QuoteAtomsPerBaseAtom {
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(mantissa, 0).unwrap()
+    }
+
+    #[test]
+    fn test_twap() {
+        let start: TwapObservation = TwapObservation::new(u128::MAX, 10);
+        // 10 for 3 slots then 20 for 1 slot.
+        let middle: TwapObservation = start.advance(price(10), 13);
+        let end: TwapObservation = middle.advance(price(20), 14);
+        // The accumulator wraps in between.
+        assert_eq!(
+            get_twap(&start, &end),
+            Some(QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(125, -1).unwrap())
+        );
+        assert_eq!(get_twap(&start, &middle), Some(price(10)));
+        assert_eq!(middle.advance(price(20), 12), middle);
+        assert_eq!(get_twap(&end, &start), None);
+        assert_eq!(get_twap(&end, &end), None);
+    }
+}
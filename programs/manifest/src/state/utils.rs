@@ -3,7 +3,7 @@ use std::cell::RefMut;
 use crate::{
     global_vault_seeds_with_bump,
     logs::{emit_stack, GlobalCleanupLog},
-    program::{get_mut_dynamic_account, invoke},
+    program::{get_mut_dynamic_account, invoke, invoke_transfer_checked_with_hook},
     quantities::{GlobalAtoms, WrapperU64},
     require,
     validation::{loaders::GlobalTradeAccounts, MintAccountInfo, TokenAccountInfo, TokenProgram},
@@ -24,7 +24,7 @@ use spl_token_2022::{
 
 use super::{
     order_type_can_take, GlobalRefMut, OrderType, RestingOrder, GAS_DEPOSIT_LAMPORTS,
-    NO_EXPIRATION_LAST_VALID_SLOT,
+    NO_EXPIRATION_LAST_VALID_SLOT, NO_EXPIRATION_LAST_VALID_UNIX_TIMESTAMP,
 };
 
 pub fn get_now_slot() -> u32 {
@@ -47,6 +47,24 @@ pub fn get_now_slot() -> u32 {
     now_slot as u32
 }
 
+pub fn get_now_unix_timestamp() -> u32 {
+    // Same as slots, without a clock only orders without a timestamp
+    // expiration can match.
+    #[cfg(feature = "no-clock")]
+    let now_unix_timestamp: i64 = 0;
+    #[cfg(not(feature = "no-clock"))]
+    let now_unix_timestamp: i64 = solana_program::clock::Clock::get()
+        .unwrap_or(solana_program::clock::Clock {
+            slot: u64::MAX,
+            epoch_start_timestamp: i64::MAX,
+            epoch: u64::MAX,
+            leader_schedule_epoch: u64::MAX,
+            unix_timestamp: i64::MAX,
+        })
+        .unix_timestamp;
+    now_unix_timestamp.clamp(0, u32::MAX as i64) as u32
+}
+
 pub(crate) fn get_now_epoch() -> u64 {
     #[cfg(feature = "no-clock")]
     let now_epoch: u64 = 0;
@@ -63,9 +81,19 @@ pub(crate) fn get_now_epoch() -> u64 {
     now_epoch
 }
 
+/// Whether the given global is the one that the global order was placed
+/// with. The gas prepayment and the balance that backs the order are there.
+pub(crate) fn is_backing_global(
+    global_trade_accounts: &GlobalTradeAccounts,
+    resting_order: &RestingOrder,
+) -> bool {
+    global_trade_accounts.is_market_global == resting_order.is_market_global()
+}
+
 #[inline(always)]
 pub(crate) fn remove_from_global(
     global_trade_accounts_opt: &Option<GlobalTradeAccounts>,
+    resting_order: &RestingOrder,
 ) -> ProgramResult {
     if global_trade_accounts_opt.is_none() {
         // Payer is forfeiting the right to claim the gas prepayment. This
@@ -73,6 +101,14 @@ pub(crate) fn remove_from_global(
         return Ok(());
     }
     let global_trade_accounts: &GlobalTradeAccounts = &global_trade_accounts_opt.as_ref().unwrap();
+    // Otherwise the gas prepayment would come out of a global that never
+    // received it.
+    require!(
+        is_backing_global(global_trade_accounts, resting_order),
+        crate::program::ManifestError::MissingGlobal,
+        "Global does not back order {}",
+        resting_order.get_sequence_number(),
+    )?;
     let GlobalTradeAccounts {
         global,
         gas_receiver_opt,
@@ -119,15 +155,18 @@ pub(crate) fn remove_from_global(
     Ok(())
 }
 
+/// Records on the order which global backs it, then adds it there.
 pub(crate) fn try_to_add_to_global(
     global_trade_accounts: &GlobalTradeAccounts,
-    resting_order: &RestingOrder,
+    resting_order: &mut RestingOrder,
 ) -> ProgramResult {
     let GlobalTradeAccounts {
         global,
+        is_market_global,
         gas_payer_opt,
         ..
     } = global_trade_accounts;
+    resting_order.set_is_market_global(*is_market_global);
 
     {
         let global_data: &mut RefMut<&mut [u8]> = &mut global.try_borrow_mut_data()?;
@@ -177,6 +216,21 @@ pub(crate) fn assert_not_already_expired(last_valid_slot: u32, now_slot: u32) ->
     Ok(())
 }
 
+pub(crate) fn assert_not_already_expired_unix_timestamp(
+    last_valid_unix_timestamp: u32,
+    now_unix_timestamp: u32,
+) -> ProgramResult {
+    require!(
+        last_valid_unix_timestamp == NO_EXPIRATION_LAST_VALID_UNIX_TIMESTAMP
+            || last_valid_unix_timestamp > now_unix_timestamp,
+        crate::program::ManifestError::AlreadyExpired,
+        "Placing an already expired order. now: {} last_valid_unix_timestamp: {}",
+        now_unix_timestamp,
+        last_valid_unix_timestamp
+    )?;
+    Ok(())
+}
+
 pub(crate) fn assert_already_has_seat(trader_index: DataIndex) -> ProgramResult {
     require!(
         trader_index != NIL,
@@ -223,6 +277,7 @@ pub(crate) fn try_to_move_global_tokens<'a, 'info>(
         gas_receiver_opt,
         market_vault_opt,
         token_program_opt,
+        hook_accounts,
         ..
     } = global_trade_accounts;
 
@@ -257,6 +312,13 @@ pub(crate) fn try_to_move_global_tokens<'a, 'info>(
     let market_vault: &TokenAccountInfo<'a, 'info> = market_vault_opt.as_ref().unwrap();
     let token_program: &TokenProgram<'a, 'info> = token_program_opt.as_ref().unwrap();
 
+    // A market global holds its balances in the market vault already, so
+    // there is nothing to transfer. This is what lets global orders fill on
+    // an ephemeral rollup, where the global vault is not available.
+    if global_vault.key == market_vault.key {
+        return Ok(true);
+    }
+
     if *token_program.key == spl_token_2022::id() {
         require!(
             mint_opt.is_some(),
@@ -273,14 +335,31 @@ pub(crate) fn try_to_move_global_tokens<'a, 'info>(
             solana_program::msg!("Treating global order as unbacked because it has a transfer fee");
             return Ok(false);
         }
-        if StateWithExtensions::<Mint>::unpack(&mint_account_info.info.data.borrow())?
-            .get_extension::<TransferHook>()
-            .is_ok_and(|f| f.program_id.0 != Pubkey::default())
-        {
-            solana_program::msg!(
-                "Treating global order as unbacked because it has a transfer hook"
-            );
-            return Ok(false);
+        let has_transfer_hook: bool =
+            StateWithExtensions::<Mint>::unpack(&mint_account_info.info.data.borrow())?
+                .get_extension::<TransferHook>()
+                .is_ok_and(|f| f.program_id.0 != Pubkey::default());
+        if has_transfer_hook {
+            // The hook cannot run without its extra accounts, so if the taker
+            // did not include them, the order cannot be filled.
+            if hook_accounts.is_empty() {
+                solana_program::msg!(
+                    "Treating global order as unbacked because it has a transfer hook"
+                );
+                return Ok(false);
+            }
+            invoke_transfer_checked_with_hook(
+                token_program.as_ref(),
+                global_vault.as_ref(),
+                mint_account_info.as_ref(),
+                market_vault.as_ref(),
+                global_vault.as_ref(),
+                hook_accounts,
+                desired_global_atoms.as_u64(),
+                mint_account_info.mint.decimals,
+                global_vault_seeds_with_bump!(mint_key, global_vault_bump),
+            )?;
+            return Ok(true);
         }
 
         invoke_signed(
@@ -0,0 +1,29 @@
+use solana_program::pubkey::Pubkey;
+
+/// MagicBlock delegation program. It owns delegated accounts on the base layer
+/// until they are undelegated.
+pub const DELEGATION_PROGRAM_ID: Pubkey =
+    solana_program::pubkey!("DELeGGvXpWV2fqJUhqcF5ZSYMS4JTLjteaAMARRSaeSh");
+/// MagicBlock program on the ephemeral rollup that schedules commits and
+/// undelegations back to the base layer.
+pub const MAGIC_PROGRAM_ID: Pubkey =
+    solana_program::pubkey!("Magic11111111111111111111111111111111111111");
+/// Context account the magic program queues scheduled commits in.
+pub const MAGIC_CONTEXT_ID: Pubkey =
+    solana_program::pubkey!("MagicContext1111111111111111111111111111111");
+
+/// Buffer, delegation record and delegation metadata accounts that the
+/// delegation program uses while an account owned by manifest is delegated.
+pub fn get_delegation_addresses(delegated_account: &Pubkey) -> (Pubkey, Pubkey, Pubkey) {
+    let (buffer, _) =
+        Pubkey::find_program_address(&[b"buffer", delegated_account.as_ref()], &crate::ID);
+    let (delegation_record, _) = Pubkey::find_program_address(
+        &[b"delegation", delegated_account.as_ref()],
+        &DELEGATION_PROGRAM_ID,
+    );
+    let (delegation_metadata, _) = Pubkey::find_program_address(
+        &[b"delegation-metadata", delegated_account.as_ref()],
+        &DELEGATION_PROGRAM_ID,
+    );
+    (buffer, delegation_record, delegation_metadata)
+}
@@ -3,6 +3,7 @@ use std::{cell::Ref, slice::Iter};
 use hypertree::{get_helper, trace};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
     program_error::ProgramError,
     pubkey::Pubkey,
     system_program,
@@ -11,9 +12,11 @@ use solana_program::{
 use crate::{
     program::ManifestError,
     require,
-    state::{GlobalFixed, MarketFixed},
+    state::{GlobalFixed, MarketFixed, MarketMetadata},
     validation::{
-        get_global_address, EmptyAccount, MintAccountInfo, Program, Signer, TokenAccountInfo,
+        get_global_address, get_market_global_address, get_market_metadata_address, EmptyAccount,
+        MintAccountInfo, Program, Signer, TokenAccountInfo, DELEGATION_PROGRAM_ID,
+        MAGIC_PROGRAM_ID,
     },
 };
 
@@ -84,16 +87,195 @@ impl<'a, 'info> CreateMarketContext<'a, 'info> {
 /// ClaimSeat account infos
 pub(crate) struct ClaimSeatContext<'a, 'info> {
     pub payer: Signer<'a, 'info>,
+    pub owner: Signer<'a, 'info>,
     pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
     pub _system_program: Program<'a, 'info>,
 }
 
 impl<'a, 'info> ClaimSeatContext<'a, 'info> {
     #[cfg_attr(all(feature = "certora", not(feature = "certora-test")), early_panic)]
+    pub fn load(accounts: &'a [AccountInfo<'info>], is_v2: bool) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let payer: Signer = Signer::new(next_account_info(account_iter)?)?;
+        let (owner, market): (Signer, ManifestAccountInfo<MarketFixed>) =
+            load_owner_and_market(&payer, account_iter, is_v2)?;
+        let _system_program: Program =
+            Program::new(next_account_info(account_iter)?, &system_program::id())?;
+        Ok(Self {
+            payer,
+            owner,
+            market,
+            _system_program,
+        })
+    }
+}
+
+/// Trader and market that follow the payer. The trader is the payer, except
+/// for ClaimSeatV2 and BatchUpdateV2 where it comes before the market so that
+/// someone else can pay the rent, the same way SwapV2 separates the owner of
+/// the token accounts.
+fn load_owner_and_market<'a, 'info>(
+    payer: &Signer<'a, 'info>,
+    account_iter: &mut Iter<'a, AccountInfo<'info>>,
+    is_v2: bool,
+) -> Result<
+    (
+        Signer<'a, 'info>,
+        ManifestAccountInfo<'a, 'info, MarketFixed>,
+    ),
+    ProgramError,
+> {
+    let owner: Signer = if is_v2 {
+        Signer::new(next_account_info(account_iter)?)?
+    } else {
+        payer.clone()
+    };
+    let market: ManifestAccountInfo<MarketFixed> =
+        ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+    Ok((owner, market))
+}
+
+/// ReleaseSeat account infos
+pub(crate) struct ReleaseSeatContext<'a, 'info> {
+    pub payer: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+}
+
+impl<'a, 'info> ReleaseSeatContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let payer: Signer = Signer::new(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        Ok(Self { payer, market })
+    }
+}
+
+/// CleanExpired account infos
+pub(crate) struct CleanExpiredContext<'a, 'info> {
+    pub _payer: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+}
+
+impl<'a, 'info> CleanExpiredContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let _payer: Signer = Signer::new(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        Ok(Self { _payer, market })
+    }
+}
+
+/// CompactMarket account infos
+pub(crate) struct CompactMarketContext<'a, 'info> {
+    pub payer: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+    pub receiver: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> CompactMarketContext<'a, 'info> {
     pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
         let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
 
         let payer: Signer = Signer::new(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        let receiver: &AccountInfo = next_account_info(account_iter)?;
+        require!(
+            receiver.is_writable,
+            ProgramError::InvalidAccountData,
+            "Receiver must be writable",
+        )?;
+        Ok(Self {
+            payer,
+            market,
+            receiver,
+        })
+    }
+}
+
+/// PlaceTriggerOrder account infos
+pub(crate) struct PlaceTriggerOrderContext<'a, 'info> {
+    pub payer: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+    pub system_program: Program<'a, 'info>,
+}
+
+impl<'a, 'info> PlaceTriggerOrderContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let payer: Signer = Signer::new_payer(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        let system_program: Program =
+            Program::new(next_account_info(account_iter)?, &system_program::id())?;
+        Ok(Self {
+            payer,
+            market,
+            system_program,
+        })
+    }
+}
+
+/// CancelTriggerOrder account infos
+pub(crate) struct CancelTriggerOrderContext<'a, 'info> {
+    pub payer: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+}
+
+impl<'a, 'info> CancelTriggerOrderContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let payer: Signer = Signer::new_payer(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        Ok(Self { payer, market })
+    }
+}
+
+/// CrankTriggerOrders account infos. The peg oracle is optional and found by
+/// key in the remaining accounts.
+pub(crate) struct CrankTriggerOrdersContext<'a, 'info> {
+    pub payer: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+    pub _system_program: Program<'a, 'info>,
+}
+
+impl<'a, 'info> CrankTriggerOrdersContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let payer: Signer = Signer::new_payer(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        let _system_program: Program =
+            Program::new(next_account_info(account_iter)?, &system_program::id())?;
+        Ok(Self {
+            payer,
+            market,
+            _system_program,
+        })
+    }
+}
+
+/// InitMarketStats account infos
+pub(crate) struct InitMarketStatsContext<'a, 'info> {
+    pub payer: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+    pub _system_program: Program<'a, 'info>,
+}
+
+impl<'a, 'info> InitMarketStatsContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let payer: Signer = Signer::new_payer(next_account_info(account_iter)?)?;
         let market: ManifestAccountInfo<MarketFixed> =
             ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
         let _system_program: Program =
@@ -106,6 +288,110 @@ impl<'a, 'info> ClaimSeatContext<'a, 'info> {
     }
 }
 
+/// PokeTwap account infos
+pub(crate) struct PokeTwapContext<'a, 'info> {
+    pub _payer: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+}
+
+impl<'a, 'info> PokeTwapContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let _payer: Signer = Signer::new(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        Ok(Self { _payer, market })
+    }
+}
+
+/// InitMarketMetadata account infos
+pub(crate) struct InitMarketMetadataContext<'a, 'info> {
+    pub payer: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+    pub market_metadata: EmptyAccount<'a, 'info>,
+    pub system_program: Program<'a, 'info>,
+}
+
+impl<'a, 'info> InitMarketMetadataContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let payer: Signer = Signer::new_payer(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        let market_metadata: EmptyAccount = EmptyAccount::new(next_account_info(account_iter)?)?;
+        let (expected_market_metadata_key, _market_metadata_bump) =
+            get_market_metadata_address(market.key);
+        require!(
+            expected_market_metadata_key == *market_metadata.info.key,
+            ManifestError::IncorrectAccount,
+            "Incorrect market metadata account",
+        )?;
+        let system_program: Program =
+            Program::new(next_account_info(account_iter)?, &system_program::id())?;
+        Ok(Self {
+            payer,
+            market,
+            market_metadata,
+            system_program,
+        })
+    }
+}
+
+/// SetMarketAuthority account infos
+pub(crate) struct SetMarketAuthorityContext<'a, 'info> {
+    pub payer: Signer<'a, 'info>,
+    pub authority: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+    pub _system_program: Program<'a, 'info>,
+}
+
+impl<'a, 'info> SetMarketAuthorityContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let payer: Signer = Signer::new_payer(next_account_info(account_iter)?)?;
+        let authority: Signer = Signer::new(next_account_info(account_iter)?)?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        let _system_program: Program =
+            Program::new(next_account_info(account_iter)?, &system_program::id())?;
+        Ok(Self {
+            payer,
+            authority,
+            market,
+            _system_program,
+        })
+    }
+}
+
+/// UpdateMarketMetadata and TransferMarketMetadataAuthority account infos
+pub(crate) struct MarketMetadataAuthorityContext<'a, 'info> {
+    pub authority: Signer<'a, 'info>,
+    pub market_metadata: ManifestAccountInfo<'a, 'info, MarketMetadata>,
+}
+
+impl<'a, 'info> MarketMetadataAuthorityContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let authority: Signer = Signer::new(next_account_info(account_iter)?)?;
+        let market_metadata: ManifestAccountInfo<MarketMetadata> =
+            ManifestAccountInfo::<MarketMetadata>::new(next_account_info(account_iter)?)?;
+        require!(
+            market_metadata.get_fixed()?.get_authority() == authority.key,
+            ManifestError::InvalidMarketMetadataAuthority,
+            "Expected market metadata authority {}",
+            market_metadata.get_fixed()?.get_authority(),
+        )?;
+        Ok(Self {
+            authority,
+            market_metadata,
+        })
+    }
+}
+
 /// ExpandMarketContext account infos
 pub(crate) struct ExpandMarketContext<'a, 'info> {
     pub payer: Signer<'a, 'info>,
@@ -138,6 +424,9 @@ pub(crate) struct DepositContext<'a, 'info> {
     pub vault: TokenAccountInfo<'a, 'info>,
     pub token_program: TokenProgram<'a, 'info>,
     pub mint: MintAccountInfo<'a, 'info>,
+    // Remaining accounts, forwarded to token22 transfers so that mints with a
+    // transfer hook can resolve the hook program and its extra accounts.
+    pub hook_accounts: &'a [AccountInfo<'info>],
 }
 
 impl<'a, 'info> DepositContext<'a, 'info> {
@@ -179,6 +468,7 @@ impl<'a, 'info> DepositContext<'a, 'info> {
 
         let token_program: TokenProgram = TokenProgram::new(next_account_info(account_iter)?)?;
         let mint: MintAccountInfo = MintAccountInfo::new(next_account_info(account_iter)?)?;
+        let hook_accounts: &'a [AccountInfo<'info>] = account_iter.as_slice();
 
         // Drop the market ref so it can be passed through the return.
         drop(market_fixed);
@@ -189,6 +479,7 @@ impl<'a, 'info> DepositContext<'a, 'info> {
             vault,
             token_program,
             mint,
+            hook_accounts,
         })
     }
 }
@@ -203,6 +494,9 @@ pub(crate) struct WithdrawContext<'a, 'info> {
     pub vault: TokenAccountInfo<'a, 'info>,
     pub token_program: TokenProgram<'a, 'info>,
     pub mint: MintAccountInfo<'a, 'info>,
+    // Remaining accounts, forwarded to token22 transfers so that mints with a
+    // transfer hook can resolve the hook program and its extra accounts.
+    pub hook_accounts: &'a [AccountInfo<'info>],
 }
 
 impl<'a, 'info> WithdrawContext<'a, 'info> {
@@ -239,6 +533,7 @@ impl<'a, 'info> WithdrawContext<'a, 'info> {
 
         let token_program: TokenProgram = TokenProgram::new(next_account_info(account_iter)?)?;
         let mint: MintAccountInfo = MintAccountInfo::new(next_account_info(account_iter)?)?;
+        let hook_accounts: &'a [AccountInfo<'info>] = account_iter.as_slice();
 
         // Drop the market ref so it can be passed through the return.
         drop(market_fixed);
@@ -249,6 +544,7 @@ impl<'a, 'info> WithdrawContext<'a, 'info> {
             vault,
             token_program,
             mint,
+            hook_accounts,
         })
     }
 }
@@ -266,6 +562,7 @@ pub(crate) struct SwapContext<'a, 'info> {
     pub token_program_quote: TokenProgram<'a, 'info>,
     pub base_mint: Option<MintAccountInfo<'a, 'info>>,
     pub quote_mint: Option<MintAccountInfo<'a, 'info>>,
+    pub hook_accounts: &'a [AccountInfo<'info>],
 
     // One for each side. First is base, then is quote.
     pub global_trade_accounts_opts: [Option<GlobalTradeAccounts<'a, 'info>>; 2],
@@ -367,13 +664,24 @@ impl<'a, 'info> SwapContext<'a, 'info> {
             current_account_info_or = next_account_info(account_iter);
         }
 
+        // Everything from here on is forwarded to token22 transfers so that
+        // mints with a transfer hook can resolve their extra accounts. The
+        // token program looks those up by key, so it is fine that the global
+        // accounts are included too.
+        let hook_accounts: &'a [AccountInfo<'info>] = if current_account_info_or.is_ok() {
+            &accounts[accounts.len() - account_iter.as_slice().len() - 1..]
+        } else {
+            &[]
+        };
+
         if current_account_info_or.is_ok() {
             let current_account_info: &AccountInfo<'info> = current_account_info_or?;
 
             // It is possible that the global account does not exist. Do not
             // throw an error. This will happen when users just blindly include
-            // global accounts that have not been initialized.
-            if !current_account_info.data_is_empty() {
+            // global accounts that have not been initialized. Anything not
+            // owned by this program is the start of the hook accounts.
+            if *current_account_info.owner == crate::ID {
                 let global: ManifestAccountInfo<'a, 'info, GlobalFixed> =
                     ManifestAccountInfo::<GlobalFixed>::new(current_account_info)?;
                 let global_data: Ref<&mut [u8]> = global.data.borrow();
@@ -395,12 +703,8 @@ impl<'a, 'info> SwapContext<'a, 'info> {
                 // the discriminator and owner match, however they cannot put it
                 // at the correct address because that requires signing the PDA
                 // which only happens through our init.
-                let (expected_global_key, _global_bump) = get_global_address(global_mint_key);
-                require!(
-                    expected_global_key == *global.info.key,
-                    ManifestError::MissingGlobal,
-                    "Unexpected global accounts",
-                )?;
+                let is_market_global: bool =
+                    verify_global_address(global.info.key, global_mint_key, market.info.key)?;
 
                 let index: usize = if *global_mint_key == base_mint_key {
                     0
@@ -421,6 +725,7 @@ impl<'a, 'info> SwapContext<'a, 'info> {
                         quote_mint.clone()
                     },
                     global,
+                    is_market_global,
                     global_vault_opt: Some(global_vault),
                     market_vault_opt: if index == 0 {
                         Some(base_vault.clone())
@@ -436,6 +741,7 @@ impl<'a, 'info> SwapContext<'a, 'info> {
                     gas_receiver_opt: Some(payer.clone()),
                     market: *market.info.key,
                     system_program: None,
+                    hook_accounts,
                 });
             }
         }
@@ -452,6 +758,7 @@ impl<'a, 'info> SwapContext<'a, 'info> {
             token_program_quote,
             base_mint,
             quote_mint,
+            hook_accounts,
             global_trade_accounts_opts,
         })
     }
@@ -463,6 +770,10 @@ pub struct GlobalTradeAccounts<'a, 'info> {
     /// Required if this is a token22 token.
     pub mint_opt: Option<MintAccountInfo<'a, 'info>>,
     pub global: ManifestAccountInfo<'a, 'info, GlobalFixed>,
+    /// Whether global is the market global for the mint rather than the
+    /// global for the mint. Global orders only fill or leave through the
+    /// kind that backs them.
+    pub is_market_global: bool,
 
     // These are required when matching a global order, not necessarily when
     // cancelling since tokens dont move in that case.
@@ -477,11 +788,16 @@ pub struct GlobalTradeAccounts<'a, 'info> {
     pub gas_payer_opt: Option<Signer<'a, 'info>>,
     pub gas_receiver_opt: Option<Signer<'a, 'info>>,
     pub market: Pubkey,
+
+    // Needed to move tokens out of the global vault when the mint has a
+    // transfer hook. Without them, hooked global orders are unbacked.
+    pub hook_accounts: &'a [AccountInfo<'info>],
 }
 
 /// BatchUpdate account infos
 pub(crate) struct BatchUpdateContext<'a, 'info> {
     pub payer: Signer<'a, 'info>,
+    pub owner: Signer<'a, 'info>,
     pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
     pub _system_program: Program<'a, 'info>,
 
@@ -490,16 +806,20 @@ pub(crate) struct BatchUpdateContext<'a, 'info> {
 }
 
 impl<'a, 'info> BatchUpdateContext<'a, 'info> {
-    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+    pub fn load(accounts: &'a [AccountInfo<'info>], is_v2: bool) -> Result<Self, ProgramError> {
         let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
 
         // Does not have to be writable, but this ix will fail if removing a
         // global or requiring expanding.
         let payer: Signer = Signer::new(next_account_info(account_iter)?)?;
-        let market: ManifestAccountInfo<MarketFixed> =
-            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        let (owner, market): (Signer, ManifestAccountInfo<MarketFixed>) =
+            load_owner_and_market(&payer, account_iter, is_v2)?;
         let system_program: Program =
             Program::new(next_account_info(account_iter)?, &system_program::id())?;
+        // Globals and transfer hook accounts. Hook accounts are resolved by
+        // key, so it is fine for this to also contain the global accounts.
+        #[cfg(not(feature = "certora"))]
+        let hook_accounts: &'a [AccountInfo<'info>] = account_iter.as_slice();
         // Certora version is not mutable.
         #[cfg(feature = "certora")]
         let global_trade_accounts_opts: [Option<GlobalTradeAccounts<'a, 'info>>; 2] = [None, None];
@@ -517,6 +837,16 @@ impl<'a, 'info> BatchUpdateContext<'a, 'info> {
             drop(market_fixed);
 
             for _ in 0..2 {
+                // Stop at the first account that is not a mint. The rest are
+                // transfer hook accounts. A mint that is not one of the market
+                // mints is still rejected below.
+                if !account_iter
+                    .as_slice()
+                    .first()
+                    .is_some_and(|f| MintAccountInfo::new(f).is_ok())
+                {
+                    break;
+                }
                 let next_account_info_or: Result<&AccountInfo<'info>, ProgramError> =
                     next_account_info(account_iter);
                 if next_account_info_or.is_ok() {
@@ -556,12 +886,8 @@ impl<'a, 'info> BatchUpdateContext<'a, 'info> {
                     let expected_global_vault_address: &Pubkey = global_fixed.get_vault();
 
                     let global_mint_key: &Pubkey = global_fixed.get_mint();
-                    let (expected_global_key, _global_bump) = get_global_address(global_mint_key);
-                    require!(
-                        expected_global_key == *global.info.key,
-                        ManifestError::MissingGlobal,
-                        "Unexpected global accounts",
-                    )?;
+                    let is_market_global: bool =
+                        verify_global_address(global.info.key, global_mint_key, market.info.key)?;
 
                     let global_vault: TokenAccountInfo<'a, 'info> =
                         TokenAccountInfo::new_with_owner_and_key(
@@ -585,6 +911,7 @@ impl<'a, 'info> BatchUpdateContext<'a, 'info> {
                     global_trade_accounts_opts[index] = Some(GlobalTradeAccounts {
                         mint_opt: Some(mint),
                         global,
+                        is_market_global,
                         global_vault_opt: Some(global_vault),
                         market_vault_opt: Some(market_vault),
                         token_program_opt: Some(token_program),
@@ -592,6 +919,7 @@ impl<'a, 'info> BatchUpdateContext<'a, 'info> {
                         gas_payer_opt: Some(payer.clone()),
                         gas_receiver_opt: Some(payer.clone()),
                         market: *market.info.key,
+                        hook_accounts,
                     })
                 };
             }
@@ -599,6 +927,7 @@ impl<'a, 'info> BatchUpdateContext<'a, 'info> {
 
         Ok(Self {
             payer,
+            owner,
             market,
             _system_program: system_program,
             global_trade_accounts_opts,
@@ -686,6 +1015,7 @@ pub(crate) struct GlobalDepositContext<'a, 'info> {
     pub global_vault: TokenAccountInfo<'a, 'info>,
     pub trader_token: TokenAccountInfo<'a, 'info>,
     pub token_program: TokenProgram<'a, 'info>,
+    pub hook_accounts: &'a [AccountInfo<'info>],
 }
 
 impl<'a, 'info> GlobalDepositContext<'a, 'info> {
@@ -723,6 +1053,7 @@ impl<'a, 'info> GlobalDepositContext<'a, 'info> {
         let trader_token: TokenAccountInfo =
             TokenAccountInfo::new_with_owner(token_account_info, mint.info.key, payer.key)?;
         let token_program: TokenProgram = TokenProgram::new(next_account_info(account_iter)?)?;
+        let hook_accounts: &'a [AccountInfo<'info>] = account_iter.as_slice();
         Ok(Self {
             payer,
             global,
@@ -730,6 +1061,7 @@ impl<'a, 'info> GlobalDepositContext<'a, 'info> {
             global_vault,
             trader_token,
             token_program,
+            hook_accounts,
         })
     }
 }
@@ -742,6 +1074,7 @@ pub(crate) struct GlobalWithdrawContext<'a, 'info> {
     pub global_vault: TokenAccountInfo<'a, 'info>,
     pub trader_token: TokenAccountInfo<'a, 'info>,
     pub token_program: TokenProgram<'a, 'info>,
+    pub hook_accounts: &'a [AccountInfo<'info>],
 }
 
 impl<'a, 'info> GlobalWithdrawContext<'a, 'info> {
@@ -779,6 +1112,7 @@ impl<'a, 'info> GlobalWithdrawContext<'a, 'info> {
         let trader_token: TokenAccountInfo =
             TokenAccountInfo::new_with_owner(token_account_info, mint.info.key, payer.key)?;
         let token_program: TokenProgram = TokenProgram::new(next_account_info(account_iter)?)?;
+        let hook_accounts: &'a [AccountInfo<'info>] = account_iter.as_slice();
         Ok(Self {
             payer,
             global,
@@ -786,6 +1120,7 @@ impl<'a, 'info> GlobalWithdrawContext<'a, 'info> {
             global_vault,
             trader_token,
             token_program,
+            hook_accounts,
         })
     }
 }
@@ -799,6 +1134,7 @@ pub(crate) struct GlobalEvictContext<'a, 'info> {
     pub trader_token: TokenAccountInfo<'a, 'info>,
     pub evictee_token: TokenAccountInfo<'a, 'info>,
     pub token_program: TokenProgram<'a, 'info>,
+    pub hook_accounts: &'a [AccountInfo<'info>],
 }
 
 impl<'a, 'info> GlobalEvictContext<'a, 'info> {
@@ -839,6 +1175,7 @@ impl<'a, 'info> GlobalEvictContext<'a, 'info> {
         let evictee_token: TokenAccountInfo =
             TokenAccountInfo::new(token_account_info, mint.info.key)?;
         let token_program: TokenProgram = TokenProgram::new(next_account_info(account_iter)?)?;
+        let hook_accounts: &'a [AccountInfo<'info>] = account_iter.as_slice();
         Ok(Self {
             payer,
             global,
@@ -847,6 +1184,7 @@ impl<'a, 'info> GlobalEvictContext<'a, 'info> {
             trader_token,
             evictee_token,
             token_program,
+            hook_accounts,
         })
     }
 }
@@ -857,6 +1195,7 @@ pub(crate) struct GlobalCleanContext<'a, 'info> {
     pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
     pub system_program: Program<'a, 'info>,
     pub global: ManifestAccountInfo<'a, 'info, GlobalFixed>,
+    pub is_market_global: bool,
 }
 
 impl<'a, 'info> GlobalCleanContext<'a, 'info> {
@@ -874,19 +1213,168 @@ impl<'a, 'info> GlobalCleanContext<'a, 'info> {
         let global_data: Ref<&mut [u8]> = global.data.borrow();
         let global_fixed: &GlobalFixed = get_helper::<GlobalFixed>(&global_data, 0_u32);
         let global_mint_key: &Pubkey = global_fixed.get_mint();
-        let (expected_global_key, _global_bump) = get_global_address(global_mint_key);
+        let is_market_global: bool =
+            verify_global_address(global.info.key, global_mint_key, market.info.key)?;
+        drop(global_data);
+
+        Ok(Self {
+            payer,
+            market,
+            system_program,
+            global,
+            is_market_global,
+        })
+    }
+}
+
+/// Orders on a market can be backed by the global for the mint or by the
+/// market global that this market keeps for the mint. Market globals of other
+/// markets are rejected, since their balances are in another market vault.
+/// Returns whether it is the market global, which resting global orders
+/// record so that the other one cannot stand in for it.
+fn verify_global_address(
+    global_key: &Pubkey,
+    mint: &Pubkey,
+    market_key: &Pubkey,
+) -> Result<bool, ProgramError> {
+    let (expected_global_key, _global_bump) = get_global_address(mint);
+    if expected_global_key == *global_key {
+        return Ok(false);
+    }
+    let (expected_market_global_key, _market_global_bump) =
+        get_market_global_address(market_key, mint);
+    require!(
+        expected_market_global_key == *global_key,
+        ManifestError::MissingGlobal,
+        "Unexpected global accounts",
+    )?;
+    Ok(true)
+}
+
+/// MarketGlobalCreate account infos
+pub(crate) struct MarketGlobalCreateContext<'a, 'info> {
+    pub payer: Signer<'a, 'info>,
+    pub market_global: EmptyAccount<'a, 'info>,
+    pub system_program: Program<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+    pub mint: MintAccountInfo<'a, 'info>,
+}
+
+impl<'a, 'info> MarketGlobalCreateContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        let payer: Signer = Signer::new_payer(next_account_info(account_iter)?)?;
+        let market_global: EmptyAccount = EmptyAccount::new(next_account_info(account_iter)?)?;
+        let system_program: Program =
+            Program::new(next_account_info(account_iter)?, &system_program::id())?;
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        let mint: MintAccountInfo = MintAccountInfo::new(next_account_info(account_iter)?)?;
+
+        {
+            let market_fixed: Ref<MarketFixed> = market.get_fixed()?;
+            require!(
+                market_fixed.get_base_mint() == mint.info.key
+                    || market_fixed.get_quote_mint() == mint.info.key,
+                ManifestError::InvalidMint,
+                "Mint {} is not traded on the market",
+                mint.info.key,
+            )?;
+        }
+        let (expected_market_global_key, _market_global_bump) =
+            get_market_global_address(market.key, mint.info.key);
+        require!(
+            expected_market_global_key == *market_global.info.key,
+            ManifestError::IncorrectAccount,
+            "Incorrect market global account",
+        )?;
+
+        Ok(Self {
+            payer,
+            market_global,
+            system_program,
+            market,
+            mint,
+        })
+    }
+}
+
+/// MarketGlobalDeposit and MarketGlobalWithdraw account infos
+pub(crate) struct MarketGlobalContext<'a, 'info> {
+    pub payer: Signer<'a, 'info>,
+    pub market: ManifestAccountInfo<'a, 'info, MarketFixed>,
+    pub _system_program: Program<'a, 'info>,
+    pub global: ManifestAccountInfo<'a, 'info, GlobalFixed>,
+    pub market_global: ManifestAccountInfo<'a, 'info, GlobalFixed>,
+    pub mint: MintAccountInfo<'a, 'info>,
+    pub global_vault: TokenAccountInfo<'a, 'info>,
+    pub market_vault: TokenAccountInfo<'a, 'info>,
+    pub token_program: TokenProgram<'a, 'info>,
+    pub hook_accounts: &'a [AccountInfo<'info>],
+}
+
+impl<'a, 'info> MarketGlobalContext<'a, 'info> {
+    pub fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+        let account_iter: &mut Iter<AccountInfo<'info>> = &mut accounts.iter();
+
+        // Writable because it pays for the seat on the market global.
+        let payer: Signer = Signer::new_payer(next_account_info(account_iter)?)?;
+        // Not written, but loading it makes sure that the market is not
+        // delegated, since the market vault moves with it.
+        let market: ManifestAccountInfo<MarketFixed> =
+            ManifestAccountInfo::<MarketFixed>::new(next_account_info(account_iter)?)?;
+        let _system_program: Program =
+            Program::new(next_account_info(account_iter)?, &system_program::id())?;
+        let global: ManifestAccountInfo<GlobalFixed> =
+            ManifestAccountInfo::<GlobalFixed>::new(next_account_info(account_iter)?)?;
+        let market_global: ManifestAccountInfo<GlobalFixed> =
+            ManifestAccountInfo::<GlobalFixed>::new(next_account_info(account_iter)?)?;
+        let mint: MintAccountInfo = MintAccountInfo::new(next_account_info(account_iter)?)?;
+
+        let (expected_global_key, _global_bump) = get_global_address(mint.info.key);
         require!(
             expected_global_key == *global.info.key,
             ManifestError::MissingGlobal,
             "Unexpected global accounts",
         )?;
-        drop(global_data);
+        let (expected_market_global_key, _market_global_bump) =
+            get_market_global_address(market.key, mint.info.key);
+        require!(
+            expected_market_global_key == *market_global.info.key,
+            ManifestError::IncorrectAccount,
+            "Incorrect market global account",
+        )?;
+
+        let expected_global_vault_address: Pubkey = *global.get_fixed()?.get_vault();
+        let global_vault: TokenAccountInfo = TokenAccountInfo::new_with_owner_and_key(
+            next_account_info(account_iter)?,
+            mint.info.key,
+            &expected_global_vault_address,
+            &expected_global_vault_address,
+        )?;
+        // Same as the market vault for the mint.
+        let expected_market_vault_address: Pubkey = *market_global.get_fixed()?.get_vault();
+        let market_vault: TokenAccountInfo = TokenAccountInfo::new_with_owner_and_key(
+            next_account_info(account_iter)?,
+            mint.info.key,
+            &expected_market_vault_address,
+            &expected_market_vault_address,
+        )?;
+        let token_program: TokenProgram = TokenProgram::new(next_account_info(account_iter)?)?;
+        let hook_accounts: &'a [AccountInfo<'info>] = account_iter.as_slice();
 
         Ok(Self {
             payer,
             market,
-            system_program,
+            _system_program,
             global,
+            market_global,
+            mint,
+            global_vault,
+            market_vault,
+            token_program,
+            hook_accounts,
         })
     }
 }
@@ -911,6 +1399,16 @@ pub(crate) struct DelegateMarketContext<'a, 'info> {
     pub quote_vault_delegation_record: &'a AccountInfo<'info>,
     pub quote_vault_delegation_metadata: &'a AccountInfo<'info>,
     pub delegation_program: Program<'a, 'info>,
+    // Market globals that go to the rollup with the market. Up to one per mint.
+    pub market_globals: Vec<MarketGlobalDelegationAccounts<'a, 'info>>,
+}
+
+/// Market global to delegate along with its delegation program accounts.
+pub(crate) struct MarketGlobalDelegationAccounts<'a, 'info> {
+    pub market_global: ManifestAccountInfo<'a, 'info, GlobalFixed>,
+    pub delegation_buffer: &'a AccountInfo<'info>,
+    pub delegation_record: &'a AccountInfo<'info>,
+    pub delegation_metadata: &'a AccountInfo<'info>,
 }
 
 impl<'a, 'info> DelegateMarketContext<'a, 'info> {
@@ -936,7 +1434,21 @@ impl<'a, 'info> DelegateMarketContext<'a, 'info> {
         let quote_vault_delegation_buffer: &AccountInfo<'info> = next_account_info(account_iter)?;
         let quote_vault_delegation_record: &AccountInfo<'info> = next_account_info(account_iter)?;
         let quote_vault_delegation_metadata: &AccountInfo<'info> = next_account_info(account_iter)?;
-        let delegation_program: Program = Program::new_any(next_account_info(account_iter)?)?;
+        let delegation_program: Program =
+            Program::new(next_account_info(account_iter)?, &DELEGATION_PROGRAM_ID)?;
+
+        let mut market_globals: Vec<MarketGlobalDelegationAccounts<'a, 'info>> = Vec::new();
+        for _ in 0..2 {
+            if account_iter.as_slice().is_empty() {
+                break;
+            }
+            market_globals.push(MarketGlobalDelegationAccounts {
+                market_global: load_market_global(next_account_info(account_iter)?, market.key)?,
+                delegation_buffer: next_account_info(account_iter)?,
+                delegation_record: next_account_info(account_iter)?,
+                delegation_metadata: next_account_info(account_iter)?,
+            });
+        }
 
         Ok(Self {
             payer,
@@ -957,10 +1469,48 @@ impl<'a, 'info> DelegateMarketContext<'a, 'info> {
             quote_vault_delegation_record,
             quote_vault_delegation_metadata,
             delegation_program,
+            market_globals,
         })
     }
 }
 
+/// Market global of the given market, which moves between the base layer and
+/// the rollup together with the market.
+fn load_market_global<'a, 'info>(
+    info: &'a AccountInfo<'info>,
+    market_key: &Pubkey,
+) -> Result<ManifestAccountInfo<'a, 'info, GlobalFixed>, ProgramError> {
+    let market_global: ManifestAccountInfo<GlobalFixed> =
+        ManifestAccountInfo::<GlobalFixed>::new(info)?;
+    let mint: Pubkey = *market_global.get_fixed()?.get_mint();
+    let (expected_market_global_key, _market_global_bump) =
+        get_market_global_address(market_key, &mint);
+    require!(
+        expected_market_global_key == *market_global.info.key,
+        ManifestError::IncorrectAccount,
+        "Incorrect market global account",
+    )?;
+    Ok(market_global)
+}
+
+/// Market globals at the end of the accounts, up to one per mint.
+fn load_market_globals<'a, 'info>(
+    account_iter: &mut Iter<'a, AccountInfo<'info>>,
+    market_key: &Pubkey,
+) -> Result<Vec<ManifestAccountInfo<'a, 'info, GlobalFixed>>, ProgramError> {
+    let mut market_globals: Vec<ManifestAccountInfo<'a, 'info, GlobalFixed>> = Vec::new();
+    for _ in 0..2 {
+        if account_iter.as_slice().is_empty() {
+            break;
+        }
+        market_globals.push(load_market_global(
+            next_account_info(account_iter)?,
+            market_key,
+        )?);
+    }
+    Ok(market_globals)
+}
+
 /// UndelegateMarket account infos
 pub(crate) struct UndelegateMarketContext<'a, 'info> {
     pub payer: Signer<'a, 'info>,
@@ -969,6 +1519,7 @@ pub(crate) struct UndelegateMarketContext<'a, 'info> {
     pub quote_vault: &'a AccountInfo<'info>,
     pub magic_context: &'a AccountInfo<'info>,
     pub magic_program: Program<'a, 'info>,
+    pub market_globals: Vec<ManifestAccountInfo<'a, 'info, GlobalFixed>>,
 }
 
 impl<'a, 'info> UndelegateMarketContext<'a, 'info> {
@@ -981,7 +1532,10 @@ impl<'a, 'info> UndelegateMarketContext<'a, 'info> {
         let base_vault: &AccountInfo<'info> = next_account_info(account_iter)?;
         let quote_vault: &AccountInfo<'info> = next_account_info(account_iter)?;
         let magic_context: &AccountInfo<'info> = next_account_info(account_iter)?;
-        let magic_program: Program = Program::new_any(next_account_info(account_iter)?)?;
+        let magic_program: Program =
+            Program::new(next_account_info(account_iter)?, &MAGIC_PROGRAM_ID)?;
+        let market_globals: Vec<ManifestAccountInfo<GlobalFixed>> =
+            load_market_globals(account_iter, market.key)?;
 
         Ok(Self {
             payer,
@@ -990,6 +1544,7 @@ impl<'a, 'info> UndelegateMarketContext<'a, 'info> {
             quote_vault,
             magic_context,
             magic_program,
+            market_globals,
         })
     }
 }
@@ -1002,6 +1557,7 @@ pub(crate) struct CommitMarketContext<'a, 'info> {
     pub quote_vault: &'a AccountInfo<'info>,
     pub magic_context: &'a AccountInfo<'info>,
     pub magic_program: Program<'a, 'info>,
+    pub market_globals: Vec<ManifestAccountInfo<'a, 'info, GlobalFixed>>,
 }
 
 impl<'a, 'info> CommitMarketContext<'a, 'info> {
@@ -1014,7 +1570,10 @@ impl<'a, 'info> CommitMarketContext<'a, 'info> {
         let base_vault: &AccountInfo<'info> = next_account_info(account_iter)?;
         let quote_vault: &AccountInfo<'info> = next_account_info(account_iter)?;
         let magic_context: &AccountInfo<'info> = next_account_info(account_iter)?;
-        let magic_program: Program = Program::new_any(next_account_info(account_iter)?)?;
+        let magic_program: Program =
+            Program::new(next_account_info(account_iter)?, &MAGIC_PROGRAM_ID)?;
+        let market_globals: Vec<ManifestAccountInfo<GlobalFixed>> =
+            load_market_globals(account_iter, market.key)?;
 
         Ok(Self {
             payer,
@@ -1023,6 +1582,7 @@ impl<'a, 'info> CommitMarketContext<'a, 'info> {
             quote_vault,
             magic_context,
             magic_program,
+            market_globals,
         })
     }
 }
@@ -6,7 +6,9 @@ use solana_program::{
 };
 use std::{cell::Ref, mem::size_of, ops::Deref};
 
-use crate::require;
+use crate::{program::ManifestError, require};
+
+use super::DELEGATION_PROGRAM_ID;
 
 /// Validation for manifest accounts.
 #[derive(Clone)]
@@ -65,6 +67,13 @@ pub trait ManifestAccount {
 }
 
 fn verify_owned_by_manifest(owner: &Pubkey) -> ProgramResult {
+    // A delegated market stays owned by the delegation program on the base
+    // layer until it is undelegated. Say so instead of failing on the owner.
+    require!(
+        owner != &DELEGATION_PROGRAM_ID,
+        ManifestError::MarketDelegated,
+        "Market is delegated to an ephemeral rollup",
+    )?;
     require!(
         owner == &crate::ID,
         ProgramError::IllegalOwner,
@@ -101,7 +110,8 @@ fn verify_uninitialized<T: Pod + ManifestAccount>(info: &AccountInfo) -> Program
 #[cfg(test)]
 mod test {
     use crate::state::{
-        GlobalFixed, MarketFixed, GLOBAL_FIXED_DISCRIMINANT, MARKET_FIXED_DISCRIMINANT,
+        GlobalFixed, MarketFixed, MarketMetadata, GLOBAL_FIXED_DISCRIMINANT,
+        MARKET_FIXED_DISCRIMINANT, MARKET_METADATA_DISCRIMINANT,
     };
 
     #[test]
@@ -115,6 +125,12 @@ mod test {
         let discriminant: u64 = crate::utils::get_discriminant::<GlobalFixed>().unwrap();
         assert_eq!(discriminant, GLOBAL_FIXED_DISCRIMINANT);
     }
+
+    #[test]
+    fn test_market_metadata_discriminant() {
+        let discriminant: u64 = crate::utils::get_discriminant::<MarketMetadata>().unwrap();
+        assert_eq!(discriminant, MARKET_METADATA_DISCRIMINANT);
+    }
 }
 
 macro_rules! global_seeds {
@@ -133,3 +149,32 @@ macro_rules! global_seeds_with_bump {
 pub fn get_global_address(mint: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(global_seeds!(mint), &crate::ID)
 }
+
+macro_rules! market_global_seeds {
+    ( $market:expr, $mint:expr ) => {
+        &[b"market-global", $market.as_ref(), $mint.as_ref()]
+    };
+}
+
+#[macro_export]
+macro_rules! market_global_seeds_with_bump {
+    ( $market:expr, $mint:expr, $bump:expr ) => {
+        &[&[b"market-global", $market.as_ref(), $mint.as_ref(), &[$bump]]]
+    };
+}
+
+/// Global a market keeps for one of its mints, so that global orders can
+/// trade while the market is delegated to an ephemeral rollup.
+pub fn get_market_global_address(market: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(market_global_seeds!(market, mint), &crate::ID)
+}
+
+macro_rules! market_metadata_seeds {
+    ( $market:expr ) => {
+        &[b"market-metadata", $market.as_ref()]
+    };
+}
+
+pub fn get_market_metadata_address(market: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(market_metadata_seeds!(market), &crate::ID)
+}
@@ -1,8 +1,12 @@
+pub mod delegation;
 pub mod loaders;
 pub mod manifest_checker;
+pub mod oracle;
 pub mod solana_checkers;
 pub mod token_checkers;
 
+pub use delegation::*;
 pub use manifest_checker::*;
+pub use oracle::*;
 pub use solana_checkers::*;
 pub use token_checkers::*;
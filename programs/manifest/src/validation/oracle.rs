@@ -0,0 +1,315 @@
+use crate::{program::ManifestError, quantities::QuoteAtomsPerBaseAtom, require, state::PriceBand};
+use hypertree::trace;
+use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+
+/// Pyth push oracle program that owns legacy v2 price accounts.
+pub const PYTH_PROGRAM_ID: Pubkey =
+    solana_program::pubkey!("FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH");
+/// Switchboard v2 program that owns aggregator accounts.
+pub const SWITCHBOARD_PROGRAM_ID: Pubkey =
+    solana_program::pubkey!("SW1TCH7qEPTdLsDHRgPuMQjbQxKdH2aBStViMFnt64f");
+
+// Pyth v2 price account. Only the fields that are read are listed.
+const PYTH_MAGIC: u32 = 0xa1b2c3d4;
+const PYTH_VERSION: u32 = 2;
+const PYTH_ACCOUNT_TYPE_PRICE: u32 = 3;
+const PYTH_STATUS_TRADING: u32 = 1;
+const PYTH_EXPO_OFFSET: usize = 20;
+const PYTH_AGG_PRICE_OFFSET: usize = 208;
+const PYTH_AGG_STATUS_OFFSET: usize = 224;
+const PYTH_AGG_PUB_SLOT_OFFSET: usize = 232;
+const PYTH_MIN_SIZE: usize = 240;
+
+// Switchboard v2 AggregatorAccountData. The latest confirmed round starts at
+// 341 after the 8 byte anchor discriminant.
+const SWITCHBOARD_AGGREGATOR_DISCRIMINANT: [u8; 8] = [217, 230, 65, 101, 201, 162, 27, 125];
+const SWITCHBOARD_ROUND_OPEN_SLOT_OFFSET: usize = 350;
+const SWITCHBOARD_RESULT_MANTISSA_OFFSET: usize = 366;
+const SWITCHBOARD_RESULT_SCALE_OFFSET: usize = 382;
+const SWITCHBOARD_MIN_SIZE: usize = 386;
+
+const BPS_DENOMINATOR: u128 = 10_000;
+// Neither oracle publishes prices with more decimals than this. Bounding the
+// exponent also bounds the rescaling loops below.
+const MAX_ORACLE_DECIMALS: i32 = 28;
+
+/// Reference price read from an oracle in display units of quote per base,
+/// mantissa * 10^exponent.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OraclePrice {
+    pub mantissa: u128,
+    pub exponent: i32,
+    pub publish_slot: u64,
+}
+
+/// Validated Pyth or Switchboard price account.
+pub struct OracleAccountInfo<'a, 'info> {
+    pub info: &'a AccountInfo<'info>,
+    pub price: OraclePrice,
+}
+
+impl<'a, 'info> OracleAccountInfo<'a, 'info> {
+    pub fn new(info: &'a AccountInfo<'info>) -> Result<Self, ProgramError> {
+        let data: &[u8] = &info.try_borrow_data()?;
+        let price: OraclePrice = if *info.owner == PYTH_PROGRAM_ID {
+            read_pyth_price(data)?
+        } else {
+            require!(
+                *info.owner == SWITCHBOARD_PROGRAM_ID,
+                ProgramError::IllegalOwner,
+                "Oracle must be owned by Pyth or Switchboard {}",
+                info.owner,
+            )?;
+            read_switchboard_price(data)?
+        };
+        Ok(Self { info, price })
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+pub fn read_pyth_price(data: &[u8]) -> Result<OraclePrice, ProgramError> {
+    require!(
+        data.len() >= PYTH_MIN_SIZE
+            && read_u32(data, 0) == PYTH_MAGIC
+            && read_u32(data, 4) == PYTH_VERSION
+            && read_u32(data, 8) == PYTH_ACCOUNT_TYPE_PRICE,
+        ManifestError::InvalidOraclePrice,
+        "Not a pyth price account",
+    )?;
+    require!(
+        read_u32(data, PYTH_AGG_STATUS_OFFSET) == PYTH_STATUS_TRADING,
+        ManifestError::InvalidOraclePrice,
+        "Pyth price is not trading",
+    )?;
+    let price: i64 = read_u64(data, PYTH_AGG_PRICE_OFFSET) as i64;
+    let exponent: i32 = read_u32(data, PYTH_EXPO_OFFSET) as i32;
+    require!(
+        price > 0 && exponent.abs() <= MAX_ORACLE_DECIMALS,
+        ManifestError::InvalidOraclePrice,
+        "Pyth price {}e{} is not supported",
+        price,
+        exponent,
+    )?;
+    Ok(OraclePrice {
+        mantissa: price as u128,
+        exponent,
+        publish_slot: read_u64(data, PYTH_AGG_PUB_SLOT_OFFSET),
+    })
+}
+
+pub fn read_switchboard_price(data: &[u8]) -> Result<OraclePrice, ProgramError> {
+    require!(
+        data.len() >= SWITCHBOARD_MIN_SIZE && data[0..8] == SWITCHBOARD_AGGREGATOR_DISCRIMINANT,
+        ManifestError::InvalidOraclePrice,
+        "Not a switchboard aggregator account",
+    )?;
+    let mantissa: i128 = i128::from_le_bytes(
+        data[SWITCHBOARD_RESULT_MANTISSA_OFFSET..SWITCHBOARD_RESULT_MANTISSA_OFFSET + 16]
+            .try_into()
+            .unwrap(),
+    );
+    require!(
+        mantissa > 0,
+        ManifestError::InvalidOraclePrice,
+        "Switchboard price {} is not positive",
+        mantissa,
+    )?;
+    let scale: u32 = read_u32(data, SWITCHBOARD_RESULT_SCALE_OFFSET);
+    require!(
+        scale <= MAX_ORACLE_DECIMALS as u32,
+        ManifestError::InvalidOraclePrice,
+        "Switchboard scale {} is too large",
+        scale,
+    )?;
+    Ok(OraclePrice {
+        mantissa: mantissa as u128,
+        exponent: -(scale as i32),
+        publish_slot: read_u64(data, SWITCHBOARD_ROUND_OPEN_SLOT_OFFSET),
+    })
+}
+
+impl OraclePrice {
+    pub fn is_fresh(&self, now_slot: u64, max_staleness_slots: u64) -> bool {
+        now_slot.saturating_sub(self.publish_slot) <= max_staleness_slots
+    }
+
+    pub fn assert_fresh(
+        &self,
+        now_slot: u64,
+        max_staleness_slots: u64,
+    ) -> Result<(), ProgramError> {
+        require!(
+            self.is_fresh(now_slot, max_staleness_slots),
+            ManifestError::InvalidOraclePrice,
+            "Oracle price from slot {} is stale at slot {}",
+            self.publish_slot,
+            now_slot,
+        )?;
+        Ok(())
+    }
+
+    /// Prices within max_deviation_bps of the oracle price, converted from
+    /// display units to atoms. The band is rounded outwards.
+    pub fn get_price_band(
+        &self,
+        base_mint_decimals: u8,
+        quote_mint_decimals: u8,
+        max_deviation_bps: u16,
+    ) -> Result<PriceBand, ProgramError> {
+        let max_deviation_bps: u128 = max_deviation_bps as u128;
+        require!(
+            max_deviation_bps <= BPS_DENOMINATOR,
+            ManifestError::InvalidOraclePrice,
+            "Price band of {} bps is wider than the price",
+            max_deviation_bps,
+        )?;
+        // Display price to atoms price shifts by the difference in decimals.
+        let exponent: i32 = self.exponent + quote_mint_decimals as i32 - base_mint_decimals as i32;
+        let min_mantissa: u128 = self
+            .mantissa
+            .checked_mul(BPS_DENOMINATOR - max_deviation_bps)
+            .ok_or(ManifestError::InvalidOraclePrice)?
+            / BPS_DENOMINATOR;
+        let max_mantissa: u128 = self
+            .mantissa
+            .checked_mul(BPS_DENOMINATOR + max_deviation_bps)
+            .ok_or(ManifestError::InvalidOraclePrice)?
+            .div_ceil(BPS_DENOMINATOR);
+        Ok(PriceBand {
+            min_price: to_quote_atoms_per_base_atom(min_mantissa, exponent, false)?,
+            max_price: to_quote_atoms_per_base_atom(max_mantissa, exponent, true)?,
+        })
+    }
+
+    /// Oracle price converted from display units to atoms, rounded down.
+    pub fn get_quote_atoms_per_base_atom(
+        &self,
+        base_mint_decimals: u8,
+        quote_mint_decimals: u8,
+    ) -> Result<QuoteAtomsPerBaseAtom, ProgramError> {
+        let exponent: i32 = self.exponent + quote_mint_decimals as i32 - base_mint_decimals as i32;
+        to_quote_atoms_per_base_atom(self.mantissa, exponent, false)
+    }
+}
+
+/// Fits an arbitrary mantissa and exponent onto the u32 mantissa and exponent
+/// range of prices, dropping precision in the given direction.
+fn to_quote_atoms_per_base_atom(
+    mut mantissa: u128,
+    mut exponent: i32,
+    round_up: bool,
+) -> Result<QuoteAtomsPerBaseAtom, ProgramError> {
+    while mantissa > u32::MAX as u128 || exponent < QuoteAtomsPerBaseAtom::MIN_EXP as i32 {
+        mantissa = if round_up {
+            mantissa.div_ceil(10)
+        } else {
+            mantissa / 10
+        };
+        exponent += 1;
+    }
+    while exponent > QuoteAtomsPerBaseAtom::MAX_EXP as i32 && mantissa * 10 <= u32::MAX as u128 {
+        mantissa *= 10;
+        exponent -= 1;
+    }
+    if exponent > QuoteAtomsPerBaseAtom::MAX_EXP as i32 {
+        trace!("oracle price {mantissa}e{exponent} is too large");
+        return Err(ManifestError::InvalidOraclePrice.into());
+    }
+    Ok(QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(
+        mantissa as u32,
+        exponent as i8,
+    )?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_price_band() {
+        // 150.25 USDC per SOL. 6 quote decimals and 9 base decimals.
+        let price: OraclePrice = OraclePrice {
+            mantissa: 15_025_000_000,
+            exponent: -8,
+            publish_slot: 0,
+        };
+        let band: PriceBand = price.get_price_band(9, 6, 100).unwrap();
+        assert_eq!(
+            band.min_price,
+            QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(1_487_475_000, -10).unwrap()
+        );
+        assert_eq!(
+            band.max_price,
+            QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(1_517_525_000, -10).unwrap()
+        );
+        assert!(band.contains(QuoteAtomsPerBaseAtom::try_from(0.15).unwrap()));
+        assert!(!band.contains(QuoteAtomsPerBaseAtom::try_from(0.16).unwrap()));
+        assert!(price.get_price_band(9, 6, 10_001).is_err());
+        assert_eq!(
+            price.get_quote_atoms_per_base_atom(9, 6).unwrap(),
+            QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(1_502_500_000, -10).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_stale() {
+        let price: OraclePrice = OraclePrice {
+            mantissa: 1,
+            exponent: 0,
+            publish_slot: 100,
+        };
+        assert!(price.assert_fresh(110, 10).is_ok());
+        assert!(price.assert_fresh(111, 10).is_err());
+        assert!(price.assert_fresh(50, 0).is_ok());
+    }
+
+    #[test]
+    fn test_read_pyth_price() {
+        let mut data: [u8; PYTH_MIN_SIZE] = [0; PYTH_MIN_SIZE];
+        data[0..4].copy_from_slice(&PYTH_MAGIC.to_le_bytes());
+        data[4..8].copy_from_slice(&PYTH_VERSION.to_le_bytes());
+        data[8..12].copy_from_slice(&PYTH_ACCOUNT_TYPE_PRICE.to_le_bytes());
+        data[20..24].copy_from_slice(&(-8_i32).to_le_bytes());
+        data[208..216].copy_from_slice(&15_025_000_000_i64.to_le_bytes());
+        data[224..228].copy_from_slice(&PYTH_STATUS_TRADING.to_le_bytes());
+        data[232..240].copy_from_slice(&42_u64.to_le_bytes());
+        assert_eq!(
+            read_pyth_price(&data).unwrap(),
+            OraclePrice {
+                mantissa: 15_025_000_000,
+                exponent: -8,
+                publish_slot: 42,
+            }
+        );
+
+        data[224..228].copy_from_slice(&0_u32.to_le_bytes());
+        assert!(read_pyth_price(&data).is_err());
+    }
+
+    #[test]
+    fn test_read_switchboard_price() {
+        let mut data: [u8; SWITCHBOARD_MIN_SIZE] = [0; SWITCHBOARD_MIN_SIZE];
+        data[0..8].copy_from_slice(&SWITCHBOARD_AGGREGATOR_DISCRIMINANT);
+        data[350..358].copy_from_slice(&42_u64.to_le_bytes());
+        data[366..382].copy_from_slice(&15_025_i128.to_le_bytes());
+        data[382..386].copy_from_slice(&2_u32.to_le_bytes());
+        assert_eq!(
+            read_switchboard_price(&data).unwrap(),
+            OraclePrice {
+                mantissa: 15_025,
+                exponent: -2,
+                publish_slot: 42,
+            }
+        );
+
+        data[0] = 0;
+        assert!(read_switchboard_price(&data).is_err());
+    }
+}
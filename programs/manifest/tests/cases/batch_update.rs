@@ -1,11 +1,24 @@
+use std::rc::Rc;
+
+use borsh::BorshSerialize;
 use hypertree::DataIndex;
 use manifest::{
-    program::batch_update::{CancelOrderParams, PlaceOrderParams},
-    state::{OrderType, MARKET_BLOCK_SIZE, NO_EXPIRATION_LAST_VALID_SLOT},
+    program::{
+        batch_update::{CancelOrderParams, ModifyOrderParams, PlaceOrderParams},
+        batch_update_instruction, ManifestInstruction,
+    },
+    quantities::WrapperU64,
+    state::{
+        OrderType, RestingOrder, SelfTradeBehavior, MARKET_BLOCK_SIZE,
+        NO_EXPIRATION_LAST_VALID_SLOT,
+    },
 };
 use solana_program_test::tokio;
+use solana_sdk::{
+    account::Account, instruction::Instruction, pubkey::Pubkey, signature::Keypair, signer::Signer,
+};
 
-use crate::{TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE};
+use crate::{send_tx_with_retry, Side, TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE};
 
 #[tokio::test]
 async fn batch_update_test() -> anyhow::Result<()> {
@@ -76,6 +89,82 @@ async fn batch_update_test() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn batch_update_legacy_layout_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 2 * SOL_UNIT_SIZE).await?;
+    let payer: Pubkey = test_fixture.payer();
+    let payer_keypair: Keypair = test_fixture.payer_keypair();
+
+    // An order the way clients encoded it before order options existed.
+    let legacy_order: Vec<u8> = [
+        SOL_UNIT_SIZE.to_le_bytes().as_slice(),
+        &1_u32.to_le_bytes(),
+        &[0, 0],
+        &NO_EXPIRATION_LAST_VALID_SLOT.to_le_bytes(),
+        &[OrderType::Limit as u8],
+    ]
+    .concat();
+    let order: PlaceOrderParams = PlaceOrderParams::new(
+        SOL_UNIT_SIZE,
+        1,
+        0,
+        false,
+        OrderType::Limit,
+        NO_EXPIRATION_LAST_VALID_SLOT,
+    );
+    assert_eq!(order.try_to_vec()?, legacy_order);
+
+    // No trader hint, no cancels, one order and nothing after it.
+    let mut batch_update_ix: Instruction = batch_update_instruction(
+        &test_fixture.market_fixture.key,
+        &payer,
+        None,
+        vec![],
+        vec![],
+        None,
+        None,
+        None,
+        None,
+    );
+    batch_update_ix.data = [
+        ManifestInstruction::BatchUpdate.to_vec(),
+        vec![0],
+        0_u32.to_le_bytes().to_vec(),
+        1_u32.to_le_bytes().to_vec(),
+        legacy_order,
+    ]
+    .concat();
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[batch_update_ix],
+        Some(&payer),
+        &[&payer_keypair],
+    )
+    .await?;
+
+    // Options go in the trailing vector and still reach the order.
+    let last_valid_unix_timestamp: u32 = test_fixture.get_unix_timestamp().await + 100;
+    test_fixture
+        .batch_update_for_keypair(
+            None,
+            vec![],
+            vec![order.with_last_valid_unix_timestamp(last_valid_unix_timestamp)],
+            &payer_keypair,
+        )
+        .await?;
+    let orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(orders.len(), 2);
+    assert_eq!(orders[0].get_last_valid_unix_timestamp(), 0);
+    assert_eq!(
+        orders[1].get_last_valid_unix_timestamp(),
+        last_valid_unix_timestamp
+    );
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn batch_update_fill_test() -> anyhow::Result<()> {
     let mut test_fixture: TestFixture = TestFixture::new().await;
@@ -198,3 +287,287 @@ async fn batch_update_partial_fill_then_cancel_test() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn batch_update_modify_reduce_keeps_priority_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 1_000).await?;
+
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, 1_000, &second_keypair)
+        .await?;
+    test_fixture
+        .deposit_for_keypair(Token::USDC, USDC_UNIT_SIZE, &second_keypair)
+        .await?;
+
+    test_fixture
+        .place_order(
+            Side::Ask,
+            1_000,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+    test_fixture
+        .place_order_for_keypair(
+            Side::Ask,
+            1_000,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await?;
+
+    // Reduce at the same price is done in place.
+    test_fixture
+        .batch_update_with_modifies_for_keypair(
+            None,
+            vec![],
+            vec![ModifyOrderParams::new(0, None, 400, 1, 0)],
+            vec![],
+            &test_fixture.payer_keypair(),
+        )
+        .await?;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&test_fixture.payer())
+            .await,
+        600
+    );
+    let orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    let modified: &RestingOrder = orders
+        .iter()
+        .find(|order| order.get_sequence_number() == 0)
+        .unwrap();
+    assert_eq!(modified.get_num_base_atoms().as_u64(), 400);
+
+    // Taker matches the reduced order first because it kept priority.
+    test_fixture
+        .place_order_for_keypair(
+            Side::Bid,
+            400,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+            &second_keypair,
+        )
+        .await?;
+    let orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].get_sequence_number(), 1);
+    assert_eq!(orders[0].get_num_base_atoms().as_u64(), 1_000);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn batch_update_modify_price_replaces_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::USDC, USDC_UNIT_SIZE).await?;
+
+    test_fixture
+        .place_order(
+            Side::Bid,
+            1_000,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+
+    test_fixture
+        .batch_update_with_modifies_for_keypair(
+            None,
+            vec![],
+            vec![ModifyOrderParams::new(
+                0,
+                Some(MARKET_BLOCK_SIZE as DataIndex),
+                1_000,
+                2,
+                0,
+            )],
+            vec![],
+            &test_fixture.payer_keypair(),
+        )
+        .await?;
+
+    let orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].get_sequence_number(), 1);
+    assert_eq!(orders[0].get_num_base_atoms().as_u64(), 1_000);
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&test_fixture.payer())
+            .await,
+        USDC_UNIT_SIZE - 2_000
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn batch_update_modify_self_trade_behavior_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 1_000).await?;
+    test_fixture.deposit(Token::USDC, USDC_UNIT_SIZE).await?;
+
+    test_fixture
+        .place_order(
+            Side::Ask,
+            1_000,
+            2,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+    test_fixture
+        .place_order(
+            Side::Bid,
+            1_000,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+
+    // Repricing the bid onto the own ask cancels the ask instead of trading.
+    test_fixture
+        .batch_update_with_modifies_for_keypair(
+            None,
+            vec![],
+            vec![ModifyOrderParams::new(1, None, 1_000, 2, 0)
+                .with_self_trade_behavior(SelfTradeBehavior::CancelResting)],
+            vec![],
+            &test_fixture.payer_keypair(),
+        )
+        .await?;
+
+    let orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(orders.len(), 1);
+    assert!(orders[0].get_is_bid());
+    assert_eq!(orders[0].get_num_base_atoms().as_u64(), 1_000);
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&test_fixture.payer())
+            .await,
+        1_000
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn batch_update_modify_other_trader_fail_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 1_000).await?;
+    test_fixture
+        .place_order(
+            Side::Ask,
+            1_000,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    assert!(test_fixture
+        .batch_update_with_modifies_for_keypair(
+            None,
+            vec![],
+            vec![ModifyOrderParams::new(0, None, 400, 1, 0)],
+            vec![],
+            &second_keypair,
+        )
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn batch_update_v2_sponsored_rent_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+
+    test_fixture
+        .claim_seat_v2_for_keypair(&test_fixture.payer_keypair(), &second_keypair)
+        .await?;
+    test_fixture
+        .deposit_for_keypair(Token::USDC, 10 * USDC_UNIT_SIZE, &second_keypair)
+        .await?;
+
+    let trader_lamports: u64 = test_fixture
+        .try_load(&second_keypair.pubkey())
+        .await?
+        .unwrap()
+        .lamports;
+    test_fixture
+        .batch_update_v2_for_keypair(
+            None,
+            vec![],
+            (0..4)
+                .map(|_| {
+                    PlaceOrderParams::new(
+                        1_000,
+                        1,
+                        0,
+                        true,
+                        OrderType::Limit,
+                        NO_EXPIRATION_LAST_VALID_SLOT,
+                    )
+                })
+                .collect(),
+            &test_fixture.payer_keypair(),
+            &second_keypair,
+        )
+        .await?;
+
+    // The orders are the trader's, but the payer covered any expansion.
+    let resting_orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(resting_orders.len(), 4);
+    let trader_index: DataIndex = test_fixture
+        .market_fixture
+        .market
+        .get_trader_index(&second_keypair.pubkey());
+    assert!(resting_orders
+        .iter()
+        .all(|resting_order| resting_order.get_trader_index() == trader_index));
+    let trader_account: Account = test_fixture
+        .try_load(&second_keypair.pubkey())
+        .await?
+        .unwrap();
+    assert_eq!(trader_account.lamports, trader_lamports);
+
+    // The payer has no seat, so a regular batch update from them fails.
+    assert!(test_fixture
+        .batch_update_for_keypair(
+            None,
+            vec![CancelOrderParams::new(0)],
+            vec![],
+            &test_fixture.payer_keypair(),
+        )
+        .await
+        .is_err());
+
+    Ok(())
+}
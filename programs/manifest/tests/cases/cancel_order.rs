@@ -3,7 +3,7 @@ use std::rc::Rc;
 use hypertree::DataIndex;
 use manifest::{
     program::{batch_update::CancelOrderParams, batch_update_instruction},
-    state::{OrderType, MARKET_BLOCK_SIZE},
+    state::{OrderType, RestingOrder, MARKET_BLOCK_SIZE},
 };
 use solana_program_test::tokio;
 use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signature::Keypair, signer::Signer};
@@ -180,3 +180,103 @@ async fn cancel_order_with_hint_test() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn cancel_all_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, SOL_UNIT_SIZE).await?;
+    test_fixture.deposit(Token::USDC, USDC_UNIT_SIZE).await?;
+
+    test_fixture
+        .place_order(Side::Ask, 1_000, 2, 0, u32::MAX, OrderType::Limit)
+        .await?;
+    test_fixture
+        .place_order(Side::Ask, 1_000, 3, 0, u32::MAX, OrderType::Limit)
+        .await?;
+    test_fixture
+        .place_order(Side::Bid, 1_000, 1, 0, u32::MAX, OrderType::Limit)
+        .await?;
+
+    // Another trader's order is not touched.
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+    test_fixture
+        .place_order_for_keypair(
+            Side::Ask,
+            1_000,
+            4,
+            0,
+            u32::MAX,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await?;
+
+    let payer_keypair: Keypair = test_fixture.payer_keypair().insecure_clone();
+    test_fixture
+        .cancel_all_for_keypair(None, &payer_keypair)
+        .await?;
+
+    let orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].get_sequence_number(), 3);
+
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&test_fixture.payer())
+            .await,
+        SOL_UNIT_SIZE
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&test_fixture.payer())
+            .await,
+        USDC_UNIT_SIZE
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn cancel_all_one_side_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, SOL_UNIT_SIZE).await?;
+    test_fixture.deposit(Token::USDC, USDC_UNIT_SIZE).await?;
+
+    test_fixture
+        .place_order(Side::Ask, 1_000, 2, 0, u32::MAX, OrderType::Limit)
+        .await?;
+    test_fixture
+        .place_order(Side::Bid, 1_000, 1, 0, u32::MAX, OrderType::Limit)
+        .await?;
+
+    let payer_keypair: Keypair = test_fixture.payer_keypair().insecure_clone();
+    test_fixture
+        .cancel_all_for_keypair(Some(true), &payer_keypair)
+        .await?;
+
+    let orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(orders.len(), 1);
+    assert!(!orders[0].get_is_bid());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn cancel_all_without_seat_fail_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let payer_keypair: Keypair = test_fixture.payer_keypair().insecure_clone();
+    assert!(test_fixture
+        .cancel_all_for_keypair(None, &payer_keypair)
+        .await
+        .is_err());
+
+    Ok(())
+}
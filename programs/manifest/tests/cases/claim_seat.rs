@@ -1,6 +1,14 @@
+use std::rc::Rc;
+
+use hypertree::{DataIndex, NIL};
+use manifest::program::{
+    claim_seat_instruction::{claim_seat_instruction, claim_seat_v2_instruction},
+    ManifestInstruction,
+};
 use solana_program_test::tokio;
+use solana_sdk::{instruction::Instruction, signature::Keypair, signer::Signer};
 
-use crate::TestFixture;
+use crate::{send_tx_with_retry, TestFixture};
 
 #[tokio::test]
 async fn claim_seat() -> anyhow::Result<()> {
@@ -34,3 +42,77 @@ async fn claim_seat_different_user() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn claim_seat_v2_sponsored_rent() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+
+    // The trader has no lamports at all, the payer covers the rent.
+    let trader_keypair: Keypair = Keypair::new();
+    test_fixture
+        .claim_seat_v2_for_keypair(&test_fixture.payer_keypair(), &trader_keypair)
+        .await?;
+
+    test_fixture.market_fixture.reload().await;
+    let trader_index: DataIndex = test_fixture
+        .market_fixture
+        .market
+        .get_trader_index(&trader_keypair.pubkey());
+    assert_ne!(trader_index, NIL);
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .market
+            .get_trader_index(&test_fixture.payer()),
+        NIL
+    );
+    assert!(test_fixture
+        .try_load(&trader_keypair.pubkey())
+        .await?
+        .is_none());
+
+    // The seat is the trader's, so they cannot claim it again.
+    assert!(test_fixture
+        .claim_seat_v2_for_keypair(&test_fixture.payer_keypair(), &trader_keypair)
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn claim_seat_layout_follows_instruction() -> anyhow::Result<()> {
+    let test_fixture: TestFixture = TestFixture::new().await;
+    let trader_keypair: Keypair = Keypair::new();
+
+    // ClaimSeatV2 with the ClaimSeat accounts takes the market as the trader.
+    let mut claim_seat_ix: Instruction =
+        claim_seat_instruction(&test_fixture.market_fixture.key, &test_fixture.payer());
+    claim_seat_ix.data = ManifestInstruction::ClaimSeatV2.to_vec();
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[claim_seat_ix],
+        Some(&test_fixture.payer()),
+        &[&test_fixture.payer_keypair()],
+    )
+    .await
+    .is_err());
+
+    // ClaimSeat with the ClaimSeatV2 accounts takes the trader as the market.
+    let mut claim_seat_v2_ix: Instruction = claim_seat_v2_instruction(
+        &test_fixture.market_fixture.key,
+        &test_fixture.payer(),
+        &trader_keypair.pubkey(),
+    );
+    claim_seat_v2_ix.data = ManifestInstruction::ClaimSeat.to_vec();
+    assert!(send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[claim_seat_v2_ix],
+        Some(&test_fixture.payer()),
+        &[&test_fixture.payer_keypair(), &trader_keypair],
+    )
+    .await
+    .is_err());
+
+    Ok(())
+}
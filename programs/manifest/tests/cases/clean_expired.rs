@@ -0,0 +1,156 @@
+use hypertree::DataIndex;
+use manifest::{
+    program::batch_update::PlaceOrderParams,
+    state::{OrderType, RestingOrder, MARKET_BLOCK_SIZE, NO_EXPIRATION_LAST_VALID_SLOT},
+};
+use solana_program_test::tokio;
+use solana_sdk::signature::Keypair;
+
+use crate::{Side, TestFixture, Token, SOL_UNIT_SIZE};
+
+#[tokio::test]
+async fn clean_expired_scan_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 2 * SOL_UNIT_SIZE).await?;
+
+    // Slots may advance during tests, so expiration is set pretty far out.
+    test_fixture
+        .place_order(Side::Ask, 1 * SOL_UNIT_SIZE, 1, 0, 1_000, OrderType::Limit)
+        .await?;
+    test_fixture
+        .place_order(
+            Side::Ask,
+            1 * SOL_UNIT_SIZE,
+            2,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+    test_fixture.advance_time_seconds(10_000).await;
+
+    // Anyone can clean.
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture
+        .clean_expired_for_keypair(vec![], 10, &second_keypair)
+        .await?;
+
+    let orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].get_sequence_number(), 1);
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&test_fixture.payer())
+            .await,
+        1 * SOL_UNIT_SIZE
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn clean_expired_by_index_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 2 * SOL_UNIT_SIZE).await?;
+
+    let now_unix_timestamp: u32 = test_fixture.get_unix_timestamp().await;
+    test_fixture
+        .batch_update_for_keypair(
+            None,
+            vec![],
+            vec![
+                PlaceOrderParams::new(
+                    1 * SOL_UNIT_SIZE,
+                    1,
+                    0,
+                    false,
+                    OrderType::Limit,
+                    NO_EXPIRATION_LAST_VALID_SLOT,
+                )
+                .with_last_valid_unix_timestamp(now_unix_timestamp + 100),
+                PlaceOrderParams::new(
+                    1 * SOL_UNIT_SIZE,
+                    2,
+                    0,
+                    false,
+                    OrderType::Limit,
+                    NO_EXPIRATION_LAST_VALID_SLOT,
+                ),
+            ],
+            &test_fixture.payer_keypair(),
+        )
+        .await?;
+    test_fixture.market_fixture.reload().await;
+    let expiring_order_index: DataIndex = test_fixture
+        .market_fixture
+        .market
+        .get_order_index_by_sequence_number(0);
+    let resting_order_index: DataIndex = test_fixture
+        .market_fixture
+        .market
+        .get_order_index_by_sequence_number(1);
+
+    // Not expired yet, so nothing happens.
+    test_fixture
+        .clean_expired_for_keypair(vec![expiring_order_index], 0, &test_fixture.payer_keypair())
+        .await?;
+    assert_eq!(
+        test_fixture.market_fixture.get_resting_orders().await.len(),
+        2
+    );
+
+    test_fixture.advance_time_seconds(1_000).await;
+
+    // Orders that are not expired are skipped.
+    test_fixture
+        .clean_expired_for_keypair(
+            vec![resting_order_index, expiring_order_index],
+            0,
+            &test_fixture.payer_keypair(),
+        )
+        .await?;
+    let orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].get_sequence_number(), 1);
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&test_fixture.payer())
+            .await,
+        1 * SOL_UNIT_SIZE
+    );
+
+    // Already removed, so it is skipped too.
+    test_fixture
+        .clean_expired_for_keypair(vec![expiring_order_index], 0, &test_fixture.payer_keypair())
+        .await?;
+
+    // Past the end of the market, so it is skipped too.
+    test_fixture
+        .clean_expired_for_keypair(
+            vec![1_000 * MARKET_BLOCK_SIZE as DataIndex],
+            0,
+            &test_fixture.payer_keypair(),
+        )
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn clean_expired_bad_index_fail_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    assert!(test_fixture
+        .clean_expired_for_keypair(
+            vec![(MARKET_BLOCK_SIZE as DataIndex) + 1],
+            0,
+            &test_fixture.payer_keypair(),
+        )
+        .await
+        .is_err());
+
+    Ok(())
+}
@@ -0,0 +1,218 @@
+use hypertree::DataIndex;
+use manifest::{
+    program::batch_update::{CancelOrderParams, PlaceOrderParams},
+    state::{
+        OrderType, RestingOrder, MARKET_BLOCK_SIZE, MARKET_COMPACT_MIN_FREE_BLOCKS,
+        MARKET_FIXED_SIZE, NO_EXPIRATION_LAST_VALID_SLOT,
+    },
+};
+use solana_program::pubkey::Pubkey;
+use solana_program_test::tokio;
+use solana_sdk::{account::Account, signature::Keypair, signer::Signer};
+
+use crate::{TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE};
+
+#[tokio::test]
+async fn compact_market_moves_orders_and_seats() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 40 * SOL_UNIT_SIZE).await?;
+    test_fixture.expand_market(60).await?;
+
+    // Asks fill the market from the front, so the second seat and its bid end
+    // up near the end of the account.
+    let asks: Vec<PlaceOrderParams> = (0..40)
+        .map(|i| {
+            PlaceOrderParams::new(
+                SOL_UNIT_SIZE,
+                10 + i,
+                -3,
+                false,
+                OrderType::Limit,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )
+        })
+        .collect();
+    for batch in asks.chunks(20) {
+        test_fixture
+            .batch_update_for_keypair(None, vec![], batch.to_vec(), &test_fixture.payer_keypair())
+            .await?;
+    }
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::USDC, 100 * USDC_UNIT_SIZE, &second_keypair)
+        .await?;
+    test_fixture
+        .batch_update_for_keypair(
+            None,
+            vec![],
+            vec![PlaceOrderParams::new(
+                SOL_UNIT_SIZE,
+                5,
+                -3,
+                true,
+                OrderType::Limit,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )],
+            &second_keypair,
+        )
+        .await?;
+
+    // Leave only the two highest asks.
+    let cancels: Vec<CancelOrderParams> = (0..38).map(CancelOrderParams::new).collect();
+    for batch in cancels.chunks(20) {
+        test_fixture
+            .batch_update_for_keypair(None, batch.to_vec(), vec![], &test_fixture.payer_keypair())
+            .await?;
+    }
+
+    let receiver: Pubkey = Pubkey::new_unique();
+    let market_before: Account = test_fixture
+        .try_load(&test_fixture.market_fixture.key)
+        .await?
+        .unwrap();
+    test_fixture.compact_market(&receiver).await?;
+    let market_after: Account = test_fixture
+        .try_load(&test_fixture.market_fixture.key)
+        .await?
+        .unwrap();
+
+    // 2 seats and 3 orders are live.
+    let num_blocks_kept: usize = 5 + MARKET_COMPACT_MIN_FREE_BLOCKS as usize;
+    assert_eq!(
+        market_after.data.len(),
+        MARKET_FIXED_SIZE + num_blocks_kept * MARKET_BLOCK_SIZE
+    );
+    let receiver_account: Account = test_fixture.try_load(&receiver).await?.unwrap();
+    assert!(receiver_account.lamports > 0);
+    assert_eq!(
+        receiver_account.lamports,
+        market_before.lamports - market_after.lamports
+    );
+
+    let resting_orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(resting_orders.len(), 3);
+    assert!(resting_orders
+        .iter()
+        .all(|order| order.get_trader_index() as usize / MARKET_BLOCK_SIZE < num_blocks_kept));
+    let payer_index: DataIndex = test_fixture
+        .market_fixture
+        .market
+        .get_trader_index(&test_fixture.payer());
+    let second_index: DataIndex = test_fixture
+        .market_fixture
+        .market
+        .get_trader_index(&second_keypair.pubkey());
+    assert_eq!(
+        resting_orders
+            .iter()
+            .filter(|order| order.get_trader_index() == second_index)
+            .count(),
+        1
+    );
+    assert_eq!(
+        resting_orders
+            .iter()
+            .filter(|order| order.get_trader_index() == payer_index)
+            .count(),
+        2
+    );
+
+    // The moved seat still trades and cancels like before.
+    test_fixture
+        .batch_update_for_keypair(
+            None,
+            vec![CancelOrderParams::new(40)],
+            vec![PlaceOrderParams::new(
+                SOL_UNIT_SIZE,
+                48,
+                -3,
+                true,
+                OrderType::ImmediateOrCancel,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )],
+            &second_keypair,
+        )
+        .await?;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&second_keypair.pubkey())
+            .await,
+        SOL_UNIT_SIZE
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&test_fixture.payer())
+            .await,
+        48_000_000
+    );
+    assert_eq!(
+        test_fixture.market_fixture.get_resting_orders().await.len(),
+        1
+    );
+
+    // The filled ask frees one more block and the cancelled bid another.
+    test_fixture.compact_market(&receiver).await?;
+    let market_final: Account = test_fixture
+        .try_load(&test_fixture.market_fixture.key)
+        .await?
+        .unwrap();
+    assert_eq!(
+        market_final.data.len(),
+        market_after.data.len() - 2 * MARKET_BLOCK_SIZE
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn compact_market_noop_when_small() -> anyhow::Result<()> {
+    let test_fixture: TestFixture = TestFixture::new().await;
+    let market_before: Account = test_fixture
+        .try_load(&test_fixture.market_fixture.key)
+        .await?
+        .unwrap();
+    test_fixture.compact_market(&test_fixture.payer()).await?;
+    let market_after: Account = test_fixture
+        .try_load(&test_fixture.market_fixture.key)
+        .await?
+        .unwrap();
+    assert_eq!(market_before.data.len(), market_after.data.len());
+    assert_eq!(market_before.lamports, market_after.lamports);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn compact_market_only_market_authority() -> anyhow::Result<()> {
+    let test_fixture: TestFixture = TestFixture::new().await;
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.expand_market(40).await?;
+    let market_before: Account = test_fixture
+        .try_load(&test_fixture.market_fixture.key)
+        .await?
+        .unwrap();
+
+    // Anyone else could send the freed rent to themselves.
+    assert!(test_fixture
+        .compact_market_for_keypair(&second_keypair.pubkey(), &second_keypair)
+        .await
+        .is_err());
+    let market_after: Account = test_fixture
+        .try_load(&test_fixture.market_fixture.key)
+        .await?
+        .unwrap();
+    assert_eq!(market_before.data.len(), market_after.data.len());
+
+    test_fixture.compact_market(&test_fixture.payer()).await?;
+    let market_after: Account = test_fixture
+        .try_load(&test_fixture.market_fixture.key)
+        .await?
+        .unwrap();
+    assert!(market_after.data.len() < market_before.data.len());
+
+    Ok(())
+}
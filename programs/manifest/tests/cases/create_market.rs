@@ -1,6 +1,13 @@
 use std::cell::RefMut;
 
-use manifest::program::create_market_instructions;
+use manifest::{
+    program::{
+        batch_update::ModifyOrderParams, create_market::CreateMarketParams,
+        create_market_instructions,
+    },
+    quantities::{BaseAtoms, QuoteAtomsPerBaseAtom, WrapperU64},
+    state::{MarketFixed, OrderType, RestingOrder, NO_EXPIRATION_LAST_VALID_SLOT},
+};
 use solana_program_test::{tokio, ProgramTestContext};
 use solana_sdk::{
     instruction::Instruction,
@@ -9,7 +16,7 @@ use solana_sdk::{
     transaction::Transaction,
 };
 
-use crate::TestFixture;
+use crate::{Side, TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE};
 
 #[tokio::test]
 async fn create_market() -> anyhow::Result<()> {
@@ -78,3 +85,171 @@ async fn create_market_fail_already_initialized() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn create_market_with_params() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+
+    // Markets created without params have no limits.
+    test_fixture.market_fixture.reload().await;
+    let fixed: &MarketFixed = &test_fixture.market_fixture.market.fixed;
+    assert_eq!(fixed.get_min_base_atoms(), BaseAtoms::ZERO);
+    assert_eq!(fixed.get_price_tick(), QuoteAtomsPerBaseAtom::ZERO);
+
+    test_fixture.market_fixture.key = test_fixture
+        .create_new_market_with_params(
+            &test_fixture.sol_mint_fixture.key,
+            &test_fixture.usdc_mint_fixture.key,
+            CreateMarketParams::new(1_000, 5, -1),
+        )
+        .await?;
+    test_fixture.market_fixture.reload().await;
+    let fixed: &MarketFixed = &test_fixture.market_fixture.market.fixed;
+    assert_eq!(fixed.get_min_base_atoms(), BaseAtoms::new(1_000));
+    assert_eq!(
+        fixed.get_price_tick(),
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(5, -1).unwrap()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn create_market_fail_invalid_tick_exponent() -> anyhow::Result<()> {
+    let test_fixture: TestFixture = TestFixture::new().await;
+
+    assert!(test_fixture
+        .create_new_market_with_params(
+            &test_fixture.sol_mint_fixture.key,
+            &test_fixture.usdc_mint_fixture.key,
+            CreateMarketParams::new(0, 1, 20),
+        )
+        .await
+        .is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn min_base_atoms_and_price_tick_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.market_fixture.key = test_fixture
+        .create_new_market_with_params(
+            &test_fixture.sol_mint_fixture.key,
+            &test_fixture.usdc_mint_fixture.key,
+            CreateMarketParams::new(SOL_UNIT_SIZE, 5, -4),
+        )
+        .await?;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 10 * SOL_UNIT_SIZE).await?;
+    test_fixture
+        .deposit(Token::USDC, 10 * USDC_UNIT_SIZE)
+        .await?;
+
+    // Below the minimum size.
+    assert!(test_fixture
+        .place_order(
+            Side::Ask,
+            SOL_UNIT_SIZE / 2,
+            1,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await
+        .is_err());
+    // Off the 0.0005 tick.
+    assert!(test_fixture
+        .place_order(
+            Side::Ask,
+            SOL_UNIT_SIZE,
+            7,
+            -4,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await
+        .is_err());
+    // Same price with a different representation is on the tick.
+    test_fixture
+        .place_order(
+            Side::Ask,
+            2 * SOL_UNIT_SIZE,
+            10,
+            -4,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+
+    // Taking is not held to the minimum or the tick.
+    test_fixture
+        .place_order(
+            Side::Bid,
+            SOL_UNIT_SIZE / 2,
+            11,
+            -4,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+        )
+        .await?;
+    let orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(orders.len(), 1);
+    assert_eq!(
+        orders[0].get_num_base_atoms(),
+        BaseAtoms::new(3 * SOL_UNIT_SIZE / 2)
+    );
+
+    // A limit bid that fills most of its size does not rest the remainder
+    // below the minimum.
+    test_fixture
+        .place_order(
+            Side::Bid,
+            2 * SOL_UNIT_SIZE,
+            1,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+    let orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(orders.len(), 0);
+
+    // Shrinking in place is held to the minimum too.
+    test_fixture
+        .place_order(
+            Side::Ask,
+            2 * SOL_UNIT_SIZE,
+            10,
+            -4,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+    assert!(test_fixture
+        .batch_update_with_modifies_for_keypair(
+            None,
+            vec![],
+            vec![ModifyOrderParams::new(3, None, SOL_UNIT_SIZE / 2, 10, -4)],
+            vec![],
+            &test_fixture.payer_keypair(),
+        )
+        .await
+        .is_err());
+    test_fixture
+        .batch_update_with_modifies_for_keypair(
+            None,
+            vec![],
+            vec![ModifyOrderParams::new(3, None, SOL_UNIT_SIZE, 10, -4)],
+            vec![],
+            &test_fixture.payer_keypair(),
+        )
+        .await?;
+    let orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(orders.len(), 1);
+    assert_eq!(
+        orders[0].get_num_base_atoms(),
+        BaseAtoms::new(SOL_UNIT_SIZE)
+    );
+
+    Ok(())
+}
@@ -0,0 +1,296 @@
+use manifest::{
+    program::{
+        batch_update::PlaceOrderParams, delegate_market::DelegateMarketParams, ManifestError,
+    },
+    state::{DelegationStatus, OrderType, NO_EXPIRATION_LAST_VALID_SLOT},
+    validation::DELEGATION_PROGRAM_ID,
+};
+use solana_program::{instruction::InstructionError, pubkey::Pubkey};
+use solana_program_test::{tokio, BanksClientError};
+use solana_sdk::{signature::Keypair, signer::Signer, transaction::TransactionError};
+
+use crate::{TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE};
+
+fn is_manifest_error(result: Result<(), BanksClientError>, error: ManifestError) -> bool {
+    matches!(
+        result,
+        Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(code),
+        ))) if code == error as u32
+    )
+}
+
+fn delegate_params() -> DelegateMarketParams {
+    DelegateMarketParams {
+        update_frequency_ms: 1_000,
+        time_limit: 3_600,
+        validator: None,
+    }
+}
+
+#[tokio::test]
+async fn delegation_needs_market_authority() -> anyhow::Result<()> {
+    let test_fixture: TestFixture = TestFixture::new().await;
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+
+    // Until an authority is set only the market keypair could delegate.
+    assert!(is_manifest_error(
+        test_fixture
+            .delegate_market_for_keypair(delegate_params(), &test_fixture.payer_keypair())
+            .await,
+        ManifestError::InvalidMarketAuthority,
+    ));
+
+    test_fixture
+        .set_market_authority(&second_keypair.pubkey())
+        .await?;
+
+    assert!(is_manifest_error(
+        test_fixture
+            .delegate_market_for_keypair(delegate_params(), &test_fixture.payer_keypair())
+            .await,
+        ManifestError::InvalidMarketAuthority,
+    ));
+    assert!(is_manifest_error(
+        test_fixture
+            .undelegate_market_for_keypair(&test_fixture.payer_keypair())
+            .await,
+        ManifestError::InvalidMarketAuthority,
+    ));
+
+    // The authority gets past the policy checks. The delegation and magic
+    // programs are not loaded here, so the CPI is what fails.
+    let result = test_fixture
+        .delegate_market_for_keypair(delegate_params(), &second_keypair)
+        .await;
+    assert!(result.is_err());
+    assert!(!is_manifest_error(
+        result,
+        ManifestError::InvalidMarketAuthority
+    ));
+    let result = test_fixture
+        .undelegate_market_for_keypair(&second_keypair)
+        .await;
+    assert!(result.is_err());
+    assert!(!is_manifest_error(
+        result,
+        ManifestError::InvalidMarketAuthority
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn delegated_market_base_layer_error() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+
+    test_fixture.set_market_owner(&DELEGATION_PROGRAM_ID).await;
+    assert!(is_manifest_error(
+        test_fixture.deposit(Token::SOL, SOL_UNIT_SIZE).await,
+        ManifestError::MarketDelegated,
+    ));
+
+    test_fixture.set_market_owner(&manifest::id()).await;
+    test_fixture.deposit(Token::SOL, SOL_UNIT_SIZE).await?;
+
+    // Never delegated, so there is no status.
+    test_fixture.market_fixture.reload().await;
+    assert!(!test_fixture.market_fixture.market.is_delegated());
+    assert!(test_fixture
+        .market_fixture
+        .market
+        .get_delegation_status()
+        .is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn delegation_round_trip() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new_with_ephemeral_rollup().await;
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 10 * SOL_UNIT_SIZE).await?;
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::USDC, 1_000 * USDC_UNIT_SIZE, &second_keypair)
+        .await?;
+    test_fixture
+        .set_market_authority(&test_fixture.payer())
+        .await?;
+
+    test_fixture
+        .delegate_market_to_rollup(delegate_params(), &[], &test_fixture.payer_keypair())
+        .await?;
+    assert!(is_manifest_error(
+        test_fixture.withdraw(Token::SOL, 1_000).await,
+        ManifestError::MarketDelegated,
+    ));
+    test_fixture.market_fixture.reload().await;
+    assert!(test_fixture.market_fixture.market.is_delegated());
+
+    // Trade on the rollup.
+    test_fixture.open_rollup().await;
+    test_fixture
+        .batch_update_for_keypair(
+            None,
+            vec![],
+            vec![PlaceOrderParams::new(
+                1_000,
+                1,
+                0,
+                false,
+                OrderType::Limit,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )],
+            &test_fixture.payer_keypair(),
+        )
+        .await?;
+    test_fixture
+        .batch_update_for_keypair(
+            None,
+            vec![],
+            vec![PlaceOrderParams::new(
+                1_000,
+                1,
+                0,
+                true,
+                OrderType::Limit,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )],
+            &second_keypair,
+        )
+        .await?;
+
+    // A commit leaves the market delegated.
+    test_fixture.advance_time_seconds(10).await;
+    test_fixture
+        .commit_market_for_keypair(&test_fixture.payer_keypair())
+        .await?;
+    test_fixture.settle_rollup().await;
+    assert!(is_manifest_error(
+        test_fixture.withdraw(Token::SOL, 1_000).await,
+        ManifestError::MarketDelegated,
+    ));
+    test_fixture.market_fixture.reload().await;
+    assert!(test_fixture.market_fixture.market.is_delegated());
+    let delegation_status: &DelegationStatus = test_fixture
+        .market_fixture
+        .market
+        .get_delegation_status()
+        .unwrap();
+    assert!(delegation_status.get_last_commit_slot() > delegation_status.get_delegated_at_slot());
+
+    test_fixture.open_rollup().await;
+    test_fixture
+        .undelegate_market_for_keypair(&test_fixture.payer_keypair())
+        .await?;
+    test_fixture.settle_rollup().await;
+    test_fixture.market_fixture.reload().await;
+    assert!(!test_fixture.market_fixture.market.is_delegated());
+
+    // Balances from the rollup are withdrawable on the base layer.
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&test_fixture.payer())
+            .await,
+        10 * SOL_UNIT_SIZE - 1_000
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&test_fixture.payer())
+            .await,
+        1_000
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&second_keypair.pubkey())
+            .await,
+        1_000
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&second_keypair.pubkey())
+            .await,
+        1_000 * USDC_UNIT_SIZE - 1_000
+    );
+
+    test_fixture
+        .withdraw(Token::SOL, 10 * SOL_UNIT_SIZE - 1_000)
+        .await?;
+    test_fixture.withdraw(Token::USDC, 1_000).await?;
+    test_fixture
+        .withdraw_for_keypair(Token::SOL, 1_000, &second_keypair)
+        .await?;
+    test_fixture
+        .withdraw_for_keypair(Token::USDC, 1_000 * USDC_UNIT_SIZE - 1_000, &second_keypair)
+        .await?;
+    assert_eq!(
+        test_fixture.payer_sol_fixture.balance_atoms().await,
+        10 * SOL_UNIT_SIZE - 1_000
+    );
+    assert_eq!(test_fixture.payer_usdc_fixture.balance_atoms().await, 1_000);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn undelegation_needs_delegated_market_globals() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new_with_ephemeral_rollup().await;
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    let quote_mint: Pubkey = test_fixture.usdc_mint_fixture.key;
+
+    test_fixture
+        .set_market_authority(&test_fixture.payer())
+        .await?;
+    test_fixture.market_global_create().await?;
+    test_fixture
+        .delegate_market_to_rollup(
+            delegate_params(),
+            &[quote_mint],
+            &test_fixture.payer_keypair(),
+        )
+        .await?;
+    test_fixture.market_fixture.reload().await;
+    let delegation_status: &DelegationStatus = test_fixture
+        .market_fixture
+        .market
+        .get_delegation_status()
+        .unwrap();
+    assert!(!delegation_status.has_base_market_global());
+    assert!(delegation_status.has_quote_market_global());
+
+    // After the time limit anyone can undelegate, but not without the market
+    // global, which would stay with the delegation program.
+    test_fixture.open_rollup().await;
+    test_fixture.advance_time_seconds(3_601).await;
+    assert!(is_manifest_error(
+        test_fixture
+            .undelegate_market_for_keypair(&second_keypair)
+            .await,
+        ManifestError::MissingGlobal,
+    ));
+    assert!(is_manifest_error(
+        test_fixture
+            .commit_market_for_keypair(&second_keypair)
+            .await,
+        ManifestError::MissingGlobal,
+    ));
+
+    test_fixture
+        .undelegate_market_with_market_globals_for_keypair(&[quote_mint], &second_keypair)
+        .await?;
+    test_fixture.settle_rollup().await;
+    assert!(test_fixture.delegated_account_owners.is_empty());
+    test_fixture.market_fixture.reload().await;
+    assert!(!test_fixture.market_fixture.market.is_delegated());
+
+    Ok(())
+}
@@ -0,0 +1,282 @@
+use std::rc::Rc;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use manifest::{
+    program::{
+        commit_market_instruction, delegate_market::DelegateMarketParams,
+        delegate_market_instruction, undelegate_market_instruction, ManifestInstruction,
+    },
+    validation::{
+        get_delegation_addresses, get_market_global_address, get_vault_address,
+        DELEGATION_PROGRAM_ID, MAGIC_CONTEXT_ID, MAGIC_PROGRAM_ID,
+    },
+};
+use solana_program::{
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    system_program,
+};
+use solana_program_test::{tokio, BanksClientError};
+use solana_sdk::{signature::Keypair, signer::Signer, transaction::TransactionError};
+
+use crate::{send_tx_with_retry, TestFixture};
+
+// Stand-ins for the delegation and magic programs, which are pinned.
+const MOCK_DELEGATION_PROGRAM_ID: Pubkey = Pubkey::new_from_array([1; 32]);
+const MOCK_MAGIC_PROGRAM_ID: Pubkey = Pubkey::new_from_array([2; 32]);
+
+fn is_instruction_error(result: Result<(), BanksClientError>, error: InstructionError) -> bool {
+    matches!(
+        result,
+        Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            instruction_error,
+        ))) if instruction_error == error
+    )
+}
+
+fn delegate_params() -> DelegateMarketParams {
+    DelegateMarketParams {
+        update_frequency_ms: 5_000,
+        time_limit: 0,
+        validator: None,
+    }
+}
+
+async fn send_for_payer(
+    test_fixture: &TestFixture,
+    instruction: Instruction,
+) -> anyhow::Result<(), BanksClientError> {
+    send_tx_with_retry(
+        Rc::clone(&test_fixture.context),
+        &[instruction],
+        Some(&test_fixture.payer()),
+        &[&test_fixture.payer_keypair()],
+    )
+    .await
+}
+
+#[test]
+fn delegate_market_instruction_shape() {
+    let market: Pubkey = Pubkey::new_unique();
+    let authority: Pubkey = Pubkey::new_unique();
+    let base_mint: Pubkey = Pubkey::new_unique();
+    let quote_mint: Pubkey = Pubkey::new_unique();
+    let (base_vault, _) = get_vault_address(&market, &base_mint);
+    let (quote_vault, _) = get_vault_address(&market, &quote_mint);
+
+    let instruction: Instruction = delegate_market_instruction(
+        &market,
+        &authority,
+        &base_mint,
+        &quote_mint,
+        &[],
+        delegate_params(),
+    );
+    assert_eq!(instruction.program_id, manifest::id());
+    assert_eq!(instruction.accounts.len(), 18);
+    assert_eq!(instruction.accounts[0].pubkey, authority);
+    assert!(instruction.accounts[0].is_signer);
+    assert!(instruction.accounts[0].is_writable);
+    assert_eq!(instruction.accounts[1].pubkey, system_program::id());
+    assert_eq!(instruction.accounts[2].pubkey, market);
+    assert!(instruction.accounts[2].is_writable);
+    assert_eq!(instruction.accounts[3].pubkey, manifest::id());
+    assert_eq!(instruction.accounts[4].pubkey, base_vault);
+    assert_eq!(instruction.accounts[5].pubkey, quote_vault);
+    assert_eq!(instruction.accounts[6].pubkey, base_mint);
+    assert_eq!(instruction.accounts[7].pubkey, quote_mint);
+    for (offset, delegated) in [market, base_vault, quote_vault].iter().enumerate() {
+        let (buffer, record, delegation_metadata) = get_delegation_addresses(delegated);
+        assert_eq!(instruction.accounts[8 + 3 * offset].pubkey, buffer);
+        assert_eq!(instruction.accounts[9 + 3 * offset].pubkey, record);
+        assert_eq!(
+            instruction.accounts[10 + 3 * offset].pubkey,
+            delegation_metadata
+        );
+    }
+    assert_eq!(instruction.accounts[17].pubkey, DELEGATION_PROGRAM_ID);
+    assert!(instruction.accounts[1..]
+        .iter()
+        .all(|account_meta| !account_meta.is_signer));
+
+    assert_eq!(
+        instruction.data[0],
+        ManifestInstruction::DelegateMarket as u8
+    );
+    let params: DelegateMarketParams =
+        DelegateMarketParams::try_from_slice(&instruction.data[1..]).unwrap();
+    assert_eq!(params.update_frequency_ms, 5_000);
+    assert_eq!(params.time_limit, 0);
+    assert_eq!(params.validator, None);
+
+    // Each market global follows with its own delegation accounts.
+    let instruction: Instruction = delegate_market_instruction(
+        &market,
+        &authority,
+        &base_mint,
+        &quote_mint,
+        &[base_mint],
+        delegate_params(),
+    );
+    let (market_global, _) = get_market_global_address(&market, &base_mint);
+    let (buffer, record, delegation_metadata) = get_delegation_addresses(&market_global);
+    assert_eq!(instruction.accounts.len(), 22);
+    assert_eq!(instruction.accounts[18].pubkey, market_global);
+    assert!(instruction.accounts[18].is_writable);
+    assert_eq!(instruction.accounts[19].pubkey, buffer);
+    assert_eq!(instruction.accounts[20].pubkey, record);
+    assert_eq!(instruction.accounts[21].pubkey, delegation_metadata);
+}
+
+#[test]
+fn undelegate_market_instruction_shape() {
+    let market: Pubkey = Pubkey::new_unique();
+    let payer: Pubkey = Pubkey::new_unique();
+    let base_mint: Pubkey = Pubkey::new_unique();
+    let quote_mint: Pubkey = Pubkey::new_unique();
+    let (base_vault, _) = get_vault_address(&market, &base_mint);
+    let (quote_vault, _) = get_vault_address(&market, &quote_mint);
+
+    let instruction: Instruction =
+        undelegate_market_instruction(&market, &payer, &base_mint, &quote_mint, &[]);
+    assert_eq!(instruction.program_id, manifest::id());
+    assert_eq!(instruction.accounts.len(), 6);
+    assert_eq!(instruction.accounts[0].pubkey, payer);
+    assert!(instruction.accounts[0].is_signer);
+    assert_eq!(instruction.accounts[1].pubkey, market);
+    assert!(instruction.accounts[1].is_writable);
+    assert_eq!(instruction.accounts[2].pubkey, base_vault);
+    assert_eq!(instruction.accounts[3].pubkey, quote_vault);
+    assert_eq!(instruction.accounts[4].pubkey, MAGIC_CONTEXT_ID);
+    assert!(instruction.accounts[4].is_writable);
+    assert_eq!(instruction.accounts[5].pubkey, MAGIC_PROGRAM_ID);
+    assert_eq!(
+        instruction.data,
+        vec![ManifestInstruction::UndelegateMarket as u8]
+    );
+
+    let instruction: Instruction =
+        undelegate_market_instruction(&market, &payer, &base_mint, &quote_mint, &[quote_mint]);
+    let (market_global, _) = get_market_global_address(&market, &quote_mint);
+    assert_eq!(instruction.accounts.len(), 7);
+    assert_eq!(instruction.accounts[6].pubkey, market_global);
+}
+
+#[test]
+fn commit_market_instruction_shape() {
+    let market: Pubkey = Pubkey::new_unique();
+    let payer: Pubkey = Pubkey::new_unique();
+    let base_mint: Pubkey = Pubkey::new_unique();
+    let quote_mint: Pubkey = Pubkey::new_unique();
+    let (base_vault, _) = get_vault_address(&market, &base_mint);
+    let (quote_vault, _) = get_vault_address(&market, &quote_mint);
+
+    let instruction: Instruction =
+        commit_market_instruction(&market, &payer, &base_mint, &quote_mint, &[]);
+    assert_eq!(instruction.program_id, manifest::id());
+    assert_eq!(instruction.accounts.len(), 6);
+    assert_eq!(instruction.accounts[0].pubkey, payer);
+    assert!(instruction.accounts[0].is_signer);
+    assert_eq!(instruction.accounts[1].pubkey, market);
+    assert!(instruction.accounts[1].is_writable);
+    assert_eq!(instruction.accounts[2].pubkey, base_vault);
+    assert_eq!(instruction.accounts[3].pubkey, quote_vault);
+    assert_eq!(instruction.accounts[4].pubkey, MAGIC_CONTEXT_ID);
+    assert_eq!(instruction.accounts[5].pubkey, MAGIC_PROGRAM_ID);
+    assert_eq!(
+        instruction.data,
+        vec![ManifestInstruction::CommitMarket as u8]
+    );
+
+    let instruction: Instruction =
+        commit_market_instruction(&market, &payer, &base_mint, &quote_mint, &[base_mint]);
+    let (market_global, _) = get_market_global_address(&market, &base_mint);
+    assert_eq!(instruction.accounts.len(), 7);
+    assert_eq!(instruction.accounts[6].pubkey, market_global);
+}
+
+#[test]
+fn delegate_market_params_serialization() {
+    let params: DelegateMarketParams = DelegateMarketParams {
+        update_frequency_ms: 10_000,
+        time_limit: 3_600,
+        validator: Some(Pubkey::new_unique()),
+    };
+
+    let serialized: Vec<u8> = params.try_to_vec().unwrap();
+    let deserialized: DelegateMarketParams =
+        DelegateMarketParams::try_from_slice(&serialized).unwrap();
+
+    assert_eq!(params.update_frequency_ms, deserialized.update_frequency_ms);
+    assert_eq!(params.time_limit, deserialized.time_limit);
+    assert_eq!(params.validator, deserialized.validator);
+}
+
+#[tokio::test]
+async fn delegation_rejects_unpinned_programs() -> anyhow::Result<()> {
+    let test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture
+        .set_market_authority(&test_fixture.payer())
+        .await?;
+
+    let mut delegate_ix: Instruction = delegate_market_instruction(
+        &test_fixture.market_fixture.key,
+        &test_fixture.payer(),
+        &test_fixture.sol_mint_fixture.key,
+        &test_fixture.usdc_mint_fixture.key,
+        &[],
+        delegate_params(),
+    );
+    delegate_ix.accounts[17].pubkey = MOCK_DELEGATION_PROGRAM_ID;
+    assert!(is_instruction_error(
+        send_for_payer(&test_fixture, delegate_ix).await,
+        InstructionError::IncorrectProgramId,
+    ));
+
+    let mut undelegate_ix: Instruction = undelegate_market_instruction(
+        &test_fixture.market_fixture.key,
+        &test_fixture.payer(),
+        &test_fixture.sol_mint_fixture.key,
+        &test_fixture.usdc_mint_fixture.key,
+        &[],
+    );
+    undelegate_ix.accounts[5].pubkey = MOCK_MAGIC_PROGRAM_ID;
+    assert!(is_instruction_error(
+        send_for_payer(&test_fixture, undelegate_ix).await,
+        InstructionError::IncorrectProgramId,
+    ));
+
+    let mut commit_ix: Instruction = commit_market_instruction(
+        &test_fixture.market_fixture.key,
+        &test_fixture.payer(),
+        &test_fixture.sol_mint_fixture.key,
+        &test_fixture.usdc_mint_fixture.key,
+        &[],
+    );
+    commit_ix.accounts[5].pubkey = MOCK_MAGIC_PROGRAM_ID;
+    assert!(is_instruction_error(
+        send_for_payer(&test_fixture, commit_ix).await,
+        InstructionError::IncorrectProgramId,
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn delegation_rejects_non_market_account() -> anyhow::Result<()> {
+    let test_fixture: TestFixture = TestFixture::new().await;
+    let fake_market: Keypair = Keypair::new();
+
+    let delegate_ix: Instruction = delegate_market_instruction(
+        &fake_market.pubkey(),
+        &test_fixture.payer(),
+        &test_fixture.sol_mint_fixture.key,
+        &test_fixture.usdc_mint_fixture.key,
+        &[],
+        delegate_params(),
+    );
+    assert!(send_for_payer(&test_fixture, delegate_ix).await.is_err());
+
+    Ok(())
+}
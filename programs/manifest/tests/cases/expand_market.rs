@@ -0,0 +1,69 @@
+use manifest::{
+    program::batch_update::PlaceOrderParams,
+    state::{
+        OrderType, MARKET_BLOCK_SIZE, MARKET_MAX_EXPAND_BLOCKS, NO_EXPIRATION_LAST_VALID_SLOT,
+    },
+};
+use solana_program_test::tokio;
+
+use crate::{TestFixture, Token, SOL_UNIT_SIZE};
+
+#[tokio::test]
+async fn expand_market_multiple_blocks() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 50 * SOL_UNIT_SIZE).await?;
+
+    test_fixture.market_fixture.reload().await;
+    let initial_len: usize = test_fixture.market_fixture.market.dynamic.len();
+
+    test_fixture.expand_market(50).await?;
+    test_fixture.market_fixture.reload().await;
+    assert_eq!(
+        test_fixture.market_fixture.market.dynamic.len(),
+        initial_len + 50 * MARKET_BLOCK_SIZE
+    );
+
+    // Enough free blocks for 50 orders, so placing them does not grow the
+    // market any further.
+    for batch in 0..2 {
+        let orders: Vec<PlaceOrderParams> = (0..25)
+            .map(|i| {
+                PlaceOrderParams::new(
+                    SOL_UNIT_SIZE,
+                    10 + batch * 25 + i,
+                    -3,
+                    false,
+                    OrderType::Limit,
+                    NO_EXPIRATION_LAST_VALID_SLOT,
+                )
+            })
+            .collect();
+        test_fixture
+            .batch_update_for_keypair(None, vec![], orders, &test_fixture.payer_keypair())
+            .await?;
+    }
+    test_fixture.market_fixture.reload().await;
+    assert_eq!(
+        test_fixture.market_fixture.market.dynamic.len(),
+        initial_len + 50 * MARKET_BLOCK_SIZE
+    );
+    assert_eq!(
+        test_fixture.market_fixture.get_resting_orders().await.len(),
+        50
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn expand_market_too_many_blocks_fail() -> anyhow::Result<()> {
+    let test_fixture: TestFixture = TestFixture::new().await;
+    assert!(test_fixture
+        .expand_market(MARKET_MAX_EXPAND_BLOCKS + 1)
+        .await
+        .is_err());
+    test_fixture.expand_market(MARKET_MAX_EXPAND_BLOCKS).await?;
+
+    Ok(())
+}
@@ -10,8 +10,8 @@ use manifest::{
     },
     quantities::{GlobalAtoms, QuoteAtomsPerBaseAtom, WrapperU64},
     state::{
-        DynamicAccount, GlobalFixed, OrderType, RestingOrder, MARKET_BLOCK_SIZE, MAX_GLOBAL_SEATS,
-        NO_EXPIRATION_LAST_VALID_SLOT,
+        DynamicAccount, GlobalFixed, OrderType, RestingOrder, GAS_DEPOSIT_LAMPORTS,
+        MARKET_BLOCK_SIZE, MAX_GLOBAL_SEATS, NO_EXPIRATION_LAST_VALID_SLOT,
     },
 };
 use solana_program_test::tokio;
@@ -221,6 +221,63 @@ async fn global_cancel_order() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn global_cancel_all() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+
+    test_fixture.global_add_trader().await?;
+    test_fixture.global_deposit(1_000_000).await?;
+
+    test_fixture
+        .batch_update_with_global_for_keypair(
+            None,
+            vec![],
+            vec![PlaceOrderParams::new(
+                10,
+                1,
+                0,
+                true,
+                OrderType::Global,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )],
+            &test_fixture.payer_keypair().insecure_clone(),
+        )
+        .await?;
+
+    let global_lamports_before: u64 = test_fixture
+        .context
+        .borrow_mut()
+        .banks_client
+        .get_account(test_fixture.global_fixture.key)
+        .await?
+        .unwrap()
+        .lamports;
+
+    test_fixture
+        .cancel_all_for_keypair(None, &test_fixture.payer_keypair().insecure_clone())
+        .await?;
+
+    let orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(orders.len(), 0, "Did not cancel");
+
+    // Gas prepayment is returned to the trader.
+    let global_lamports_after: u64 = test_fixture
+        .context
+        .borrow_mut()
+        .banks_client
+        .get_account(test_fixture.global_fixture.key)
+        .await?
+        .unwrap()
+        .lamports;
+    assert_eq!(
+        global_lamports_before - global_lamports_after,
+        GAS_DEPOSIT_LAMPORTS
+    );
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn global_match_order() -> anyhow::Result<()> {
     let mut test_fixture: TestFixture = TestFixture::new().await;
@@ -1342,3 +1399,183 @@ async fn global_crash_without_global() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn market_global_match_order() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat().await?;
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .global_add_trader_for_keypair(&second_keypair)
+        .await?;
+    test_fixture
+        .global_deposit_for_keypair(&second_keypair, 1_000_000)
+        .await?;
+
+    // Part of the global balance goes to the market global, which is what
+    // trades while the market is on a rollup.
+    test_fixture.market_global_create().await?;
+    test_fixture
+        .market_global_deposit_for_keypair(&second_keypair, 600_000)
+        .await?;
+    test_fixture.global_fixture.reload().await;
+    assert_eq!(
+        test_fixture
+            .global_fixture
+            .global
+            .get_balance_atoms(&second_keypair.pubkey())
+            .as_u64(),
+        400_000
+    );
+    assert_eq!(
+        test_fixture
+            .get_market_global()
+            .await
+            .get_balance_atoms(&second_keypair.pubkey())
+            .as_u64(),
+        600_000
+    );
+
+    test_fixture
+        .batch_update_with_market_globals_for_keypair(
+            None,
+            vec![],
+            vec![PlaceOrderParams::new(
+                100,
+                11,
+                -1,
+                true,
+                OrderType::Global,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )],
+            &second_keypair,
+        )
+        .await?;
+    assert!(test_fixture.market_fixture.get_resting_orders().await[0].is_market_global());
+
+    test_fixture.deposit(Token::SOL, 1_000_000).await?;
+    test_fixture
+        .batch_update_with_market_globals_for_keypair(
+            None,
+            vec![],
+            vec![PlaceOrderParams::new(
+                100,
+                9,
+                -1,
+                false,
+                OrderType::Limit,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )],
+            &test_fixture.payer_keypair().insecure_clone(),
+        )
+        .await?;
+
+    test_fixture.market_fixture.reload().await;
+    let orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(orders.len(), 0, "Order still on orderbook");
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&second_keypair.pubkey())
+            .await,
+        100
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&test_fixture.payer())
+            .await,
+        110
+    );
+    assert_eq!(
+        test_fixture
+            .get_market_global()
+            .await
+            .get_balance_atoms(&second_keypair.pubkey())
+            .as_u64(),
+        600_000 - 110
+    );
+
+    // The quote never left the market vault, so it can be withdrawn, and the
+    // rest of the market global balance goes back to the global.
+    test_fixture.withdraw(Token::USDC, 110).await?;
+    test_fixture
+        .market_global_withdraw_for_keypair(&second_keypair, 600_000 - 110)
+        .await?;
+    test_fixture.global_fixture.reload().await;
+    assert_eq!(
+        test_fixture
+            .global_fixture
+            .global
+            .get_balance_atoms(&second_keypair.pubkey())
+            .as_u64(),
+        1_000_000 - 110
+    );
+    assert_eq!(
+        test_fixture
+            .get_market_global()
+            .await
+            .get_balance_atoms(&second_keypair.pubkey()),
+        GlobalAtoms::ZERO
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn market_global_cannot_stand_in_for_global() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let payer_keypair: Keypair = test_fixture.payer_keypair().insecure_clone();
+    test_fixture.claim_seat().await?;
+    test_fixture.global_add_trader().await?;
+    test_fixture.global_deposit(1_000_000).await?;
+    test_fixture.market_global_create().await?;
+    test_fixture
+        .market_global_deposit_for_keypair(&payer_keypair, 500_000)
+        .await?;
+
+    test_fixture
+        .batch_update_with_global_for_keypair(
+            None,
+            vec![],
+            vec![PlaceOrderParams::new(
+                10,
+                1,
+                0,
+                true,
+                OrderType::Global,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )],
+            &payer_keypair,
+        )
+        .await?;
+    let orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert!(!orders[0].is_market_global());
+
+    // The gas prepayment went to the global, so the market global cannot
+    // refund it.
+    assert!(test_fixture
+        .batch_update_with_market_globals_for_keypair(
+            None,
+            vec![CancelOrderParams::new(0)],
+            vec![],
+            &payer_keypair,
+        )
+        .await
+        .is_err());
+    test_fixture
+        .batch_update_with_global_for_keypair(
+            None,
+            vec![CancelOrderParams::new(0)],
+            vec![],
+            &payer_keypair,
+        )
+        .await?;
+    assert_eq!(
+        test_fixture.market_fixture.get_resting_orders().await.len(),
+        0
+    );
+
+    Ok(())
+}
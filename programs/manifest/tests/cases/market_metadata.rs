@@ -0,0 +1,188 @@
+use manifest::{
+    program::init_market_metadata::MarketMetadataParams,
+    state::{MarketMetadata, MARKET_METADATA_NAME_LEN},
+};
+use solana_program_test::tokio;
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+use crate::TestFixture;
+
+#[tokio::test]
+async fn market_metadata_update_and_transfer_authority() -> anyhow::Result<()> {
+    let test_fixture: TestFixture = TestFixture::new().await;
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+
+    test_fixture
+        .set_market_authority(&test_fixture.payer())
+        .await?;
+    test_fixture
+        .init_market_metadata(MarketMetadataParams::new(
+            "SOL/USDC",
+            "SOL",
+            "https://example.com/sol-usdc.json",
+            3,
+            2,
+        ))
+        .await?;
+    let metadata: MarketMetadata = test_fixture.get_market_metadata().await;
+    assert_eq!(*metadata.get_market(), test_fixture.market_fixture.key);
+    assert_eq!(*metadata.get_authority(), test_fixture.payer());
+    assert_eq!(metadata.get_name(), "SOL/USDC");
+    assert_eq!(metadata.get_symbol(), "SOL");
+    assert_eq!(metadata.get_uri(), "https://example.com/sol-usdc.json");
+    assert_eq!(metadata.get_price_display_decimals(), 3);
+    assert_eq!(metadata.get_size_display_decimals(), 2);
+
+    // Only the authority can update.
+    assert!(test_fixture
+        .update_market_metadata_for_keypair(
+            MarketMetadataParams::new("Scam", "", "", 0, 0),
+            &second_keypair,
+        )
+        .await
+        .is_err());
+    test_fixture
+        .update_market_metadata_for_keypair(
+            MarketMetadataParams::new("Wrapped SOL/USDC", "WSOL", "", 4, 1),
+            &test_fixture.payer_keypair(),
+        )
+        .await?;
+    let metadata: MarketMetadata = test_fixture.get_market_metadata().await;
+    assert_eq!(metadata.get_name(), "Wrapped SOL/USDC");
+    assert_eq!(metadata.get_symbol(), "WSOL");
+    assert_eq!(metadata.get_uri(), "");
+    assert_eq!(metadata.get_price_display_decimals(), 4);
+
+    // After a transfer, the old authority is locked out.
+    assert!(test_fixture
+        .transfer_market_metadata_authority_for_keypair(&second_keypair.pubkey(), &second_keypair)
+        .await
+        .is_err());
+    test_fixture
+        .transfer_market_metadata_authority_for_keypair(
+            &second_keypair.pubkey(),
+            &test_fixture.payer_keypair(),
+        )
+        .await?;
+    assert_eq!(
+        *test_fixture.get_market_metadata().await.get_authority(),
+        second_keypair.pubkey()
+    );
+    assert!(test_fixture
+        .update_market_metadata_for_keypair(
+            MarketMetadataParams::new("SOL/USDC", "SOL", "", 3, 2),
+            &test_fixture.payer_keypair(),
+        )
+        .await
+        .is_err());
+    test_fixture
+        .update_market_metadata_for_keypair(
+            MarketMetadataParams::new("SOL/USDC", "SOL", "", 3, 2),
+            &second_keypair,
+        )
+        .await?;
+    assert_eq!(
+        test_fixture.get_market_metadata().await.get_name(),
+        "SOL/USDC"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn market_metadata_init_fail() -> anyhow::Result<()> {
+    let test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture
+        .set_market_authority(&test_fixture.payer())
+        .await?;
+
+    // Name does not fit.
+    assert!(test_fixture
+        .init_market_metadata(MarketMetadataParams::new(
+            &"a".repeat(MARKET_METADATA_NAME_LEN + 1),
+            "",
+            "",
+            0,
+            0,
+        ))
+        .await
+        .is_err());
+
+    test_fixture
+        .init_market_metadata(MarketMetadataParams::default())
+        .await?;
+    // Can only init once.
+    assert!(test_fixture
+        .init_market_metadata(MarketMetadataParams::new("SOL/USDC", "SOL", "", 3, 2))
+        .await
+        .is_err());
+    assert_eq!(test_fixture.get_market_metadata().await.get_name(), "");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn market_metadata_init_only_market_authority() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+
+    // No authority is recorded yet and the market keypair stands in for it,
+    // so nobody can take the metadata authority first.
+    test_fixture.market_fixture.reload().await;
+    assert_eq!(
+        test_fixture.market_fixture.market.get_market_authority(),
+        None
+    );
+    assert!(test_fixture
+        .init_market_metadata(MarketMetadataParams::default())
+        .await
+        .is_err());
+    assert!(test_fixture
+        .set_market_authority_for_keypair(&second_keypair.pubkey(), &second_keypair)
+        .await
+        .is_err());
+
+    test_fixture
+        .set_market_authority(&test_fixture.payer())
+        .await?;
+    test_fixture.market_fixture.reload().await;
+    assert_eq!(
+        test_fixture.market_fixture.market.get_market_authority(),
+        Some(&test_fixture.payer())
+    );
+
+    // Once set, only the market authority can hand it over.
+    assert!(test_fixture
+        .set_market_authority(&second_keypair.pubkey())
+        .await
+        .is_err());
+    assert!(test_fixture
+        .set_market_authority_for_keypair(&second_keypair.pubkey(), &second_keypair)
+        .await
+        .is_err());
+    test_fixture
+        .set_market_authority_for_keypair(&second_keypair.pubkey(), &test_fixture.payer_keypair())
+        .await?;
+    test_fixture.market_fixture.reload().await;
+    assert_eq!(
+        test_fixture.market_fixture.market.get_market_authority(),
+        Some(&second_keypair.pubkey())
+    );
+
+    assert!(test_fixture
+        .init_market_metadata(MarketMetadataParams::default())
+        .await
+        .is_err());
+    test_fixture
+        .init_market_metadata_for_keypair(
+            MarketMetadataParams::new("SOL/USDC", "SOL", "", 3, 2),
+            &second_keypair,
+        )
+        .await?;
+    assert_eq!(
+        *test_fixture.get_market_metadata().await.get_authority(),
+        second_keypair.pubkey()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use manifest::{
+    quantities::{BaseAtoms, WrapperU64},
+    state::{MarketStats, MarketValue, OrderType, NO_EXPIRATION_LAST_VALID_SLOT},
+};
+use solana_program_test::tokio;
+use solana_sdk::signature::Keypair;
+
+use crate::{price, Side, TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE};
+
+#[tokio::test]
+async fn market_stats_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.market_fixture.reload().await;
+    assert!(test_fixture
+        .market_fixture
+        .market
+        .get_market_stats()
+        .is_none());
+
+    test_fixture.init_market_stats().await?;
+    // Only once per market.
+    assert!(test_fixture.init_market_stats().await.is_err());
+
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, 2 * SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+    for price_mantissa in [10, 12] {
+        test_fixture
+            .place_order_for_keypair(
+                Side::Ask,
+                SOL_UNIT_SIZE,
+                price_mantissa,
+                -3,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+                OrderType::Limit,
+                &second_keypair,
+            )
+            .await?;
+    }
+
+    test_fixture.claim_seat().await?;
+    test_fixture
+        .deposit(Token::USDC, 30 * USDC_UNIT_SIZE)
+        .await?;
+    test_fixture
+        .place_order(
+            Side::Bid,
+            2 * SOL_UNIT_SIZE,
+            12,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+    test_fixture
+        .place_order(
+            Side::Bid,
+            SOL_UNIT_SIZE,
+            9,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+
+    test_fixture.market_fixture.reload().await;
+    let market: &MarketValue = &test_fixture.market_fixture.market;
+    let stats: &MarketStats = market.get_market_stats().unwrap();
+    assert_eq!(stats.get_epoch_open_price(), price(10, -3));
+    assert_eq!(stats.get_epoch_high_price(), price(12, -3));
+    assert_eq!(stats.get_epoch_low_price(), price(10, -3));
+    assert_eq!(stats.get_base_volume(), BaseAtoms::new(2 * SOL_UNIT_SIZE));
+    assert_eq!(market.fixed.get_last_trade_price(), price(12, -3));
+    assert_eq!(market.get_best_bid_price(), Some(price(9, -3)));
+    assert_eq!(market.get_best_ask_price(), None);
+
+    // Trading against your own bid does not count.
+    test_fixture.deposit(Token::SOL, SOL_UNIT_SIZE).await?;
+    test_fixture
+        .place_order(
+            Side::Ask,
+            SOL_UNIT_SIZE,
+            9,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+        )
+        .await?;
+
+    test_fixture.market_fixture.reload().await;
+    let market: &MarketValue = &test_fixture.market_fixture.market;
+    let stats: &MarketStats = market.get_market_stats().unwrap();
+    assert_eq!(stats.get_epoch_low_price(), price(10, -3));
+    assert_eq!(stats.get_base_volume(), BaseAtoms::new(2 * SOL_UNIT_SIZE));
+    assert_eq!(market.fixed.get_last_trade_price(), price(12, -3));
+    assert_eq!(market.get_best_bid_price(), None);
+
+    Ok(())
+}
@@ -1,13 +1,26 @@
 pub mod batch_update;
 pub mod cancel_order;
 pub mod claim_seat;
+pub mod clean_expired;
+pub mod compact_market;
 pub mod create_market;
+pub mod delegation;
+pub mod delegation_instructions;
 pub mod deposit;
+pub mod expand_market;
 pub mod global;
 pub mod loaders;
+pub mod market_metadata;
+pub mod market_stats;
 pub mod matching;
+pub mod oracle_price_band;
+pub mod orderbook_snapshot;
+pub mod pegged_order;
 pub mod place_order;
+pub mod release_seat;
 pub mod reverse;
 pub mod swap;
 pub mod token22;
+pub mod trigger_order;
+pub mod twap;
 pub mod withdraw;
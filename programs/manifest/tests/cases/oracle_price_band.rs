@@ -0,0 +1,143 @@
+use manifest::{
+    program::swap::OraclePriceBandParams,
+    state::{constants::NO_EXPIRATION_LAST_VALID_SLOT, OrderType},
+};
+use solana_program_test::tokio;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+use crate::{MockOracleFixture, Side, TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE};
+
+// Asks for 1 SOL at 10 USDC and 1 SOL at 20 USDC.
+async fn place_asks(test_fixture: &mut TestFixture) -> anyhow::Result<()> {
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, 2 * SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+    for price_mantissa in [1, 2] {
+        test_fixture
+            .place_order_for_keypair(
+                Side::Ask,
+                SOL_UNIT_SIZE,
+                price_mantissa,
+                -2,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+                OrderType::Limit,
+                &second_keypair,
+            )
+            .await?;
+    }
+    test_fixture
+        .usdc_mint_fixture
+        .mint_to(&test_fixture.payer_usdc_fixture.key, 40 * USDC_UNIT_SIZE)
+        .await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn swap_oracle_price_band_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    place_asks(&mut test_fixture).await?;
+
+    // Pyth at 10.00 USDC per SOL.
+    let oracle: MockOracleFixture = MockOracleFixture::new(test_fixture.context.clone());
+    let slot: u64 = test_fixture.get_slot().await;
+    oracle.set_pyth_price(1_000, -2, slot);
+    let band: OraclePriceBandParams = OraclePriceBandParams::new(500, 10);
+
+    // Sweeping both asks would fill at 20, far outside of 5%.
+    assert!(test_fixture
+        .swap_v2_with_oracle_price_band(30 * USDC_UNIT_SIZE, 0, false, true, &oracle.key, band)
+        .await
+        .is_err());
+    assert_eq!(test_fixture.payer_sol_fixture.balance_atoms().await, 0);
+
+    // Only the ask at the oracle price.
+    test_fixture
+        .swap_v2_with_oracle_price_band(10 * USDC_UNIT_SIZE, 0, false, true, &oracle.key, band)
+        .await?;
+    assert_eq!(
+        test_fixture.payer_sol_fixture.balance_atoms().await,
+        SOL_UNIT_SIZE
+    );
+
+    // Switchboard moves to 20.00 and the remaining ask is in the band.
+    let oracle: MockOracleFixture = MockOracleFixture::new(test_fixture.context.clone());
+    let slot: u64 = test_fixture.get_slot().await;
+    oracle.set_switchboard_price(2_000, 2, slot);
+    test_fixture
+        .swap_v2_with_oracle_price_band(
+            20 * USDC_UNIT_SIZE,
+            0,
+            false,
+            true,
+            &oracle.key,
+            OraclePriceBandParams::new(100, 10),
+        )
+        .await?;
+    assert_eq!(
+        test_fixture.payer_sol_fixture.balance_atoms().await,
+        2 * SOL_UNIT_SIZE
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn swap_oracle_price_band_fail_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    place_asks(&mut test_fixture).await?;
+
+    let oracle: MockOracleFixture = MockOracleFixture::new(test_fixture.context.clone());
+    let slot: u64 = test_fixture.get_slot().await;
+    oracle.set_pyth_price(1_000, -2, slot);
+    test_fixture.advance_time_seconds(100).await;
+
+    // Stale oracle.
+    assert!(test_fixture
+        .swap_v2_with_oracle_price_band(
+            10 * USDC_UNIT_SIZE,
+            0,
+            false,
+            true,
+            &oracle.key,
+            OraclePriceBandParams::new(500, 10),
+        )
+        .await
+        .is_err());
+
+    // Not an oracle.
+    let not_oracle_key: Pubkey = test_fixture.second_keypair.pubkey();
+    assert!(test_fixture
+        .swap_v2_with_oracle_price_band(
+            10 * USDC_UNIT_SIZE,
+            0,
+            false,
+            true,
+            &not_oracle_key,
+            OraclePriceBandParams::new(500, 1_000),
+        )
+        .await
+        .is_err());
+
+    // Same oracle with a looser staleness bound goes through.
+    test_fixture
+        .swap_v2_with_oracle_price_band(
+            10 * USDC_UNIT_SIZE,
+            0,
+            false,
+            true,
+            &oracle.key,
+            OraclePriceBandParams::new(500, 1_000),
+        )
+        .await?;
+    assert_eq!(
+        test_fixture.payer_sol_fixture.balance_atoms().await,
+        SOL_UNIT_SIZE
+    );
+
+    Ok(())
+}
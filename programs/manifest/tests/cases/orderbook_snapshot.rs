@@ -0,0 +1,193 @@
+use manifest::{
+    program::batch_update::PlaceOrderParams,
+    quantities::{BaseAtoms, QuoteAtomsPerBaseAtom, WrapperU64},
+    state::{GlobalRef, GlobalValue, L2Level, L3Order, OrderType, NO_EXPIRATION_LAST_VALID_SLOT},
+};
+use solana_program_test::tokio;
+
+use crate::{Side, TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE};
+
+#[tokio::test]
+async fn orderbook_snapshot_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 10 * SOL_UNIT_SIZE).await?;
+    test_fixture
+        .deposit(Token::USDC, 10 * USDC_UNIT_SIZE)
+        .await?;
+
+    test_fixture
+        .place_order(
+            Side::Ask,
+            1 * SOL_UNIT_SIZE,
+            2,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+    test_fixture
+        .place_order(
+            Side::Ask,
+            2 * SOL_UNIT_SIZE,
+            2,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+    test_fixture
+        .place_order(Side::Ask, 1 * SOL_UNIT_SIZE, 3, -3, 1_000, OrderType::Limit)
+        .await?;
+    test_fixture
+        .place_order(
+            Side::Bid,
+            1 * SOL_UNIT_SIZE,
+            1,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+
+    test_fixture.market_fixture.reload().await;
+    let now_unix_timestamp: u32 = test_fixture.get_unix_timestamp().await;
+    let globals: [Option<GlobalRef>; 2] = [None, None];
+
+    let asks: Vec<L3Order> =
+        test_fixture
+            .market_fixture
+            .market
+            .get_l3_snapshot(false, 0, now_unix_timestamp, &globals);
+    assert_eq!(asks.len(), 3);
+    assert_eq!(asks[0].sequence_number, 0);
+    assert_eq!(asks[0].trader, test_fixture.payer());
+    assert_eq!(asks[0].order_type, OrderType::Limit);
+    assert_eq!(asks[1].sequence_number, 1);
+    assert_eq!(asks[2].last_valid_slot, 1_000);
+
+    // The third ask is expired after slot 1_000.
+    let asks: Vec<L3Order> = test_fixture.market_fixture.market.get_l3_snapshot(
+        false,
+        1_001,
+        now_unix_timestamp,
+        &globals,
+    );
+    assert_eq!(asks.len(), 2);
+
+    let levels: Vec<L2Level> = test_fixture.market_fixture.market.get_l2_snapshot(
+        false,
+        5,
+        0,
+        now_unix_timestamp,
+        &globals,
+    );
+    assert_eq!(
+        levels,
+        vec![
+            L2Level {
+                price: QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(2, -3).unwrap(),
+                num_base_atoms: BaseAtoms::new(3 * SOL_UNIT_SIZE),
+                num_orders: 2,
+            },
+            L2Level {
+                price: QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(3, -3).unwrap(),
+                num_base_atoms: BaseAtoms::new(1 * SOL_UNIT_SIZE),
+                num_orders: 1,
+            },
+        ]
+    );
+
+    // Number of levels is capped.
+    let levels: Vec<L2Level> = test_fixture.market_fixture.market.get_l2_snapshot(
+        false,
+        1,
+        0,
+        now_unix_timestamp,
+        &globals,
+    );
+    assert_eq!(levels.len(), 1);
+
+    let bids: Vec<L2Level> = test_fixture.market_fixture.market.get_l2_snapshot(
+        true,
+        5,
+        0,
+        now_unix_timestamp,
+        &globals,
+    );
+    assert_eq!(bids.len(), 1);
+    assert_eq!(bids[0].num_base_atoms, BaseAtoms::new(1 * SOL_UNIT_SIZE));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn orderbook_snapshot_unbacked_global_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.global_add_trader().await?;
+    test_fixture.global_deposit(1_000_000).await?;
+
+    test_fixture
+        .batch_update_with_global_for_keypair(
+            None,
+            vec![],
+            vec![PlaceOrderParams::new(
+                10,
+                1,
+                0,
+                true,
+                OrderType::Global,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )],
+            &test_fixture.payer_keypair().insecure_clone(),
+        )
+        .await?;
+    test_fixture.global_withdraw(1_000_000).await?;
+
+    test_fixture.market_fixture.reload().await;
+    test_fixture.global_fixture.reload().await;
+    let now_unix_timestamp: u32 = test_fixture.get_unix_timestamp().await;
+
+    // Without the global, the order is reported as is.
+    let bids: Vec<L3Order> = test_fixture.market_fixture.market.get_l3_snapshot(
+        true,
+        0,
+        now_unix_timestamp,
+        &[None, None],
+    );
+    assert_eq!(bids.len(), 1);
+    assert_eq!(bids[0].order_type, OrderType::Global);
+
+    // With the quote global, it is skipped because it is no longer backed.
+    let quote_global: GlobalRef = GlobalRef {
+        fixed: &test_fixture.global_fixture.global.fixed,
+        dynamic: &test_fixture.global_fixture.global.dynamic,
+    };
+    let bids: Vec<L3Order> = test_fixture.market_fixture.market.get_l3_snapshot(
+        true,
+        0,
+        now_unix_timestamp,
+        &[None, Some(quote_global)],
+    );
+    assert_eq!(bids.len(), 0);
+
+    // The market global does not back the order, so it is reported as is.
+    test_fixture.market_global_create().await?;
+    let market_global: GlobalValue = test_fixture.get_market_global().await;
+    let bids: Vec<L3Order> = test_fixture.market_fixture.market.get_l3_snapshot(
+        true,
+        0,
+        now_unix_timestamp,
+        &[
+            None,
+            Some(GlobalRef {
+                fixed: &market_global.fixed,
+                dynamic: &market_global.dynamic,
+            }),
+        ],
+    );
+    assert_eq!(bids.len(), 1);
+
+    Ok(())
+}
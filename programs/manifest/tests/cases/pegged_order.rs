@@ -0,0 +1,407 @@
+use manifest::{
+    program::{
+        batch_update::{CancelOrderParams, PlaceOrderParams},
+        create_market::CreateMarketParams,
+        swap::OraclePriceBandParams,
+    },
+    quantities::QuoteAtomsPerBaseAtom,
+    state::{constants::NO_EXPIRATION_LAST_VALID_SLOT, OrderType, RestingOrder},
+};
+use solana_program_test::tokio;
+use solana_sdk::signature::{Keypair, Signer};
+
+use crate::{price, MockOracleFixture, TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE};
+
+// Switches the fixture to a market that pegs to a Pyth oracle at 10.00 USDC
+// per SOL.
+async fn create_pegged_market(test_fixture: &mut TestFixture) -> MockOracleFixture {
+    let oracle: MockOracleFixture = MockOracleFixture::new(test_fixture.context.clone());
+    oracle.set_pyth_price(1_000, -2, test_fixture.get_slot().await);
+    test_fixture.market_fixture.key = test_fixture
+        .create_new_market_with_params(
+            &test_fixture.sol_mint_fixture.key,
+            &test_fixture.usdc_mint_fixture.key,
+            CreateMarketParams::new(0, 0, 0).with_peg_oracle(oracle.key, 10),
+        )
+        .await
+        .unwrap();
+    test_fixture.market_fixture.reload().await;
+    oracle
+}
+
+#[tokio::test]
+async fn pegged_order_reprice_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let oracle: MockOracleFixture = create_pegged_market(&mut test_fixture).await;
+    assert_eq!(
+        *test_fixture
+            .market_fixture
+            .market
+            .get_peg_config()
+            .unwrap()
+            .get_oracle(),
+        oracle.key
+    );
+
+    let payer: Keypair = test_fixture.payer_keypair();
+    test_fixture.claim_seat().await?;
+    test_fixture
+        .deposit(Token::USDC, 20 * USDC_UNIT_SIZE)
+        .await?;
+
+    // Bid 1.00 under the oracle, paying up to 11.00.
+    let pegged_bid: PlaceOrderParams = PlaceOrderParams::new(
+        SOL_UNIT_SIZE,
+        11,
+        -3,
+        true,
+        OrderType::Pegged,
+        NO_EXPIRATION_LAST_VALID_SLOT,
+    )
+    .with_peg_offset(-1, -3);
+    assert!(test_fixture
+        .batch_update_for_keypair(None, vec![], vec![pegged_bid.clone()], &payer)
+        .await
+        .is_err());
+    test_fixture
+        .batch_update_with_peg_oracle_for_keypair(vec![], vec![pegged_bid], &oracle.key, &payer)
+        .await?;
+    let resting_order: RestingOrder = test_fixture.market_fixture.get_resting_orders().await[0];
+    assert_eq!(resting_order.get_price(), price(9, -3));
+    // Backed at the limit so it can follow the oracle up to it.
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&payer.pubkey())
+            .await,
+        9 * USDC_UNIT_SIZE
+    );
+
+    // Oracle goes up and the bid follows, until the limit.
+    let slot: u64 = test_fixture.get_slot().await;
+    oracle.set_pyth_price(1_300, -2, slot);
+    test_fixture
+        .batch_update_with_peg_oracle_for_keypair(vec![], vec![], &oracle.key, &payer)
+        .await?;
+    assert_eq!(
+        test_fixture.market_fixture.get_resting_orders().await[0].get_price(),
+        price(11, -3)
+    );
+    oracle.set_pyth_price(1_050, -2, slot);
+    test_fixture
+        .batch_update_with_peg_oracle_for_keypair(vec![], vec![], &oracle.key, &payer)
+        .await?;
+    assert_eq!(
+        test_fixture.market_fixture.get_resting_orders().await[0].get_price(),
+        price(95, -4)
+    );
+
+    // Ask at 9.00 matches the bid at its repriced 9.50.
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+    test_fixture
+        .batch_update_with_peg_oracle_for_keypair(
+            vec![],
+            vec![PlaceOrderParams::new(
+                SOL_UNIT_SIZE,
+                9,
+                -3,
+                false,
+                OrderType::Limit,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )],
+            &oracle.key,
+            &second_keypair,
+        )
+        .await?;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&second_keypair.pubkey())
+            .await,
+        9_500_000
+    );
+    // Maker gets back what the limit reserved over the fill.
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer.pubkey())
+            .await,
+        SOL_UNIT_SIZE
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&payer.pubkey())
+            .await,
+        10_500_000
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn pegged_order_skipped_without_oracle_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let oracle: MockOracleFixture = create_pegged_market(&mut test_fixture).await;
+
+    // Ask 1.00 over the oracle, selling down to 10.00.
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+    test_fixture
+        .batch_update_with_peg_oracle_for_keypair(
+            vec![],
+            vec![PlaceOrderParams::new(
+                SOL_UNIT_SIZE,
+                10,
+                -3,
+                false,
+                OrderType::Pegged,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )
+            .with_peg_offset(1, -3)],
+            &oracle.key,
+            &second_keypair,
+        )
+        .await?;
+    assert_eq!(
+        test_fixture.market_fixture.get_resting_orders().await[0].get_price(),
+        price(11, -3)
+    );
+    test_fixture
+        .usdc_mint_fixture
+        .mint_to(&test_fixture.payer_usdc_fixture.key, 20 * USDC_UNIT_SIZE)
+        .await;
+
+    // A later slot needs a reprice before the ask can be matched, so the swap
+    // walks past it instead of failing.
+    test_fixture.advance_time_seconds(100).await;
+    test_fixture
+        .swap_v2(20 * USDC_UNIT_SIZE, 0, false, true)
+        .await?;
+    assert_eq!(test_fixture.payer_sol_fixture.balance_atoms().await, 0);
+    assert_eq!(
+        test_fixture.market_fixture.get_resting_orders().await.len(),
+        1
+    );
+    // Snapshots leave it out the same way.
+    test_fixture.market_fixture.reload().await;
+    assert!(test_fixture
+        .market_fixture
+        .market
+        .get_l3_snapshot(
+            false,
+            test_fixture.get_slot().await as u32,
+            test_fixture.get_unix_timestamp().await,
+            &[None, None],
+        )
+        .is_empty());
+    // The oracle is too stale to reprice to. Only the price band, which needs
+    // a fresh oracle, fails the swap.
+    assert!(test_fixture
+        .swap_v2_with_oracle_price_band(
+            20 * USDC_UNIT_SIZE,
+            0,
+            false,
+            true,
+            &oracle.key,
+            OraclePriceBandParams::new(1_000, 1_000),
+        )
+        .await
+        .is_err());
+    assert_eq!(test_fixture.payer_sol_fixture.balance_atoms().await, 0);
+
+    oracle.set_pyth_price(1_050, -2, test_fixture.get_slot().await);
+    test_fixture
+        .swap_v2_with_oracle_price_band(
+            20 * USDC_UNIT_SIZE,
+            0,
+            false,
+            true,
+            &oracle.key,
+            OraclePriceBandParams::new(1_000, 10),
+        )
+        .await?;
+    assert_eq!(
+        test_fixture.payer_sol_fixture.balance_atoms().await,
+        SOL_UNIT_SIZE
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&second_keypair.pubkey())
+            .await,
+        11_500_000
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn pegged_order_stale_not_crossed_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let oracle: MockOracleFixture = create_pegged_market(&mut test_fixture).await;
+    let payer: Keypair = test_fixture.payer_keypair();
+
+    // Ask 1.00 over the oracle at 11.00.
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+    test_fixture
+        .batch_update_with_peg_oracle_for_keypair(
+            vec![],
+            vec![PlaceOrderParams::new(
+                SOL_UNIT_SIZE,
+                10,
+                -3,
+                false,
+                OrderType::Pegged,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )
+            .with_peg_offset(1, -3)],
+            &oracle.key,
+            &second_keypair,
+        )
+        .await?;
+    test_fixture.claim_seat().await?;
+    test_fixture
+        .deposit(Token::USDC, 30 * USDC_UNIT_SIZE)
+        .await?;
+
+    // Without a reprice the ask is walked past. A limit bid through it and a
+    // post only bid that would have failed on it both rest behind it, so the
+    // book does not cross.
+    test_fixture.advance_time_seconds(100).await;
+    test_fixture
+        .batch_update_for_keypair(
+            None,
+            vec![],
+            vec![
+                PlaceOrderParams::new(
+                    SOL_UNIT_SIZE,
+                    12,
+                    -3,
+                    true,
+                    OrderType::Limit,
+                    NO_EXPIRATION_LAST_VALID_SLOT,
+                ),
+                PlaceOrderParams::new(
+                    SOL_UNIT_SIZE,
+                    115,
+                    -4,
+                    true,
+                    OrderType::PostOnly,
+                    NO_EXPIRATION_LAST_VALID_SLOT,
+                ),
+            ],
+            &payer,
+        )
+        .await?;
+    let resting_orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(resting_orders.len(), 3);
+    let ask_price: QuoteAtomsPerBaseAtom = resting_orders
+        .iter()
+        .find(|order| !order.get_is_bid())
+        .unwrap()
+        .get_price();
+    assert_eq!(ask_price, price(11, -3));
+    assert!(resting_orders
+        .iter()
+        .filter(|order| order.get_is_bid())
+        .all(|order| order.get_price() < ask_price));
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer.pubkey())
+            .await,
+        0
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn pegged_order_cancel_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let oracle: MockOracleFixture = create_pegged_market(&mut test_fixture).await;
+
+    let payer: Keypair = test_fixture.payer_keypair();
+    test_fixture.claim_seat().await?;
+    test_fixture
+        .deposit(Token::USDC, 20 * USDC_UNIT_SIZE)
+        .await?;
+    test_fixture
+        .batch_update_with_peg_oracle_for_keypair(
+            vec![],
+            vec![PlaceOrderParams::new(
+                SOL_UNIT_SIZE,
+                11,
+                -3,
+                true,
+                OrderType::Pegged,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )
+            .with_peg_offset(-1, -3)],
+            &oracle.key,
+            &payer,
+        )
+        .await?;
+
+    // Cancels do not need the oracle and refund what the limit reserved.
+    let resting_order: RestingOrder = test_fixture.market_fixture.get_resting_orders().await[0];
+    test_fixture.advance_time_seconds(100).await;
+    test_fixture
+        .batch_update_for_keypair(
+            None,
+            vec![CancelOrderParams::new(resting_order.get_sequence_number())],
+            vec![],
+            &payer,
+        )
+        .await?;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&payer.pubkey())
+            .await,
+        20 * USDC_UNIT_SIZE
+    );
+
+    // Markets without a peg oracle do not take pegged orders.
+    test_fixture.market_fixture.key = test_fixture
+        .create_new_market(
+            &test_fixture.sol_mint_fixture.key,
+            &test_fixture.usdc_mint_fixture.key,
+        )
+        .await?;
+    test_fixture.claim_seat().await?;
+    test_fixture
+        .deposit(Token::USDC, 20 * USDC_UNIT_SIZE)
+        .await?;
+    assert!(test_fixture
+        .batch_update_with_peg_oracle_for_keypair(
+            vec![],
+            vec![PlaceOrderParams::new(
+                SOL_UNIT_SIZE,
+                11,
+                -3,
+                true,
+                OrderType::Pegged,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )
+            .with_peg_offset(-1, -3)],
+            &oracle.key,
+            &payer,
+        )
+        .await
+        .is_err());
+
+    Ok(())
+}
@@ -2,15 +2,19 @@ use std::u64;
 
 use hypertree::HyperTreeValueIteratorTrait;
 use manifest::{
+    program::batch_update::PlaceOrderParams,
     quantities::{QuoteAtomsPerBaseAtom, WrapperU64},
     state::{
         constants::{MARKET_BLOCK_SIZE, MARKET_FIXED_SIZE, NO_EXPIRATION_LAST_VALID_SLOT},
-        OrderType, RestingOrder,
+        OrderType, RestingOrder, SelfTradeBehavior,
     },
     validation::get_vault_address,
 };
 use solana_program_test::tokio;
-use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
 
 use crate::{Side, TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE};
 
@@ -427,6 +431,114 @@ async fn match_limit_orders_fail_expired_test() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn match_limit_orders_fail_expired_unix_timestamp_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 2 * SOL_UNIT_SIZE).await?;
+
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::USDC, 4_000 * USDC_UNIT_SIZE, &second_keypair)
+        .await?;
+
+    let now_unix_timestamp: u32 = test_fixture.get_unix_timestamp().await;
+    test_fixture
+        .batch_update_for_keypair(
+            None,
+            vec![],
+            vec![
+                PlaceOrderParams::new(
+                    1 * SOL_UNIT_SIZE,
+                    1,
+                    0,
+                    false,
+                    OrderType::Limit,
+                    NO_EXPIRATION_LAST_VALID_SLOT,
+                )
+                .with_last_valid_unix_timestamp(now_unix_timestamp + 100),
+                PlaceOrderParams::new(
+                    1 * SOL_UNIT_SIZE,
+                    2,
+                    0,
+                    false,
+                    OrderType::Limit,
+                    NO_EXPIRATION_LAST_VALID_SLOT,
+                ),
+            ],
+            &test_fixture.payer_keypair(),
+        )
+        .await?;
+    let orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert!(orders
+        .iter()
+        .any(|order| order.get_last_valid_unix_timestamp() == now_unix_timestamp + 100));
+
+    // Expire the order @1
+    test_fixture.advance_time_seconds(1_000).await;
+
+    // Should only match the order @2.
+    test_fixture
+        .place_order_for_keypair(
+            Side::Bid,
+            2 * SOL_UNIT_SIZE,
+            2,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+            &second_keypair,
+        )
+        .await?;
+
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&test_fixture.payer())
+            .await,
+        2_000 * USDC_UNIT_SIZE
+    );
+    // Expired order was returned to the maker.
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&test_fixture.payer())
+            .await,
+        1 * SOL_UNIT_SIZE
+    );
+    let orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(orders.len(), 1);
+    assert!(orders[0].get_is_bid());
+    Ok(())
+}
+
+#[tokio::test]
+async fn place_order_already_expired_unix_timestamp_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 1 * SOL_UNIT_SIZE).await?;
+
+    let now_unix_timestamp: u32 = test_fixture.get_unix_timestamp().await;
+    assert!(test_fixture
+        .batch_update_for_keypair(
+            None,
+            vec![],
+            vec![PlaceOrderParams::new(
+                1 * SOL_UNIT_SIZE,
+                1,
+                0,
+                false,
+                OrderType::Limit,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )
+            .with_last_valid_unix_timestamp(now_unix_timestamp)],
+            &test_fixture.payer_keypair(),
+        )
+        .await
+        .is_err());
+    Ok(())
+}
+
 #[tokio::test]
 async fn match_limit_orders_partial_match_price_test() -> anyhow::Result<()> {
     let mut test_fixture: TestFixture = TestFixture::new().await;
@@ -711,6 +823,62 @@ async fn post_only_fail_test() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn post_only_slide_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+
+    // Ask for 2@10
+    test_fixture.deposit(Token::SOL, 20 * SOL_UNIT_SIZE).await?;
+    test_fixture
+        .place_order(
+            Side::Ask,
+            2 * SOL_UNIT_SIZE,
+            10,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::USDC, 20_000 * USDC_UNIT_SIZE, &second_keypair)
+        .await?;
+
+    // Would cross at 10, so it slides to rest one tick below.
+    test_fixture
+        .place_order_for_keypair(
+            Side::Bid,
+            1 * SOL_UNIT_SIZE,
+            11,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::PostOnlySlide,
+            &second_keypair,
+        )
+        .await?;
+
+    let orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(orders.len(), 2);
+    let bid: &RestingOrder = orders.iter().find(|order| order.get_is_bid()).unwrap();
+    assert_eq!(bid.get_num_base_atoms().as_u64(), 1 * SOL_UNIT_SIZE);
+    assert_eq!(
+        bid.get_price(),
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(999_999_999, -8).unwrap()
+    );
+    // Nothing traded.
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&second_keypair.pubkey())
+            .await,
+        0
+    );
+    Ok(())
+}
+
 #[tokio::test]
 async fn place_order_already_expired_test() -> anyhow::Result<()> {
     let mut test_fixture: TestFixture = TestFixture::new().await;
@@ -980,3 +1148,274 @@ async fn reverse_order_type_test() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+// Rests an ask for 1_000@1 then sends a bid for 400@1 from the same trader.
+async fn self_trade_test_setup(
+    self_trade_behavior: SelfTradeBehavior,
+) -> anyhow::Result<TestFixture> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 1_000).await?;
+    test_fixture.deposit(Token::USDC, 1_000).await?;
+    test_fixture
+        .place_order(
+            Side::Ask,
+            1_000,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+    test_fixture
+        .batch_update_for_keypair(
+            None,
+            vec![],
+            vec![PlaceOrderParams::new(
+                400,
+                1,
+                0,
+                true,
+                OrderType::Limit,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )
+            .with_self_trade_behavior(self_trade_behavior)],
+            &test_fixture.payer_keypair(),
+        )
+        .await?;
+    Ok(test_fixture)
+}
+
+#[tokio::test]
+async fn self_trade_cancel_resting_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture =
+        self_trade_test_setup(SelfTradeBehavior::CancelResting).await?;
+    let payer: Pubkey = test_fixture.payer();
+
+    // Ask was cancelled and the bid rested in full.
+    let orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(orders.len(), 1);
+    assert!(orders[0].get_is_bid());
+    assert_eq!(orders[0].get_num_base_atoms().as_u64(), 400);
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer)
+            .await,
+        1_000
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&payer)
+            .await,
+        600
+    );
+    assert_eq!(
+        test_fixture.market_fixture.get_quote_volume(&payer).await,
+        0
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn self_trade_cancel_taking_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture =
+        self_trade_test_setup(SelfTradeBehavior::CancelTaking).await?;
+    let payer: Pubkey = test_fixture.payer();
+
+    // Ask is untouched and the bid did not rest.
+    let orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(orders.len(), 1);
+    assert!(!orders[0].get_is_bid());
+    assert_eq!(orders[0].get_num_base_atoms().as_u64(), 1_000);
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer)
+            .await,
+        0
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&payer)
+            .await,
+        1_000
+    );
+    assert_eq!(
+        test_fixture.market_fixture.get_quote_volume(&payer).await,
+        0
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn self_trade_decrement_both_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture =
+        self_trade_test_setup(SelfTradeBehavior::DecrementBoth).await?;
+    let payer: Pubkey = test_fixture.payer();
+
+    // Ask shrinks by the bid size and the bid is fully consumed.
+    let orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(orders.len(), 1);
+    assert!(!orders[0].get_is_bid());
+    assert_eq!(orders[0].get_num_base_atoms().as_u64(), 600);
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer)
+            .await,
+        400
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&payer)
+            .await,
+        1_000
+    );
+    assert_eq!(
+        test_fixture.market_fixture.get_quote_volume(&payer).await,
+        0
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn self_trade_allow_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = self_trade_test_setup(SelfTradeBehavior::Allow).await?;
+    let payer: Pubkey = test_fixture.payer();
+
+    let orders: Vec<RestingOrder> = test_fixture.market_fixture.get_resting_orders().await;
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].get_num_base_atoms().as_u64(), 600);
+    // Both the maker and taker side of the fill are counted.
+    assert_eq!(
+        test_fixture.market_fixture.get_quote_volume(&payer).await,
+        800
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn fill_or_kill_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 1_000).await?;
+    test_fixture.deposit(Token::USDC, 2_000).await?;
+    test_fixture
+        .place_order(
+            Side::Ask,
+            1_000,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+
+    // Only 1_000 available, so the whole order fails.
+    assert!(test_fixture
+        .place_order(
+            Side::Bid,
+            2_000,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::FillOrKill,
+        )
+        .await
+        .is_err());
+    assert_eq!(
+        test_fixture.market_fixture.get_resting_orders().await.len(),
+        1
+    );
+
+    test_fixture
+        .place_order(
+            Side::Bid,
+            1_000,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::FillOrKill,
+        )
+        .await?;
+    assert_eq!(
+        test_fixture.market_fixture.get_resting_orders().await.len(),
+        0
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn immediate_or_cancel_min_fill_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 1_000).await?;
+    test_fixture.deposit(Token::USDC, 2_000).await?;
+    test_fixture
+        .place_order(
+            Side::Ask,
+            1_000,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+
+    assert!(test_fixture
+        .batch_update_for_keypair(
+            None,
+            vec![],
+            vec![PlaceOrderParams::new(
+                2_000,
+                1,
+                0,
+                true,
+                OrderType::ImmediateOrCancel,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )
+            .with_min_base_atoms_filled(1_500)],
+            &test_fixture.payer_keypair(),
+        )
+        .await
+        .is_err());
+
+    test_fixture
+        .batch_update_for_keypair(
+            None,
+            vec![],
+            vec![PlaceOrderParams::new(
+                2_000,
+                1,
+                0,
+                true,
+                OrderType::ImmediateOrCancel,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )
+            .with_min_base_atoms_filled(1_000)],
+            &test_fixture.payer_keypair(),
+        )
+        .await?;
+    assert_eq!(
+        test_fixture.market_fixture.get_resting_orders().await.len(),
+        0
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&test_fixture.payer())
+            .await,
+        1_000
+    );
+
+    Ok(())
+}
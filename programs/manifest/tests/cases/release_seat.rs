@@ -0,0 +1,80 @@
+use hypertree::{DataIndex, NIL};
+use manifest::state::{OrderType, NO_EXPIRATION_LAST_VALID_SLOT};
+use solana_program_test::tokio;
+
+use crate::{Side, TestFixture, Token, SOL_UNIT_SIZE};
+
+#[tokio::test]
+async fn release_seat() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.release_seat().await?;
+
+    test_fixture.market_fixture.reload().await;
+    let trader_index: DataIndex = test_fixture
+        .market_fixture
+        .market
+        .get_trader_index(&test_fixture.payer());
+    assert_eq!(trader_index, NIL);
+
+    // The seat can be claimed again after release.
+    test_fixture.claim_seat().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn release_seat_without_seat_fail() -> anyhow::Result<()> {
+    let test_fixture: TestFixture = TestFixture::new().await;
+    assert!(test_fixture.release_seat().await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn release_seat_with_balance_fail() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, SOL_UNIT_SIZE).await?;
+
+    assert!(test_fixture.release_seat().await.is_err());
+
+    // Succeeds once everything is withdrawn.
+    test_fixture.withdraw(Token::SOL, SOL_UNIT_SIZE).await?;
+    test_fixture.release_seat().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn release_seat_with_open_order_fail() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, SOL_UNIT_SIZE).await?;
+    test_fixture
+        .place_order(
+            Side::Ask,
+            SOL_UNIT_SIZE,
+            1,
+            0,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+
+    // Whole balance is locked in the order, so only the order blocks release.
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&test_fixture.payer())
+            .await,
+        0
+    );
+    assert!(test_fixture.release_seat().await.is_err());
+
+    test_fixture.cancel_order(0).await?;
+    test_fixture.withdraw(Token::SOL, SOL_UNIT_SIZE).await?;
+    test_fixture.release_seat().await?;
+
+    Ok(())
+}
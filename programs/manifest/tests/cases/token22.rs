@@ -3,16 +3,26 @@ use std::{cell::RefCell, rc::Rc, u64};
 use manifest::{
     program::{
         batch_update::PlaceOrderParams, batch_update_instruction, claim_seat_instruction,
-        create_market_instructions, deposit_instruction, swap_instruction, withdraw_instruction,
+        create_global_instruction, create_market_instructions, deposit_instruction,
+        global_add_trader_instruction, global_deposit_instruction, global_withdraw_instruction,
+        swap_instruction, withdraw_instruction,
     },
     quantities::WrapperU64,
     state::{OrderType, NO_EXPIRATION_LAST_VALID_SLOT},
+    validation::get_global_address,
 };
-use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
 use solana_sdk::{
-    instruction::Instruction, program_pack::Pack, pubkey::Pubkey, rent::Rent, signature::Keypair,
-    signer::Signer, system_instruction::create_account,
+    instruction::{AccountMeta, Instruction},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::Keypair,
+    signer::Signer,
+    system_instruction::create_account,
 };
+use spl_token_2022::extension::ExtensionType;
 
 use crate::{send_tx_with_retry, MintFixture, RUST_LOG_DEFAULT};
 
@@ -740,3 +750,448 @@ async fn token22_deposit_transfer_fee() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+// First 8 bytes of sha256("spl-transfer-hook-interface:execute").
+const TRANSFER_HOOK_EXECUTE_DISCRIMINATOR: [u8; 8] = [105, 37, 101, 197, 75, 251, 102, 26];
+
+// Stand in for a transfer hook program. It only implements Execute and counts
+// how many times it ran in the one extra account that it asks for. Accounts are
+// source, mint, destination, authority, extra account metas and then the
+// counter.
+fn process_transfer_hook(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    assert_eq!(data[0..8], TRANSFER_HOOK_EXECUTE_DISCRIMINATOR);
+    let mut counter_data = accounts[5].try_borrow_mut_data()?;
+    let count: u64 = u64::from_le_bytes(counter_data[0..8].try_into().unwrap());
+    counter_data[0..8].copy_from_slice(&(count + 1).to_le_bytes());
+    Ok(())
+}
+
+// ExtraAccountMetaList TLV with a single writable fixed address entry.
+fn transfer_hook_extra_account_metas_data(counter: &Pubkey) -> Vec<u8> {
+    let mut data: Vec<u8> = TRANSFER_HOOK_EXECUTE_DISCRIMINATOR.to_vec();
+    // Value length, then a pod slice of 35 byte ExtraAccountMeta.
+    data.extend_from_slice(&(4_u32 + 35).to_le_bytes());
+    data.extend_from_slice(&1_u32.to_le_bytes());
+    // Discriminator 0 is a fixed address.
+    data.push(0);
+    data.extend_from_slice(counter.as_ref());
+    // is_signer, is_writable
+    data.push(0);
+    data.push(1);
+    data
+}
+
+struct TransferHookMarket {
+    context: Rc<RefCell<ProgramTestContext>>,
+    payer_keypair: Keypair,
+    market: Pubkey,
+    hook_mint: Pubkey,
+    usdc_mint: Pubkey,
+    hook_token_account: Pubkey,
+    usdc_token_account: Pubkey,
+    counter: Pubkey,
+    // Remaining accounts that let token22 run the hook.
+    hook_account_metas: Vec<AccountMeta>,
+}
+
+impl TransferHookMarket {
+    async fn get_hook_count(&self) -> u64 {
+        let counter_account: solana_sdk::account::Account = self
+            .context
+            .borrow_mut()
+            .banks_client
+            .get_account(self.counter)
+            .await
+            .unwrap()
+            .unwrap();
+        u64::from_le_bytes(counter_account.data[0..8].try_into().unwrap())
+    }
+
+    async fn send(&self, instruction: Instruction) -> Result<(), BanksClientError> {
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[instruction],
+            Some(&self.payer_keypair.pubkey()),
+            &[&self.payer_keypair.insecure_clone()],
+        )
+        .await
+    }
+
+    fn with_hook_accounts(&self, mut instruction: Instruction) -> Instruction {
+        instruction
+            .accounts
+            .extend(self.hook_account_metas.iter().cloned());
+        instruction
+    }
+}
+
+// Market with a token22 base that has a transfer hook and a normal USDC quote.
+// Payer has a seat and funded token accounts for both.
+async fn setup_transfer_hook_market() -> anyhow::Result<TransferHookMarket> {
+    let hook_program_id: Pubkey = Pubkey::new_unique();
+    let hook_mint_keypair: Keypair = Keypair::new();
+    let hook_mint: Pubkey = hook_mint_keypair.pubkey();
+    let counter: Pubkey = Pubkey::new_unique();
+    let (extra_account_metas, _) = Pubkey::find_program_address(
+        &[b"extra-account-metas", hook_mint.as_ref()],
+        &hook_program_id,
+    );
+
+    let mut program_test: ProgramTest = ProgramTest::new(
+        "manifest",
+        manifest::ID,
+        processor!(manifest::process_instruction),
+    );
+    program_test.add_program(
+        "transfer_hook",
+        hook_program_id,
+        processor!(process_transfer_hook),
+    );
+    program_test.add_account(
+        extra_account_metas,
+        solana_sdk::account::Account {
+            lamports: 1_000_000_000,
+            data: transfer_hook_extra_account_metas_data(&counter),
+            owner: hook_program_id,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    program_test.add_account(
+        counter,
+        solana_sdk::account::Account {
+            lamports: 1_000_000_000,
+            data: vec![0; 8],
+            owner: hook_program_id,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    solana_logger::setup_with_default(RUST_LOG_DEFAULT);
+
+    let context: Rc<RefCell<ProgramTestContext>> =
+        Rc::new(RefCell::new(program_test.start_with_context().await));
+    let payer_keypair: Keypair = context.borrow().payer.insecure_clone();
+    let payer: &Pubkey = &payer_keypair.pubkey();
+
+    let usdc_mint_f: MintFixture =
+        MintFixture::new_with_version(Rc::clone(&context), Some(6), false).await;
+    let usdc_mint: Pubkey = usdc_mint_f.key;
+
+    let mint_space: usize =
+        ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+            ExtensionType::TransferHook,
+        ])
+        .unwrap();
+    let rent: Rent = context.borrow_mut().banks_client.get_rent().await.unwrap();
+    send_tx_with_retry(
+        Rc::clone(&context),
+        &[
+            create_account(
+                payer,
+                &hook_mint,
+                rent.minimum_balance(mint_space),
+                mint_space as u64,
+                &spl_token_2022::id(),
+            ),
+            spl_token_2022::extension::transfer_hook::instruction::initialize(
+                &spl_token_2022::id(),
+                &hook_mint,
+                None,
+                Some(hook_program_id),
+            )
+            .unwrap(),
+            spl_token_2022::instruction::initialize_mint2(
+                &spl_token_2022::id(),
+                &hook_mint,
+                payer,
+                None,
+                9,
+            )
+            .unwrap(),
+        ],
+        Some(payer),
+        &[&payer_keypair, &hook_mint_keypair],
+    )
+    .await?;
+
+    let market_keypair: Keypair = Keypair::new();
+    let create_market_ixs: Vec<Instruction> =
+        create_market_instructions(&market_keypair.pubkey(), &hook_mint, &usdc_mint, payer)
+            .unwrap();
+    send_tx_with_retry(
+        Rc::clone(&context),
+        &create_market_ixs[..],
+        Some(payer),
+        &[&payer_keypair, &market_keypair],
+    )
+    .await?;
+    send_tx_with_retry(
+        Rc::clone(&context),
+        &[claim_seat_instruction(&market_keypair.pubkey(), payer)],
+        Some(payer),
+        &[&payer_keypair],
+    )
+    .await?;
+
+    // Token accounts for a hooked mint need room for TransferHookAccount.
+    let hook_token_account_keypair: Keypair = Keypair::new();
+    let usdc_token_account_keypair: Keypair = Keypair::new();
+    let hook_token_account_space: usize = ExtensionType::try_calculate_account_len::<
+        spl_token_2022::state::Account,
+    >(&[ExtensionType::TransferHookAccount])
+    .unwrap();
+    send_tx_with_retry(
+        Rc::clone(&context),
+        &[
+            create_account(
+                payer,
+                &hook_token_account_keypair.pubkey(),
+                rent.minimum_balance(hook_token_account_space),
+                hook_token_account_space as u64,
+                &spl_token_2022::id(),
+            ),
+            spl_token_2022::instruction::initialize_account(
+                &spl_token_2022::id(),
+                &hook_token_account_keypair.pubkey(),
+                &hook_mint,
+                payer,
+            )
+            .unwrap(),
+            create_account(
+                payer,
+                &usdc_token_account_keypair.pubkey(),
+                rent.minimum_balance(spl_token::state::Account::LEN),
+                spl_token::state::Account::LEN as u64,
+                &spl_token::id(),
+            ),
+            spl_token::instruction::initialize_account(
+                &spl_token::id(),
+                &usdc_token_account_keypair.pubkey(),
+                &usdc_mint,
+                payer,
+            )
+            .unwrap(),
+            spl_token_2022::instruction::mint_to(
+                &spl_token_2022::id(),
+                &hook_mint,
+                &hook_token_account_keypair.pubkey(),
+                payer,
+                &[payer],
+                1_000_000_000_000,
+            )
+            .unwrap(),
+            spl_token::instruction::mint_to(
+                &spl_token::id(),
+                &usdc_mint,
+                &usdc_token_account_keypair.pubkey(),
+                payer,
+                &[payer],
+                1_000_000_000_000,
+            )
+            .unwrap(),
+        ],
+        Some(payer),
+        &[
+            &payer_keypair,
+            &hook_token_account_keypair,
+            &usdc_token_account_keypair,
+        ],
+    )
+    .await?;
+
+    Ok(TransferHookMarket {
+        context,
+        payer_keypair,
+        market: market_keypair.pubkey(),
+        hook_mint,
+        usdc_mint,
+        hook_token_account: hook_token_account_keypair.pubkey(),
+        usdc_token_account: usdc_token_account_keypair.pubkey(),
+        counter,
+        hook_account_metas: vec![
+            AccountMeta::new_readonly(hook_program_id, false),
+            AccountMeta::new_readonly(extra_account_metas, false),
+            AccountMeta::new(counter, false),
+        ],
+    })
+}
+
+#[tokio::test]
+async fn token22_transfer_hook() -> anyhow::Result<()> {
+    let hook_market: TransferHookMarket = setup_transfer_hook_market().await?;
+    let payer: Pubkey = hook_market.payer_keypair.pubkey();
+
+    // Without the hook accounts, token22 cannot run the hook.
+    let deposit_ix: Instruction = deposit_instruction(
+        &hook_market.market,
+        &payer,
+        &hook_market.hook_mint,
+        1_000_000_000,
+        &hook_market.hook_token_account,
+        spl_token_2022::id(),
+        None,
+    );
+    assert!(hook_market.send(deposit_ix.clone()).await.is_err());
+
+    hook_market
+        .send(hook_market.with_hook_accounts(deposit_ix))
+        .await?;
+    assert_eq!(hook_market.get_hook_count().await, 1);
+
+    // Quote has no hook, nothing extra needed.
+    hook_market
+        .send(deposit_instruction(
+            &hook_market.market,
+            &payer,
+            &hook_market.usdc_mint,
+            1_000_000_000,
+            &hook_market.usdc_token_account,
+            spl_token::id(),
+            None,
+        ))
+        .await?;
+    hook_market
+        .send(batch_update_instruction(
+            &hook_market.market,
+            &payer,
+            None,
+            vec![],
+            vec![PlaceOrderParams::new(
+                1_000,
+                9,
+                -1,
+                true,
+                OrderType::PostOnly,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )],
+            None,
+            None,
+            None,
+            None,
+        ))
+        .await?;
+    assert_eq!(hook_market.get_hook_count().await, 1);
+
+    // Base in goes from the trader to the vault through the hook.
+    hook_market
+        .send(hook_market.with_hook_accounts(swap_instruction(
+            &hook_market.market,
+            &payer,
+            &hook_market.hook_mint,
+            &hook_market.usdc_mint,
+            &hook_market.hook_token_account,
+            &hook_market.usdc_token_account,
+            100,
+            10,
+            true,
+            true,
+            spl_token_2022::id(),
+            spl_token::id(),
+            false,
+        )))
+        .await?;
+    assert_eq!(hook_market.get_hook_count().await, 2);
+
+    hook_market
+        .send(hook_market.with_hook_accounts(withdraw_instruction(
+            &hook_market.market,
+            &payer,
+            &hook_market.hook_mint,
+            1_000,
+            &hook_market.hook_token_account,
+            spl_token_2022::id(),
+            None,
+        )))
+        .await?;
+    assert_eq!(hook_market.get_hook_count().await, 3);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn token22_transfer_hook_global() -> anyhow::Result<()> {
+    let hook_market: TransferHookMarket = setup_transfer_hook_market().await?;
+    let payer: Pubkey = hook_market.payer_keypair.pubkey();
+    let (global, _) = get_global_address(&hook_market.hook_mint);
+
+    hook_market
+        .send(create_global_instruction(
+            &hook_market.hook_mint,
+            &payer,
+            &spl_token_2022::id(),
+        ))
+        .await?;
+    hook_market
+        .send(global_add_trader_instruction(&global, &payer))
+        .await?;
+    hook_market
+        .send(hook_market.with_hook_accounts(global_deposit_instruction(
+            &hook_market.hook_mint,
+            &payer,
+            &hook_market.hook_token_account,
+            &spl_token_2022::id(),
+            1_000_000,
+        )))
+        .await?;
+    assert_eq!(hook_market.get_hook_count().await, 1);
+
+    hook_market
+        .send(batch_update_instruction(
+            &hook_market.market,
+            &payer,
+            None,
+            vec![],
+            vec![PlaceOrderParams::new(
+                1_000,
+                9,
+                -1,
+                false,
+                OrderType::Global,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )],
+            Some(hook_market.hook_mint),
+            Some(spl_token_2022::id()),
+            None,
+            None,
+        ))
+        .await?;
+
+    // Filling the global ask moves base from the global vault to the market
+    // vault and then from the market vault to the taker, both through the hook.
+    hook_market
+        .send(hook_market.with_hook_accounts(swap_instruction(
+            &hook_market.market,
+            &payer,
+            &hook_market.hook_mint,
+            &hook_market.usdc_mint,
+            &hook_market.hook_token_account,
+            &hook_market.usdc_token_account,
+            1_000,
+            1_000,
+            false,
+            true,
+            spl_token_2022::id(),
+            spl_token::id(),
+            true,
+        )))
+        .await?;
+    assert_eq!(hook_market.get_hook_count().await, 3);
+
+    hook_market
+        .send(hook_market.with_hook_accounts(global_withdraw_instruction(
+            &hook_market.hook_mint,
+            &payer,
+            &hook_market.hook_token_account,
+            &spl_token_2022::id(),
+            1_000,
+        )))
+        .await?;
+    assert_eq!(hook_market.get_hook_count().await, 4);
+
+    Ok(())
+}
@@ -0,0 +1,611 @@
+use hypertree::DataIndex;
+use manifest::{
+    program::{
+        batch_update::{CancelOrderParams, PlaceOrderParams},
+        create_market::CreateMarketParams,
+        place_trigger_order::PlaceTriggerOrderParams,
+    },
+    state::{
+        OrderType, TriggerOrder, TriggerOrderType, GAS_DEPOSIT_LAMPORTS,
+        NO_EXPIRATION_LAST_VALID_SLOT,
+    },
+};
+use solana_program::pubkey::Pubkey;
+use solana_program_test::tokio;
+use solana_sdk::{
+    account::Account,
+    signature::{Keypair, Signer},
+};
+
+use crate::{price, MockOracleFixture, TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE};
+
+async fn get_lamports(test_fixture: &TestFixture, key: &Pubkey) -> u64 {
+    let account: Account = test_fixture.try_load(key).await.unwrap().unwrap();
+    account.lamports
+}
+
+fn limit_order(price_mantissa: u32, is_bid: bool) -> PlaceOrderParams {
+    PlaceOrderParams::new(
+        SOL_UNIT_SIZE,
+        price_mantissa,
+        -3,
+        is_bid,
+        OrderType::Limit,
+        NO_EXPIRATION_LAST_VALID_SLOT,
+    )
+}
+
+// Second trader bids 1 SOL at each of the given prices, in thousandths of a
+// USDC atom per SOL atom.
+async fn place_bids(test_fixture: &mut TestFixture, prices: &[u32]) -> anyhow::Result<()> {
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::USDC, 100 * USDC_UNIT_SIZE, &second_keypair)
+        .await?;
+    test_fixture
+        .batch_update_for_keypair(
+            None,
+            vec![],
+            prices
+                .iter()
+                .map(|price| limit_order(*price, true))
+                .collect(),
+            &second_keypair,
+        )
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn trigger_order_stop_market_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let payer: Keypair = test_fixture.payer_keypair();
+    place_bids(&mut test_fixture, &[9, 8]).await?;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 2 * SOL_UNIT_SIZE).await?;
+
+    // Sell 1 SOL at 8.00 or better once the price gets down to 9.00.
+    let market_lamports: u64 = get_lamports(&test_fixture, &test_fixture.market_fixture.key).await;
+    test_fixture
+        .place_trigger_order_for_keypair(
+            PlaceTriggerOrderParams::new(
+                SOL_UNIT_SIZE,
+                9,
+                -3,
+                8,
+                -3,
+                false,
+                TriggerOrderType::StopMarket,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            ),
+            &payer,
+        )
+        .await?;
+    let trigger_orders: Vec<(DataIndex, TriggerOrder)> =
+        test_fixture.market_fixture.get_trigger_orders().await;
+    assert_eq!(trigger_orders.len(), 1);
+    let (trigger_order_index, trigger_order) = trigger_orders[0];
+    assert_eq!(trigger_order.get_trigger_price(), price(9, -3));
+    assert!(
+        get_lamports(&test_fixture, &test_fixture.market_fixture.key).await
+            >= market_lamports + GAS_DEPOSIT_LAMPORTS
+    );
+    // Nothing is reserved until it triggers.
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer.pubkey())
+            .await,
+        2 * SOL_UNIT_SIZE
+    );
+
+    // No trade yet, so there is nothing to trigger on.
+    test_fixture
+        .crank_trigger_orders_for_keypair(vec![trigger_order_index], None, &payer)
+        .await?;
+    assert_eq!(
+        test_fixture.market_fixture.get_trigger_orders().await.len(),
+        1
+    );
+
+    // Trade at 9.00.
+    test_fixture
+        .batch_update_for_keypair(None, vec![], vec![limit_order(9, false)], &payer)
+        .await?;
+    test_fixture.market_fixture.reload().await;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .market
+            .fixed
+            .get_last_trade_price(),
+        price(9, -3)
+    );
+
+    // Cranker gets the deposit and the stop sells into the 8.00 bid.
+    let market_lamports: u64 = get_lamports(&test_fixture, &test_fixture.market_fixture.key).await;
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture
+        .crank_trigger_orders_for_keypair(vec![trigger_order_index], None, &second_keypair)
+        .await?;
+    assert!(test_fixture
+        .market_fixture
+        .get_trigger_orders()
+        .await
+        .is_empty());
+    assert_eq!(
+        get_lamports(&test_fixture, &test_fixture.market_fixture.key).await,
+        market_lamports - GAS_DEPOSIT_LAMPORTS
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&payer.pubkey())
+            .await,
+        17 * USDC_UNIT_SIZE
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&second_keypair.pubkey())
+            .await,
+        2 * SOL_UNIT_SIZE
+    );
+
+    // Already activated, so cranking again is a no-op.
+    test_fixture
+        .crank_trigger_orders_for_keypair(vec![trigger_order_index], None, &second_keypair)
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn trigger_order_trailing_stop_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let payer: Keypair = test_fixture.payer_keypair();
+    place_bids(&mut test_fixture, &[10, 7, 6]).await?;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 3 * SOL_UNIT_SIZE).await?;
+
+    // Sell 1 SOL once the price drops 2.00 from its high.
+    test_fixture
+        .place_trigger_order_for_keypair(
+            PlaceTriggerOrderParams::new(
+                SOL_UNIT_SIZE,
+                5,
+                -3,
+                5,
+                -3,
+                false,
+                TriggerOrderType::TrailingStop,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )
+            .with_trail(2, -3),
+            &payer,
+        )
+        .await?;
+    let trigger_order_index: DataIndex =
+        test_fixture.market_fixture.get_trigger_orders().await[0].0;
+
+    // Trade at 10.00 pulls the trigger up to 8.00.
+    test_fixture
+        .batch_update_for_keypair(None, vec![], vec![limit_order(10, false)], &payer)
+        .await?;
+    test_fixture
+        .crank_trigger_orders_for_keypair(vec![trigger_order_index], None, &payer)
+        .await?;
+    let trigger_orders: Vec<(DataIndex, TriggerOrder)> =
+        test_fixture.market_fixture.get_trigger_orders().await;
+    assert_eq!(trigger_orders.len(), 1);
+    assert_eq!(trigger_orders[0].1.get_trigger_price(), price(8, -3));
+
+    // Trade at 7.00 triggers it and it sells into the 6.00 bid.
+    test_fixture
+        .batch_update_for_keypair(None, vec![], vec![limit_order(7, false)], &payer)
+        .await?;
+    test_fixture
+        .crank_trigger_orders_for_keypair(vec![trigger_orders[0].0], None, &payer)
+        .await?;
+    assert!(test_fixture
+        .market_fixture
+        .get_trigger_orders()
+        .await
+        .is_empty());
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&payer.pubkey())
+            .await,
+        23 * USDC_UNIT_SIZE
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn trigger_order_oracle_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let oracle: MockOracleFixture = MockOracleFixture::new(test_fixture.context.clone());
+    oracle.set_pyth_price(1_000, -2, test_fixture.get_slot().await);
+    test_fixture.market_fixture.key = test_fixture
+        .create_new_market_with_params(
+            &test_fixture.sol_mint_fixture.key,
+            &test_fixture.usdc_mint_fixture.key,
+            CreateMarketParams::new(0, 0, 0).with_peg_oracle(oracle.key, 10),
+        )
+        .await?;
+    test_fixture.market_fixture.reload().await;
+
+    let payer: Keypair = test_fixture.payer_keypair();
+    place_bids(&mut test_fixture, &[9]).await?;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, SOL_UNIT_SIZE).await?;
+
+    // Sell 1 SOL once the oracle is down at 9.50.
+    test_fixture
+        .place_trigger_order_for_keypair(
+            PlaceTriggerOrderParams::new(
+                SOL_UNIT_SIZE,
+                95,
+                -4,
+                9,
+                -3,
+                false,
+                TriggerOrderType::StopMarket,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            ),
+            &payer,
+        )
+        .await?;
+    let trigger_order_index: DataIndex =
+        test_fixture.market_fixture.get_trigger_orders().await[0].0;
+
+    // Oracle above the trigger.
+    test_fixture
+        .crank_trigger_orders_for_keypair(vec![trigger_order_index], Some(&oracle.key), &payer)
+        .await?;
+    assert_eq!(
+        test_fixture.market_fixture.get_trigger_orders().await.len(),
+        1
+    );
+
+    // Without the oracle the drop is not seen.
+    oracle.set_pyth_price(900, -2, test_fixture.get_slot().await);
+    test_fixture
+        .crank_trigger_orders_for_keypair(vec![trigger_order_index], None, &payer)
+        .await?;
+    assert_eq!(
+        test_fixture.market_fixture.get_trigger_orders().await.len(),
+        1
+    );
+    test_fixture
+        .crank_trigger_orders_for_keypair(vec![trigger_order_index], Some(&oracle.key), &payer)
+        .await?;
+    assert!(test_fixture
+        .market_fixture
+        .get_trigger_orders()
+        .await
+        .is_empty());
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_quote_balance_atoms(&payer.pubkey())
+            .await,
+        9 * USDC_UNIT_SIZE
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn trigger_order_waits_for_global_maker_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let payer: Keypair = test_fixture.payer_keypair();
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    place_bids(&mut test_fixture, &[9]).await?;
+    test_fixture.claim_seat().await?;
+    test_fixture.deposit(Token::SOL, 3 * SOL_UNIT_SIZE).await?;
+
+    // Trade at 9.00, then a global bid takes the top of the book.
+    test_fixture
+        .batch_update_for_keypair(None, vec![], vec![limit_order(9, false)], &payer)
+        .await?;
+    test_fixture
+        .global_add_trader_for_keypair(&second_keypair)
+        .await?;
+    test_fixture
+        .global_deposit_for_keypair(&second_keypair, 10 * USDC_UNIT_SIZE)
+        .await?;
+    test_fixture
+        .batch_update_with_global_for_keypair(
+            None,
+            vec![],
+            vec![PlaceOrderParams::new(
+                SOL_UNIT_SIZE,
+                9,
+                -3,
+                true,
+                OrderType::Global,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )],
+            &second_keypair,
+        )
+        .await?;
+
+    for trigger_type in [TriggerOrderType::StopLimit, TriggerOrderType::StopMarket] {
+        test_fixture
+            .place_trigger_order_for_keypair(
+                PlaceTriggerOrderParams::new(
+                    SOL_UNIT_SIZE,
+                    9,
+                    -3,
+                    8,
+                    -3,
+                    false,
+                    trigger_type,
+                    NO_EXPIRATION_LAST_VALID_SLOT,
+                ),
+                &payer,
+            )
+            .await?;
+    }
+    let trigger_order_indices: Vec<DataIndex> = test_fixture
+        .market_fixture
+        .get_trigger_orders()
+        .await
+        .iter()
+        .map(|(index, _)| *index)
+        .collect();
+
+    // The crank has no global accounts, so both triggers wait instead of
+    // failing the crank or using up the stop market.
+    let market_lamports: u64 = get_lamports(&test_fixture, &test_fixture.market_fixture.key).await;
+    test_fixture
+        .crank_trigger_orders_for_keypair(trigger_order_indices.clone(), None, &second_keypair)
+        .await?;
+    assert_eq!(
+        test_fixture.market_fixture.get_trigger_orders().await.len(),
+        2
+    );
+    assert_eq!(
+        get_lamports(&test_fixture, &test_fixture.market_fixture.key).await,
+        market_lamports
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .get_base_balance_atoms(&payer.pubkey())
+            .await,
+        2 * SOL_UNIT_SIZE
+    );
+
+    // Once the global bid is gone they activate.
+    let global_order_sequence_number: u64 =
+        test_fixture.market_fixture.get_resting_orders().await[0].get_sequence_number();
+    test_fixture
+        .batch_update_with_global_for_keypair(
+            None,
+            vec![CancelOrderParams::new(global_order_sequence_number)],
+            vec![],
+            &second_keypair,
+        )
+        .await?;
+    test_fixture
+        .crank_trigger_orders_for_keypair(trigger_order_indices, None, &second_keypair)
+        .await?;
+    assert!(test_fixture
+        .market_fixture
+        .get_trigger_orders()
+        .await
+        .is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn trigger_order_cancel_and_remove_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let payer: Keypair = test_fixture.payer_keypair();
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    place_bids(&mut test_fixture, &[9]).await?;
+    test_fixture.claim_seat().await?;
+    test_fixture
+        .deposit(Token::USDC, 10 * USDC_UNIT_SIZE)
+        .await?;
+
+    let stop_limit_bid: PlaceTriggerOrderParams = PlaceTriggerOrderParams::new(
+        SOL_UNIT_SIZE,
+        10,
+        -3,
+        11,
+        -3,
+        true,
+        TriggerOrderType::StopLimit,
+        NO_EXPIRATION_LAST_VALID_SLOT,
+    );
+    test_fixture
+        .place_trigger_order_for_keypair(stop_limit_bid.clone(), &payer)
+        .await?;
+    let trigger_order: TriggerOrder = test_fixture.market_fixture.get_trigger_orders().await[0].1;
+
+    // Seat cannot be released and only the trader can cancel.
+    test_fixture
+        .withdraw(Token::USDC, 10 * USDC_UNIT_SIZE)
+        .await?;
+    assert!(test_fixture.release_seat().await.is_err());
+    assert!(test_fixture
+        .cancel_trigger_order_for_keypair(trigger_order.get_sequence_number(), &second_keypair)
+        .await
+        .is_err());
+
+    let market_lamports: u64 = get_lamports(&test_fixture, &test_fixture.market_fixture.key).await;
+    test_fixture
+        .cancel_trigger_order_for_keypair(trigger_order.get_sequence_number(), &payer)
+        .await?;
+    assert_eq!(
+        get_lamports(&test_fixture, &test_fixture.market_fixture.key).await,
+        market_lamports - GAS_DEPOSIT_LAMPORTS
+    );
+    assert!(test_fixture
+        .market_fixture
+        .get_trigger_orders()
+        .await
+        .is_empty());
+    assert!(test_fixture
+        .cancel_trigger_order_for_keypair(trigger_order.get_sequence_number(), &payer)
+        .await
+        .is_err());
+
+    // Triggers on the next trade without the funds behind it, so the crank
+    // drops it instead.
+    test_fixture
+        .place_trigger_order_for_keypair(stop_limit_bid, &payer)
+        .await?;
+    let trigger_order_index: DataIndex =
+        test_fixture.market_fixture.get_trigger_orders().await[0].0;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+    test_fixture
+        .batch_update_for_keypair(None, vec![], vec![limit_order(12, false)], &second_keypair)
+        .await?;
+    // Trade at 12.00 against itself.
+    test_fixture
+        .batch_update_for_keypair(
+            None,
+            vec![],
+            vec![PlaceOrderParams::new(
+                1_000,
+                12,
+                -3,
+                true,
+                OrderType::ImmediateOrCancel,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            )],
+            &second_keypair,
+        )
+        .await?;
+    test_fixture
+        .crank_trigger_orders_for_keypair(vec![trigger_order_index], None, &second_keypair)
+        .await?;
+    assert!(test_fixture
+        .market_fixture
+        .get_trigger_orders()
+        .await
+        .is_empty());
+    assert_eq!(
+        test_fixture.market_fixture.get_resting_orders().await.len(),
+        2
+    );
+
+    // Expired ones are dropped as well.
+    let last_valid_slot: u32 = test_fixture.get_slot().await as u32 + 10;
+    test_fixture
+        .place_trigger_order_for_keypair(
+            PlaceTriggerOrderParams::new(
+                SOL_UNIT_SIZE,
+                20,
+                -3,
+                21,
+                -3,
+                true,
+                TriggerOrderType::StopMarket,
+                last_valid_slot,
+            ),
+            &payer,
+        )
+        .await?;
+    let trigger_order_index: DataIndex =
+        test_fixture.market_fixture.get_trigger_orders().await[0].0;
+    test_fixture.advance_time_seconds(100).await;
+    test_fixture
+        .crank_trigger_orders_for_keypair(vec![trigger_order_index], None, &second_keypair)
+        .await?;
+    assert!(test_fixture
+        .market_fixture
+        .get_trigger_orders()
+        .await
+        .is_empty());
+    test_fixture.release_seat().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn trigger_order_invalid_params_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let payer: Keypair = test_fixture.payer_keypair();
+    let stop_market: PlaceTriggerOrderParams = PlaceTriggerOrderParams::new(
+        SOL_UNIT_SIZE,
+        9,
+        -3,
+        8,
+        -3,
+        false,
+        TriggerOrderType::StopMarket,
+        NO_EXPIRATION_LAST_VALID_SLOT,
+    );
+
+    // Needs a seat.
+    assert!(test_fixture
+        .place_trigger_order_for_keypair(stop_market.clone(), &payer)
+        .await
+        .is_err());
+    test_fixture.claim_seat().await?;
+
+    // Only trailing stops have a trail, and they need one.
+    assert!(test_fixture
+        .place_trigger_order_for_keypair(stop_market.clone().with_trail(1, -3), &payer)
+        .await
+        .is_err());
+    assert!(test_fixture
+        .place_trigger_order_for_keypair(
+            PlaceTriggerOrderParams::new(
+                SOL_UNIT_SIZE,
+                9,
+                -3,
+                8,
+                -3,
+                false,
+                TriggerOrderType::TrailingStop,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            ),
+            &payer,
+        )
+        .await
+        .is_err());
+    assert!(test_fixture
+        .place_trigger_order_for_keypair(
+            PlaceTriggerOrderParams::new(
+                0,
+                9,
+                -3,
+                8,
+                -3,
+                false,
+                TriggerOrderType::StopMarket,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+            ),
+            &payer,
+        )
+        .await
+        .is_err());
+    assert!(test_fixture
+        .market_fixture
+        .get_trigger_orders()
+        .await
+        .is_empty());
+
+    test_fixture
+        .place_trigger_order_for_keypair(stop_market, &payer)
+        .await?;
+    // Not block aligned.
+    assert!(test_fixture
+        .crank_trigger_orders_for_keypair(vec![1], None, &payer)
+        .await
+        .is_err());
+
+    Ok(())
+}
@@ -0,0 +1,130 @@
+use manifest::{
+    quantities::QuoteAtomsPerBaseAtom,
+    state::{get_twap, OrderType, TwapObservation, NO_EXPIRATION_LAST_VALID_SLOT},
+};
+use solana_program_test::tokio;
+use solana_sdk::signature::Keypair;
+
+use crate::{Side, TestFixture, Token, SOL_UNIT_SIZE, USDC_UNIT_SIZE};
+
+async fn get_twap_observation(test_fixture: &mut TestFixture) -> TwapObservation {
+    let slot: u32 = test_fixture.get_slot().await as u32;
+    test_fixture.market_fixture.reload().await;
+    test_fixture
+        .market_fixture
+        .market
+        .fixed
+        .get_twap_observation(slot)
+}
+
+#[tokio::test]
+async fn twap_test() -> anyhow::Result<()> {
+    let mut test_fixture: TestFixture = TestFixture::new().await;
+    let second_keypair: Keypair = test_fixture.second_keypair.insecure_clone();
+    test_fixture.claim_seat_for_keypair(&second_keypair).await?;
+    test_fixture
+        .deposit_for_keypair(Token::SOL, 2 * SOL_UNIT_SIZE, &second_keypair)
+        .await?;
+    for price_mantissa in [10, 20] {
+        test_fixture
+            .place_order_for_keypair(
+                Side::Ask,
+                SOL_UNIT_SIZE,
+                price_mantissa,
+                -3,
+                NO_EXPIRATION_LAST_VALID_SLOT,
+                OrderType::Limit,
+                &second_keypair,
+            )
+            .await?;
+    }
+    test_fixture.claim_seat().await?;
+    test_fixture
+        .deposit(Token::USDC, 30 * USDC_UNIT_SIZE)
+        .await?;
+
+    // 10 slots at 10 then 10 slots at 20.
+    test_fixture
+        .place_order(
+            Side::Bid,
+            SOL_UNIT_SIZE,
+            10,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+        )
+        .await?;
+    let start: TwapObservation = get_twap_observation(&mut test_fixture).await;
+    test_fixture.advance_time_seconds(20).await;
+    test_fixture
+        .place_order(
+            Side::Bid,
+            SOL_UNIT_SIZE,
+            20,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+        )
+        .await?;
+    test_fixture.advance_time_seconds(20).await;
+    let end: TwapObservation = get_twap_observation(&mut test_fixture).await;
+    assert_eq!(end.slot, start.slot + 20);
+    assert_eq!(
+        get_twap(&start, &end),
+        Some(QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(15, -3).unwrap())
+    );
+
+    // Poking stores what readers would otherwise extrapolate.
+    test_fixture.poke_twap().await?;
+    assert_eq!(get_twap_observation(&mut test_fixture).await, end);
+    test_fixture.market_fixture.reload().await;
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .market
+            .fixed
+            .get_twap_observation(0),
+        end
+    );
+
+    // Trading with yourself moves neither the last trade price nor the TWAP.
+    test_fixture
+        .deposit(Token::USDC, 100 * USDC_UNIT_SIZE)
+        .await?;
+    test_fixture
+        .place_order(
+            Side::Ask,
+            SOL_UNIT_SIZE,
+            100,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::Limit,
+        )
+        .await?;
+    test_fixture
+        .place_order(
+            Side::Bid,
+            SOL_UNIT_SIZE,
+            100,
+            -3,
+            NO_EXPIRATION_LAST_VALID_SLOT,
+            OrderType::ImmediateOrCancel,
+        )
+        .await?;
+    test_fixture.advance_time_seconds(20).await;
+    let after_self_trade: TwapObservation = get_twap_observation(&mut test_fixture).await;
+    assert_eq!(
+        get_twap(&end, &after_self_trade),
+        Some(QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(20, -3).unwrap())
+    );
+    assert_eq!(
+        test_fixture
+            .market_fixture
+            .market
+            .fixed
+            .get_last_trade_price(),
+        QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(20, -3).unwrap()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,180 @@
+use std::cell::RefMut;
+
+use manifest::validation::{DELEGATION_PROGRAM_ID, MAGIC_CONTEXT_ID, MAGIC_PROGRAM_ID};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::account::Account;
+
+// Stand-ins for the MagicBlock delegation program and magic program. They do
+// just enough for a market to go through delegate, trade, commit and
+// undelegate in solana-program-test. There is only one bank, so it plays both
+// the base layer and the rollup. TestFixture::open_rollup and
+// TestFixture::settle_rollup do what the rollup validator does in between.
+
+// Delegate instruction that ephemeral_rollups_sdk::cpi::delegate_account sends.
+const DELEGATE_DISCRIMINATOR: [u8; 8] = [0; 8];
+
+// Magic program instructions, bincode enum tags.
+const SCHEDULE_COMMIT_TAG: u32 = 1;
+const SCHEDULE_COMMIT_AND_UNDELEGATE_TAG: u32 = 2;
+
+// The magic context is a count followed by the scheduled accounts, each with
+// a flag for whether it gets undelegated.
+const MAGIC_CONTEXT_CAPACITY: usize = 16;
+const MAGIC_CONTEXT_ENTRY_SIZE: usize = 33;
+const MAGIC_CONTEXT_SIZE: usize = 8 + MAGIC_CONTEXT_CAPACITY * MAGIC_CONTEXT_ENTRY_SIZE;
+
+// The delegation record only holds the program that delegated the account.
+const DELEGATION_RECORD_SIZE: usize = 32;
+
+pub struct ScheduledCommit {
+    pub account: Pubkey,
+    pub undelegate: bool,
+}
+
+pub fn add_mock_ephemeral_rollup(program: &mut ProgramTest) {
+    program.add_program(
+        "mock_delegation",
+        DELEGATION_PROGRAM_ID,
+        processor!(process_mock_delegation_instruction),
+    );
+    program.add_program(
+        "mock_magic",
+        MAGIC_PROGRAM_ID,
+        processor!(process_mock_magic_instruction),
+    );
+    program.add_account(MAGIC_CONTEXT_ID, empty_magic_context());
+}
+
+pub fn process_mock_delegation_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if data.get(..8) != Some(&DELEGATE_DISCRIMINATOR[..]) {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let account_iter: &mut std::slice::Iter<AccountInfo> = &mut accounts.iter();
+    let payer: &AccountInfo = next_account_info(account_iter)?;
+    let delegated_account: &AccountInfo = next_account_info(account_iter)?;
+    let owner_program: &AccountInfo = next_account_info(account_iter)?;
+    let buffer: &AccountInfo = next_account_info(account_iter)?;
+    let delegation_record: &AccountInfo = next_account_info(account_iter)?;
+    let _delegation_metadata: &AccountInfo = next_account_info(account_iter)?;
+    let system_program: &AccountInfo = next_account_info(account_iter)?;
+
+    // The sdk already emptied the account and handed it to this program. Its
+    // state waits in the buffer, which the sdk closes after this returns.
+    if delegated_account.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+    delegated_account
+        .try_borrow_mut_data()?
+        .copy_from_slice(&buffer.try_borrow_data()?);
+
+    let (expected_delegation_record, delegation_record_bump) =
+        Pubkey::find_program_address(&[b"delegation", delegated_account.key.as_ref()], program_id);
+    if expected_delegation_record != *delegation_record.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            delegation_record.key,
+            Rent::get()?.minimum_balance(DELEGATION_RECORD_SIZE),
+            DELEGATION_RECORD_SIZE as u64,
+            program_id,
+        ),
+        &[
+            payer.clone(),
+            delegation_record.clone(),
+            system_program.clone(),
+        ],
+        &[&[
+            b"delegation",
+            delegated_account.key.as_ref(),
+            &[delegation_record_bump],
+        ]],
+    )?;
+    delegation_record
+        .try_borrow_mut_data()?
+        .copy_from_slice(owner_program.key.as_ref());
+
+    Ok(())
+}
+
+pub fn process_mock_magic_instruction(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let tag: Option<u32> = data
+        .get(..4)
+        .map(|tag_bytes| u32::from_le_bytes(tag_bytes.try_into().unwrap()));
+    let undelegate: bool = match tag {
+        Some(SCHEDULE_COMMIT_TAG) => false,
+        Some(SCHEDULE_COMMIT_AND_UNDELEGATE_TAG) => true,
+        _ => return Err(ProgramError::InvalidInstructionData),
+    };
+    let account_iter: &mut std::slice::Iter<AccountInfo> = &mut accounts.iter();
+    let payer: &AccountInfo = next_account_info(account_iter)?;
+    let magic_context: &AccountInfo = next_account_info(account_iter)?;
+    if !payer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *magic_context.key != MAGIC_CONTEXT_ID {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Like the real magic program, only schedule. Nothing reaches the base
+    // layer until the validator, here TestFixture::settle_rollup, sends it.
+    let mut magic_context_data: RefMut<&mut [u8]> = magic_context.try_borrow_mut_data()?;
+    let mut num_scheduled: usize =
+        u64::from_le_bytes(magic_context_data[..8].try_into().unwrap()) as usize;
+    for account in account_iter {
+        if !account.is_writable {
+            return Err(ProgramError::InvalidArgument);
+        }
+        if num_scheduled == MAGIC_CONTEXT_CAPACITY {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        let entry_start: usize = 8 + num_scheduled * MAGIC_CONTEXT_ENTRY_SIZE;
+        magic_context_data[entry_start..entry_start + 32].copy_from_slice(account.key.as_ref());
+        magic_context_data[entry_start + 32] = undelegate as u8;
+        num_scheduled += 1;
+    }
+    magic_context_data[..8].copy_from_slice(&(num_scheduled as u64).to_le_bytes());
+
+    Ok(())
+}
+
+pub fn read_scheduled_commits(magic_context_data: &[u8]) -> Vec<ScheduledCommit> {
+    let num_scheduled: usize =
+        u64::from_le_bytes(magic_context_data[..8].try_into().unwrap()) as usize;
+    (0..num_scheduled)
+        .map(|index| {
+            let entry_start: usize = 8 + index * MAGIC_CONTEXT_ENTRY_SIZE;
+            ScheduledCommit {
+                account: Pubkey::new_from_array(
+                    magic_context_data[entry_start..entry_start + 32]
+                        .try_into()
+                        .unwrap(),
+                ),
+                undelegate: magic_context_data[entry_start + 32] == 1,
+            }
+        })
+        .collect()
+}
+
+pub fn empty_magic_context() -> Account {
+    Account::new(u32::MAX as u64, MAGIC_CONTEXT_SIZE, &MAGIC_PROGRAM_ID)
+}
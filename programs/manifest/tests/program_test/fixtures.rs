@@ -1,6 +1,7 @@
 use spl_associated_token_account::get_associated_token_address;
 use std::{
     cell::{Ref, RefCell, RefMut},
+    collections::HashMap,
     io::Error,
     str::FromStr,
 };
@@ -8,29 +9,63 @@ use std::{
 use hypertree::{DataIndex, HyperTreeValueIteratorTrait};
 use manifest::{
     program::{
-        batch_update::{CancelOrderParams, PlaceOrderParams},
-        batch_update_instruction,
-        claim_seat_instruction::claim_seat_instruction,
-        create_market_instructions, deposit_instruction, get_dynamic_value,
-        global_add_trader_instruction,
+        batch_update::{CancelOrderParams, ModifyOrderParams, PlaceOrderParams},
+        batch_update_instruction, batch_update_v2_instruction,
+        batch_update_with_market_globals_instruction, batch_update_with_modifies_instruction,
+        cancel_all_instruction, cancel_trigger_order_instruction,
+        claim_seat_instruction::{claim_seat_instruction, claim_seat_v2_instruction},
+        clean_expired_instruction, commit_market_instruction, compact_market_instruction,
+        crank_trigger_orders_instruction,
+        create_market::CreateMarketParams,
+        create_market_instructions, create_market_with_params_instructions,
+        delegate_market::DelegateMarketParams,
+        delegate_market_instruction, deposit_instruction, expand_market_by_instruction,
+        get_dynamic_value, global_add_trader_instruction,
         global_create_instruction::create_global_instruction,
-        global_deposit_instruction, global_withdraw_instruction, swap_instruction,
-        swap_v2_instruction, withdraw_instruction,
+        global_deposit_instruction, global_withdraw_instruction,
+        init_market_metadata::MarketMetadataParams,
+        init_market_metadata_instruction, init_market_stats_instruction,
+        market_global_create_instruction, market_global_deposit_instruction,
+        market_global_withdraw_instruction,
+        place_trigger_order::PlaceTriggerOrderParams,
+        place_trigger_order_instruction, poke_twap_instruction, release_seat_instruction,
+        set_market_authority_instruction,
+        swap::OraclePriceBandParams,
+        swap_instruction, swap_v2_instruction, swap_v2_with_oracle_price_band_instruction,
+        transfer_market_metadata_authority_instruction, undelegate_market_instruction,
+        update_market_metadata_instruction, withdraw_instruction,
+    },
+    quantities::{QuoteAtomsPerBaseAtom, WrapperU64},
+    state::{
+        GlobalFixed, GlobalValue, MarketFixed, MarketMetadata, MarketValue, OrderType,
+        RestingOrder, TriggerOrder,
+    },
+    validation::{
+        get_delegation_addresses, get_global_address, get_market_global_address,
+        get_market_metadata_address, get_vault_address, MintAccountInfo, DELEGATION_PROGRAM_ID,
+        MAGIC_CONTEXT_ID, PYTH_PROGRAM_ID, SWITCHBOARD_PROGRAM_ID,
     },
-    quantities::WrapperU64,
-    state::{GlobalFixed, GlobalValue, MarketFixed, MarketValue, OrderType, RestingOrder},
-    validation::{get_global_address, MintAccountInfo},
 };
 use solana_program::{hash::Hash, pubkey::Pubkey, rent::Rent};
 use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
 use solana_sdk::{
-    account::Account, account_info::AccountInfo, clock::Clock, instruction::Instruction,
-    program_pack::Pack, signature::Keypair, signer::Signer, system_instruction::create_account,
+    account::{Account, AccountSharedData},
+    account_info::AccountInfo,
+    clock::Clock,
+    instruction::{AccountMeta, Instruction},
+    program_pack::Pack,
+    signature::Keypair,
+    signer::Signer,
+    system_instruction::create_account,
     transaction::Transaction,
 };
 use spl_token_2022::state::Mint;
 use std::rc::Rc;
 
+use super::ephemeral_rollup::{
+    add_mock_ephemeral_rollup, empty_magic_context, read_scheduled_commits,
+};
+
 #[derive(PartialEq)]
 pub enum Token {
     USDC = 0,
@@ -64,15 +99,30 @@ pub struct TestFixture {
     pub global_fixture: GlobalFixture,
     pub sol_global_fixture: GlobalFixture,
     pub second_keypair: Keypair,
+    // Owner of each account handed to the mock delegation program, which is
+    // the owner the rollup shows and undelegation restores.
+    pub delegated_account_owners: HashMap<Pubkey, Pubkey>,
 }
 
 impl TestFixture {
     pub async fn new() -> TestFixture {
+        TestFixture::new_with_options(false).await
+    }
+
+    /// Also loads the mock delegation and magic programs, see ephemeral_rollup.
+    pub async fn new_with_ephemeral_rollup() -> TestFixture {
+        TestFixture::new_with_options(true).await
+    }
+
+    async fn new_with_options(with_ephemeral_rollup: bool) -> TestFixture {
         let mut program: ProgramTest = ProgramTest::new(
             "manifest",
             manifest::ID,
             processor!(manifest::process_instruction),
         );
+        if with_ephemeral_rollup {
+            add_mock_ephemeral_rollup(&mut program);
+        }
 
         let second_keypair: Keypair = Keypair::new();
         program.add_account(
@@ -196,6 +246,7 @@ impl TestFixture {
             payer_sol_fixture,
             payer_usdc_fixture,
             second_keypair,
+            delegated_account_owners: HashMap::new(),
         }
     }
 
@@ -253,6 +304,28 @@ impl TestFixture {
         self.context.borrow_mut().set_sysvar(&clock);
     }
 
+    pub async fn get_unix_timestamp(&self) -> u32 {
+        let clock: Clock = self
+            .context
+            .borrow_mut()
+            .banks_client
+            .get_sysvar()
+            .await
+            .unwrap();
+        clock.unix_timestamp as u32
+    }
+
+    pub async fn get_slot(&self) -> u64 {
+        let clock: Clock = self
+            .context
+            .borrow_mut()
+            .banks_client
+            .get_sysvar()
+            .await
+            .unwrap();
+        clock.slot
+    }
+
     pub async fn create_new_market(
         &self,
         base_mint: &Pubkey,
@@ -276,6 +349,35 @@ impl TestFixture {
         Ok(market_keypair.pubkey())
     }
 
+    pub async fn create_new_market_with_params(
+        &self,
+        base_mint: &Pubkey,
+        quote_mint: &Pubkey,
+        params: CreateMarketParams,
+    ) -> anyhow::Result<Pubkey, BanksClientError> {
+        let market_keypair: Keypair = Keypair::new();
+        let payer: Pubkey = self.context.borrow().payer.pubkey();
+        let payer_keypair: Keypair = self.context.borrow().payer.insecure_clone();
+
+        let create_market_ixs: Vec<Instruction> = create_market_with_params_instructions(
+            &market_keypair.pubkey(),
+            base_mint,
+            quote_mint,
+            &payer,
+            params,
+        )
+        .unwrap();
+
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &create_market_ixs[..],
+            Some(&payer),
+            &[&payer_keypair, &market_keypair],
+        )
+        .await?;
+        Ok(market_keypair.pubkey())
+    }
+
     pub async fn claim_seat(&self) -> anyhow::Result<(), BanksClientError> {
         self.claim_seat_for_keypair(&self.payer_keypair()).await
     }
@@ -295,6 +397,399 @@ impl TestFixture {
         .await
     }
 
+    pub async fn claim_seat_v2_for_keypair(
+        &self,
+        payer_keypair: &Keypair,
+        owner_keypair: &Keypair,
+    ) -> anyhow::Result<(), BanksClientError> {
+        let claim_seat_ix: Instruction = claim_seat_v2_instruction(
+            &self.market_fixture.key,
+            &payer_keypair.pubkey(),
+            &owner_keypair.pubkey(),
+        );
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[claim_seat_ix],
+            Some(&payer_keypair.pubkey()),
+            &[payer_keypair, owner_keypair],
+        )
+        .await
+    }
+
+    pub async fn release_seat(&self) -> anyhow::Result<(), BanksClientError> {
+        self.release_seat_for_keypair(&self.payer_keypair()).await
+    }
+
+    pub async fn release_seat_for_keypair(
+        &self,
+        keypair: &Keypair,
+    ) -> anyhow::Result<(), BanksClientError> {
+        let release_seat_ix: Instruction =
+            release_seat_instruction(&self.market_fixture.key, &keypair.pubkey());
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[release_seat_ix],
+            Some(&keypair.pubkey()),
+            &[keypair],
+        )
+        .await
+    }
+
+    pub async fn expand_market(&self, num_blocks: u32) -> anyhow::Result<(), BanksClientError> {
+        let payer_keypair: Keypair = self.payer_keypair();
+        let expand_market_ix: Instruction = expand_market_by_instruction(
+            &self.market_fixture.key,
+            &payer_keypair.pubkey(),
+            num_blocks,
+        );
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[expand_market_ix],
+            Some(&payer_keypair.pubkey()),
+            &[&payer_keypair],
+        )
+        .await
+    }
+
+    pub async fn compact_market(&self, receiver: &Pubkey) -> anyhow::Result<(), BanksClientError> {
+        let payer_keypair: Keypair = self.payer_keypair();
+        let market_keypair: &Keypair = &self.market_fixture.keypair;
+        let compact_market_ix: Instruction = compact_market_instruction(
+            &self.market_fixture.key,
+            &market_keypair.pubkey(),
+            receiver,
+        );
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[compact_market_ix],
+            Some(&payer_keypair.pubkey()),
+            &[&payer_keypair, market_keypair],
+        )
+        .await
+    }
+
+    pub async fn compact_market_for_keypair(
+        &self,
+        receiver: &Pubkey,
+        keypair: &Keypair,
+    ) -> anyhow::Result<(), BanksClientError> {
+        let compact_market_ix: Instruction =
+            compact_market_instruction(&self.market_fixture.key, &keypair.pubkey(), receiver);
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[compact_market_ix],
+            Some(&keypair.pubkey()),
+            &[keypair],
+        )
+        .await
+    }
+
+    pub async fn init_market_stats(&self) -> anyhow::Result<(), BanksClientError> {
+        let payer_keypair: Keypair = self.payer_keypair();
+        let init_market_stats_ix: Instruction =
+            init_market_stats_instruction(&self.market_fixture.key, &payer_keypair.pubkey());
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[init_market_stats_ix],
+            Some(&payer_keypair.pubkey()),
+            &[&payer_keypair],
+        )
+        .await
+    }
+
+    pub async fn poke_twap(&self) -> anyhow::Result<(), BanksClientError> {
+        let payer_keypair: Keypair = self.payer_keypair();
+        let poke_twap_ix: Instruction =
+            poke_twap_instruction(&self.market_fixture.key, &payer_keypair.pubkey());
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[poke_twap_ix],
+            Some(&payer_keypair.pubkey()),
+            &[&payer_keypair],
+        )
+        .await
+    }
+
+    pub async fn init_market_metadata(
+        &self,
+        params: MarketMetadataParams,
+    ) -> anyhow::Result<(), BanksClientError> {
+        self.init_market_metadata_for_keypair(params, &self.payer_keypair())
+            .await
+    }
+
+    pub async fn init_market_metadata_for_keypair(
+        &self,
+        params: MarketMetadataParams,
+        keypair: &Keypair,
+    ) -> anyhow::Result<(), BanksClientError> {
+        let init_market_metadata_ix: Instruction =
+            init_market_metadata_instruction(&self.market_fixture.key, &keypair.pubkey(), params);
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[init_market_metadata_ix],
+            Some(&keypair.pubkey()),
+            &[keypair],
+        )
+        .await
+    }
+
+    pub async fn set_market_authority(
+        &self,
+        new_authority: &Pubkey,
+    ) -> anyhow::Result<(), BanksClientError> {
+        let payer_keypair: Keypair = self.payer_keypair();
+        let market_keypair: &Keypair = &self.market_fixture.keypair;
+        let set_market_authority_ix: Instruction = set_market_authority_instruction(
+            &self.market_fixture.key,
+            &payer_keypair.pubkey(),
+            &market_keypair.pubkey(),
+            new_authority,
+        );
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[set_market_authority_ix],
+            Some(&payer_keypair.pubkey()),
+            &[&payer_keypair, market_keypair],
+        )
+        .await
+    }
+
+    pub async fn set_market_authority_for_keypair(
+        &self,
+        new_authority: &Pubkey,
+        keypair: &Keypair,
+    ) -> anyhow::Result<(), BanksClientError> {
+        let set_market_authority_ix: Instruction = set_market_authority_instruction(
+            &self.market_fixture.key,
+            &keypair.pubkey(),
+            &keypair.pubkey(),
+            new_authority,
+        );
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[set_market_authority_ix],
+            Some(&keypair.pubkey()),
+            &[keypair],
+        )
+        .await
+    }
+
+    pub async fn update_market_metadata_for_keypair(
+        &self,
+        params: MarketMetadataParams,
+        keypair: &Keypair,
+    ) -> anyhow::Result<(), BanksClientError> {
+        let update_market_metadata_ix: Instruction =
+            update_market_metadata_instruction(&self.market_fixture.key, &keypair.pubkey(), params);
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[update_market_metadata_ix],
+            Some(&keypair.pubkey()),
+            &[keypair],
+        )
+        .await
+    }
+
+    pub async fn transfer_market_metadata_authority_for_keypair(
+        &self,
+        new_authority: &Pubkey,
+        keypair: &Keypair,
+    ) -> anyhow::Result<(), BanksClientError> {
+        let transfer_ix: Instruction = transfer_market_metadata_authority_instruction(
+            &self.market_fixture.key,
+            &keypair.pubkey(),
+            new_authority,
+        );
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[transfer_ix],
+            Some(&keypair.pubkey()),
+            &[keypair],
+        )
+        .await
+    }
+
+    pub async fn delegate_market_for_keypair(
+        &self,
+        params: DelegateMarketParams,
+        keypair: &Keypair,
+    ) -> anyhow::Result<(), BanksClientError> {
+        self.delegate_market_with_market_globals_for_keypair(params, &[], keypair)
+            .await
+    }
+
+    pub async fn delegate_market_with_market_globals_for_keypair(
+        &self,
+        params: DelegateMarketParams,
+        market_global_mints: &[Pubkey],
+        keypair: &Keypair,
+    ) -> anyhow::Result<(), BanksClientError> {
+        let delegate_market_ix: Instruction = delegate_market_instruction(
+            &self.market_fixture.key,
+            &keypair.pubkey(),
+            &self.sol_mint_fixture.key,
+            &self.usdc_mint_fixture.key,
+            market_global_mints,
+            params,
+        );
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[delegate_market_ix],
+            Some(&keypair.pubkey()),
+            &[keypair],
+        )
+        .await
+    }
+
+    pub async fn undelegate_market_for_keypair(
+        &self,
+        keypair: &Keypair,
+    ) -> anyhow::Result<(), BanksClientError> {
+        self.undelegate_market_with_market_globals_for_keypair(&[], keypair)
+            .await
+    }
+
+    pub async fn undelegate_market_with_market_globals_for_keypair(
+        &self,
+        market_global_mints: &[Pubkey],
+        keypair: &Keypair,
+    ) -> anyhow::Result<(), BanksClientError> {
+        let undelegate_market_ix: Instruction = undelegate_market_instruction(
+            &self.market_fixture.key,
+            &keypair.pubkey(),
+            &self.sol_mint_fixture.key,
+            &self.usdc_mint_fixture.key,
+            market_global_mints,
+        );
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[undelegate_market_ix],
+            Some(&keypair.pubkey()),
+            &[keypair],
+        )
+        .await
+    }
+
+    pub async fn commit_market_for_keypair(
+        &self,
+        keypair: &Keypair,
+    ) -> anyhow::Result<(), BanksClientError> {
+        self.commit_market_with_market_globals_for_keypair(&[], keypair)
+            .await
+    }
+
+    pub async fn commit_market_with_market_globals_for_keypair(
+        &self,
+        market_global_mints: &[Pubkey],
+        keypair: &Keypair,
+    ) -> anyhow::Result<(), BanksClientError> {
+        let commit_market_ix: Instruction = commit_market_instruction(
+            &self.market_fixture.key,
+            &keypair.pubkey(),
+            &self.sol_mint_fixture.key,
+            &self.usdc_mint_fixture.key,
+            market_global_mints,
+        );
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[commit_market_ix],
+            Some(&keypair.pubkey()),
+            &[keypair],
+        )
+        .await
+    }
+
+    /// Delegates the market, both vaults and the market globals for the
+    /// given mints to the mock delegation program, remembering who owned
+    /// each of them.
+    pub async fn delegate_market_to_rollup(
+        &mut self,
+        params: DelegateMarketParams,
+        market_global_mints: &[Pubkey],
+        keypair: &Keypair,
+    ) -> anyhow::Result<(), BanksClientError> {
+        let (base_vault, _) =
+            get_vault_address(&self.market_fixture.key, &self.sol_mint_fixture.key);
+        let (quote_vault, _) =
+            get_vault_address(&self.market_fixture.key, &self.usdc_mint_fixture.key);
+        let mut keys: Vec<Pubkey> = vec![self.market_fixture.key, base_vault, quote_vault];
+        for mint in market_global_mints {
+            keys.push(get_market_global_address(&self.market_fixture.key, mint).0);
+        }
+        for key in keys {
+            let account: Account = self.try_load(&key).await.unwrap().unwrap();
+            self.delegated_account_owners.insert(key, account.owner);
+        }
+        self.delegate_market_with_market_globals_for_keypair(params, market_global_mints, keypair)
+            .await
+    }
+
+    /// The rollup validator clones delegated accounts with the owner they had
+    /// before delegation. Transactions sent after this run as on the rollup.
+    pub async fn open_rollup(&self) {
+        for (key, owner) in self.delegated_account_owners.iter() {
+            self.set_account_owner(key, owner).await;
+        }
+    }
+
+    /// Sends what the mock magic program scheduled to the base layer, like
+    /// the rollup validator does. Committed accounts stay with the delegation
+    /// program, undelegated ones go back to their owner and lose their
+    /// delegation record. Data needs no copying since both layers are the
+    /// same bank.
+    pub async fn settle_rollup(&mut self) {
+        let magic_context: Account = self.try_load(&MAGIC_CONTEXT_ID).await.unwrap().unwrap();
+        for scheduled_commit in read_scheduled_commits(&magic_context.data) {
+            let (_buffer, delegation_record, _delegation_metadata) =
+                get_delegation_addresses(&scheduled_commit.account);
+            assert!(
+                self.try_load(&delegation_record).await.unwrap().is_some(),
+                "{} was committed without being delegated",
+                scheduled_commit.account
+            );
+            if scheduled_commit.undelegate {
+                let owner: Pubkey = self
+                    .delegated_account_owners
+                    .remove(&scheduled_commit.account)
+                    .unwrap();
+                self.set_account_owner(&scheduled_commit.account, &owner)
+                    .await;
+                self.context
+                    .borrow_mut()
+                    .set_account(&delegation_record, &AccountSharedData::default());
+            } else {
+                self.set_account_owner(&scheduled_commit.account, &DELEGATION_PROGRAM_ID)
+                    .await;
+            }
+        }
+        self.context.borrow_mut().set_account(
+            &MAGIC_CONTEXT_ID,
+            &AccountSharedData::from(empty_magic_context()),
+        );
+    }
+
+    /// Hands the market account to another owner, like the delegation
+    /// program does on the base layer while a market is delegated.
+    pub async fn set_market_owner(&self, owner: &Pubkey) {
+        self.set_account_owner(&self.market_fixture.key, owner)
+            .await;
+    }
+
+    async fn set_account_owner(&self, key: &Pubkey, owner: &Pubkey) {
+        let mut account: Account = self.try_load(key).await.unwrap().unwrap();
+        account.owner = *owner;
+        self.context
+            .borrow_mut()
+            .set_account(key, &AccountSharedData::from(account));
+    }
+
+    pub async fn get_market_metadata(&self) -> MarketMetadata {
+        let (market_metadata_key, _) = get_market_metadata_address(&self.market_fixture.key);
+        let account: Account = self.try_load(&market_metadata_key).await.unwrap().unwrap();
+        bytemuck::pod_read_unaligned::<MarketMetadata>(&account.data)
+    }
+
     pub async fn global_add_trader(&self) -> anyhow::Result<(), BanksClientError> {
         self.global_add_trader_for_keypair(&self.payer_keypair())
             .await
@@ -353,46 +848,107 @@ impl TestFixture {
         .await
     }
 
-    pub async fn global_withdraw(
-        &mut self,
-        num_atoms: u64,
-    ) -> anyhow::Result<(), BanksClientError> {
-        self.global_withdraw_for_keypair(&self.payer_keypair(), num_atoms)
-            .await
-    }
-
-    pub async fn global_withdraw_for_keypair(
-        &mut self,
+    pub async fn global_withdraw(
+        &mut self,
+        num_atoms: u64,
+    ) -> anyhow::Result<(), BanksClientError> {
+        self.global_withdraw_for_keypair(&self.payer_keypair(), num_atoms)
+            .await
+    }
+
+    pub async fn global_withdraw_for_keypair(
+        &mut self,
+        keypair: &Keypair,
+        num_atoms: u64,
+    ) -> anyhow::Result<(), BanksClientError> {
+        // Make a throw away token account
+        let token_account_keypair: Keypair = Keypair::new();
+        let token_account_fixture: TokenAccountFixture = TokenAccountFixture::new_with_keypair(
+            Rc::clone(&self.context),
+            &self.global_fixture.mint_key,
+            &keypair.pubkey(),
+            &token_account_keypair,
+        )
+        .await;
+        self.usdc_mint_fixture
+            .mint_to(&token_account_fixture.key, num_atoms)
+            .await;
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[global_withdraw_instruction(
+                &self.global_fixture.mint_key,
+                &keypair.pubkey(),
+                &token_account_fixture.key,
+                &spl_token::id(),
+                num_atoms,
+            )],
+            Some(&keypair.pubkey()),
+            &[&keypair],
+        )
+        .await
+    }
+
+    pub async fn market_global_create(&self) -> anyhow::Result<(), BanksClientError> {
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[market_global_create_instruction(
+                &self.market_fixture.key,
+                &self.global_fixture.mint_key,
+                &self.payer(),
+            )],
+            Some(&self.payer()),
+            &[&self.payer_keypair()],
+        )
+        .await
+    }
+
+    pub async fn market_global_deposit_for_keypair(
+        &self,
+        keypair: &Keypair,
+        num_atoms: u64,
+    ) -> anyhow::Result<(), BanksClientError> {
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[market_global_deposit_instruction(
+                &self.market_fixture.key,
+                &self.global_fixture.mint_key,
+                &keypair.pubkey(),
+                &spl_token::id(),
+                num_atoms,
+            )],
+            Some(&keypair.pubkey()),
+            &[keypair],
+        )
+        .await
+    }
+
+    pub async fn market_global_withdraw_for_keypair(
+        &self,
         keypair: &Keypair,
         num_atoms: u64,
     ) -> anyhow::Result<(), BanksClientError> {
-        // Make a throw away token account
-        let token_account_keypair: Keypair = Keypair::new();
-        let token_account_fixture: TokenAccountFixture = TokenAccountFixture::new_with_keypair(
-            Rc::clone(&self.context),
-            &self.global_fixture.mint_key,
-            &keypair.pubkey(),
-            &token_account_keypair,
-        )
-        .await;
-        self.usdc_mint_fixture
-            .mint_to(&token_account_fixture.key, num_atoms)
-            .await;
         send_tx_with_retry(
             Rc::clone(&self.context),
-            &[global_withdraw_instruction(
+            &[market_global_withdraw_instruction(
+                &self.market_fixture.key,
                 &self.global_fixture.mint_key,
                 &keypair.pubkey(),
-                &token_account_fixture.key,
                 &spl_token::id(),
                 num_atoms,
             )],
             Some(&keypair.pubkey()),
-            &[&keypair],
+            &[keypair],
         )
         .await
     }
 
+    pub async fn get_market_global(&self) -> GlobalValue {
+        let (market_global_key, _) =
+            get_market_global_address(&self.market_fixture.key, &self.global_fixture.mint_key);
+        let account: Account = self.try_load(&market_global_key).await.unwrap().unwrap();
+        get_dynamic_value(account.data.as_slice())
+    }
+
     pub async fn deposit(
         &mut self,
         token: Token,
@@ -640,6 +1196,48 @@ impl TestFixture {
         .await
     }
 
+    // Same as swap_v2, but fails when a fill is outside of the band around the
+    // oracle price.
+    pub async fn swap_v2_with_oracle_price_band(
+        &mut self,
+        in_atoms: u64,
+        out_atoms: u64,
+        is_base_in: bool,
+        is_exact_in: bool,
+        oracle: &Pubkey,
+        oracle_price_band: OraclePriceBandParams,
+    ) -> anyhow::Result<(), BanksClientError> {
+        let payer: Pubkey = self.context.borrow().payer.pubkey();
+        let payer_keypair: Keypair = self.context.borrow().payer.insecure_clone();
+
+        let swap_ix: Instruction = swap_v2_with_oracle_price_band_instruction(
+            &self.market_fixture.key,
+            &self.second_keypair.pubkey(),
+            &payer,
+            &self.sol_mint_fixture.key,
+            &self.usdc_mint_fixture.key,
+            &self.payer_sol_fixture.key,
+            &self.payer_usdc_fixture.key,
+            in_atoms,
+            out_atoms,
+            is_base_in,
+            is_exact_in,
+            spl_token::id(),
+            spl_token::id(),
+            false,
+            oracle,
+            oracle_price_band,
+        );
+
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[swap_ix],
+            Some(&self.second_keypair.pubkey()),
+            &[&payer_keypair, &self.second_keypair.insecure_clone()],
+        )
+        .await
+    }
+
     pub async fn swap(
         &mut self,
         in_atoms: u64,
@@ -734,6 +1332,107 @@ impl TestFixture {
         .await
     }
 
+    pub async fn cancel_all_for_keypair(
+        &mut self,
+        is_bid_opt: Option<bool>,
+        keypair: &Keypair,
+    ) -> anyhow::Result<(), BanksClientError> {
+        let cancel_all_ix: Instruction = cancel_all_instruction(
+            &self.market_fixture.key,
+            &keypair.pubkey(),
+            None,
+            is_bid_opt,
+            Some(*self.market_fixture.market.get_base_mint()),
+            None,
+            Some(*self.market_fixture.market.get_quote_mint()),
+            None,
+        );
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[cancel_all_ix],
+            Some(&keypair.pubkey()),
+            &[keypair],
+        )
+        .await
+    }
+
+    pub async fn clean_expired_for_keypair(
+        &mut self,
+        order_indices: Vec<DataIndex>,
+        num_orders_to_scan: u16,
+        keypair: &Keypair,
+    ) -> anyhow::Result<(), BanksClientError> {
+        let clean_expired_ix: Instruction = clean_expired_instruction(
+            &self.market_fixture.key,
+            &keypair.pubkey(),
+            order_indices,
+            num_orders_to_scan,
+        );
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[clean_expired_ix],
+            Some(&keypair.pubkey()),
+            &[keypair],
+        )
+        .await
+    }
+
+    pub async fn place_trigger_order_for_keypair(
+        &mut self,
+        params: PlaceTriggerOrderParams,
+        keypair: &Keypair,
+    ) -> anyhow::Result<(), BanksClientError> {
+        let place_trigger_order_ix: Instruction =
+            place_trigger_order_instruction(&self.market_fixture.key, &keypair.pubkey(), params);
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[place_trigger_order_ix],
+            Some(&keypair.pubkey()),
+            &[keypair],
+        )
+        .await
+    }
+
+    pub async fn cancel_trigger_order_for_keypair(
+        &mut self,
+        order_sequence_number: u64,
+        keypair: &Keypair,
+    ) -> anyhow::Result<(), BanksClientError> {
+        let cancel_trigger_order_ix: Instruction = cancel_trigger_order_instruction(
+            &self.market_fixture.key,
+            &keypair.pubkey(),
+            order_sequence_number,
+        );
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[cancel_trigger_order_ix],
+            Some(&keypair.pubkey()),
+            &[keypair],
+        )
+        .await
+    }
+
+    pub async fn crank_trigger_orders_for_keypair(
+        &mut self,
+        order_indices: Vec<DataIndex>,
+        peg_oracle_opt: Option<&Pubkey>,
+        keypair: &Keypair,
+    ) -> anyhow::Result<(), BanksClientError> {
+        let crank_trigger_orders_ix: Instruction = crank_trigger_orders_instruction(
+            &self.market_fixture.key,
+            &keypair.pubkey(),
+            order_indices,
+            peg_oracle_opt,
+        );
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[crank_trigger_orders_ix],
+            Some(&keypair.pubkey()),
+            &[keypair],
+        )
+        .await
+    }
+
     pub async fn batch_update_for_keypair(
         &mut self,
         trader_index_hint: Option<DataIndex>,
@@ -761,6 +1460,95 @@ impl TestFixture {
         .await
     }
 
+    pub async fn batch_update_v2_for_keypair(
+        &mut self,
+        trader_index_hint: Option<DataIndex>,
+        cancels: Vec<CancelOrderParams>,
+        orders: Vec<PlaceOrderParams>,
+        payer_keypair: &Keypair,
+        owner_keypair: &Keypair,
+    ) -> anyhow::Result<(), BanksClientError> {
+        let batch_update_ix: Instruction = batch_update_v2_instruction(
+            &self.market_fixture.key,
+            &payer_keypair.pubkey(),
+            &owner_keypair.pubkey(),
+            trader_index_hint,
+            cancels,
+            orders,
+            None,
+            None,
+            None,
+            None,
+        );
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[batch_update_ix],
+            Some(&payer_keypair.pubkey()),
+            &[payer_keypair, owner_keypair],
+        )
+        .await
+    }
+
+    pub async fn batch_update_with_modifies_for_keypair(
+        &mut self,
+        trader_index_hint: Option<DataIndex>,
+        cancels: Vec<CancelOrderParams>,
+        modifies: Vec<ModifyOrderParams>,
+        orders: Vec<PlaceOrderParams>,
+        keypair: &Keypair,
+    ) -> anyhow::Result<(), BanksClientError> {
+        let batch_update_ix: Instruction = batch_update_with_modifies_instruction(
+            &self.market_fixture.key,
+            &keypair.pubkey(),
+            trader_index_hint,
+            cancels,
+            modifies,
+            orders,
+            None,
+            None,
+            None,
+            None,
+        );
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[batch_update_ix],
+            Some(&keypair.pubkey()),
+            &[keypair],
+        )
+        .await
+    }
+
+    // The peg oracle goes after the other accounts, it is found by key.
+    pub async fn batch_update_with_peg_oracle_for_keypair(
+        &mut self,
+        cancels: Vec<CancelOrderParams>,
+        orders: Vec<PlaceOrderParams>,
+        oracle: &Pubkey,
+        keypair: &Keypair,
+    ) -> anyhow::Result<(), BanksClientError> {
+        let mut batch_update_ix: Instruction = batch_update_instruction(
+            &self.market_fixture.key,
+            &keypair.pubkey(),
+            None,
+            cancels,
+            orders,
+            None,
+            None,
+            None,
+            None,
+        );
+        batch_update_ix
+            .accounts
+            .push(AccountMeta::new_readonly(*oracle, false));
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[batch_update_ix],
+            Some(&keypair.pubkey()),
+            &[keypair],
+        )
+        .await
+    }
+
     pub async fn batch_update_with_global_for_keypair(
         &mut self,
         trader_index_hint: Option<DataIndex>,
@@ -788,12 +1576,42 @@ impl TestFixture {
         )
         .await
     }
+
+    pub async fn batch_update_with_market_globals_for_keypair(
+        &mut self,
+        trader_index_hint: Option<DataIndex>,
+        cancels: Vec<CancelOrderParams>,
+        orders: Vec<PlaceOrderParams>,
+        keypair: &Keypair,
+    ) -> anyhow::Result<(), BanksClientError> {
+        let batch_update_ix: Instruction = batch_update_with_market_globals_instruction(
+            &self.market_fixture.key,
+            &keypair.pubkey(),
+            trader_index_hint,
+            cancels,
+            orders,
+            Some(*self.market_fixture.market.get_base_mint()),
+            None,
+            Some(*self.market_fixture.market.get_quote_mint()),
+            None,
+        );
+
+        send_tx_with_retry(
+            Rc::clone(&self.context),
+            &[batch_update_ix],
+            Some(&keypair.pubkey()),
+            &[keypair],
+        )
+        .await
+    }
 }
 
 #[derive(Clone)]
 pub struct MarketFixture {
     pub context: Rc<RefCell<ProgramTestContext>>,
     pub key: Pubkey,
+    // Signs as the market authority until SetMarketAuthority.
+    pub keypair: Rc<Keypair>,
     pub market: MarketValue,
 }
 
@@ -870,6 +1688,7 @@ impl MarketFixture {
                 fixed: MarketFixed::new_empty(&base_mint, &quote_mint, &market_keypair.pubkey()),
                 dynamic: Vec::new(),
             },
+            keypair: Rc::new(market_keypair),
         }
     }
 
@@ -919,6 +1738,19 @@ impl MarketFixture {
         bids_vec.extend(asks_vec);
         bids_vec
     }
+
+    /// Trigger orders with their indices, lowest trigger price first.
+    pub async fn get_trigger_orders(&mut self) -> Vec<(DataIndex, TriggerOrder)> {
+        self.reload().await;
+        let mut trigger_orders: Vec<(DataIndex, TriggerOrder)> = self
+            .market
+            .get_trigger_orders()
+            .iter::<TriggerOrder>()
+            .map(|(index, trigger_order)| (index, *trigger_order))
+            .collect();
+        trigger_orders.reverse();
+        trigger_orders
+    }
 }
 
 #[derive(Clone)]
@@ -1280,6 +2112,64 @@ impl TokenAccountFixture {
     }
 }
 
+/// Local stand in for oracle price accounts. Writes the Pyth and Switchboard
+/// layouts directly, owned by their programs, so the oracle programs do not
+/// need to be loaded.
+pub struct MockOracleFixture {
+    pub context: Rc<RefCell<ProgramTestContext>>,
+    pub key: Pubkey,
+}
+
+impl MockOracleFixture {
+    pub fn new(context: Rc<RefCell<ProgramTestContext>>) -> Self {
+        MockOracleFixture {
+            context,
+            key: Pubkey::new_unique(),
+        }
+    }
+
+    pub fn set_pyth_price(&self, price: i64, expo: i32, publish_slot: u64) {
+        let mut data: Vec<u8> = vec![0; 3312];
+        data[0..4].copy_from_slice(&0xa1b2c3d4_u32.to_le_bytes());
+        // Version 2, price account.
+        data[4..8].copy_from_slice(&2_u32.to_le_bytes());
+        data[8..12].copy_from_slice(&3_u32.to_le_bytes());
+        data[20..24].copy_from_slice(&expo.to_le_bytes());
+        data[208..216].copy_from_slice(&price.to_le_bytes());
+        // Trading status.
+        data[224..228].copy_from_slice(&1_u32.to_le_bytes());
+        data[232..240].copy_from_slice(&publish_slot.to_le_bytes());
+        self.set_account(PYTH_PROGRAM_ID, data);
+    }
+
+    pub fn set_switchboard_price(&self, mantissa: i128, scale: u32, round_open_slot: u64) {
+        let mut data: Vec<u8> = vec![0; 3851];
+        data[0..8].copy_from_slice(&[217, 230, 65, 101, 201, 162, 27, 125]);
+        data[350..358].copy_from_slice(&round_open_slot.to_le_bytes());
+        data[366..382].copy_from_slice(&mantissa.to_le_bytes());
+        data[382..386].copy_from_slice(&scale.to_le_bytes());
+        self.set_account(SWITCHBOARD_PROGRAM_ID, data);
+    }
+
+    fn set_account(&self, owner: Pubkey, data: Vec<u8>) {
+        let account: Account = Account {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.context
+            .borrow_mut()
+            .set_account(&self.key, &AccountSharedData::from(account));
+    }
+}
+
+/// Price from a mantissa and exponent that are known to be valid.
+pub fn price(mantissa: u32, exponent: i8) -> QuoteAtomsPerBaseAtom {
+    QuoteAtomsPerBaseAtom::try_from_mantissa_and_exponent(mantissa, exponent).unwrap()
+}
+
 pub async fn get_and_deserialize<T: Pack>(
     context: Rc<RefCell<ProgramTestContext>>,
     pubkey: Pubkey,
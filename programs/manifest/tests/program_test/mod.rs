@@ -1,3 +1,5 @@
+pub mod ephemeral_rollup;
 pub mod fixtures;
 
+pub use ephemeral_rollup::*;
 pub use fixtures::*;
@@ -1,564 +0,0 @@
-use borsh::{BorshDeserialize, BorshSerialize};
-use manifest::program::{
-    instruction::ManifestInstruction, processor::delegate_market::DelegateMarketParams,
-};
-use solana_program::{
-    instruction::{AccountMeta, Instruction},
-    pubkey::Pubkey,
-    system_program,
-};
-use solana_program_test::tokio;
-use solana_sdk::{signature::Keypair, signer::Signer};
-use std::rc::Rc;
-
-mod program_test;
-use manifest::state::{OrderType, NO_EXPIRATION_LAST_VALID_SLOT};
-use program_test::fixtures::{send_tx_with_retry, TestFixture};
-
-// Mock MagicBlock program IDs for testing
-const MOCK_DELEGATION_PROGRAM_ID: Pubkey = Pubkey::new_from_array([1; 32]);
-const MOCK_MAGIC_PROGRAM_ID: Pubkey = Pubkey::new_from_array([2; 32]);
-const MOCK_MAGIC_CONTEXT_ID: Pubkey = Pubkey::new_from_array([3; 32]);
-
-#[test]
-fn test_delegate_market_instruction_creation() {
-    let program_id = manifest::id();
-    let payer = Pubkey::new_unique();
-    let market = Pubkey::new_unique();
-    let delegation_buffer = Pubkey::new_unique();
-    let delegation_record = Pubkey::new_unique();
-    let delegation_metadata = Pubkey::new_unique();
-
-    // Test delegate market instruction creation
-    let delegate_params = DelegateMarketParams {
-        update_frequency_ms: 5000, // 5 seconds
-        time_limit: 0,             // No time limit
-    };
-
-    let delegate_instruction = create_delegate_market_instruction(
-        &program_id,
-        &payer,
-        &market,
-        &delegation_buffer,
-        &delegation_record,
-        &delegation_metadata,
-        &MOCK_DELEGATION_PROGRAM_ID,
-        delegate_params,
-    );
-
-    // Validate instruction structure
-    assert_eq!(delegate_instruction.program_id, program_id);
-    assert_eq!(delegate_instruction.accounts.len(), 8);
-    assert_eq!(delegate_instruction.accounts[0].pubkey, payer);
-    assert_eq!(delegate_instruction.accounts[0].is_signer, true);
-    assert_eq!(delegate_instruction.accounts[2].pubkey, market);
-
-    // Validate instruction data
-    assert_eq!(
-        delegate_instruction.data[0],
-        ManifestInstruction::DelegateMarket as u8
-    );
-
-    println!("✅ Delegate market instruction creation test passed");
-}
-
-#[test]
-fn test_undelegate_market_instruction_creation() {
-    let program_id = manifest::id();
-    let payer = Pubkey::new_unique();
-    let market = Pubkey::new_unique();
-
-    let undelegate_instruction = create_undelegate_market_instruction(
-        &program_id,
-        &payer,
-        &market,
-        &MOCK_MAGIC_CONTEXT_ID,
-        &MOCK_MAGIC_PROGRAM_ID,
-    );
-
-    // Validate instruction structure
-    assert_eq!(undelegate_instruction.program_id, program_id);
-    assert_eq!(undelegate_instruction.accounts.len(), 4);
-    assert_eq!(undelegate_instruction.accounts[0].pubkey, payer);
-    assert_eq!(undelegate_instruction.accounts[0].is_signer, true);
-    assert_eq!(undelegate_instruction.accounts[1].pubkey, market);
-
-    // Validate instruction data
-    assert_eq!(
-        undelegate_instruction.data[0],
-        ManifestInstruction::UndelegateMarket as u8
-    );
-
-    println!("✅ Undelegate market instruction creation test passed");
-}
-
-#[test]
-fn test_commit_market_instruction_creation() {
-    let program_id = manifest::id();
-    let payer = Pubkey::new_unique();
-    let market = Pubkey::new_unique();
-
-    let commit_instruction = create_commit_market_instruction(
-        &program_id,
-        &payer,
-        &market,
-        &MOCK_MAGIC_CONTEXT_ID,
-        &MOCK_MAGIC_PROGRAM_ID,
-    );
-
-    // Validate instruction structure
-    assert_eq!(commit_instruction.program_id, program_id);
-    assert_eq!(commit_instruction.accounts.len(), 4);
-    assert_eq!(commit_instruction.accounts[0].pubkey, payer);
-    assert_eq!(commit_instruction.accounts[0].is_signer, true);
-    assert_eq!(commit_instruction.accounts[1].pubkey, market);
-
-    // Validate instruction data
-    assert_eq!(
-        commit_instruction.data[0],
-        ManifestInstruction::CommitMarket as u8
-    );
-
-    println!("✅ Commit market instruction creation test passed");
-}
-
-#[test]
-fn test_delegate_market_params_serialization() {
-    let params = DelegateMarketParams {
-        update_frequency_ms: 10000,
-        time_limit: 3600,
-    };
-
-    let serialized = params.try_to_vec().unwrap();
-    let deserialized = DelegateMarketParams::try_from_slice(&serialized).unwrap();
-
-    assert_eq!(params.update_frequency_ms, deserialized.update_frequency_ms);
-    assert_eq!(params.time_limit, deserialized.time_limit);
-
-    println!("✅ Delegate market params serialization test passed");
-}
-
-// Integration tests that actually execute the MagicBlock functionality
-#[tokio::test]
-async fn test_delegate_market_integration() -> anyhow::Result<()> {
-    let mut test_fixture = TestFixture::new().await;
-    test_fixture.claim_seat().await?;
-
-    // Create delegation accounts
-    let delegation_buffer = Keypair::new();
-    let delegation_record = Keypair::new();
-    let delegation_metadata = Keypair::new();
-
-    let delegate_params = DelegateMarketParams {
-        update_frequency_ms: 5000, // 5 seconds
-        time_limit: 0,             // No time limit
-    };
-
-    let delegate_instruction = create_delegate_market_instruction(
-        &manifest::id(),
-        &test_fixture.payer(),
-        &test_fixture.market_fixture.key,
-        &delegation_buffer.pubkey(),
-        &delegation_record.pubkey(),
-        &delegation_metadata.pubkey(),
-        &MOCK_DELEGATION_PROGRAM_ID,
-        delegate_params,
-    );
-
-    // Note: This test will fail with actual MagicBlock SDK calls since we're using mock program IDs
-    // In a real environment, you would use actual MagicBlock program IDs
-    let result = send_tx_with_retry(
-        Rc::clone(&test_fixture.context),
-        &[delegate_instruction],
-        Some(&test_fixture.payer()),
-        &[&test_fixture.payer_keypair().insecure_clone()],
-    )
-    .await;
-
-    // We expect this to fail with mock program IDs, but the instruction should be well-formed
-    assert!(
-        result.is_err(),
-        "Expected delegation to fail with mock program IDs"
-    );
-
-    println!("✅ Delegate market integration test completed (expected failure with mock IDs)");
-    Ok(())
-}
-
-#[tokio::test]
-async fn test_undelegate_market_integration() -> anyhow::Result<()> {
-    let mut test_fixture = TestFixture::new().await;
-    test_fixture.claim_seat().await?;
-
-    let undelegate_instruction = create_undelegate_market_instruction(
-        &manifest::id(),
-        &test_fixture.payer(),
-        &test_fixture.market_fixture.key,
-        &MOCK_MAGIC_CONTEXT_ID,
-        &MOCK_MAGIC_PROGRAM_ID,
-    );
-
-    // Note: This test will fail with actual MagicBlock SDK calls since we're using mock program IDs
-    let result = send_tx_with_retry(
-        Rc::clone(&test_fixture.context),
-        &[undelegate_instruction],
-        Some(&test_fixture.payer()),
-        &[&test_fixture.payer_keypair().insecure_clone()],
-    )
-    .await;
-
-    // We expect this to fail with mock program IDs, but the instruction should be well-formed
-    assert!(
-        result.is_err(),
-        "Expected undelegation to fail with mock program IDs"
-    );
-
-    println!("✅ Undelegate market integration test completed (expected failure with mock IDs)");
-    Ok(())
-}
-
-#[tokio::test]
-async fn test_commit_market_integration() -> anyhow::Result<()> {
-    let mut test_fixture = TestFixture::new().await;
-    test_fixture.claim_seat().await?;
-
-    let commit_instruction = create_commit_market_instruction(
-        &manifest::id(),
-        &test_fixture.payer(),
-        &test_fixture.market_fixture.key,
-        &MOCK_MAGIC_CONTEXT_ID,
-        &MOCK_MAGIC_PROGRAM_ID,
-    );
-
-    // Note: This test will fail with actual MagicBlock SDK calls since we're using mock program IDs
-    let result = send_tx_with_retry(
-        Rc::clone(&test_fixture.context),
-        &[commit_instruction],
-        Some(&test_fixture.payer()),
-        &[&test_fixture.payer_keypair().insecure_clone()],
-    )
-    .await;
-
-    // We expect this to fail with mock program IDs, but the instruction should be well-formed
-    assert!(
-        result.is_err(),
-        "Expected commit to fail with mock program IDs"
-    );
-
-    println!("✅ Commit market integration test completed (expected failure with mock IDs)");
-    Ok(())
-}
-
-#[tokio::test]
-async fn test_delegate_market_params_validation() -> anyhow::Result<()> {
-    let mut test_fixture = TestFixture::new().await;
-    test_fixture.claim_seat().await?;
-
-    // Test with invalid parameters (extremely high frequency)
-    let invalid_params = DelegateMarketParams {
-        update_frequency_ms: 0, // Invalid: 0ms frequency
-        time_limit: 0,
-    };
-
-    let delegation_buffer = Keypair::new();
-    let delegation_record = Keypair::new();
-    let delegation_metadata = Keypair::new();
-
-    let delegate_instruction = create_delegate_market_instruction(
-        &manifest::id(),
-        &test_fixture.payer(),
-        &test_fixture.market_fixture.key,
-        &delegation_buffer.pubkey(),
-        &delegation_record.pubkey(),
-        &delegation_metadata.pubkey(),
-        &MOCK_DELEGATION_PROGRAM_ID,
-        invalid_params,
-    );
-
-    let result = send_tx_with_retry(
-        Rc::clone(&test_fixture.context),
-        &[delegate_instruction],
-        Some(&test_fixture.payer()),
-        &[&test_fixture.payer_keypair().insecure_clone()],
-    )
-    .await;
-
-    // Should fail due to invalid parameters or mock program IDs
-    assert!(
-        result.is_err(),
-        "Expected delegation with invalid params to fail"
-    );
-
-    println!("✅ Delegate market params validation test completed");
-    Ok(())
-}
-
-#[tokio::test]
-async fn test_magicblock_workflow_simulation() -> anyhow::Result<()> {
-    // This test simulates the complete MagicBlock workflow:
-    // 1. Create market
-    // 2. Delegate to ephemeral rollup
-    // 3. Perform trading operations (would be faster on rollup)
-    // 4. Commit state periodically
-    // 5. Undelegate when done
-
-    let mut test_fixture = TestFixture::new().await;
-    test_fixture.claim_seat().await?;
-
-    // Step 1: Market is already created by test fixture
-    println!("📊 Market created: {}", test_fixture.market_fixture.key);
-
-    // Step 2: Attempt delegation (will fail with mock IDs but validates instruction)
-    let delegation_buffer = Keypair::new();
-    let delegation_record = Keypair::new();
-    let delegation_metadata = Keypair::new();
-
-    let delegate_params = DelegateMarketParams {
-        update_frequency_ms: 1000, // 1 second for fast testing
-        time_limit: 3600,          // 1 hour limit
-    };
-
-    let delegate_instruction = create_delegate_market_instruction(
-        &manifest::id(),
-        &test_fixture.payer(),
-        &test_fixture.market_fixture.key,
-        &delegation_buffer.pubkey(),
-        &delegation_record.pubkey(),
-        &delegation_metadata.pubkey(),
-        &MOCK_DELEGATION_PROGRAM_ID,
-        delegate_params,
-    );
-
-    println!("🔄 Attempting delegation to ephemeral rollup...");
-    let delegate_result = send_tx_with_retry(
-        Rc::clone(&test_fixture.context),
-        &[delegate_instruction],
-        Some(&test_fixture.payer()),
-        &[&test_fixture.payer_keypair().insecure_clone()],
-    )
-    .await;
-
-    // Expected to fail with mock program IDs
-    assert!(
-        delegate_result.is_err(),
-        "Expected delegation to fail with mock program IDs"
-    );
-    println!("❌ Delegation failed as expected (mock program IDs)");
-
-    // Step 3: Simulate trading operations that would benefit from ephemeral rollup
-    // In a real scenario, these would execute with 10ms latency on the rollup
-    println!("💱 Simulating high-frequency trading operations...");
-
-    // Deposit funds for trading
-    test_fixture
-        .deposit(program_test::fixtures::Token::SOL, 1_000_000_000)
-        .await?;
-    test_fixture
-        .deposit(program_test::fixtures::Token::USDC, 1_000_000)
-        .await?;
-
-    // Place multiple orders (these would be much faster on ephemeral rollup)
-    for i in 0..3 {
-        let price = 100 + i; // Varying prices
-        test_fixture
-            .place_order(
-                program_test::fixtures::Side::Bid,
-                1_000_000,    // 1 SOL in base atoms
-                price as u32, // price mantissa
-                0,            // price exponent
-                0,            // NO_EXPIRATION_LAST_VALID_SLOT
-                manifest::state::OrderType::Limit,
-            )
-            .await?;
-        println!("📈 Placed bid order #{} at price {}", i + 1, price);
-    }
-
-    // Step 4: Simulate periodic commits (would sync state to base layer)
-    println!("💾 Simulating periodic state commits...");
-    let commit_instruction = create_commit_market_instruction(
-        &manifest::id(),
-        &test_fixture.payer(),
-        &test_fixture.market_fixture.key,
-        &MOCK_MAGIC_CONTEXT_ID,
-        &MOCK_MAGIC_PROGRAM_ID,
-    );
-
-    let commit_result = send_tx_with_retry(
-        Rc::clone(&test_fixture.context),
-        &[commit_instruction],
-        Some(&test_fixture.payer()),
-        &[&test_fixture.payer_keypair().insecure_clone()],
-    )
-    .await;
-
-    assert!(
-        commit_result.is_err(),
-        "Expected commit to fail with mock program IDs"
-    );
-    println!("❌ Commit failed as expected (mock program IDs)");
-
-    // Step 5: Simulate undelegation
-    println!("🔚 Simulating undelegation from ephemeral rollup...");
-    let undelegate_instruction = create_undelegate_market_instruction(
-        &manifest::id(),
-        &test_fixture.payer(),
-        &test_fixture.market_fixture.key,
-        &MOCK_MAGIC_CONTEXT_ID,
-        &MOCK_MAGIC_PROGRAM_ID,
-    );
-
-    let undelegate_result = send_tx_with_retry(
-        Rc::clone(&test_fixture.context),
-        &[undelegate_instruction],
-        Some(&test_fixture.payer()),
-        &[&test_fixture.payer_keypair().insecure_clone()],
-    )
-    .await;
-
-    assert!(
-        undelegate_result.is_err(),
-        "Expected undelegation to fail with mock program IDs"
-    );
-    println!("❌ Undelegation failed as expected (mock program IDs)");
-
-    println!("✅ Complete MagicBlock workflow simulation completed");
-    println!("📝 Note: In production, use real MagicBlock program IDs for actual delegation");
-
-    Ok(())
-}
-
-#[tokio::test]
-async fn test_delegation_account_validation() -> anyhow::Result<()> {
-    let test_fixture = TestFixture::new().await;
-
-    // Test with invalid market account (wrong program owner)
-    let fake_market = Keypair::new();
-    let delegation_buffer = Keypair::new();
-    let delegation_record = Keypair::new();
-    let delegation_metadata = Keypair::new();
-
-    let delegate_params = DelegateMarketParams {
-        update_frequency_ms: 5000,
-        time_limit: 0,
-    };
-
-    let delegate_instruction = create_delegate_market_instruction(
-        &manifest::id(),
-        &test_fixture.payer(),
-        &fake_market.pubkey(), // Invalid market account
-        &delegation_buffer.pubkey(),
-        &delegation_record.pubkey(),
-        &delegation_metadata.pubkey(),
-        &MOCK_DELEGATION_PROGRAM_ID,
-        delegate_params,
-    );
-
-    let result = send_tx_with_retry(
-        Rc::clone(&test_fixture.context),
-        &[delegate_instruction],
-        Some(&test_fixture.payer()),
-        &[&test_fixture.payer_keypair().insecure_clone()],
-    )
-    .await;
-
-    // Should fail due to invalid market account
-    assert!(
-        result.is_err(),
-        "Expected delegation with invalid market to fail"
-    );
-
-    println!("✅ Delegation account validation test completed");
-    Ok(())
-}
-
-// Helper functions to create instructions
-fn create_delegate_market_instruction(
-    program_id: &Pubkey,
-    payer: &Pubkey,
-    market: &Pubkey,
-    base_vault: &Pubkey,
-    quote_vault: &Pubkey,
-    base_mint: &Pubkey,
-    quote_mint: &Pubkey,
-    market_delegation_buffer: &Pubkey,
-    market_delegation_record: &Pubkey,
-    market_delegation_metadata: &Pubkey,
-    base_vault_delegation_buffer: &Pubkey,
-    base_vault_delegation_record: &Pubkey,
-    base_vault_delegation_metadata: &Pubkey,
-    quote_vault_delegation_buffer: &Pubkey,
-    quote_vault_delegation_record: &Pubkey,
-    quote_vault_delegation_metadata: &Pubkey,
-    delegation_program: &Pubkey,
-    params: DelegateMarketParams,
-) -> Instruction {
-    let mut instruction_data = vec![ManifestInstruction::DelegateMarket as u8];
-    instruction_data.extend_from_slice(&params.try_to_vec().unwrap());
-
-    Instruction {
-        program_id: *program_id,
-        accounts: vec![
-            AccountMeta::new(*payer, true),                            // payer
-            AccountMeta::new_readonly(system_program::id(), false),    // system_program
-            AccountMeta::new(*market, false),                          // market
-            AccountMeta::new_readonly(*program_id, false),             // owner_program
-            AccountMeta::new(*base_vault, false),                      // base_vault
-            AccountMeta::new(*quote_vault, false),                     // quote_vault
-            AccountMeta::new_readonly(*base_mint, false),              // base_mint
-            AccountMeta::new_readonly(*quote_mint, false),             // quote_mint
-            AccountMeta::new(*market_delegation_buffer, false),        // market_delegation_buffer
-            AccountMeta::new(*market_delegation_record, false),        // market_delegation_record
-            AccountMeta::new(*market_delegation_metadata, false),      // market_delegation_metadata
-            AccountMeta::new(*base_vault_delegation_buffer, false), // base_vault_delegation_buffer
-            AccountMeta::new(*base_vault_delegation_record, false), // base_vault_delegation_record
-            AccountMeta::new(*base_vault_delegation_metadata, false), // base_vault_delegation_metadata
-            AccountMeta::new(*quote_vault_delegation_buffer, false), // quote_vault_delegation_buffer
-            AccountMeta::new(*quote_vault_delegation_record, false), // quote_vault_delegation_record
-            AccountMeta::new(*quote_vault_delegation_metadata, false), // quote_vault_delegation_metadata
-            AccountMeta::new_readonly(*delegation_program, false),     // delegation_program
-        ],
-        data: instruction_data,
-    }
-}
-
-fn create_undelegate_market_instruction(
-    program_id: &Pubkey,
-    payer: &Pubkey,
-    market: &Pubkey,
-    magic_context: &Pubkey,
-    magic_program: &Pubkey,
-) -> Instruction {
-    let instruction_data = vec![ManifestInstruction::UndelegateMarket as u8];
-
-    Instruction {
-        program_id: *program_id,
-        accounts: vec![
-            AccountMeta::new(*payer, true),                   // payer
-            AccountMeta::new(*market, false),                 // market
-            AccountMeta::new_readonly(*magic_context, false), // magic_context
-            AccountMeta::new_readonly(*magic_program, false), // magic_program
-        ],
-        data: instruction_data,
-    }
-}
-
-fn create_commit_market_instruction(
-    program_id: &Pubkey,
-    payer: &Pubkey,
-    market: &Pubkey,
-    magic_context: &Pubkey,
-    magic_program: &Pubkey,
-) -> Instruction {
-    let instruction_data = vec![ManifestInstruction::CommitMarket as u8];
-
-    Instruction {
-        program_id: *program_id,
-        accounts: vec![
-            AccountMeta::new(*payer, true),                   // payer
-            AccountMeta::new(*market, false),                 // market
-            AccountMeta::new_readonly(*magic_context, false), // magic_context
-            AccountMeta::new_readonly(*magic_program, false), // magic_program
-        ],
-        data: instruction_data,
-    }
-}
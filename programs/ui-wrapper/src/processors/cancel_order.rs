@@ -29,8 +29,8 @@ use crate::{
 };
 
 use super::shared::{
-    check_signer, get_market_info_index_for_market, OpenOrdersTree, UnusedWrapperFreeListPadding,
-    WrapperStateAccountInfo,
+    check_signer, get_market_info_index_for_market, sync_fast, OpenOrdersTree,
+    UnusedWrapperFreeListPadding, WrapperStateAccountInfo,
 };
 
 #[derive(BorshDeserialize, BorshSerialize, Clone)]
@@ -66,6 +66,10 @@ pub(crate) fn process_cancel_order(
     check_signer(&wrapper_state, owner.key);
     let market_info_index: DataIndex = get_market_info_index_for_market(&wrapper_state, market.key);
 
+    // Sync first so the seat and order hints are fresh if the market was
+    // compacted since the order was placed.
+    sync_fast(&wrapper_state, &market, market_info_index)?;
+
     let cancel = WrapperCancelOrderParams::try_from_slice(data)?;
 
     // prepare cancel
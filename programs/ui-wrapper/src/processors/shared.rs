@@ -17,7 +17,7 @@ use manifest::{
     program::{get_dynamic_account, invoke},
     quantities::BaseAtoms,
     require,
-    state::{claimed_seat::ClaimedSeat, MarketFixed, RestingOrder},
+    state::{claimed_seat::ClaimedSeat, get_helper_seat, MarketFixed, RestingOrder},
     validation::{ManifestAccountInfo, Program, Signer},
 };
 use solana_program::{
@@ -149,9 +149,18 @@ pub(crate) fn sync_fast(
     let (fixed_data, wrapper_dynamic_data) =
         wrapper_data.split_at_mut(size_of::<ManifestWrapperUserFixed>());
 
+    // Compacting the market moves seats and orders to new indices, so
+    // refresh the seat index if it no longer points at this trader.
+    let trader: Pubkey = get_helper::<ManifestWrapperUserFixed>(fixed_data, 0).trader;
     let market_info: &mut MarketInfo =
         get_mut_helper::<RBNode<MarketInfo>>(wrapper_dynamic_data, market_info_index)
             .get_mut_value();
+    if !is_trader_seat(market_ref.dynamic, market_info.trader_index, &trader) {
+        market_info.trader_index = market_ref.get_trader_index(&trader);
+        if market_info.trader_index == NIL {
+            return Err(ProgramError::InvalidAccountData);
+        }
+    }
     let mut orders_root_index: DataIndex = market_info.orders_root_index;
 
     if orders_root_index != NIL {
@@ -165,7 +174,22 @@ pub(crate) fn sync_fast(
             Vec::with_capacity(EXPECTED_ORDER_BATCH_SIZE);
         for (order_index, order) in orders_tree.iter::<WrapperOpenOrder>() {
             let expected_sequence_number: u64 = order.get_order_sequence_number();
-            let core_data_index: DataIndex = order.get_market_data_index();
+            let mut core_data_index: DataIndex = order.get_market_data_index();
+            // Compaction moves orders to new indices, and a later expand can
+            // bring the old index back in range. Look the order up again by
+            // sequence number whenever the old index no longer holds it.
+            if !is_order_at_index(
+                market_ref.dynamic,
+                core_data_index,
+                expected_sequence_number,
+            ) {
+                core_data_index =
+                    market_ref.get_order_index_by_sequence_number(expected_sequence_number);
+                if core_data_index == NIL {
+                    to_remove_indices.push(order_index);
+                    continue;
+                }
+            }
             // Verifies that it is not just zeroed and happens to match seq num,
             // also check that there are base atoms left.
             let core_resting_order: &RestingOrder =
@@ -185,6 +209,7 @@ pub(crate) fn sync_fast(
             let core_resting_order: &RestingOrder =
                 get_helper::<RBNode<RestingOrder>>(market_ref.dynamic, *core_data_index)
                     .get_value();
+            node.get_mut_value().set_market_data_index(*core_data_index);
             node.get_mut_value()
                 .update_remaining(core_resting_order.get_num_base_atoms());
 
@@ -237,6 +262,26 @@ pub(crate) fn sync_fast(
     Ok(())
 }
 
+fn is_trader_seat(market_dynamic: &[u8], trader_index: DataIndex, trader: &Pubkey) -> bool {
+    (trader_index as usize) < market_dynamic.len()
+        && get_helper_seat(market_dynamic, trader_index)
+            .get_value()
+            .trader
+            .eq(trader)
+}
+
+fn is_order_at_index(
+    market_dynamic: &[u8],
+    order_index: DataIndex,
+    order_sequence_number: u64,
+) -> bool {
+    (order_index as usize) < market_dynamic.len()
+        && get_helper::<RBNode<RestingOrder>>(market_dynamic, order_index)
+            .get_value()
+            .get_sequence_number()
+            == order_sequence_number
+}
+
 pub(crate) fn get_market_info_index_for_market(
     wrapper_state: &WrapperStateAccountInfo,
     market: &Pubkey,
@@ -81,6 +81,11 @@ impl WrapperOpenOrder {
         self.market_data_index
     }
 
+    /// Set the DataIndex for the order in the core program.
+    pub fn set_market_data_index(&mut self, market_data_index: DataIndex) {
+        self.market_data_index = market_data_index;
+    }
+
     /// Get the remaining number of base atoms in the order.
     pub fn get_num_base_atoms(&self) -> BaseAtoms {
         self.num_base_atoms
@@ -16,8 +16,9 @@ use manifest::{
     },
     quantities::{BaseAtoms, QuoteAtoms, QuoteAtomsPerBaseAtom, WrapperU64},
     state::{
-        utils::get_now_slot, DynamicAccount, MarketFixed, OrderType, RestingOrder,
-        MARKET_FIXED_SIZE, NO_EXPIRATION_LAST_VALID_SLOT,
+        utils::{get_now_slot, get_now_unix_timestamp},
+        DynamicAccount, MarketFixed, OrderType, RestingOrder, MARKET_FIXED_SIZE,
+        NO_EXPIRATION_LAST_VALID_SLOT,
     },
     validation::{ManifestAccountInfo, Program, Signer},
 };
@@ -175,6 +176,7 @@ fn prepare_orders(
     // Also, changes orders with last_valid_slot < 1_000_000 to now +
     // last_valid_slot.
     let now_slot: u32 = get_now_slot();
+    let now_unix_timestamp: u32 = get_now_unix_timestamp();
 
     while best_ask_index != NIL
         && get_helper::<RBNode<RestingOrder>>(
@@ -182,7 +184,7 @@ fn prepare_orders(
             best_ask_index + (MARKET_FIXED_SIZE as DataIndex),
         )
         .get_value()
-        .is_expired(now_slot)
+        .is_expired(now_slot, now_unix_timestamp)
     {
         best_ask_index = market_ref
             .get_asks()
@@ -194,7 +196,7 @@ fn prepare_orders(
             best_bid_index + (MARKET_FIXED_SIZE as DataIndex),
         )
         .get_value()
-        .is_expired(now_slot)
+        .is_expired(now_slot, now_unix_timestamp)
     {
         best_bid_index = market_ref
             .get_bids()
@@ -64,7 +64,7 @@ pub(crate) fn process_deposit(
     let WrapperDepositParams { amount_atoms } = WrapperDepositParams::try_from_slice(data)?;
 
     let trader_index_hint: Option<DataIndex> =
-        get_trader_index_hint_for_market(&wrapper_state, &market.info.key)?;
+        get_trader_index_hint_for_market(&wrapper_state, &market)?;
 
     // Call the deposit CPI.
     invoke(
@@ -65,7 +65,7 @@ pub(crate) fn process_withdraw(
     let WrapperWithdrawParams { amount_atoms } = WrapperWithdrawParams::try_from_slice(data)?;
 
     let trader_index_hint: Option<DataIndex> =
-        get_trader_index_hint_for_market(&wrapper_state, &market.info.key)?;
+        get_trader_index_hint_for_market(&wrapper_state, &market)?;
 
     // Call the withdraw CPI
     invoke(